@@ -0,0 +1,89 @@
+use super::errors::DnssecError;
+
+use ring::signature;
+
+/// RSA/SHA-256, as per [RFC5702](https://tools.ietf.org/html/rfc5702).
+pub const RSASHA256: u8 = 8;
+/// RSA/SHA-512, as per [RFC5702](https://tools.ietf.org/html/rfc5702).
+pub const RSASHA512: u8 = 10;
+/// ECDSA on curve P-256 with SHA-256, as per
+/// [RFC6605](https://tools.ietf.org/html/rfc6605).
+pub const ECDSAP256SHA256: u8 = 13;
+/// ECDSA on curve P-384 with SHA-384, as per
+/// [RFC6605](https://tools.ietf.org/html/rfc6605).
+pub const ECDSAP384SHA384: u8 = 14;
+/// Ed25519, as per [RFC8080](https://tools.ietf.org/html/rfc8080).
+pub const ED25519: u8 = 15;
+
+/// The Zone Key flag of a DNSKEY, as per
+/// [RFC4034§2.1.1](https://tools.ietf.org/html/rfc4034#section-2.1.1).
+pub const ZONE_KEY: u16 = 0x0100;
+/// The Secure Entry Point flag of a DNSKEY, set on key signing keys.
+pub const SECURE_ENTRY_POINT: u16 = 0x0001;
+/// The protocol field every DNSKEY must have.
+pub const DNSKEY_PROTOCOL: u8 = 3;
+
+/// Whether signatures of `algorithm` can be verified.
+pub fn is_supported(algorithm: u8) -> bool {
+    matches!(algorithm, RSASHA256 | RSASHA512 | ECDSAP256SHA256 | ECDSAP384SHA384 | ED25519)
+}
+
+/// Split an RSA public key in the format of
+/// [RFC3110§2](https://tools.ietf.org/html/rfc3110#section-2) into its
+/// exponent and modulus, without leading zero octets.
+fn rsa_components(public_key: &[u8]) -> Result<(&[u8], &[u8]), DnssecError> {
+    let (len, start) = match public_key {
+        [0, a, b, ..] => (u16::from_be_bytes([*a, *b]) as usize, 3),
+        [a, ..] => (*a as usize, 1),
+        [] => return Err(DnssecError::InvalidKeyError),
+    };
+    if len == 0 || public_key.len() <= start + len {
+        return Err(DnssecError::InvalidKeyError);
+    }
+    let strip = |s: &[u8]| -> usize { s.iter().take_while(|&&c| c == 0).count() };
+    let e = &public_key[start..start + len];
+    let n = &public_key[start + len..];
+    Ok((&e[strip(e)..], &n[strip(n)..]))
+}
+
+/// Verify `signature` over `data` with a DNSKEY public key of the given
+/// algorithm, in the wire formats of the algorithm's RFC.
+///
+/// RSA keys must have a modulus of 1024 to 8192 bits. A key that the
+/// underlying implementation rejects is reported as a bad signature.
+pub fn verify_signature(algorithm: u8, public_key: &[u8], data: &[u8], sig: &[u8]) -> Result<(), DnssecError> {
+    let result = match algorithm {
+        RSASHA256 | RSASHA512 => {
+            let (e, n) = rsa_components(public_key)?;
+            let params = if algorithm == RSASHA256 {
+                &signature::RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY
+            } else {
+                &signature::RSA_PKCS1_1024_8192_SHA512_FOR_LEGACY_USE_ONLY
+            };
+            signature::RsaPublicKeyComponents { n, e }.verify(params, data, sig)
+        },
+        ECDSAP256SHA256 | ECDSAP384SHA384 => {
+            // The key is the uncompressed point without its 0x04 prefix
+            let (len, alg) = if algorithm == ECDSAP256SHA256 {
+                (64, &signature::ECDSA_P256_SHA256_FIXED)
+            } else {
+                (96, &signature::ECDSA_P384_SHA384_FIXED)
+            };
+            if public_key.len() != len {
+                return Err(DnssecError::InvalidKeyError);
+            }
+            let mut point = Vec::with_capacity(len + 1);
+            point.push(0x04);
+            point.extend_from_slice(public_key);
+            signature::UnparsedPublicKey::new(alg, point).verify(data, sig)
+        },
+        ED25519 => {
+            if public_key.len() != 32 {
+                return Err(DnssecError::InvalidKeyError);
+            }
+            signature::UnparsedPublicKey::new(&signature::ED25519, public_key).verify(data, sig)
+        },
+        x => return Err(DnssecError::UnsupportedAlgorithmError(x)),
+    };
+    result.map_err(|_| DnssecError::BadSignatureError)
+}
@@ -0,0 +1,182 @@
+use super::{Type,Class,Name,RData,ResourceRecord};
+use super::errors::DnssecError;
+use super::super::encoding::{decode_hex,parse_timestamp};
+use super::super::parser::{OwnedName,OwnedResourceRecord};
+use super::super::writer::write_dns_rdata;
+use super::super::zone::{ZoneError,read_zone_str};
+
+use std::borrow::Cow;
+
+/// The trust anchors a validator starts from: DS, TA or DNSKEY records
+/// for zones whose keys are trusted without a chain of trust from their
+/// parent, as per
+/// [RFC4035§4.4](https://tools.ietf.org/html/rfc4035#section-4.4).
+#[derive(PartialEq,Debug,Clone,Default)]
+pub struct TrustAnchors {
+    anchors: Vec<OwnedResourceRecord>,
+}
+
+impl TrustAnchors {
+    pub fn new() -> TrustAnchors {
+        TrustAnchors {
+            anchors: Vec::new(),
+        }
+    }
+
+    /// Add a DS, TA or DNSKEY record as an anchor.
+    pub fn add(&mut self, rr: OwnedResourceRecord) -> Result<(), DnssecError> {
+        match rr.rtype {
+            Type::DS | Type::TA | Type::DNSKEY => {
+                rr.rdata()?;
+                self.anchors.push(rr);
+                Ok(())
+            },
+            x => Err(DnssecError::RecordTypeError(x)),
+        }
+    }
+
+    /// Read anchors from zone file text, such as a `root.key` file or
+    /// the output of a `DNSKEY` query. Records of types other than DS,
+    /// TA and DNSKEY, such as RRSIGs, are ignored.
+    pub fn from_zone_str(s: &str) -> Result<TrustAnchors, ZoneError> {
+        let mut anchors = TrustAnchors::new();
+        for rr in read_zone_str(s, None)? {
+            // Only records of other types are rejected; the zone reader
+            // has already checked the RDATA
+            let _ = anchors.add(rr);
+        }
+        Ok(anchors)
+    }
+
+    /// Read the root zone anchors published by IANA in the
+    /// `root-anchors.xml` format of
+    /// [RFC9718§2](https://tools.ietf.org/html/rfc9718#section-2),
+    /// keeping the key digests whose validity period includes `now`.
+    pub fn from_root_anchors_xml(xml: &str, now: u32) -> Result<TrustAnchors, DnssecError> {
+        let zone = element(xml, "Zone").ok_or(DnssecError::AnchorFormatError)?;
+        let zone = Name::from_str(zone.trim()).map_err(|_| DnssecError::AnchorFormatError)?.into_owned();
+        let mut anchors = TrustAnchors::new();
+        let mut rest = xml;
+        while let Some((attributes, body, next)) = next_element(rest, "KeyDigest") {
+            rest = next;
+            let valid_from = attribute(attributes, "validFrom").ok_or(DnssecError::AnchorFormatError)?;
+            if xml_datetime(valid_from).ok_or(DnssecError::AnchorFormatError)? > now {
+                continue;
+            }
+            if let Some(valid_until) = attribute(attributes, "validUntil") {
+                if xml_datetime(valid_until).ok_or(DnssecError::AnchorFormatError)? <= now {
+                    continue;
+                }
+            }
+            let number = |tag: &str| element(body, tag).and_then(|n| n.trim().parse::<u16>().ok()).ok_or(DnssecError::AnchorFormatError);
+            let algorithm = number("Algorithm")?;
+            let digest_type = number("DigestType")?;
+            if algorithm > 255 || digest_type > 255 {
+                return Err(DnssecError::AnchorFormatError);
+            }
+            let digest = element(body, "Digest").ok_or(DnssecError::AnchorFormatError)?;
+            let ds = RData::DS {
+                key_tag: number("KeyTag")?,
+                algorithm: algorithm as u8,
+                digest_type: digest_type as u8,
+                digest: Cow::Owned(decode_hex(digest.trim().as_bytes()).ok_or(DnssecError::AnchorFormatError)?),
+            };
+            let rdata = write_dns_rdata(&ds)?;
+            anchors.add(ResourceRecord::new(zone.clone(), Type::DS, Class::IN, 0, rdata))?;
+        }
+        Ok(anchors)
+    }
+
+    /// All anchors, in the order they were added.
+    pub fn records(&self) -> &[OwnedResourceRecord] {
+        &self.anchors
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchors.is_empty()
+    }
+
+    /// The zone of the closest anchor at or above `name`, and all the
+    /// anchors for that zone.
+    pub fn closest(&self, name: &Name) -> Option<(&OwnedName, Vec<&OwnedResourceRecord>)> {
+        let zone = self.anchors.iter()
+            .map(|rr| &rr.rname)
+            .filter(|zone| name.is_subdomain_of(zone))
+            .max_by_key(|zone| zone.labels.len())?;
+        Some((zone, self.anchors.iter().filter(|rr| rr.rname == *zone).collect()))
+    }
+}
+
+/// The attributes and body of the first `<tag ...>body</tag>` element
+/// in `xml`, and the text after it.
+fn next_element<'x>(xml: &'x str, tag: &str) -> Option<(&'x str, &'x str, &'x str)> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut from = 0;
+    loop {
+        let start = from + xml[from..].find(&open)? + open.len();
+        // Skip elements whose name merely starts with `tag`
+        match xml[start..].chars().next() {
+            Some('>') | Some(' ') | Some('\t') | Some('\r') | Some('\n') => {},
+            _ => {
+                from = start;
+                continue;
+            },
+        }
+        let body = start + xml[start..].find('>')? + 1;
+        let end = body + xml[body..].find(&close)?;
+        return Some((&xml[start..body - 1], &xml[body..end], &xml[end + close.len()..]));
+    }
+}
+
+/// The body of the first `tag` element in `xml`.
+fn element<'x>(xml: &'x str, tag: &str) -> Option<&'x str> {
+    next_element(xml, tag).map(|(_, body, _)| body)
+}
+
+/// The value of a double-quoted attribute.
+fn attribute<'x>(attributes: &'x str, name: &str) -> Option<&'x str> {
+    let key = format!("{}=\"", name);
+    let mut from = 0;
+    loop {
+        let start = from + attributes[from..].find(&key)?;
+        // Require a separator so `validFrom` does not match `xvalidFrom`
+        if start > 0 && !attributes[..start].ends_with(char::is_whitespace) {
+            from = start + key.len();
+            continue;
+        }
+        let value = start + key.len();
+        let end = value + attributes[value..].find('"')?;
+        return Some(&attributes[value..end]);
+    }
+}
+
+/// Parse an XML Schema `dateTime` such as `2017-02-02T00:00:00+00:00`
+/// into seconds since the epoch. A missing time zone is taken as UTC.
+fn xml_datetime(text: &str) -> Option<u32> {
+    let text = text.trim().as_bytes();
+    if text.len() < 19 || text[4] != b'-' || text[7] != b'-' || text[10] != b'T' || text[13] != b':' || text[16] != b':' {
+        return None;
+    }
+    let digits = text[..19].iter().filter(|c| c.is_ascii_digit()).cloned().collect::<Vec<u8>>();
+    let secs = parse_timestamp(&digits)? as i64;
+    // Skip fractional seconds
+    let mut zone = &text[19..];
+    if zone.first() == Some(&b'.') {
+        let len = zone[1..].iter().take_while(|c| c.is_ascii_digit()).count();
+        zone = &zone[1 + len..];
+    }
+    let offset = match zone {
+        b"" | b"Z" => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] if [h1, h2, m1, m2].iter().all(|c| c.is_ascii_digit()) => {
+            let minutes = ((h1 - b'0') as i64 * 10 + (h2 - b'0') as i64) * 60 + (m1 - b'0') as i64 * 10 + (m2 - b'0') as i64;
+            if *sign == b'+' { minutes * 60 } else { -minutes * 60 }
+        },
+        _ => return None,
+    };
+    let utc = secs - offset;
+    if !(0..=0xFFFFFFFF).contains(&utc) {
+        return None;
+    }
+    Some(utc as u32)
+}
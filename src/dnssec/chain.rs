@@ -0,0 +1,197 @@
+use super::{Type,Name,RData,ResourceRecord};
+use super::algorithm::is_supported;
+use super::anchors::TrustAnchors;
+use super::denial::{unsigned_delegation,MAX_NSEC3_ITERATIONS};
+use super::ds::{ds_matches,is_digest_supported,DIGEST_SHA1};
+use super::errors::DnssecError;
+use super::validate::verify_rrsig;
+
+/// The security status of an RRset, as per
+/// [RFC4035§4.3](https://tools.ietf.org/html/rfc4035#section-4.3).
+#[derive(PartialEq,Debug,Clone,Copy)]
+pub enum ValidationStatus {
+    /// A chain of signed DNSKEY and DS RRsets leads from a trust anchor
+    /// to the RRset.
+    Secure,
+    /// The chain from a trust anchor ends at an authenticated unsigned
+    /// delegation, or at a zone signed only with unsupported algorithms
+    /// or digest types.
+    Insecure,
+    /// The RRset should be secure but could not be validated, for the
+    /// given reason.
+    Bogus(DnssecError),
+    /// No trust anchor covers the RRset.
+    Indeterminate,
+}
+
+/// The records of type `rtype` owned by `name`.
+fn rrset_of<'a>(records: &[ResourceRecord<'a>], name: &Name, rtype: Type) -> Vec<ResourceRecord<'a>> {
+    records.iter().filter(|rr| rr.rtype == rtype && rr.rname == *name).cloned().collect()
+}
+
+/// The signer of an RRSIG record, if it covers `rtype`.
+fn signer<'r>(rr: &'r ResourceRecord, rtype: Type) -> Option<Name<'r>> {
+    if rr.rtype != Type::RRSIG {
+        return None;
+    }
+    match rr.rdata() {
+        Ok(RData::RRSIG { type_covered, signer_name, .. }) if type_covered == rtype => Some(signer_name),
+        _ => None,
+    }
+}
+
+/// Whether any RRSIG in `records` covering `rrset` was made by `zone`.
+fn is_signed_by(records: &[ResourceRecord], rrset: &[ResourceRecord], zone: &Name) -> bool {
+    let first = &rrset[0];
+    records.iter()
+        .filter(|rr| rr.rname == first.rname)
+        .any(|rr| signer(rr, first.rtype).is_some_and(|s| s == *zone))
+}
+
+/// Verify an RRset with any of the RRSIGs by `zone` in `records` and
+/// any of `keys`, the validated DNSKEYs of `zone`.
+fn verify_with_keys(records: &[ResourceRecord], rrset: &[ResourceRecord], zone: &Name, keys: &[ResourceRecord], now: u32) -> Result<(), DnssecError> {
+    let first = &rrset[0];
+    let sigs = records.iter()
+        .filter(|rr| rr.rname == first.rname && signer(rr, first.rtype).is_some_and(|s| s == *zone))
+        .collect::<Vec<&ResourceRecord>>();
+    if sigs.is_empty() {
+        return Err(DnssecError::MissingSignatureError(first.rtype));
+    }
+    // Report why the most promising pair failed: a key that the RRSIG
+    // names says more than one with another tag or algorithm
+    let mut mismatch = None;
+    let mut failure = None;
+    for sig in sigs.iter() {
+        for key in keys.iter() {
+            match verify_rrsig(rrset, sig, key, now) {
+                Ok(()) => return Ok(()),
+                Err(e @ DnssecError::KeyTagMismatchError(_)) |
+                Err(e @ DnssecError::AlgorithmMismatchError(_)) => { mismatch.get_or_insert(e); },
+                Err(e) => { failure.get_or_insert(e); },
+            }
+        }
+    }
+    Err(failure.or(mismatch).unwrap_or(DnssecError::UntrustedKeyError))
+}
+
+/// Find the DNSKEY RRset of `zone` in `records` and authenticate it
+/// with the DS-format records `ds` or the trusted DNSKEY RDATA `trusted`,
+/// as per [RFC4035§5.2](https://tools.ietf.org/html/rfc4035#section-5.2).
+///
+/// Returns `None` if none of `ds` and `trusted` use a supported
+/// algorithm and digest type, in which case the zone is insecure.
+fn zone_keys<'a>(records: &[ResourceRecord<'a>], zone: &Name, ds: &[RData], trusted: &[RData], now: u32) -> Result<Option<Vec<ResourceRecord<'a>>>, DnssecError> {
+    let supported = |rdata: &&RData| match **rdata {
+        RData::DS { algorithm, digest_type, .. } |
+        RData::TA { algorithm, digest_type, .. } => is_supported(algorithm) && is_digest_supported(digest_type),
+        RData::DNSKEY { algorithm, .. } => is_supported(algorithm),
+        _ => false,
+    };
+    let mut ds = ds.iter().filter(supported).collect::<Vec<&RData>>();
+    let trusted = trusted.iter().filter(supported).collect::<Vec<&RData>>();
+    if ds.is_empty() && trusted.is_empty() {
+        return Ok(None);
+    }
+    // SHA-1 digests are ignored when stronger ones are present, as per
+    // RFC4509§3
+    if ds.iter().any(|rdata| matches!(**rdata, RData::DS { digest_type, .. } | RData::TA { digest_type, .. } if digest_type != DIGEST_SHA1)) {
+        ds.retain(|rdata| !matches!(**rdata, RData::DS { digest_type: DIGEST_SHA1, .. } | RData::TA { digest_type: DIGEST_SHA1, .. }));
+    }
+
+    let dnskeys = rrset_of(records, zone, Type::DNSKEY);
+    if dnskeys.is_empty() {
+        return Err(DnssecError::MissingRRsetError(Type::DNSKEY));
+    }
+    let entry = dnskeys.iter()
+        .filter(|rr| rr.rdata().is_ok_and(|key| {
+            ds.iter().any(|d| ds_matches(d, zone, &key)) || trusted.contains(&&key)
+        }))
+        .cloned()
+        .collect::<Vec<ResourceRecord>>();
+    if entry.is_empty() {
+        return Err(DnssecError::UntrustedKeyError);
+    }
+    verify_with_keys(records, &dnskeys, zone, &entry, now)?;
+    Ok(Some(dnskeys))
+}
+
+/// Validate an RRset by building a chain of trust down to it from the
+/// closest of `anchors`, as per
+/// [RFC4035§5](https://tools.ietf.org/html/rfc4035#section-5).
+///
+/// `records` holds the DNSKEY, DS, NSEC and NSEC3 RRsets and RRSIGs
+/// collected while resolving the RRset, and the RRSIGs of the RRset
+/// itself. From the anchor's zone, the chain follows the DS RRsets in
+/// `records` down to the zone that signed the RRset. A delegation whose
+/// lack of a DS RRset is proven by signed NSEC or NSEC3 records,
+/// including an NSEC3 Opt-Out span, makes the RRset insecure.
+pub fn validate_chain(anchors: &TrustAnchors, records: &[ResourceRecord], rrset: &[ResourceRecord], now: u32) -> ValidationStatus {
+    let first = match rrset.first() {
+        Some(first) => first,
+        None => return ValidationStatus::Bogus(DnssecError::EmptyRRsetError),
+    };
+    let (anchor_zone, anchor_records) = match anchors.closest(&first.rname) {
+        Some(closest) => closest,
+        None => return ValidationStatus::Indeterminate,
+    };
+    let mut ds = Vec::new();
+    let mut trusted = Vec::new();
+    for rr in anchor_records {
+        match rr.rdata() {
+            Ok(rdata @ RData::DNSKEY { .. }) => trusted.push(rdata),
+            Ok(rdata) => ds.push(rdata),
+            Err(e) => return ValidationStatus::Bogus(DnssecError::InvalidRecordError(e)),
+        }
+    }
+
+    let mut zone = anchor_zone.clone();
+    let mut keys = match zone_keys(records, &zone, &ds, &trusted, now) {
+        Ok(Some(keys)) => keys,
+        Ok(None) => return ValidationStatus::Insecure,
+        Err(e) => return ValidationStatus::Bogus(e),
+    };
+    'chain: loop {
+        if is_signed_by(records, rrset, &zone) {
+            return match verify_with_keys(records, rrset, &zone, &keys, now) {
+                Ok(()) => ValidationStatus::Secure,
+                Err(e) => ValidationStatus::Bogus(e),
+            };
+        }
+
+        // Look for the next zone cut towards the owner
+        for depth in zone.labels.len() + 1..=first.rname.labels.len() {
+            let child = Name { labels: first.rname.labels[first.rname.labels.len() - depth..].to_vec() };
+            let ds_rrset = rrset_of(records, &child, Type::DS);
+            if !ds_rrset.is_empty() {
+                if let Err(e) = verify_with_keys(records, &ds_rrset, &zone, &keys, now) {
+                    return ValidationStatus::Bogus(e);
+                }
+                let ds = ds_rrset.iter().filter_map(|rr| rr.rdata().ok()).collect::<Vec<RData>>();
+                keys = match zone_keys(records, &child, &ds, &[], now) {
+                    Ok(Some(keys)) => keys,
+                    Ok(None) => return ValidationStatus::Insecure,
+                    Err(e) => return ValidationStatus::Bogus(e),
+                };
+                zone = child.into_owned();
+                continue 'chain;
+            }
+            if let Some(proof) = unsigned_delegation(records, &zone, &child, MAX_NSEC3_ITERATIONS) {
+                // Each NSEC3 record is an RRset of its own
+                for rr in proof.iter() {
+                    if let Err(e) = verify_with_keys(records, &rrset_of(&proof, &rr.rname, rr.rtype), &zone, &keys, now) {
+                        return ValidationStatus::Bogus(e);
+                    }
+                }
+                return ValidationStatus::Insecure;
+            }
+        }
+
+        let signed = records.iter().any(|rr| rr.rname == first.rname && signer(rr, first.rtype).is_some());
+        return ValidationStatus::Bogus(if signed {
+            DnssecError::MissingRRsetError(Type::DS)
+        } else {
+            DnssecError::MissingSignatureError(first.rtype)
+        });
+    }
+}
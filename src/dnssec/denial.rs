@@ -0,0 +1,397 @@
+use super::{Type,Name,Label,RData,ResourceRecord};
+use super::chain::ValidationStatus;
+use super::errors::DnssecError;
+use super::validate::write_canonical_name;
+use super::super::encoding::decode_base32hex;
+use super::super::parser::OwnedName;
+
+use ring::digest;
+
+/// The SHA-1 NSEC3 hash algorithm, as per
+/// [RFC5155§11](https://tools.ietf.org/html/rfc5155#section-11).
+pub const NSEC3_SHA1: u8 = 1;
+/// The Opt-Out flag of an NSEC3 record, as per
+/// [RFC5155§3.1.2.1](https://tools.ietf.org/html/rfc5155#section-3.1.2.1).
+pub const NSEC3_OPT_OUT: u8 = 0x01;
+/// The default cap on NSEC3 iterations. Proofs using more are treated
+/// as insecure, as
+/// [RFC9276§3.2](https://tools.ietf.org/html/rfc9276#section-3.2)
+/// allows.
+pub const MAX_NSEC3_ITERATIONS: u16 = 100;
+
+/// What a set of NSEC or NSEC3 records is expected to prove about a
+/// query name.
+#[derive(PartialEq,Debug,Clone,Copy)]
+pub enum Denial {
+    /// The name does not exist, and no wildcard could have answered
+    /// for it.
+    NxDomain,
+    /// The name, or the wildcard the answer would have been synthesized
+    /// from, exists but has no RRset of the type.
+    NoData(Type),
+    /// The answer was expanded from a wildcard, with the given RRSIG
+    /// labels field, so no closer match to the name may exist.
+    WildcardAnswer(u8),
+}
+
+/// Hash `name` as per
+/// [RFC5155§5](https://tools.ietf.org/html/rfc5155#section-5): its
+/// canonical form is hashed with the salt, and the result rehashed with
+/// the salt `iterations` more times.
+pub fn nsec3_hash(name: &Name, hash_algorithm: u8, iterations: u16, salt: &[u8]) -> Result<Vec<u8>, DnssecError> {
+    if hash_algorithm != NSEC3_SHA1 {
+        return Err(DnssecError::UnsupportedHashError(hash_algorithm));
+    }
+    let mut hash = Vec::new();
+    write_canonical_name(&mut hash, name)?;
+    for _ in 0..=iterations {
+        let mut ctx = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+        ctx.update(&hash);
+        ctx.update(salt);
+        hash = ctx.finish().as_ref().to_vec();
+    }
+    Ok(hash)
+}
+
+/// The ancestor of `name` with the given number of labels.
+fn ancestor<'n>(name: &Name<'n>, labels: usize) -> Name<'n> {
+    Name { labels: name.labels[name.labels.len() - labels..].to_vec() }
+}
+
+/// The wildcard name directly below `name`.
+fn wildcard<'n>(name: &Name<'n>) -> Result<Name<'n>, DnssecError> {
+    let mut labels = vec![Label::from_slice(b"*")?];
+    labels.extend(name.labels.iter().cloned());
+    Ok(Name { labels })
+}
+
+/// The number of labels `a` and `b` have in common, from the root.
+fn common_labels(a: &Name, b: &Name) -> usize {
+    a.labels.iter().rev().zip(b.labels.iter().rev()).take_while(|&(x, y)| x == y).count()
+}
+
+/// Whether a record with these types is at a delegation point or a
+/// DNAME, so that it cannot deny names below it, as per
+/// [RFC6840§4.1](https://tools.ietf.org/html/rfc6840#section-4.1).
+fn is_cut(types: &[Type]) -> bool {
+    (types.contains(&Type::NS) && !types.contains(&Type::SOA)) || types.contains(&Type::DNAME)
+}
+
+/// Check that the types of a record matching the query name prove
+/// there is no RRset of type `rtype`, as per
+/// [RFC4035§5.4](https://tools.ietf.org/html/rfc4035#section-5.4).
+///
+/// The parent side of a zone cut may only deny the DS RRset, and the
+/// child side may not deny it at all.
+fn check_nodata(types: &[Type], rtype: Type) -> Result<(), DnssecError> {
+    if types.contains(&rtype) {
+        return Err(DnssecError::TypeExistsError(rtype));
+    }
+    if types.contains(&Type::CNAME) {
+        return Err(DnssecError::TypeExistsError(Type::CNAME));
+    }
+    let parent_side = types.contains(&Type::NS) && !types.contains(&Type::SOA);
+    if (rtype == Type::DS && types.contains(&Type::SOA)) || (rtype != Type::DS && parent_side) {
+        return Err(DnssecError::DelegationError);
+    }
+    Ok(())
+}
+
+struct Nsec<'r> {
+    owner: &'r Name<'r>,
+    next: Name<'r>,
+    types: Vec<Type>,
+}
+
+impl<'r> Nsec<'r> {
+    /// Whether the name falls strictly between the owner and next name,
+    /// wrapping around at the end of the zone.
+    fn covers(&self, name: &Name) -> bool {
+        let after = self.owner.canonical_cmp(name).is_lt();
+        let before = name.canonical_cmp(&self.next).is_lt();
+        if self.owner.canonical_cmp(&self.next).is_lt() {
+            after && before
+        } else {
+            after || before
+        }
+    }
+}
+
+fn nsec_records<'r>(nsecs: &'r [ResourceRecord]) -> Result<Vec<Nsec<'r>>, DnssecError> {
+    let mut out = Vec::new();
+    for rr in nsecs.iter().filter(|rr| rr.rtype == Type::NSEC) {
+        if let RData::NSEC { next_domain, types } = rr.rdata()? {
+            out.push(Nsec { owner: &rr.rname, next: next_domain, types });
+        }
+    }
+    Ok(out)
+}
+
+/// The NSEC record that covers `name`, skipping those that cannot deny
+/// it because they sit at a zone cut above it.
+fn nsec_covering<'a, 'r>(nsecs: &'a [Nsec<'r>], name: &Name) -> Result<&'a Nsec<'r>, DnssecError> {
+    let mut err = DnssecError::NotCoveredError;
+    for nsec in nsecs.iter().filter(|nsec| nsec.covers(name)) {
+        if name.is_subdomain_of(nsec.owner) && is_cut(&nsec.types) {
+            err = DnssecError::DelegationError;
+            continue;
+        }
+        return Ok(nsec);
+    }
+    Err(err)
+}
+
+fn nsec_proof(nsecs: &[ResourceRecord], qname: &Name, denial: Denial) -> Result<(), DnssecError> {
+    let nsecs = nsec_records(nsecs)?;
+    if let Some(nsec) = nsecs.iter().find(|nsec| *nsec.owner == *qname) {
+        return match denial {
+            Denial::NoData(rtype) => check_nodata(&nsec.types, rtype),
+            _ => Err(DnssecError::NameExistsError),
+        };
+    }
+    let cover = nsec_covering(&nsecs, qname)?;
+    // An empty non-terminal has no NSEC record, but the names below it
+    // follow it in canonical order
+    if cover.next.is_subdomain_of(qname) {
+        return match denial {
+            Denial::NoData(_) => Ok(()),
+            _ => Err(DnssecError::NameExistsError),
+        };
+    }
+
+    // The closest encloser is the longest ancestor of the name that the
+    // covering NSEC shows to exist
+    let encloser = ancestor(qname, common_labels(qname, cover.owner).max(common_labels(qname, &cover.next)));
+    let source = wildcard(&encloser)?;
+    match denial {
+        Denial::NxDomain => {
+            if nsecs.iter().any(|nsec| *nsec.owner == source) {
+                return Err(DnssecError::WildcardError);
+            }
+            nsec_covering(&nsecs, &source).map(|_| ())
+        },
+        Denial::NoData(rtype) => match nsecs.iter().find(|nsec| *nsec.owner == source) {
+            Some(nsec) => check_nodata(&nsec.types, rtype),
+            None => Err(DnssecError::WildcardError),
+        },
+        Denial::WildcardAnswer(labels) => {
+            if encloser.labels.len() != labels as usize {
+                return Err(DnssecError::WildcardError);
+            }
+            Ok(())
+        },
+    }
+}
+
+/// Check that NSEC records prove `denial` for `qname`, as per
+/// [RFC4035§5.4](https://tools.ietf.org/html/rfc4035#section-5.4) and
+/// [RFC4035§5.3.4](https://tools.ietf.org/html/rfc4035#section-5.3.4).
+///
+/// `nsecs` should hold the NSEC records of the response; records of
+/// other types are ignored. Only the proof is checked: the caller must
+/// validate the signatures of the NSEC RRsets, such as with
+/// `validate_chain`.
+pub fn verify_nsec(nsecs: &[ResourceRecord], qname: &Name, denial: Denial) -> ValidationStatus {
+    match nsec_proof(nsecs, qname, denial) {
+        Ok(()) => ValidationStatus::Secure,
+        Err(e) => ValidationStatus::Bogus(e),
+    }
+}
+
+struct Nsec3 {
+    hash: Vec<u8>,
+    next: Vec<u8>,
+    flags: u8,
+    types: Vec<Type>,
+}
+
+impl Nsec3 {
+    fn covers(&self, hash: &[u8]) -> bool {
+        let after = self.hash.as_slice() < hash;
+        let before = hash < self.next.as_slice();
+        if self.hash < self.next {
+            after && before
+        } else {
+            after || before
+        }
+    }
+
+    fn is_opt_out(&self) -> bool {
+        self.flags & NSEC3_OPT_OUT != 0
+    }
+}
+
+/// The NSEC3 records of one zone that share a salt and iteration count.
+struct Nsec3Chain {
+    zone: OwnedName,
+    iterations: u16,
+    salt: Vec<u8>,
+    records: Vec<Nsec3>,
+}
+
+impl Nsec3Chain {
+    /// Collect the NSEC3 records of the zone enclosing `qname`, taking
+    /// the parameters of the first one. Records with an unknown hash
+    /// algorithm or flags are ignored, as per
+    /// [RFC5155§8.1](https://tools.ietf.org/html/rfc5155#section-8.1)
+    /// and [RFC5155§8.2](https://tools.ietf.org/html/rfc5155#section-8.2).
+    fn new(nsec3s: &[ResourceRecord], qname: &Name) -> Result<Option<Nsec3Chain>, DnssecError> {
+        let mut chain: Option<Nsec3Chain> = None;
+        for rr in nsec3s.iter().filter(|rr| rr.rtype == Type::NSEC3) {
+            let (flags, iterations, salt, next, types) = match rr.rdata()? {
+                RData::NSEC3 { hash_algorithm: NSEC3_SHA1, flags, iterations, salt, next_hashed_owner, types } if flags & !NSEC3_OPT_OUT == 0 =>
+                    (flags, iterations, salt, next_hashed_owner, types),
+                _ => continue,
+            };
+            let hash = match rr.rname.labels.first().and_then(|l| decode_base32hex(l.as_bytes())) {
+                Some(hash) => hash,
+                None => continue,
+            };
+            let zone = Name { labels: rr.rname.labels[1..].to_vec() };
+            if !qname.is_subdomain_of(&zone) {
+                continue;
+            }
+            let chain = chain.get_or_insert_with(|| Nsec3Chain {
+                zone: zone.clone().into_owned(),
+                iterations,
+                salt: salt.to_vec(),
+                records: Vec::new(),
+            });
+            if chain.zone == zone && chain.iterations == iterations && *chain.salt == *salt {
+                chain.records.push(Nsec3 { hash, next: next.to_vec(), flags, types });
+            }
+        }
+        Ok(chain)
+    }
+
+    fn hash(&self, name: &Name) -> Result<Vec<u8>, DnssecError> {
+        nsec3_hash(name, NSEC3_SHA1, self.iterations, &self.salt)
+    }
+
+    fn matching(&self, hash: &[u8]) -> Option<&Nsec3> {
+        self.records.iter().find(|r| r.hash == hash)
+    }
+
+    fn covering(&self, hash: &[u8]) -> Option<&Nsec3> {
+        self.records.iter().find(|r| r.covers(hash))
+    }
+
+    /// Find the closest encloser of a name with no NSEC3 record of its
+    /// own, and the record covering the next closer name, as per
+    /// [RFC5155§8.3](https://tools.ietf.org/html/rfc5155#section-8.3).
+    fn closest_encloser<'n>(&self, qname: &Name<'n>) -> Result<(Name<'n>, &Nsec3), DnssecError> {
+        let mut next_closer = None;
+        for len in (self.zone.labels.len()..=qname.labels.len()).rev() {
+            let name = ancestor(qname, len);
+            let hash = self.hash(&name)?;
+            if let Some(encloser) = self.matching(&hash) {
+                if len == qname.labels.len() {
+                    return Err(DnssecError::NameExistsError);
+                }
+                if is_cut(&encloser.types) {
+                    return Err(DnssecError::DelegationError);
+                }
+                return next_closer.map(|cover| (name, cover)).ok_or(DnssecError::NotCoveredError);
+            }
+            next_closer = self.covering(&hash);
+        }
+        Err(DnssecError::ClosestEncloserError)
+    }
+}
+
+fn nsec3_proof(nsec3s: &[ResourceRecord], qname: &Name, denial: Denial, max_iterations: u16) -> Result<ValidationStatus, DnssecError> {
+    let chain = Nsec3Chain::new(nsec3s, qname)?.ok_or(DnssecError::NotCoveredError)?;
+    if chain.iterations > max_iterations {
+        return Ok(ValidationStatus::Insecure);
+    }
+    // Opt-out spans may hide unsigned delegations, so names they cover
+    // are only proven not to exist securely
+    let secure_unless = |opt_out: bool| if opt_out { ValidationStatus::Insecure } else { ValidationStatus::Secure };
+
+    if let Some(record) = chain.matching(&chain.hash(qname)?) {
+        return match denial {
+            Denial::NoData(rtype) => check_nodata(&record.types, rtype).map(|_| ValidationStatus::Secure),
+            _ => Err(DnssecError::NameExistsError),
+        };
+    }
+    if let Denial::WildcardAnswer(labels) = denial {
+        // Only the next closer name needs to be covered, as per
+        // RFC5155§8.8
+        let labels = labels as usize;
+        if labels >= qname.labels.len() || labels < chain.zone.labels.len() {
+            return Err(DnssecError::WildcardError);
+        }
+        let cover = chain.covering(&chain.hash(&ancestor(qname, labels + 1))?).ok_or(DnssecError::NotCoveredError)?;
+        return Ok(secure_unless(cover.is_opt_out()));
+    }
+
+    let (encloser, cover) = chain.closest_encloser(qname)?;
+    let source = chain.hash(&wildcard(&encloser)?)?;
+    match denial {
+        // RFC5155§8.4
+        Denial::NxDomain => {
+            if chain.matching(&source).is_some() {
+                return Err(DnssecError::WildcardError);
+            }
+            chain.covering(&source).ok_or(DnssecError::NotCoveredError)?;
+            Ok(secure_unless(cover.is_opt_out()))
+        },
+        // RFC5155§8.6
+        Denial::NoData(Type::DS) if cover.is_opt_out() => Ok(ValidationStatus::Insecure),
+        // RFC5155§8.7
+        Denial::NoData(rtype) => match chain.matching(&source) {
+            Some(record) => check_nodata(&record.types, rtype).map(|_| ValidationStatus::Secure),
+            None => Err(DnssecError::WildcardError),
+        },
+        Denial::WildcardAnswer(_) => unreachable!(),
+    }
+}
+
+/// Check that NSEC3 records prove `denial` for `qname`, as per
+/// [RFC5155§8](https://tools.ietf.org/html/rfc5155#section-8).
+///
+/// The proof is `Insecure` if the records use more than
+/// `max_iterations` iterations, or if it relies on an Opt-Out span for
+/// a DS RRset, a nonexistent name or a wildcard answer. As with
+/// `verify_nsec`, the caller must validate the signatures of the
+/// records.
+pub fn verify_nsec3(nsec3s: &[ResourceRecord], qname: &Name, denial: Denial, max_iterations: u16) -> ValidationStatus {
+    match nsec3_proof(nsec3s, qname, denial, max_iterations) {
+        Ok(status) => status,
+        Err(e) => ValidationStatus::Bogus(e),
+    }
+}
+
+/// Find the NSEC or NSEC3 records of `zone` in `records` that prove
+/// `name` is an unsigned delegation, as per
+/// [RFC4035§5.2](https://tools.ietf.org/html/rfc4035#section-5.2) and
+/// [RFC5155§8.9](https://tools.ietf.org/html/rfc5155#section-8.9): an
+/// NSEC or NSEC3 record at the name with the NS bit but neither the DS
+/// nor the SOA bit, or an Opt-Out span covering it. An NSEC3 chain over
+/// `max_iterations` also counts, as it makes the name insecure.
+///
+/// The records still need their signatures checked before the name is
+/// treated as insecure.
+pub fn unsigned_delegation<'a>(records: &[ResourceRecord<'a>], zone: &Name, name: &Name, max_iterations: u16) -> Option<Vec<ResourceRecord<'a>>> {
+    let delegation = |types: &[Type]| types.contains(&Type::NS) && !types.contains(&Type::DS) && !types.contains(&Type::SOA);
+
+    let nsec = records.iter().filter(|rr| rr.rtype == Type::NSEC && rr.rname == *name).cloned().collect::<Vec<ResourceRecord>>();
+    if nsec.iter().any(|rr| matches!(rr.rdata(), Ok(RData::NSEC { ref types, .. }) if delegation(types))) {
+        return Some(nsec);
+    }
+
+    let nsec3 = records.iter()
+        .filter(|rr| rr.rtype == Type::NSEC3 && rr.rname.labels.len() == zone.labels.len() + 1 && rr.rname.is_subdomain_of(zone))
+        .cloned()
+        .collect::<Vec<ResourceRecord>>();
+    let chain = Nsec3Chain::new(&nsec3, name).ok()??;
+    let insecure = chain.iterations > max_iterations || match chain.matching(&chain.hash(name).ok()?) {
+        Some(record) => delegation(&record.types),
+        None => chain.closest_encloser(name).is_ok_and(|(_, cover)| cover.is_opt_out()),
+    };
+    if insecure {
+        Some(nsec3)
+    } else {
+        None
+    }
+}
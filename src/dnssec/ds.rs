@@ -0,0 +1,65 @@
+use super::{Type,Name,RData};
+use super::errors::DnssecError;
+use super::validate::write_canonical_name;
+
+use ring::digest;
+
+/// DS digest type SHA-1, as per
+/// [RFC4034§5.1.4](https://tools.ietf.org/html/rfc4034#section-5.1.4).
+pub const DIGEST_SHA1: u8 = 1;
+/// DS digest type SHA-256, as per
+/// [RFC4509](https://tools.ietf.org/html/rfc4509).
+pub const DIGEST_SHA256: u8 = 2;
+/// DS digest type SHA-384, as per
+/// [RFC6605§2](https://tools.ietf.org/html/rfc6605#section-2).
+pub const DIGEST_SHA384: u8 = 4;
+
+/// Whether DS digests of `digest_type` can be computed.
+pub fn is_digest_supported(digest_type: u8) -> bool {
+    matches!(digest_type, DIGEST_SHA1 | DIGEST_SHA256 | DIGEST_SHA384)
+}
+
+/// Compute the DS digest of the DNSKEY or CDNSKEY RDATA `dnskey` owned
+/// by `owner`, as per
+/// [RFC4034§5.1.4](https://tools.ietf.org/html/rfc4034#section-5.1.4).
+pub fn ds_digest(owner: &Name, dnskey: &RData, digest_type: u8) -> Result<Vec<u8>, DnssecError> {
+    let algorithm = match digest_type {
+        DIGEST_SHA1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+        DIGEST_SHA256 => &digest::SHA256,
+        DIGEST_SHA384 => &digest::SHA384,
+        x => return Err(DnssecError::UnsupportedDigestError(x)),
+    };
+    let mut data = Vec::new();
+    write_canonical_name(&mut data, owner)?;
+    match *dnskey {
+        RData::DNSKEY { flags, protocol, algorithm, ref public_key } |
+        RData::CDNSKEY { flags, protocol, algorithm, ref public_key } => {
+            data.extend_from_slice(&flags.to_be_bytes());
+            data.push(protocol);
+            data.push(algorithm);
+            data.extend_from_slice(public_key);
+        },
+        _ => return Err(DnssecError::RecordTypeError(Type::DNSKEY)),
+    }
+    Ok(digest::digest(algorithm, &data).as_ref().to_vec())
+}
+
+/// Whether DS-format RDATA (DS, CDS, TA or DLV) refers to the DNSKEY
+/// RDATA `dnskey` owned by `owner`. Digests of unsupported types never
+/// match.
+pub fn ds_matches(ds: &RData, owner: &Name, dnskey: &RData) -> bool {
+    let (key_tag, algorithm, digest_type, digest) = match *ds {
+        RData::DS { key_tag, algorithm, digest_type, ref digest } |
+        RData::CDS { key_tag, algorithm, digest_type, ref digest } |
+        RData::TA { key_tag, algorithm, digest_type, ref digest } |
+        RData::DLV { key_tag, algorithm, digest_type, ref digest } => (key_tag, algorithm, digest_type, digest),
+        _ => return false,
+    };
+    let key_algorithm = match *dnskey {
+        RData::DNSKEY { algorithm, .. } | RData::CDNSKEY { algorithm, .. } => algorithm,
+        _ => return false,
+    };
+    key_algorithm == algorithm
+        && dnskey.key_tag() == Some(key_tag)
+        && ds_digest(owner, dnskey, digest_type).is_ok_and(|d| d[..] == digest[..])
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::error;
+use std::convert;
+use super::{Type,ReadError,WriteError};
+
+/// The reason an RRset failed DNSSEC validation.
+#[derive(PartialEq,Copy,Clone,Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum DnssecError {
+    InvalidRecordError(ReadError),
+    CanonicalFormError(WriteError),
+    RecordTypeError(Type),
+    EmptyRRsetError,
+    RRsetMismatchError,
+    TypeCoveredError(Type),
+    OwnerMismatchError,
+    LabelsError(u8),
+    SignerMismatchError,
+    SignerScopeError,
+    AlgorithmMismatchError(u8),
+    KeyTagMismatchError(u16),
+    ProtocolError(u8),
+    NotZoneKeyError,
+    ExpiredError(u32),
+    NotYetValidError(u32),
+    UnsupportedAlgorithmError(u8),
+    InvalidKeyError,
+    BadSignatureError,
+    UnsupportedDigestError(u8),
+    MissingRRsetError(Type),
+    MissingSignatureError(Type),
+    UntrustedKeyError,
+    AnchorFormatError,
+    UnsupportedHashError(u8),
+    NameExistsError,
+    TypeExistsError(Type),
+    NotCoveredError,
+    WildcardError,
+    ClosestEncloserError,
+    DelegationError,
+    PrivateKeyError,
+    SigningError,
+    NoSigningKeyError,
+    OutOfZoneError,
+    TransactionSignatureError,
+}
+
+impl error::Error for DnssecError {
+    fn description(&self) -> &str {
+        match *self {
+            DnssecError::InvalidRecordError(_) => "Record has invalid RDATA",
+            DnssecError::CanonicalFormError(_) => "Record cannot be written in canonical form",
+            DnssecError::RecordTypeError(_) => "Record has the wrong type",
+            DnssecError::EmptyRRsetError => "RRset is empty",
+            DnssecError::RRsetMismatchError => "RRset records differ in owner, type or class",
+            DnssecError::TypeCoveredError(_) => "RRSIG covers a different type",
+            DnssecError::OwnerMismatchError => "RRSIG owner or class differs from the RRset",
+            DnssecError::LabelsError(_) => "RRSIG labels field exceeds the owner's label count",
+            DnssecError::SignerMismatchError => "RRSIG signer is not the DNSKEY owner",
+            DnssecError::SignerScopeError => "RRset owner is not within the signer's zone",
+            DnssecError::AlgorithmMismatchError(_) => "RRSIG algorithm differs from the DNSKEY",
+            DnssecError::KeyTagMismatchError(_) => "RRSIG key tag differs from the DNSKEY",
+            DnssecError::ProtocolError(_) => "DNSKEY protocol is not 3",
+            DnssecError::NotZoneKeyError => "DNSKEY is not a zone key",
+            DnssecError::ExpiredError(_) => "RRSIG has expired",
+            DnssecError::NotYetValidError(_) => "RRSIG is not yet valid",
+            DnssecError::UnsupportedAlgorithmError(_) => "Unsupported DNSSEC algorithm",
+            DnssecError::InvalidKeyError => "DNSKEY public key is malformed",
+            DnssecError::BadSignatureError => "Signature does not verify",
+            DnssecError::UnsupportedDigestError(_) => "Unsupported DS digest type",
+            DnssecError::MissingRRsetError(_) => "RRset needed for the chain of trust is missing",
+            DnssecError::MissingSignatureError(_) => "RRset has no RRSIG from the expected signer",
+            DnssecError::UntrustedKeyError => "No DNSKEY matches a trust anchor or DS record",
+            DnssecError::AnchorFormatError => "Malformed trust anchor",
+            DnssecError::UnsupportedHashError(_) => "Unsupported NSEC3 hash algorithm",
+            DnssecError::NameExistsError => "Denial of existence shows the name exists",
+            DnssecError::TypeExistsError(_) => "Denial of existence shows the type exists",
+            DnssecError::NotCoveredError => "No NSEC or NSEC3 record covers the name",
+            DnssecError::WildcardError => "Wildcard does not match the denial of existence",
+            DnssecError::ClosestEncloserError => "No NSEC3 record proves a closest encloser",
+            DnssecError::DelegationError => "NSEC or NSEC3 record is from the wrong side of a zone cut",
+            DnssecError::PrivateKeyError => "Private key is malformed or of another algorithm",
+            DnssecError::SigningError => "Signing failed",
+            DnssecError::NoSigningKeyError => "Zone signer has no keys",
+            DnssecError::OutOfZoneError => "Record is outside the zone being signed",
+            DnssecError::TransactionSignatureError => "SIG(0) record is malformed or misplaced",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match *self {
+            DnssecError::InvalidRecordError(ref err) => Some(err as &dyn error::Error),
+            DnssecError::CanonicalFormError(ref err) => Some(err as &dyn error::Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DnssecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DnssecError::InvalidRecordError(x) => write!(f, "Record has invalid RDATA: {}", x),
+            DnssecError::CanonicalFormError(x) => write!(f, "Record cannot be written in canonical form: {}", x),
+            DnssecError::RecordTypeError(x) => write!(f, "Record has the wrong type: {}", x),
+            DnssecError::EmptyRRsetError => write!(f, "RRset is empty"),
+            DnssecError::RRsetMismatchError => write!(f, "RRset records differ in owner, type or class"),
+            DnssecError::TypeCoveredError(x) => write!(f, "RRSIG covers a different type: {}", x),
+            DnssecError::OwnerMismatchError => write!(f, "RRSIG owner or class differs from the RRset"),
+            DnssecError::LabelsError(x) => write!(f, "RRSIG labels field exceeds the owner's label count: {}", x),
+            DnssecError::SignerMismatchError => write!(f, "RRSIG signer is not the DNSKEY owner"),
+            DnssecError::SignerScopeError => write!(f, "RRset owner is not within the signer's zone"),
+            DnssecError::AlgorithmMismatchError(x) => write!(f, "RRSIG algorithm differs from the DNSKEY: {}", x),
+            DnssecError::KeyTagMismatchError(x) => write!(f, "RRSIG key tag differs from the DNSKEY: {}", x),
+            DnssecError::ProtocolError(x) => write!(f, "DNSKEY protocol is not 3: {}", x),
+            DnssecError::NotZoneKeyError => write!(f, "DNSKEY is not a zone key"),
+            DnssecError::ExpiredError(x) => write!(f, "RRSIG has expired: {}", x),
+            DnssecError::NotYetValidError(x) => write!(f, "RRSIG is not yet valid: {}", x),
+            DnssecError::UnsupportedAlgorithmError(x) => write!(f, "Unsupported DNSSEC algorithm: {}", x),
+            DnssecError::InvalidKeyError => write!(f, "DNSKEY public key is malformed"),
+            DnssecError::BadSignatureError => write!(f, "Signature does not verify"),
+            DnssecError::UnsupportedDigestError(x) => write!(f, "Unsupported DS digest type: {}", x),
+            DnssecError::MissingRRsetError(x) => write!(f, "RRset needed for the chain of trust is missing: {}", x),
+            DnssecError::MissingSignatureError(x) => write!(f, "RRset has no RRSIG from the expected signer: {}", x),
+            DnssecError::UntrustedKeyError => write!(f, "No DNSKEY matches a trust anchor or DS record"),
+            DnssecError::AnchorFormatError => write!(f, "Malformed trust anchor"),
+            DnssecError::UnsupportedHashError(x) => write!(f, "Unsupported NSEC3 hash algorithm: {}", x),
+            DnssecError::NameExistsError => write!(f, "Denial of existence shows the name exists"),
+            DnssecError::TypeExistsError(x) => write!(f, "Denial of existence shows the type exists: {}", x),
+            DnssecError::NotCoveredError => write!(f, "No NSEC or NSEC3 record covers the name"),
+            DnssecError::WildcardError => write!(f, "Wildcard does not match the denial of existence"),
+            DnssecError::ClosestEncloserError => write!(f, "No NSEC3 record proves a closest encloser"),
+            DnssecError::DelegationError => write!(f, "NSEC or NSEC3 record is from the wrong side of a zone cut"),
+            DnssecError::PrivateKeyError => write!(f, "Private key is malformed or of another algorithm"),
+            DnssecError::SigningError => write!(f, "Signing failed"),
+            DnssecError::NoSigningKeyError => write!(f, "Zone signer has no keys"),
+            DnssecError::OutOfZoneError => write!(f, "Record is outside the zone being signed"),
+            DnssecError::TransactionSignatureError => write!(f, "SIG(0) record is malformed or misplaced"),
+        }
+    }
+}
+
+impl convert::From<ReadError> for DnssecError {
+    fn from(err: ReadError) -> DnssecError {
+        DnssecError::InvalidRecordError(err)
+    }
+}
+
+impl convert::From<WriteError> for DnssecError {
+    fn from(err: WriteError) -> DnssecError {
+        DnssecError::CanonicalFormError(err)
+    }
+}
@@ -0,0 +1,133 @@
+use super::RData;
+use super::algorithm::{ECDSAP256SHA256,ECDSAP384SHA384,ED25519,DNSKEY_PROTOCOL,SECURE_ENTRY_POINT};
+use super::errors::DnssecError;
+use super::super::parser::key_tag;
+
+use ring::rand::SystemRandom;
+use ring::signature::{self,KeyPair};
+
+use std::borrow::Cow;
+
+#[derive(Debug)]
+enum Pair {
+    Ed25519(signature::Ed25519KeyPair),
+    Ecdsa(signature::EcdsaKeyPair),
+}
+
+/// A private key for signing a zone, with the flags of its DNSKEY.
+///
+/// Keys with the Secure Entry Point flag are key signing keys, which
+/// sign the DNSKEY RRset; the others are zone signing keys. Ed25519,
+/// ECDSA P-256 and ECDSA P-384 keys are supported.
+#[derive(Debug)]
+pub struct SigningKey {
+    algorithm: u8,
+    flags: u16,
+    pkcs8: Vec<u8>,
+    public_key: Vec<u8>,
+    pair: Pair,
+}
+
+impl SigningKey {
+    /// Generate a new key of `algorithm` with the given DNSKEY flags.
+    pub fn generate(algorithm: u8, flags: u16) -> Result<SigningKey, DnssecError> {
+        let rng = SystemRandom::new();
+        let pkcs8 = match algorithm {
+            ED25519 => signature::Ed25519KeyPair::generate_pkcs8(&rng),
+            ECDSAP256SHA256 => signature::EcdsaKeyPair::generate_pkcs8(&signature::ECDSA_P256_SHA256_FIXED_SIGNING, &rng),
+            ECDSAP384SHA384 => signature::EcdsaKeyPair::generate_pkcs8(&signature::ECDSA_P384_SHA384_FIXED_SIGNING, &rng),
+            x => return Err(DnssecError::UnsupportedAlgorithmError(x)),
+        };
+        let pkcs8 = pkcs8.map_err(|_| DnssecError::SigningError)?;
+        SigningKey::from_pkcs8(algorithm, flags, pkcs8.as_ref())
+    }
+
+    /// Load a key of `algorithm` from a PKCS#8 document, such as one
+    /// returned by `pkcs8`.
+    pub fn from_pkcs8(algorithm: u8, flags: u16, pkcs8: &[u8]) -> Result<SigningKey, DnssecError> {
+        let (public_key, pair) = match algorithm {
+            ED25519 => {
+                let pair = signature::Ed25519KeyPair::from_pkcs8_maybe_unchecked(pkcs8).map_err(|_| DnssecError::PrivateKeyError)?;
+                (pair.public_key().as_ref().to_vec(), Pair::Ed25519(pair))
+            },
+            ECDSAP256SHA256 | ECDSAP384SHA384 => {
+                let alg = if algorithm == ECDSAP256SHA256 {
+                    &signature::ECDSA_P256_SHA256_FIXED_SIGNING
+                } else {
+                    &signature::ECDSA_P384_SHA384_FIXED_SIGNING
+                };
+                let pair = signature::EcdsaKeyPair::from_pkcs8(alg, pkcs8, &SystemRandom::new()).map_err(|_| DnssecError::PrivateKeyError)?;
+                // DNSKEYs hold the uncompressed point without its 0x04 prefix
+                (pair.public_key().as_ref()[1..].to_vec(), Pair::Ecdsa(pair))
+            },
+            x => return Err(DnssecError::UnsupportedAlgorithmError(x)),
+        };
+        Ok(SigningKey {
+            algorithm,
+            flags,
+            pkcs8: pkcs8.to_vec(),
+            public_key,
+            pair,
+        })
+    }
+
+    pub fn algorithm(&self) -> u8 {
+        self.algorithm
+    }
+
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    /// The private key as a PKCS#8 document, for storing the key.
+    pub fn pkcs8(&self) -> &[u8] {
+        &self.pkcs8
+    }
+
+    /// The public key in DNSKEY format.
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Whether this is a key signing key, with the Secure Entry Point
+    /// flag set.
+    pub fn is_key_signing_key(&self) -> bool {
+        self.flags & SECURE_ENTRY_POINT != 0
+    }
+
+    pub fn key_tag(&self) -> u16 {
+        key_tag(self.flags, DNSKEY_PROTOCOL, self.algorithm, &self.public_key)
+    }
+
+    /// The DNSKEY RDATA publishing this key.
+    pub fn dnskey(&self) -> RData<'_> {
+        RData::DNSKEY {
+            flags: self.flags,
+            protocol: DNSKEY_PROTOCOL,
+            algorithm: self.algorithm,
+            public_key: Cow::Borrowed(&self.public_key),
+        }
+    }
+
+    /// The KEY RDATA publishing this key for SIG(0), with the flags
+    /// and protocol given to the key.
+    pub fn key(&self) -> RData<'_> {
+        RData::KEY {
+            flags: self.flags,
+            protocol: DNSKEY_PROTOCOL,
+            algorithm: self.algorithm,
+            public_key: Cow::Borrowed(&self.public_key),
+        }
+    }
+
+    /// Sign `data`, giving the signature in the wire format of the
+    /// algorithm's RFC.
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, DnssecError> {
+        match self.pair {
+            Pair::Ed25519(ref pair) => Ok(pair.sign(data).as_ref().to_vec()),
+            Pair::Ecdsa(ref pair) => pair.sign(&SystemRandom::new(), data)
+                .map(|sig| sig.as_ref().to_vec())
+                .map_err(|_| DnssecError::SigningError),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+pub use super::iana::types::Type;
+pub use super::iana::classes::Class;
+pub use super::parser::{Name,Label,RData,ResourceRecord,ReadError};
+pub use super::writer::WriteError;
+
+pub use super::dnssec::algorithm::{RSASHA256,RSASHA512,ECDSAP256SHA256,ECDSAP384SHA384,ED25519};
+pub use super::dnssec::algorithm::{ZONE_KEY,SECURE_ENTRY_POINT,DNSKEY_PROTOCOL,is_supported,verify_signature};
+pub use super::dnssec::anchors::TrustAnchors;
+pub use super::dnssec::chain::{ValidationStatus,validate_chain};
+pub use super::dnssec::denial::{NSEC3_SHA1,NSEC3_OPT_OUT,MAX_NSEC3_ITERATIONS,Denial,nsec3_hash,verify_nsec,verify_nsec3,unsigned_delegation};
+pub use super::dnssec::ds::{DIGEST_SHA1,DIGEST_SHA256,DIGEST_SHA384,is_digest_supported,ds_digest,ds_matches};
+pub use super::dnssec::errors::DnssecError;
+pub use super::dnssec::key::SigningKey;
+pub use super::dnssec::sig0::{sign_sig0,verify_sig0};
+pub use super::dnssec::signer::{Nsec3Params,ZoneSigner};
+pub use super::dnssec::validate::{rrsig_labels,signed_data,verify_rrsig};
+
+mod algorithm;
+mod anchors;
+mod chain;
+mod denial;
+mod ds;
+mod errors;
+mod key;
+mod sig0;
+mod signer;
+mod validate;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,182 @@
+use super::{Class,Name,RData,ResourceRecord,ReadError,Type};
+use super::algorithm::{DNSKEY_PROTOCOL,is_supported,verify_signature};
+use super::errors::DnssecError;
+use super::key::SigningKey;
+use super::super::encoding::serial_cmp;
+use super::super::parser::{_read_be_u16,read_dns_question,read_dns_resource_record};
+use super::super::writer::{MessageWriter,WriteError,write_dns_rdata,write_dns_rdata_canonical};
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// The protocol field of a KEY usable for any protocol, as per
+/// [RFC2535§3.1.3](https://tools.ietf.org/html/rfc2535#section-3.1.3).
+const ANY_PROTOCOL: u8 = 255;
+/// The flags of a KEY that holds no key, as per
+/// [RFC2535§3.1.2](https://tools.ietf.org/html/rfc2535#section-3.1.2).
+const NO_KEY: u16 = 0xC000;
+
+const ARCOUNT: usize = 10;
+
+/// Find the SIG(0) record of a serialized message, with the offset at
+/// which it starts. It must be the last additional record, as per
+/// [RFC2931§3](https://tools.ietf.org/html/rfc2931#section-3).
+fn find_sig0(msg: &[u8]) -> Result<Option<(usize, ResourceRecord<'_>)>, DnssecError> {
+    if msg.len() < 12 {
+        return Err(DnssecError::InvalidRecordError(ReadError::IndexOutOfRangeError(12, msg.len())));
+    }
+    let mut i = 4;
+    let qdcount = _read_be_u16(msg, &mut i);
+    let ancount = _read_be_u16(msg, &mut i) as usize;
+    let nscount = _read_be_u16(msg, &mut i) as usize;
+    let records = ancount + nscount + _read_be_u16(msg, &mut i) as usize;
+    for _ in 0..qdcount {
+        read_dns_question(msg, &mut i)?;
+    }
+    let mut found = None;
+    for n in 0..records {
+        let start = i;
+        let rr = read_dns_resource_record(msg, &mut i)?;
+        let sig0 = match rr.rdata()? {
+            RData::SIG { type_covered, .. } => type_covered == Type::from(0),
+            _ => false,
+        };
+        if sig0 {
+            if n + 1 != records || n < ancount + nscount || rr.rclass != Class::ANY || rr.rttl != 0 {
+                return Err(DnssecError::TransactionSignatureError);
+            }
+            found = Some((start, rr));
+        }
+    }
+    Ok(found)
+}
+
+/// The data covered by a SIG(0): its RDATA without the signature, the
+/// request if `msg` is a response, and then `msg` itself, as per
+/// [RFC2931§3.1](https://tools.ietf.org/html/rfc2931#section-3.1).
+fn sig0_data(sig: &RData, request: Option<&[u8]>, msg: &[u8]) -> Result<Vec<u8>, DnssecError> {
+    let unsigned = match *sig {
+        RData::SIG { type_covered, algorithm, labels, original_ttl, expiration, inception, key_tag, ref signer_name, .. } => RData::SIG {
+            type_covered,
+            algorithm,
+            labels,
+            original_ttl,
+            expiration,
+            inception,
+            key_tag,
+            signer_name: signer_name.clone(),
+            signature: Cow::Borrowed(&[]),
+        },
+        _ => return Err(DnssecError::RecordTypeError(Type::SIG)),
+    };
+    let mut data = write_dns_rdata_canonical(&unsigned)?;
+    if let Some(request) = request {
+        data.extend_from_slice(request);
+    }
+    data.extend_from_slice(msg);
+    Ok(data)
+}
+
+/// Sign a serialized message with SIG(0), returning it with the SIG
+/// record appended.
+///
+/// `signer` is the owner of the KEY record publishing `key`. The
+/// signature is valid from `inception` to `expiration`, in seconds
+/// since the epoch modulo 2^32, which
+/// [RFC2931§3.1](https://tools.ietf.org/html/rfc2931#section-3.1)
+/// suggests keep to a few minutes. Responses also cover `request`, the
+/// request exactly as it was received.
+pub fn sign_sig0(msg: &[u8], key: &SigningKey, signer: &Name, inception: u32, expiration: u32, request: Option<&[u8]>) -> Result<Vec<u8>, DnssecError> {
+    if msg.len() < 12 {
+        return Err(DnssecError::InvalidRecordError(ReadError::IndexOutOfRangeError(12, msg.len())));
+    }
+    let arcount = ((msg[ARCOUNT] as usize) << 8) + msg[ARCOUNT + 1] as usize + 1;
+    if arcount > 65535 {
+        return Err(DnssecError::CanonicalFormError(WriteError::TooManyRecordsError(arcount)));
+    }
+    let mut sig = RData::SIG {
+        type_covered: Type::from(0),
+        algorithm: key.algorithm(),
+        labels: 0,
+        original_ttl: 0,
+        expiration,
+        inception,
+        key_tag: key.key_tag(),
+        signer_name: signer.clone(),
+        signature: Cow::Borrowed(&[]),
+    };
+    let signature = key.sign(&sig0_data(&sig, request, msg)?)?;
+    if let RData::SIG { signature: ref mut s, .. } = sig {
+        *s = Cow::Owned(signature);
+    }
+
+    let rr = ResourceRecord::new(Name { labels: Vec::new() }, Type::SIG, Class::ANY, 0, write_dns_rdata(&sig)?);
+    let mut w = MessageWriter::new();
+    w.set_compression(false);
+    w.write_resource_record(&rr)?;
+    let mut out = msg.to_vec();
+    out[ARCOUNT] = (arcount >> 8) as u8;
+    out[ARCOUNT + 1] = arcount as u8;
+    out.extend_from_slice(&w.into_bytes());
+    Ok(out)
+}
+
+/// Verify the SIG(0) of a serialized message at time `now`, in seconds
+/// since the epoch modulo 2^32, with one of `keys`. Responses must also
+/// pass `request`, the request exactly as it was sent.
+///
+/// The KEY must be owned by the signer, match the SIG's algorithm and
+/// key tag, and hold a key for DNSSEC. The first failed check of the
+/// last candidate key is returned.
+pub fn verify_sig0(msg: &[u8], keys: &[ResourceRecord], now: u32, request: Option<&[u8]>) -> Result<(), DnssecError> {
+    let (offset, rr) = find_sig0(msg)?.ok_or(DnssecError::MissingSignatureError(Type::SIG))?;
+    let sig = rr.rdata()?;
+    let (algorithm, expiration, inception, key_tag, signer_name, signature) = match sig {
+        RData::SIG { algorithm, expiration, inception, key_tag, ref signer_name, ref signature, .. } =>
+            (algorithm, expiration, inception, key_tag, signer_name, signature),
+        _ => return Err(DnssecError::RecordTypeError(rr.rtype)),
+    };
+    match serial_cmp(now, expiration) {
+        Some(Ordering::Greater) | None => return Err(DnssecError::ExpiredError(expiration)),
+        _ => {},
+    }
+    match serial_cmp(now, inception) {
+        Some(Ordering::Less) | None => return Err(DnssecError::NotYetValidError(inception)),
+        _ => {},
+    }
+    if !is_supported(algorithm) {
+        return Err(DnssecError::UnsupportedAlgorithmError(algorithm));
+    }
+
+    // The message as it was before the SIG(0) was added
+    let mut unsigned = msg[..offset].to_vec();
+    let arcount = ((unsigned[ARCOUNT] as u16) << 8) + unsigned[ARCOUNT + 1] as u16 - 1;
+    unsigned[ARCOUNT] = (arcount >> 8) as u8;
+    unsigned[ARCOUNT + 1] = arcount as u8;
+    let data = sig0_data(&sig, request, &unsigned)?;
+
+    let mut result = Err(DnssecError::SignerMismatchError);
+    for key in keys.iter().filter(|key| key.rtype == Type::KEY && key.rname.canonical_cmp(signer_name) == Ordering::Equal) {
+        let rdata = key.rdata()?;
+        result = match rdata {
+            RData::KEY { flags, protocol, algorithm: key_algorithm, ref public_key } => {
+                if key_algorithm != algorithm {
+                    Err(DnssecError::AlgorithmMismatchError(algorithm))
+                } else if Some(key_tag) != rdata.key_tag() {
+                    Err(DnssecError::KeyTagMismatchError(key_tag))
+                } else if protocol != DNSKEY_PROTOCOL && protocol != ANY_PROTOCOL {
+                    Err(DnssecError::ProtocolError(protocol))
+                } else if flags & NO_KEY == NO_KEY {
+                    Err(DnssecError::InvalidKeyError)
+                } else {
+                    verify_signature(algorithm, public_key, &data, signature)
+                }
+            },
+            _ => Err(DnssecError::RecordTypeError(key.rtype)),
+        };
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
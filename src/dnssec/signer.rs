@@ -0,0 +1,352 @@
+use super::{Type,Class,Name,RData,ResourceRecord};
+use super::denial::{nsec3_hash,NSEC3_SHA1,NSEC3_OPT_OUT};
+use super::ds::ds_digest;
+use super::errors::DnssecError;
+use super::key::SigningKey;
+use super::validate::{rrsig_labels,signed_data};
+use super::super::encoding::{encode_base32hex,serial_cmp};
+use super::super::parser::{OwnedName,OwnedResourceRecord};
+use super::super::writer::{write_dns_rdata,write_dns_rdata_canonical};
+
+use siphasher::sip::SipHasher24;
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+
+/// The parameters of an NSEC3 chain, as per
+/// [RFC5155§3](https://tools.ietf.org/html/rfc5155#section-3).
+/// [RFC9276§3.1](https://tools.ietf.org/html/rfc9276#section-3.1)
+/// recommends no salt and no extra iterations.
+#[derive(PartialEq,Debug,Clone,Default)]
+pub struct Nsec3Params {
+    pub salt: Vec<u8>,
+    pub iterations: u16,
+    /// Leave unsigned delegations out of the chain.
+    pub opt_out: bool,
+}
+
+/// RRsets keyed by owner and type code, in canonical order.
+type RRsets = BTreeMap<(OwnedName, u16), Vec<OwnedResourceRecord>>;
+
+fn group<'a, I: Iterator<Item = &'a ResourceRecord<'a>>>(records: I) -> Result<RRsets, DnssecError> {
+    let mut rrsets = RRsets::new();
+    for rr in records {
+        let rr = rr.clone().into_owned()?;
+        rrsets.entry((rr.rname.clone(), rr.rtype.to_u16())).or_default().push(rr);
+    }
+    Ok(rrsets)
+}
+
+/// The TTLs and canonical RDATA of an RRset, to tell whether it changed.
+fn contents(rrset: &[OwnedResourceRecord]) -> Option<Vec<(i32, Vec<u8>)>> {
+    let mut contents = rrset.iter()
+        .map(|rr| Some((rr.rttl, write_dns_rdata_canonical(&rr.rdata().ok()?).ok()?)))
+        .collect::<Option<Vec<(i32, Vec<u8>)>>>()?;
+    contents.sort();
+    contents.dedup();
+    Some(contents)
+}
+
+/// Signs a zone with a set of keys, as per
+/// [RFC4035§2](https://tools.ietf.org/html/rfc4035#section-2).
+///
+/// Signing adds the DNSKEY RRset, an RRSIG for every authoritative
+/// RRset and an NSEC or NSEC3 chain. Key signing keys sign the DNSKEY
+/// RRset and zone signing keys everything else; if there are only keys
+/// of one kind, they sign everything.
+pub struct ZoneSigner {
+    zone: OwnedName,
+    keys: Vec<SigningKey>,
+    inception: u32,
+    expiration: u32,
+    jitter: u32,
+    refresh: u32,
+    nsec3: Option<Nsec3Params>,
+}
+
+impl ZoneSigner {
+    /// Create a signer for `zone` whose RRSIGs are valid from
+    /// `inception` to `expiration`, in seconds since the epoch modulo
+    /// 2^32.
+    pub fn new(zone: OwnedName, inception: u32, expiration: u32) -> ZoneSigner {
+        ZoneSigner {
+            zone,
+            keys: Vec::new(),
+            inception,
+            expiration,
+            jitter: 0,
+            refresh: 0,
+            nsec3: None,
+        }
+    }
+
+    pub fn add_key(&mut self, key: SigningKey) {
+        self.keys.push(key);
+    }
+
+    pub fn keys(&self) -> &[SigningKey] {
+        &self.keys
+    }
+
+    /// Spread RRSIG expirations over the `jitter` seconds before the
+    /// expiration time, so they do not all need refreshing at once.
+    /// The offset of each RRset is derived from its owner and type.
+    pub fn set_jitter(&mut self, jitter: u32) {
+        self.jitter = jitter;
+    }
+
+    /// When re-signing, replace RRSIGs that expire within `refresh`
+    /// seconds of the inception time even if their RRset is unchanged.
+    pub fn set_refresh(&mut self, refresh: u32) {
+        self.refresh = refresh;
+    }
+
+    /// Deny existence with an NSEC3 chain rather than an NSEC chain.
+    pub fn set_nsec3(&mut self, params: Option<Nsec3Params>) {
+        self.nsec3 = params;
+    }
+
+    /// The DS records for the parent zone, one for each key signing
+    /// key, with the given TTL and digest type.
+    pub fn ds_records(&self, ttl: i32, digest_type: u8) -> Result<Vec<OwnedResourceRecord>, DnssecError> {
+        self.signers(Type::DNSKEY).iter().map(|key| {
+            let ds = RData::DS {
+                key_tag: key.key_tag(),
+                algorithm: key.algorithm(),
+                digest_type,
+                digest: Cow::Owned(ds_digest(&self.zone, &key.dnskey(), digest_type)?),
+            };
+            Ok(ResourceRecord::new(self.zone.clone(), Type::DS, Class::IN, ttl, write_dns_rdata(&ds)?))
+        }).collect()
+    }
+
+    /// Sign the records of a zone, which must include the SOA RRset.
+    ///
+    /// Any RRSIG, NSEC, NSEC3 and NSEC3PARAM records are replaced, and
+    /// DNSKEYs for the signer's keys are added to any already present.
+    /// Records below a delegation or DNAME are left unsigned. The result
+    /// is in canonical order, with each RRset followed by its RRSIGs.
+    pub fn sign_zone(&self, records: &[ResourceRecord]) -> Result<Vec<OwnedResourceRecord>, DnssecError> {
+        self.sign(records, &RRsets::new())
+    }
+
+    /// Sign the new contents `records` of a zone that was previously
+    /// signed as `signed`, only signing RRsets that changed.
+    ///
+    /// The RRSIGs in `signed` are kept for unchanged RRsets, including
+    /// the NSEC or NSEC3 records, when each signing key has one that
+    /// expires after the refresh period.
+    pub fn resign_zone(&self, signed: &[ResourceRecord], records: &[ResourceRecord]) -> Result<Vec<OwnedResourceRecord>, DnssecError> {
+        self.sign(records, &group(signed.iter())?)
+    }
+
+    fn sign(&self, records: &[ResourceRecord], previous: &RRsets) -> Result<Vec<OwnedResourceRecord>, DnssecError> {
+        if self.keys.is_empty() {
+            return Err(DnssecError::NoSigningKeyError);
+        }
+        if records.iter().any(|rr| !rr.rname.is_subdomain_of(&self.zone)) {
+            return Err(DnssecError::OutOfZoneError);
+        }
+        let mut rrsets = group(records.iter().filter(|rr| !matches!(rr.rtype, Type::RRSIG | Type::NSEC | Type::NSEC3 | Type::NSEC3PARAM)))?;
+
+        let (class, soa_ttl, minimum) = match rrsets.get(&(self.zone.clone(), Type::SOA.to_u16())).and_then(|soa| soa.first()) {
+            Some(soa) => match soa.rdata()? {
+                RData::SOA { minimum, .. } => (soa.rclass, soa.rttl, minimum),
+                _ => return Err(DnssecError::RecordTypeError(Type::SOA)),
+            },
+            None => return Err(DnssecError::MissingRRsetError(Type::SOA)),
+        };
+        // Negative answers are cached for at most the lesser of the SOA
+        // TTL and minimum, as per RFC9077§3
+        let denial_ttl = (soa_ttl as u32).min(minimum) as i32;
+
+        let dnskeys = rrsets.entry((self.zone.clone(), Type::DNSKEY.to_u16())).or_default();
+        let dnskey_ttl = dnskeys.first().map_or(soa_ttl, |rr| rr.rttl);
+        for key in self.keys.iter() {
+            let dnskey = key.dnskey();
+            if !dnskeys.iter().any(|rr| rr.rdata().is_ok_and(|rdata| rdata == dnskey)) {
+                dnskeys.push(ResourceRecord::new(self.zone.clone(), Type::DNSKEY, class, dnskey_ttl, write_dns_rdata(&dnskey)?));
+            }
+        }
+        if let Some(ref params) = self.nsec3 {
+            let param = RData::NSEC3PARAM {
+                hash_algorithm: NSEC3_SHA1,
+                flags: 0,
+                iterations: params.iterations,
+                salt: Cow::Borrowed(&params.salt),
+            };
+            let rr = ResourceRecord::new(self.zone.clone(), Type::NSEC3PARAM, class, denial_ttl, write_dns_rdata(&param)?);
+            rrsets.insert((self.zone.clone(), Type::NSEC3PARAM.to_u16()), vec![rr]);
+        }
+
+        // Names below a zone cut or DNAME are not authoritative
+        let cuts = rrsets.keys()
+            .filter(|key| (key.1 == Type::NS.to_u16() && key.0 != self.zone) || key.1 == Type::DNAME.to_u16())
+            .map(|key| key.0.clone())
+            .collect::<Vec<OwnedName>>();
+        let occluded = |name: &Name| cuts.iter().any(|cut| name != cut && name.is_subdomain_of(cut));
+        let delegation = |name: &Name| *name != self.zone && rrsets.contains_key(&(name.clone().into_owned(), Type::NS.to_u16()));
+
+        let mut signed = RRsets::new();
+        let mut names = BTreeMap::<OwnedName, Vec<Type>>::new();
+        for (key, rrset) in rrsets.iter() {
+            let name = &key.0;
+            let mut out = rrset.clone();
+            if !occluded(name) {
+                let rtype = rrset[0].rtype;
+                names.entry(name.clone()).or_default().push(rtype);
+                if !delegation(name) || rtype == Type::DS {
+                    out.extend(self.rrsigs(rrset, previous)?);
+                }
+            }
+            signed.insert(key.clone(), out);
+        }
+
+        let denial = match self.nsec3 {
+            Some(ref params) => self.nsec3_chain(params, names, &delegation, class, denial_ttl)?,
+            None => self.nsec_chain(names, class, denial_ttl)?,
+        };
+        for rr in denial {
+            let mut out = vec![rr];
+            out.extend(self.rrsigs(&out, previous)?);
+            signed.insert((out[0].rname.clone(), out[0].rtype.to_u16()), out);
+        }
+        Ok(signed.into_values().flatten().collect())
+    }
+
+    /// Build the NSEC chain over the authoritative names, as per
+    /// [RFC4034§4](https://tools.ietf.org/html/rfc4034#section-4).
+    fn nsec_chain(&self, names: BTreeMap<OwnedName, Vec<Type>>, class: Class, ttl: i32) -> Result<Vec<OwnedResourceRecord>, DnssecError> {
+        let owners = names.keys().cloned().collect::<Vec<OwnedName>>();
+        let mut chain = Vec::new();
+        for (i, (name, mut types)) in names.into_iter().enumerate() {
+            types.extend_from_slice(&[Type::RRSIG, Type::NSEC]);
+            types.sort_by_key(|t| t.to_u16());
+            types.dedup();
+            let nsec = RData::NSEC {
+                next_domain: owners[(i + 1) % owners.len()].clone(),
+                types,
+            };
+            chain.push(ResourceRecord::new(name, Type::NSEC, class, ttl, write_dns_rdata(&nsec)?));
+        }
+        Ok(chain)
+    }
+
+    /// Build the NSEC3 chain over the authoritative names and the empty
+    /// non-terminals above them, as per
+    /// [RFC5155§7.1](https://tools.ietf.org/html/rfc5155#section-7.1).
+    /// With Opt-Out, unsigned delegations and the empty non-terminals
+    /// only they need are left out.
+    fn nsec3_chain<F: Fn(&Name) -> bool>(&self, params: &Nsec3Params, mut names: BTreeMap<OwnedName, Vec<Type>>, delegation: &F, class: Class, ttl: i32) -> Result<Vec<OwnedResourceRecord>, DnssecError> {
+        let insecure = |name: &Name, types: &[Type]| delegation(name) && !types.contains(&Type::DS);
+        if params.opt_out {
+            names.retain(|name, types| !insecure(name, types));
+        }
+        let enclosers = names.keys()
+            .flat_map(|name| (self.zone.labels.len() + 1..name.labels.len()).map(move |len| Name { labels: name.labels[name.labels.len() - len..].to_vec() }))
+            .collect::<Vec<OwnedName>>();
+        for name in enclosers {
+            names.entry(name).or_default();
+        }
+
+        let mut hashed = Vec::new();
+        for (name, mut types) in names.into_iter() {
+            // Empty non-terminals and unsigned delegations have no RRSIGs
+            if !types.is_empty() && !insecure(&name, &types) {
+                types.push(Type::RRSIG);
+            }
+            types.sort_by_key(|t| t.to_u16());
+            hashed.push((nsec3_hash(&name, NSEC3_SHA1, params.iterations, &params.salt)?, types));
+        }
+        hashed.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let flags = if params.opt_out { NSEC3_OPT_OUT } else { 0 };
+        let mut chain = Vec::new();
+        for (i, (hash, types)) in hashed.iter().enumerate() {
+            let nsec3 = RData::NSEC3 {
+                hash_algorithm: NSEC3_SHA1,
+                flags,
+                iterations: params.iterations,
+                salt: Cow::Borrowed(&params.salt),
+                next_hashed_owner: Cow::Borrowed(&hashed[(i + 1) % hashed.len()].0),
+                types: types.clone(),
+            };
+            let owner = format!("{}.{}", encode_base32hex(hash).to_lowercase(), self.zone);
+            let owner = Name::from_str(&owner)?.into_owned();
+            chain.push(ResourceRecord::new(owner, Type::NSEC3, class, ttl, write_dns_rdata(&nsec3)?));
+        }
+        Ok(chain)
+    }
+
+    /// The keys that sign RRsets of `rtype`.
+    fn signers(&self, rtype: Type) -> Vec<&SigningKey> {
+        let (ksks, zsks): (Vec<&SigningKey>, Vec<&SigningKey>) = self.keys.iter().partition(|key| key.is_key_signing_key());
+        let (preferred, fallback) = if rtype == Type::DNSKEY { (ksks, zsks) } else { (zsks, ksks) };
+        if preferred.is_empty() {
+            fallback
+        } else {
+            preferred
+        }
+    }
+
+    /// The RRSIGs for an RRset: those in `previous` if the RRset is
+    /// unchanged and each signer has a fresh one, or new ones.
+    fn rrsigs(&self, rrset: &[OwnedResourceRecord], previous: &RRsets) -> Result<Vec<OwnedResourceRecord>, DnssecError> {
+        let signers = self.signers(rrset[0].rtype);
+        match self.reusable(rrset, &signers, previous) {
+            Some(rrsigs) => Ok(rrsigs),
+            None => signers.iter().map(|key| self.rrsig(rrset, key)).collect(),
+        }
+    }
+
+    fn reusable(&self, rrset: &[OwnedResourceRecord], signers: &[&SigningKey], previous: &RRsets) -> Option<Vec<OwnedResourceRecord>> {
+        let first = &rrset[0];
+        let old = previous.get(&(first.rname.clone(), first.rtype.to_u16()))?;
+        if contents(old)? != contents(rrset)? {
+            return None;
+        }
+        let rrsigs = previous.get(&(first.rname.clone(), Type::RRSIG.to_u16()))?;
+        let refresh = self.inception.wrapping_add(self.refresh);
+        signers.iter().map(|key| rrsigs.iter().find(|rr| match rr.rdata() {
+            Ok(RData::RRSIG { type_covered, algorithm, expiration, key_tag, ref signer_name, .. }) =>
+                type_covered == first.rtype && algorithm == key.algorithm() && key_tag == key.key_tag()
+                    && *signer_name == self.zone && serial_cmp(expiration, refresh) == Some(Ordering::Greater),
+            _ => false,
+        }).cloned()).collect()
+    }
+
+    fn rrsig(&self, rrset: &[OwnedResourceRecord], key: &SigningKey) -> Result<OwnedResourceRecord, DnssecError> {
+        let first = &rrset[0];
+        let mut rrsig = RData::RRSIG {
+            type_covered: first.rtype,
+            algorithm: key.algorithm(),
+            labels: rrsig_labels(&first.rname),
+            original_ttl: first.rttl as u32,
+            expiration: self.expiration.wrapping_sub(self.jitter_for(&first.rname, first.rtype)),
+            inception: self.inception,
+            key_tag: key.key_tag(),
+            signer_name: self.zone.clone(),
+            signature: Cow::Borrowed(&[]),
+        };
+        let data = signed_data(rrset, &rrsig)?;
+        if let RData::RRSIG { ref mut signature, .. } = rrsig {
+            *signature = Cow::Owned(key.sign(&data)?);
+        }
+        Ok(ResourceRecord::new(first.rname.clone(), Type::RRSIG, first.rclass, first.rttl, write_dns_rdata(&rrsig)?))
+    }
+
+    fn jitter_for(&self, owner: &Name, rtype: Type) -> u32 {
+        if self.jitter == 0 {
+            return 0;
+        }
+        let mut h = SipHasher24::new();
+        for label in owner.labels.iter() {
+            h.write(&label.as_bytes().to_ascii_lowercase());
+            h.write_u8(b'.');
+        }
+        h.write_u16(rtype.to_u16());
+        (h.finish() % self.jitter as u64) as u32
+    }
+}
@@ -0,0 +1,813 @@
+use super::{Name,RData,ResourceRecord,Type,DnssecError};
+use super::{rrsig_labels,signed_data,verify_rrsig,verify_signature};
+use super::{TrustAnchors,ValidationStatus,validate_chain,ds_digest,ds_matches,DIGEST_SHA256,DIGEST_SHA384};
+use super::{Denial,nsec3_hash,verify_nsec,verify_nsec3,MAX_NSEC3_ITERATIONS};
+use super::{SigningKey,ZoneSigner,Nsec3Params,ZONE_KEY,SECURE_ENTRY_POINT,ECDSAP256SHA256,ED25519};
+use super::{Class,sign_sig0,verify_sig0};
+use super::super::encoding::encode_base32hex;
+use super::super::encoding::decode_hex;
+use super::super::parser::{Message,OwnedResourceRecord,Question,read_dns_message};
+use super::super::writer::{write_dns_message,write_dns_rdata};
+use super::super::zone::{read_zone_file,read_zone_str};
+
+fn records(s: &str) -> Vec<OwnedResourceRecord> {
+    read_zone_str(s, None).ok().unwrap()
+}
+
+/// Split zone text into the DNSKEY, the RRSIG and the RRset, in the
+/// order they appear.
+fn parts(s: &str) -> (OwnedResourceRecord, OwnedResourceRecord, Vec<OwnedResourceRecord>) {
+    let mut rrs = records(s);
+    let key = rrs.iter().position(|rr| rr.rtype == Type::DNSKEY).unwrap();
+    let key = rrs.remove(key);
+    let sig = rrs.iter().position(|rr| rr.rtype == Type::RRSIG).unwrap();
+    let sig = rrs.remove(sig);
+    (key, sig, rrs)
+}
+
+/// Rewrite the algorithm and key tag of an RRSIG.
+fn resign(rrsig: &ResourceRecord, new_algorithm: u8, new_key_tag: u16) -> OwnedResourceRecord {
+    let rdata = match rrsig.rdata().ok().unwrap() {
+        RData::RRSIG { type_covered, labels, original_ttl, expiration, inception, signer_name, signature, .. } => RData::RRSIG {
+            type_covered,
+            algorithm: new_algorithm,
+            labels,
+            original_ttl,
+            expiration,
+            inception,
+            key_tag: new_key_tag,
+            signer_name,
+            signature,
+        },
+        _ => unreachable!(),
+    };
+    ResourceRecord::new(rrsig.rname.clone(), rrsig.rtype, rrsig.rclass, rrsig.rttl, write_dns_rdata(&rdata).ok().unwrap()).into_owned().ok().unwrap()
+}
+
+// RFC8080§6
+static ED25519_1: &str = "
+example.com. 3600 IN DNSKEY 257 3 15 (
+             l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4= )
+example.com. 3600 IN MX 10 mail.example.com.
+example.com. 3600 IN RRSIG MX 15 2 3600 (
+             1440021600 1438207200 3613 example.com. (
+             oL9krJun7xfBOIWcGHi7mag5/hdZrKWw15jPGrHpjQeRAvTdszaPD+QLs3f
+             x8A4M3e23mRZ9VrbpMngwcrqNAg== ) )
+";
+static ED25519_2: &str = "
+example.com. 3600 IN DNSKEY 257 3 15 (
+             zPnZ/QwEe7S8C5SPz2OfS5RR40ATk2/rYnE9xHIEijs= )
+example.com. 3600 IN MX 10 mail.example.com.
+example.com. 3600 IN RRSIG MX 15 2 3600 (
+             1440021600 1438207200 35217 example.com. (
+             zXQ0bkYgQTEFyfLyi9QoiY6D8ZdYo4wyUhVioYZXFdT410QPRITQSqJSnzQ
+             oSm5poJ7gD7AQR0O7KuI5k2pcBg== ) )
+";
+static ED25519_NOW: u32 = 1439000000;
+
+// RFC6605§6.1
+static P256: &str = "
+example.net. 3600 IN DNSKEY 257 3 13 (
+        GojIhhXUN/u4v54ZQqGSnyhWJwaubCvTmeexv7bR6edb
+        krSqQpF64cYbcB7wNcP+e+MAnLr+Wi9xMWyQLc8NAA== )
+www.example.net. 3600 IN A 192.0.2.1
+www.example.net. 3600 IN RRSIG A 13 3 3600 (
+        20100909100439 20100812100439 55648 example.net.
+        qx6wLYqmh+l9oCKTN6qIc+bw6ya+KJ8oMz0YP107epXA
+        yGmt+3SNruPFKG7tZoLBLlUzGGus7ZwmwWep666VCw== )
+";
+static P256_NOW: u32 = 1282000000;
+
+// The RSA examples of RFC5702 use keys shorter than 1024 bits, and
+// RFC6605 only gives a P-384 example with a randomized signature, so
+// these were made with a fresh key for each algorithm over the RRset
+// www.example.net. A { 192.0.2.1, 192.0.2.2 }.
+static RSASHA256: &str = "
+example.net. 3600 IN DNSKEY 257 3 8 AwEAAYbKIGqQWU20UmcUDLU+AFZlki+kPIn1srMRF3zEnp4RRSSqxouZYb9ez/Gsx1/CpNG/H+RceSgcu+AX0S86KVEDxzGic0/xtAIUArR8sUyH6XWSkhRqb8vcM+TUVmetzy3pigaoEQsPtfDTTWOeK1Jo4vq4uUvJI6BuqDeNZwBWnpC3T/Y/aLZEiJBhITu2+mMZ7HkVtLaeq4hQ0j5SBYQ4uGGCgoNwCtOXVjk2vtAXwHJRc2vBDaQ8mbSlZflicLt/Z8NPxuqSpJSZ45LAECO8VpnHNRNgMz8DqyvYCMiXMsU+KGbPwG/S30etUZRPm/PoC1DfJ/k0QfVwKkKQIHk=
+www.example.net. 3600 IN RRSIG A 8 3 3600 20300101000000 20000101000000 6947 example.net. RtcZdKpmqVsDhjoNry9LutjSwS/ctPVLfOuLZtpwfCTnHGkpziIboxhzuJzceMmOUXkhzZk91JzcRH6uweWSmKuWEO6ryGhoUsAYthE07Paaxp9FSp5AngoveCiEJyCGxY/OROHiuCd9X+25ltKpW0xt84B0yj/TdR0RKQX5X5eE2gF1RrEd6ActdkIpxrBzmi4DmLADVODLQ7UIplMOgtpYtx1bpTCp7CLY6kS3D7QnS1lHaUBM1IqEET1j/oRPLELVNUJ1uBawBANmgWt6feP4lMHFkc8fFjgI/uFome1y+1GQjCegOhknTSUri+NPD7hEPNX3cvC1Op8MlfNE/g==
+";
+static RSASHA512: &str = "
+example.net. 3600 IN DNSKEY 257 3 10 AwEAAelFM1PSjsUCNVIX9VJ8H4we4N4ITGQRw3ZH9Oe2g46c4iv+shOLh9VYoIVBOhIE/9PXx7E/lyB+wt4SaWjVyzEsZ4YBtrpNJy4T0McmP/xtSRzDuF57hC82WwKPFzptfgVD0cygnR3HVn3Tv9oKYrV80V1TS5QS2ndLZmCuLPMGQ8ayk84ADQJos1wXSrCqHaNPTrl7+DBR+FXUCwVot8J1D0DtdWzijuVz8cSvqcK9gKY0qwS/0/zeBY6c9GikRPYMb98jz2fYR2NJD0gRoWYx7Ly5JfGattlpTbMpE/0S7ns5CE5BuceH/6ABVg52A+BABQ65y67LL7gdZLMCZP8=
+www.example.net. 3600 IN RRSIG A 10 3 3600 20300101000000 20000101000000 14689 example.net. 1afF2OZRB1Y0peRh7TlYKSI471z/wbf4RUebOh84d5K48PsXqaQDHSCeBWyEmuBKMBOrIdKmEuKQ3G7KdKScwT/wN/x28vX0xyvE+j9C583N3yGHP83mvxk/+1s4WLPvo+z/qyRfVvZRCpPfOlSFe26tLJ35qKbXjypvUNZPgEnU3buUrT8BeHg9PPC+xuuwHVfAlJpokZFSlN5/QoP0HjZlRUQo9gX8ItaZz52ngp3hC/Uf/RTfZfgJWjdMGfboT+OU1SCTpRjP2LnmQ8ZveFnU6i5hUh44FoN8oLspAqAMUUiZ3ah2IFWOGixuR5l1KUorzpOT1ihF0XsaxyPjsA==
+";
+static P384: &str = "
+example.net. 3600 IN DNSKEY 257 3 14 mWAjg8Sc1jEE0i+C6xIm8NUQLKqJuGpTN3gVoziGpu4dGHR8Tz2lWiDB0tPLP5JXfMF/1AOZvSUrhcoqKUVnZOcgHE8cIPsbLQOpPFx3cgatCB4BunIr2Tujj6auk8Ax
+www.example.net. 3600 IN RRSIG A 14 3 3600 20300101000000 20000101000000 35397 example.net. DaMEnUZuF6Lmv7yOrWJSnrA25Mneuna7wWgXWKGtTcVYh5TONCJeCJ5o9PkDR+F0rjdJk3x7dvATCcF1q6TsrLBIXFGF7MtqZGBnhbSYzboWB45HEyD1y6yw3fkXwk/f
+";
+// Signed as *.example.net.
+static WILDCARD: &str = "
+example.net. 3600 IN DNSKEY 257 3 15 afzx06KC97gdu9na4g8/W4iMimHAXgylQlsNkjhedWk=
+*.example.net. 3600 IN RRSIG A 15 2 3600 20300101000000 20000101000000 61635 example.net. 0IwPqe6jrasGLleC7wG6lhxXAN1/yzLFxmUX+wZhlDAO4YmKhYhXunjKZCakxffFspgAJbuBacE8EJtkQ/xSAA==
+";
+static GENERATED_NOW: u32 = 1600000000;
+
+fn rrset_a(owner: &str) -> Vec<OwnedResourceRecord> {
+    records(&format!("{0} 3600 IN A 192.0.2.2\n{0} 3600 IN A 192.0.2.1\n", owner))
+}
+
+#[test]
+fn test_verify_rfc_examples() {
+    for &(text, now) in [(ED25519_1, ED25519_NOW), (ED25519_2, ED25519_NOW), (P256, P256_NOW)].iter() {
+        let (key, sig, rrset) = parts(text);
+        assert_eq!(verify_rrsig(&rrset, &sig, &key, now), Ok(()));
+    }
+}
+
+#[test]
+fn test_verify_algorithms() {
+    for text in [RSASHA256, RSASHA512, P384].iter() {
+        let (key, sig, _) = parts(text);
+        let mut rrset = rrset_a("www.example.net.");
+        assert_eq!(verify_rrsig(&rrset, &sig, &key, GENERATED_NOW), Ok(()));
+
+        // Changing the RRset breaks the signature
+        rrset.pop();
+        assert_eq!(verify_rrsig(&rrset, &sig, &key, GENERATED_NOW), Err(DnssecError::BadSignatureError));
+    }
+}
+
+#[test]
+fn test_verify_canonical_form() {
+    let (key, sig, _) = parts(RSASHA256);
+    // Owner case, record order, duplicates and TTLs do not matter
+    let mut rrset = rrset_a("WWW.Example.NET.");
+    rrset.extend(rrset_a("www.example.net."));
+    for rr in rrset.iter_mut() {
+        rr.rttl = 1200;
+    }
+    let sig = ResourceRecord { rname: Name::from_str("www.EXAMPLE.net.").ok().unwrap(), ..sig };
+    assert_eq!(verify_rrsig(&rrset, &sig, &key, GENERATED_NOW), Ok(()));
+
+    let rdata = sig.rdata().ok().unwrap();
+    assert_eq!(signed_data(&rrset, &rdata), signed_data(&rrset_a("www.example.net."), &rdata));
+}
+
+#[test]
+fn test_verify_wildcard() {
+    let (key, sig, _) = parts(WILDCARD);
+    assert_eq!(rrsig_labels(&sig.rname), 2);
+
+    // The answer to a query for host.example.net, synthesized from the
+    // wildcard
+    let rrset = rrset_a("host.example.net.");
+    let sig = ResourceRecord { rname: rrset[0].rname.clone(), ..sig };
+    assert_eq!(verify_rrsig(&rrset, &sig, &key, GENERATED_NOW), Ok(()));
+
+    // The labels field cannot exceed the owner's label count
+    let rrset = rrset_a("net.");
+    let sig = ResourceRecord { rname: rrset[0].rname.clone(), ..sig };
+    assert_eq!(verify_rrsig(&rrset, &sig, &key, GENERATED_NOW), Err(DnssecError::LabelsError(2)));
+}
+
+#[test]
+fn test_verify_validity_period() {
+    let (key, sig, rrset) = parts(ED25519_1);
+    assert_eq!(verify_rrsig(&rrset, &sig, &key, 1438207200), Ok(()));
+    assert_eq!(verify_rrsig(&rrset, &sig, &key, 1440021600), Ok(()));
+    assert_eq!(verify_rrsig(&rrset, &sig, &key, 1438207199), Err(DnssecError::NotYetValidError(1438207200)));
+    assert_eq!(verify_rrsig(&rrset, &sig, &key, 1440021601), Err(DnssecError::ExpiredError(1440021600)));
+}
+
+#[test]
+fn test_verify_key_mismatch() {
+    let (key1, sig1, rrset) = parts(ED25519_1);
+    let (key2, _, _) = parts(ED25519_2);
+    assert_eq!(verify_rrsig(&rrset, &sig1, &key2, ED25519_NOW), Err(DnssecError::KeyTagMismatchError(3613)));
+
+    // A key tag collision still fails on the signature
+    let sig = resign(&sig1, 15, 35217);
+    assert_eq!(verify_rrsig(&rrset, &sig, &key2, ED25519_NOW), Err(DnssecError::BadSignatureError));
+
+    let sig = resign(&sig1, 13, 3613);
+    assert_eq!(verify_rrsig(&rrset, &sig, &key1, ED25519_NOW), Err(DnssecError::AlgorithmMismatchError(13)));
+
+    // The DNSKEY must belong to the signer
+    let (p256, p256_sig, a) = parts(P256);
+    let other = ResourceRecord { rname: Name::from_str("example.com.").ok().unwrap(), ..p256 };
+    assert_eq!(verify_rrsig(&a, &p256_sig, &other, P256_NOW), Err(DnssecError::SignerMismatchError));
+
+    let rdata = key1.rdata().ok().unwrap();
+    let rdata = match rdata {
+        RData::DNSKEY { protocol, algorithm, public_key, .. } => RData::DNSKEY { flags: 0, protocol, algorithm, public_key },
+        _ => unreachable!(),
+    };
+    let tag = rdata.key_tag().unwrap();
+    let key = ResourceRecord::new(key1.rname.clone(), Type::DNSKEY, key1.rclass, 3600, write_dns_rdata(&rdata).ok().unwrap());
+    let sig = resign(&sig1, 15, tag);
+    assert_eq!(verify_rrsig(&rrset, &sig, &key, ED25519_NOW), Err(DnssecError::NotZoneKeyError));
+}
+
+#[test]
+fn test_verify_rrset_mismatch() {
+    let (key, sig, mut rrset) = parts(ED25519_1);
+    assert_eq!(verify_rrsig(&[], &sig, &key, ED25519_NOW), Err(DnssecError::EmptyRRsetError));
+
+    // The RRSIG must cover the type of the RRset
+    let txt = records("example.com. 3600 IN TXT \"example\"");
+    assert_eq!(verify_rrsig(&txt, &sig, &key, ED25519_NOW), Err(DnssecError::TypeCoveredError(Type::MX)));
+
+    rrset.extend(records("www.example.com. 3600 IN MX 10 mail.example.com."));
+    assert_eq!(verify_rrsig(&rrset, &sig, &key, ED25519_NOW), Err(DnssecError::RRsetMismatchError));
+    assert_eq!(verify_rrsig(&rrset[1..], &sig, &key, ED25519_NOW), Err(DnssecError::OwnerMismatchError));
+    assert_eq!(verify_rrsig(&rrset[..1], &key, &key, ED25519_NOW), Err(DnssecError::RecordTypeError(Type::DNSKEY)));
+}
+
+#[test]
+fn test_verify_unsupported_algorithm() {
+    // An Ed448 key, as per RFC8080§6
+    let key = records("example.com. 3600 IN DNSKEY 257 3 16 3kgROaDjrh0H2iuixWBrc8g2EpBBLCdGzHmn+G2MpTPhpj/OiBVHHSfPodx1FYYUcJKm1MDpJtIA").remove(0);
+    let (_, sig, rrset) = parts(ED25519_1);
+    let tag = key.rdata().ok().unwrap().key_tag().unwrap();
+    let sig = resign(&sig, 16, tag);
+    assert_eq!(verify_rrsig(&rrset, &sig, &key, ED25519_NOW), Err(DnssecError::UnsupportedAlgorithmError(16)));
+    assert_eq!(verify_signature(16, &[], &[], &[]), Err(DnssecError::UnsupportedAlgorithmError(16)));
+}
+
+#[test]
+fn test_verify_malformed_keys() {
+    assert_eq!(verify_signature(super::ED25519, &[0; 31], b"", &[0; 64]), Err(DnssecError::InvalidKeyError));
+    assert_eq!(verify_signature(super::ECDSAP256SHA256, &[0; 96], b"", &[0; 64]), Err(DnssecError::InvalidKeyError));
+    assert_eq!(verify_signature(super::RSASHA256, &[], b"", &[0; 64]), Err(DnssecError::InvalidKeyError));
+    assert_eq!(verify_signature(super::RSASHA256, &[3, 1, 0, 1], b"", &[0; 64]), Err(DnssecError::InvalidKeyError));
+    assert_eq!(verify_signature(super::RSASHA256, &[0, 0, 3], b"", &[0; 64]), Err(DnssecError::InvalidKeyError));
+}
+
+#[test]
+fn test_signed_data() {
+    let (_, sig, rrset) = parts(ED25519_1);
+    let data = signed_data(&rrset, &sig.rdata().ok().unwrap()).ok().unwrap();
+    // RRSIG RDATA without the signature, then the MX record with the
+    // original TTL
+    let mut expected = vec![0x00, 0x0f, 15, 2, 0x00, 0x00, 0x0e, 0x10];
+    expected.extend_from_slice(&1440021600u32.to_be_bytes());
+    expected.extend_from_slice(&1438207200u32.to_be_bytes());
+    expected.extend_from_slice(&3613u16.to_be_bytes());
+    expected.extend_from_slice(b"\x07example\x03com\x00");
+    expected.extend_from_slice(b"\x07example\x03com\x00\x00\x0f\x00\x01\x00\x00\x0e\x10\x00\x14\x00\x0a\x04mail\x07example\x03com\x00");
+    assert_eq!(data, expected);
+}
+
+// The root zone KSK-2017
+static ROOT_KSK: &str = ". 172800 IN DNSKEY 257 3 8 AwEAAaz/tAm8yTn4Mfeh5eyI96WSVexTBAvkMgJzkKTOiW1vkIbzxeF3+/4RgWOq7HrxRixHlFlExOLAJr5emLvN7SWXgnLh4+B5xQlNVz8Og8kvArMtNROxVQuCaSnIDdD5LKyWbRd2n9WGe2R8PzgCmr3EgVLrjyBxWezF0jLHwVN8efS3rCj/EWgvIWgb9tarpVUDK/b58Da+sqqls3eNbuv7pr+eoZG+SrDK6nWeL3c6H5Apxz7LjVc1uTIdsIXxuOLYA4/ilBmSVIzuDWfdRUfhHdY6+cn8HFRm+2hM8AnXGXws9555KrUB5qihylGa8subX2Nn6UwNR1AkUTV74bU=";
+
+static ROOT_ANCHORS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrustAnchor id="0DC6F3C5-1B3A-4C20-90FC-D9B2B1B3F2F4" source="http://data.iana.org/root-anchors/root-anchors.xml">
+<Zone>.</Zone>
+<KeyDigest id="Kjqmt7v" validFrom="2010-07-15T00:00:00+00:00" validUntil="2019-01-11T00:00:00+00:00">
+<KeyTag>19036</KeyTag>
+<Algorithm>8</Algorithm>
+<DigestType>2</DigestType>
+<Digest>49AAC11D7B6F6446702E54A1607371607A1A41855200FD2CE1CDDE32F24E8FB5</Digest>
+</KeyDigest>
+<KeyDigest id="Klajeyz" validFrom="2017-02-02T00:00:00+00:00">
+<KeyTag>20326</KeyTag>
+<Algorithm>8</Algorithm>
+<DigestType>2</DigestType>
+<Digest>
+E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D
+</Digest>
+</KeyDigest>
+</TrustAnchor>
+"#;
+
+#[test]
+fn test_ds_digest() {
+    let key = records(ROOT_KSK).remove(0);
+    let rdata = key.rdata().ok().unwrap();
+    assert_eq!(rdata.key_tag(), Some(20326));
+    let digest = ds_digest(&key.rname, &rdata, DIGEST_SHA256).ok().unwrap();
+    assert_eq!(digest, decode_hex(b"E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D").unwrap());
+    assert_eq!(ds_digest(&key.rname, &rdata, DIGEST_SHA384).map(|d| d.len()), Ok(48));
+    assert_eq!(ds_digest(&key.rname, &rdata, 3), Err(DnssecError::UnsupportedDigestError(3)));
+
+    let ds = records(". 86400 IN DS 20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D").remove(0);
+    assert!(ds_matches(&ds.rdata().ok().unwrap(), &key.rname, &rdata));
+    let ta = records(". 86400 IN TA 20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D").remove(0);
+    assert!(ds_matches(&ta.rdata().ok().unwrap(), &key.rname, &rdata));
+    // The digest covers the owner name
+    assert!(!ds_matches(&ds.rdata().ok().unwrap(), &Name::from_str("com.").ok().unwrap(), &rdata));
+}
+
+#[test]
+fn test_root_anchors_xml() {
+    // 2018-06-01, when both keys were valid
+    let anchors = TrustAnchors::from_root_anchors_xml(ROOT_ANCHORS_XML, 1527811200).ok().unwrap();
+    let tags = anchors.records().iter().map(|rr| match rr.rdata().ok().unwrap() {
+        RData::DS { key_tag, .. } => key_tag,
+        _ => unreachable!(),
+    }).collect::<Vec<u16>>();
+    assert_eq!(tags, vec![19036, 20326]);
+
+    let anchors = TrustAnchors::from_root_anchors_xml(ROOT_ANCHORS_XML, 1600000000).ok().unwrap();
+    assert_eq!(anchors.records().len(), 1);
+    let ds = anchors.records()[0].rdata().ok().unwrap();
+    let key = records(ROOT_KSK).remove(0);
+    assert!(ds_matches(&ds, &key.rname, &key.rdata().ok().unwrap()));
+    assert_eq!(anchors.closest(&Name::from_str("www.example.com.").ok().unwrap()).map(|(zone, _)| zone.labels.len()), Some(0));
+
+    assert!(TrustAnchors::from_root_anchors_xml(ROOT_ANCHORS_XML, 1200000000).ok().unwrap().is_empty());
+    let broken = ROOT_ANCHORS_XML.replace("<DigestType>2</DigestType>", "<DigestType>two</DigestType>");
+    assert_eq!(TrustAnchors::from_root_anchors_xml(&broken, 1600000000), Err(DnssecError::AnchorFormatError));
+    let broken = ROOT_ANCHORS_XML.replace("+00:00\">", "+0000\">");
+    assert_eq!(TrustAnchors::from_root_anchors_xml(&broken, 1600000000), Err(DnssecError::AnchorFormatError));
+}
+
+#[test]
+fn test_trust_anchors_from_zone_str() {
+    let text = format!("{}\n. 172800 IN RRSIG DNSKEY 8 0 172800 20300101000000 20000101000000 20326 . AAAA\n", ROOT_KSK);
+    let anchors = TrustAnchors::from_zone_str(&text).ok().unwrap();
+    assert_eq!(anchors.records().len(), 1);
+    assert_eq!(anchors.records()[0].rtype, Type::DNSKEY);
+    assert!(TrustAnchors::from_zone_str(". IN DNSKEY 257 3").is_err());
+
+    let mut anchors = TrustAnchors::new();
+    assert_eq!(anchors.add(records("example. 3600 IN A 192.0.2.1").remove(0)), Err(DnssecError::RecordTypeError(Type::A)));
+    assert!(anchors.closest(&Name::from_str("example.").ok().unwrap()).is_none());
+}
+
+static CHAIN_ANCHOR: &str = ". 3600 IN DS 12594 15 2 A018239161B29E0B3FC3F189C3A1EE440E2FDBE453701BDB44099E00409A7788";
+
+fn chain() -> (TrustAnchors, Vec<OwnedResourceRecord>) {
+    let anchors = TrustAnchors::from_zone_str(CHAIN_ANCHOR).ok().unwrap();
+    (anchors, read_zone_file("tests/zones/dnssec-chain.zone", None).ok().unwrap())
+}
+
+fn answer(records: &[OwnedResourceRecord], owner: &str, rtype: Type) -> Vec<OwnedResourceRecord> {
+    let owner = Name::from_str(owner).ok().unwrap();
+    records.iter().filter(|rr| rr.rname == owner && rr.rtype == rtype).cloned().collect()
+}
+
+#[test]
+fn test_validate_chain() {
+    let (anchors, pool) = chain();
+    let a = answer(&pool, "www.example.", Type::A);
+    assert_eq!(validate_chain(&anchors, &pool, &a, GENERATED_NOW), ValidationStatus::Secure);
+    // Answers at each step of the chain are secure too
+    for &(owner, rtype) in [(".", Type::DNSKEY), ("example.", Type::DS), ("example.", Type::DNSKEY)].iter() {
+        let rrset = answer(&pool, owner, rtype);
+        assert_eq!(validate_chain(&anchors, &pool, &rrset, GENERATED_NOW), ValidationStatus::Secure);
+    }
+
+    // A DNSKEY anchor works as well as a DS
+    let root_ksk = answer(&pool, ".", Type::DNSKEY).into_iter().find(|rr| rr.rdata().ok().unwrap().key_tag() == Some(12594)).unwrap();
+    let mut dnskey_anchors = TrustAnchors::new();
+    dnskey_anchors.add(root_ksk).ok().unwrap();
+    assert_eq!(validate_chain(&dnskey_anchors, &pool, &a, GENERATED_NOW), ValidationStatus::Secure);
+
+    // Only zones below an anchor can be validated
+    let mut other = TrustAnchors::new();
+    other.add(records("other. 3600 IN DS 1 15 2 00").remove(0)).ok().unwrap();
+    assert_eq!(validate_chain(&other, &pool, &a, GENERATED_NOW), ValidationStatus::Indeterminate);
+}
+
+#[test]
+fn test_validate_chain_insecure() {
+    let (anchors, pool) = chain();
+    // An NSEC record proves insecure. has no DS
+    let a = answer(&pool, "www.insecure.", Type::A);
+    assert_eq!(validate_chain(&anchors, &pool, &a, GENERATED_NOW), ValidationStatus::Insecure);
+    // legacy. only has a DS with a digest type that is not supported
+    let a = answer(&pool, "www.legacy.", Type::A);
+    assert_eq!(validate_chain(&anchors, &pool, &a, GENERATED_NOW), ValidationStatus::Insecure);
+
+    // Without the NSEC proof the delegation cannot be shown insecure
+    let without = pool.iter().filter(|rr| rr.rname != Name::from_str("insecure.").ok().unwrap()).cloned().collect::<Vec<_>>();
+    let a = answer(&pool, "www.insecure.", Type::A);
+    assert_eq!(validate_chain(&anchors, &without, &a, GENERATED_NOW), ValidationStatus::Bogus(DnssecError::MissingSignatureError(Type::A)));
+
+    // optout.example. falls in an NSEC3 Opt-Out span, whose records must
+    // still be signed
+    let a = answer(&pool, "www.optout.example.", Type::A);
+    assert_eq!(validate_chain(&anchors, &pool, &a, GENERATED_NOW), ValidationStatus::Insecure);
+    let unsigned = pool.iter().filter(|rr| !(rr.rtype == Type::RRSIG && rr.rname.labels.len() == 2)).cloned().collect::<Vec<_>>();
+    assert_eq!(validate_chain(&anchors, &unsigned, &a, GENERATED_NOW), ValidationStatus::Bogus(DnssecError::MissingSignatureError(Type::NSEC3)));
+}
+
+#[test]
+fn test_validate_chain_bogus() {
+    let (anchors, pool) = chain();
+    let a = answer(&pool, "www.example.", Type::A);
+    let bogus = |pool: &[OwnedResourceRecord], rrset: &[OwnedResourceRecord], now: u32| match validate_chain(&anchors, pool, rrset, now) {
+        ValidationStatus::Bogus(e) => e,
+        x => panic!("expected a bogus result, got {:?}", x),
+    };
+
+    let forged = records("www.example. 3600 IN A 192.0.2.99");
+    assert_eq!(bogus(&pool, &forged, GENERATED_NOW), DnssecError::BadSignatureError);
+    assert_eq!(bogus(&pool, &a, 2000000000), DnssecError::ExpiredError(1893456000));
+
+    // Each link of the chain is needed
+    let without = |owner: &str, rtype: Type| -> Vec<OwnedResourceRecord> {
+        let owner = Name::from_str(owner).ok().unwrap();
+        pool.iter().filter(|rr| !(rr.rname == owner && rr.rtype == rtype)).cloned().collect()
+    };
+    assert_eq!(bogus(&without("example.", Type::DS), &a, GENERATED_NOW), DnssecError::MissingRRsetError(Type::DS));
+    assert_eq!(bogus(&without("example.", Type::DNSKEY), &a, GENERATED_NOW), DnssecError::MissingRRsetError(Type::DNSKEY));
+    assert_eq!(bogus(&without("example.", Type::RRSIG), &a, GENERATED_NOW), DnssecError::MissingSignatureError(Type::DS));
+    assert_eq!(bogus(&without(".", Type::RRSIG), &a, GENERATED_NOW), DnssecError::MissingSignatureError(Type::DNSKEY));
+
+    let wrong = TrustAnchors::from_zone_str(&CHAIN_ANCHOR.replace("A0182391", "B0182391")).ok().unwrap();
+    assert_eq!(validate_chain(&wrong, &pool, &a, GENERATED_NOW), ValidationStatus::Bogus(DnssecError::UntrustedKeyError));
+    assert_eq!(validate_chain(&anchors, &pool, &[], GENERATED_NOW), ValidationStatus::Bogus(DnssecError::EmptyRRsetError));
+}
+
+// The NSEC chain of the example zone of RFC4035 Appendix A
+static NSEC_CHAIN: &str = "
+example. 3600 IN NSEC a.example. NS SOA MX RRSIG NSEC DNSKEY
+a.example. 3600 IN NSEC ai.example. NS DS RRSIG NSEC
+ai.example. 3600 IN NSEC b.example. A HINFO AAAA RRSIG NSEC
+b.example. 3600 IN NSEC ns1.example. NS RRSIG NSEC
+ns1.example. 3600 IN NSEC ns2.example. A RRSIG NSEC
+ns2.example. 3600 IN NSEC *.w.example. A RRSIG NSEC
+*.w.example. 3600 IN NSEC x.w.example. MX RRSIG NSEC
+x.w.example. 3600 IN NSEC x.y.w.example. MX RRSIG NSEC
+x.y.w.example. 3600 IN NSEC xx.example. MX RRSIG NSEC
+xx.example. 3600 IN NSEC example. A HINFO AAAA RRSIG NSEC
+";
+
+fn name(s: &str) -> Name<'_> {
+    Name::from_str(s).ok().unwrap()
+}
+
+#[test]
+fn test_verify_nsec() {
+    let nsecs = records(NSEC_CHAIN);
+    let secure = |qname: &str, denial: Denial| verify_nsec(&nsecs, &name(qname), denial);
+    let bogus = |e: DnssecError| ValidationStatus::Bogus(e);
+
+    // RFC4035§B.2: ml.example. falls between b.example. and ns1.example.,
+    // and *.example. between example. and a.example.
+    assert_eq!(secure("ml.example.", Denial::NxDomain), ValidationStatus::Secure);
+    assert_eq!(secure("ns1.example.", Denial::NxDomain), bogus(DnssecError::NameExistsError));
+    // RFC4035§B.3
+    assert_eq!(secure("ns1.example.", Denial::NoData(Type::MX)), ValidationStatus::Secure);
+    assert_eq!(secure("ns1.example.", Denial::NoData(Type::A)), bogus(DnssecError::TypeExistsError(Type::A)));
+    // y.w.example. is an empty non-terminal
+    assert_eq!(secure("y.w.example.", Denial::NoData(Type::A)), ValidationStatus::Secure);
+    assert_eq!(secure("y.w.example.", Denial::NxDomain), bogus(DnssecError::NameExistsError));
+
+    // RFC4035§B.5: the NSEC at an unsigned delegation only denies its DS
+    assert_eq!(secure("b.example.", Denial::NoData(Type::DS)), ValidationStatus::Secure);
+    assert_eq!(secure("b.example.", Denial::NoData(Type::A)), bogus(DnssecError::DelegationError));
+    assert_eq!(secure("example.", Denial::NoData(Type::DS)), bogus(DnssecError::DelegationError));
+    assert_eq!(secure("mc.b.example.", Denial::NxDomain), bogus(DnssecError::DelegationError));
+
+    // RFC4035§B.6 and B.7: a.z.w.example. is answered by *.w.example.
+    assert_eq!(secure("a.z.w.example.", Denial::WildcardAnswer(2)), ValidationStatus::Secure);
+    assert_eq!(secure("a.z.w.example.", Denial::WildcardAnswer(1)), bogus(DnssecError::WildcardError));
+    assert_eq!(secure("a.z.w.example.", Denial::NoData(Type::AAAA)), ValidationStatus::Secure);
+    assert_eq!(secure("a.z.w.example.", Denial::NoData(Type::MX)), bogus(DnssecError::TypeExistsError(Type::MX)));
+    assert_eq!(secure("a.z.w.example.", Denial::NxDomain), bogus(DnssecError::WildcardError));
+    assert_eq!(secure("x.w.example.", Denial::WildcardAnswer(2)), bogus(DnssecError::NameExistsError));
+
+    // Removing the NSEC that covers *.example. breaks the proof
+    let partial = nsecs[1..].to_vec();
+    assert_eq!(verify_nsec(&partial, &name("ml.example."), Denial::NxDomain), bogus(DnssecError::NotCoveredError));
+    assert_eq!(verify_nsec(&[], &name("ml.example."), Denial::NxDomain), bogus(DnssecError::NotCoveredError));
+}
+
+#[test]
+fn test_nsec3_hash() {
+    // RFC5155 Appendix A
+    let salt = decode_hex(b"aabbccdd").unwrap();
+    for &(owner, hash) in [
+        ("example.", "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom"),
+        ("a.example.", "35mthgpgcu1qg68fab165klnsnk3dpvl"),
+        ("ns1.example.", "2t7b4g4vsa5smi47k61mv5bv1a22bojr"),
+        ("w.example.", "k8udemvp1j2f7eg6jebps17vp3n8i58h"),
+        ("*.w.example.", "r53bq7cc2uvmubfu5ocmm6pers9tk9en"),
+        ("x.w.example.", "b4um86eghhds6nea196smvmlo4ors995"),
+        ("y.w.example.", "ji6neoaepv8b5o6k4ev33abha8ht9fgc"),
+        ("xx.example.", "t644ebqk9bibcna874givr6joj62mlhv"),
+        ("XX.Example.", "t644ebqk9bibcna874givr6joj62mlhv"),
+    ].iter() {
+        let h = nsec3_hash(&name(owner), 1, 12, &salt).ok().unwrap();
+        assert_eq!(encode_base32hex(&h).to_lowercase(), hash);
+    }
+    assert_eq!(nsec3_hash(&name("example."), 2, 0, &[]), Err(DnssecError::UnsupportedHashError(2)));
+}
+
+// The NSEC3 chain of the example zone of RFC5155 Appendix A, with the
+// Opt-Out flag only on the span covering the unsigned delegation
+// c.example.
+static NSEC3_CHAIN: &str = "
+0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example. 3600 IN NSEC3 1 0 12 aabbccdd 2t7b4g4vsa5smi47k61mv5bv1a22bojr NS SOA MX RRSIG DNSKEY NSEC3PARAM
+2t7b4g4vsa5smi47k61mv5bv1a22bojr.example. 3600 IN NSEC3 1 0 12 aabbccdd 2vptu5timamqttgl4luu9kg21e0aor3s A RRSIG
+2vptu5timamqttgl4luu9kg21e0aor3s.example. 3600 IN NSEC3 1 0 12 aabbccdd 35mthgpgcu1qg68fab165klnsnk3dpvl MX RRSIG
+35mthgpgcu1qg68fab165klnsnk3dpvl.example. 3600 IN NSEC3 1 1 12 aabbccdd b4um86eghhds6nea196smvmlo4ors995 NS DS RRSIG
+b4um86eghhds6nea196smvmlo4ors995.example. 3600 IN NSEC3 1 0 12 aabbccdd gjeqe526plbf1g8mklp59enfd789njgi MX RRSIG
+gjeqe526plbf1g8mklp59enfd789njgi.example. 3600 IN NSEC3 1 0 12 aabbccdd ji6neoaepv8b5o6k4ev33abha8ht9fgc A HINFO AAAA RRSIG
+ji6neoaepv8b5o6k4ev33abha8ht9fgc.example. 3600 IN NSEC3 1 0 12 aabbccdd k8udemvp1j2f7eg6jebps17vp3n8i58h
+k8udemvp1j2f7eg6jebps17vp3n8i58h.example. 3600 IN NSEC3 1 0 12 aabbccdd q04jkcevqvmu85r014c7dkba38o0ji5r
+q04jkcevqvmu85r014c7dkba38o0ji5r.example. 3600 IN NSEC3 1 0 12 aabbccdd r53bq7cc2uvmubfu5ocmm6pers9tk9en A RRSIG
+r53bq7cc2uvmubfu5ocmm6pers9tk9en.example. 3600 IN NSEC3 1 0 12 aabbccdd t644ebqk9bibcna874givr6joj62mlhv MX RRSIG
+t644ebqk9bibcna874givr6joj62mlhv.example. 3600 IN NSEC3 1 0 12 aabbccdd 0p9mhaveqvm6t7vbl5lop2u3t2rp3tom A HINFO AAAA RRSIG
+";
+
+#[test]
+fn test_verify_nsec3() {
+    let nsec3s = records(NSEC3_CHAIN);
+    let secure = |qname: &str, denial: Denial| verify_nsec3(&nsec3s, &name(qname), denial, MAX_NSEC3_ITERATIONS);
+    let bogus = |e: DnssecError| ValidationStatus::Bogus(e);
+
+    // RFC5155§B.1: x.w.example. is the closest encloser, and the next
+    // closer c.x.w.example. and *.x.w.example. are covered
+    assert_eq!(secure("a.c.x.w.example.", Denial::NxDomain), ValidationStatus::Secure);
+    assert_eq!(secure("ns1.example.", Denial::NxDomain), bogus(DnssecError::NameExistsError));
+    // RFC5155§B.2 and B.2.1, the latter at an empty non-terminal
+    assert_eq!(secure("ns1.example.", Denial::NoData(Type::MX)), ValidationStatus::Secure);
+    assert_eq!(secure("y.w.example.", Denial::NoData(Type::A)), ValidationStatus::Secure);
+    assert_eq!(secure("ns1.example.", Denial::NoData(Type::A)), bogus(DnssecError::TypeExistsError(Type::A)));
+    assert_eq!(secure("a.example.", Denial::NoData(Type::A)), bogus(DnssecError::DelegationError));
+    assert_eq!(secure("b.a.example.", Denial::NxDomain), bogus(DnssecError::DelegationError));
+
+    // RFC5155§B.3: c.example. is in an Opt-Out span
+    assert_eq!(secure("c.example.", Denial::NoData(Type::DS)), ValidationStatus::Insecure);
+    assert_eq!(secure("c.example.", Denial::NxDomain), ValidationStatus::Insecure);
+
+    // RFC5155§B.4 and B.5: a.z.w.example. is answered by *.w.example.
+    assert_eq!(secure("a.z.w.example.", Denial::WildcardAnswer(2)), ValidationStatus::Secure);
+    assert_eq!(secure("a.z.w.example.", Denial::WildcardAnswer(4)), bogus(DnssecError::WildcardError));
+    assert_eq!(secure("a.z.w.example.", Denial::NoData(Type::AAAA)), ValidationStatus::Secure);
+    assert_eq!(secure("a.z.w.example.", Denial::NxDomain), bogus(DnssecError::WildcardError));
+
+    // Names outside the chain's zone have no proof
+    assert_eq!(secure("www.example.com.", Denial::NxDomain), bogus(DnssecError::NotCoveredError));
+    // Without the record matching x.w.example. there is no closest
+    // encloser below the apex, whose next closer w.example. exists
+    let partial = nsec3s.iter().filter(|rr| !rr.rname.labels[0].as_bytes().starts_with(b"b4um")).cloned().collect::<Vec<_>>();
+    assert_eq!(verify_nsec3(&partial, &name("a.c.x.w.example."), Denial::NxDomain, MAX_NSEC3_ITERATIONS), bogus(DnssecError::NotCoveredError));
+}
+
+#[test]
+fn test_verify_nsec3_parameters() {
+    let nsec3s = records(NSEC3_CHAIN);
+    // RFC9276§3.2: too many iterations make the answer insecure
+    assert_eq!(verify_nsec3(&nsec3s, &name("a.c.x.w.example."), Denial::NxDomain, 10), ValidationStatus::Insecure);
+
+    // Records with unknown flags or hash algorithms are ignored
+    for &(from, to) in [(" 0 12 ", " 2 12 "), (" NSEC3 1 ", " NSEC3 2 ")].iter() {
+        let changed = records(&NSEC3_CHAIN.replace(from, to).replace(" 1 12 ", " 2 12 "));
+        assert_eq!(verify_nsec3(&changed, &name("ns1.example."), Denial::NoData(Type::MX), MAX_NSEC3_ITERATIONS),
+                   ValidationStatus::Bogus(DnssecError::NotCoveredError));
+    }
+}
+
+static UNSIGNED: &str = "
+example. 3600 IN SOA ns1.example. hostmaster.example. 1 7200 3600 1209600 300
+example. 3600 IN NS ns1.example.
+ns1.example. 3600 IN A 192.0.2.1
+www.example. 3600 IN A 192.0.2.2
+www.example. 3600 IN A 192.0.2.3
+*.w.example. 3600 IN MX 10 mail.example.
+x.y.example. 3600 IN TXT \"below an empty non-terminal\"
+sub.example. 3600 IN NS ns.sub.example.
+sub.example. 3600 IN DS 1 15 2 00
+ns.sub.example. 3600 IN A 192.0.2.4
+insecure.example. 3600 IN NS ns.other.
+";
+static INCEPTION: u32 = 1600000000;
+static EXPIRATION: u32 = 1700000000;
+
+fn signer(ksk: u8, zsk: Option<u8>) -> ZoneSigner {
+    let mut signer = ZoneSigner::new(name("example.").into_owned(), INCEPTION, EXPIRATION);
+    signer.add_key(SigningKey::generate(ksk, ZONE_KEY | SECURE_ENTRY_POINT).ok().unwrap());
+    if let Some(zsk) = zsk {
+        signer.add_key(SigningKey::generate(zsk, ZONE_KEY).ok().unwrap());
+    }
+    signer
+}
+
+fn covered(rrsig: &ResourceRecord) -> (Type, u16) {
+    match rrsig.rdata().ok().unwrap() {
+        RData::RRSIG { type_covered, key_tag, .. } => (type_covered, key_tag),
+        _ => unreachable!(),
+    }
+}
+
+/// Check that every RRSIG verifies with a DNSKEY of the zone at `now`, and
+/// return the owner, type covered and key tag of each.
+fn verify_signed(signed: &[OwnedResourceRecord], now: u32) -> Vec<(String, Type, u16)> {
+    let keys = signed.iter().filter(|rr| rr.rtype == Type::DNSKEY).collect::<Vec<_>>();
+    let mut sigs = Vec::new();
+    for sig in signed.iter().filter(|rr| rr.rtype == Type::RRSIG) {
+        let (rtype, key_tag) = covered(sig);
+        let rrset = signed.iter().filter(|rr| rr.rname == sig.rname && rr.rtype == rtype).cloned().collect::<Vec<_>>();
+        assert!(keys.iter().any(|key| verify_rrsig(&rrset, sig, key, now).is_ok()), "{} does not verify", sig);
+        sigs.push((sig.rname.to_string(), rtype, key_tag));
+    }
+    sigs
+}
+
+#[test]
+fn test_sign_zone() {
+    let signer = signer(15, Some(15));
+    let (ksk, zsk) = (signer.keys()[0].key_tag(), signer.keys()[1].key_tag());
+    let signed = signer.sign_zone(&records(UNSIGNED)).ok().unwrap();
+    let sigs = verify_signed(&signed, INCEPTION);
+
+    // The KSK only signs the DNSKEY RRset
+    assert_eq!(sigs.iter().filter(|s| s.2 == ksk).map(|s| s.1).collect::<Vec<_>>(), vec![Type::DNSKEY]);
+    assert!(sigs.iter().all(|s| s.1 == Type::DNSKEY || s.2 == zsk));
+    assert_eq!(signed.iter().filter(|rr| rr.rtype == Type::DNSKEY).count(), 2);
+    // Delegations only have their DS and NSEC signed, and glue is not
+    // signed at all
+    for &(owner, rtype) in [("sub.example.", Type::DS), ("sub.example.", Type::NSEC), ("insecure.example.", Type::NSEC), ("www.example.", Type::A)].iter() {
+        assert!(sigs.contains(&(owner.to_string(), rtype, zsk)), "{} {} is not signed", owner, rtype);
+    }
+    for &(owner, rtype) in [("sub.example.", Type::NS), ("insecure.example.", Type::NS), ("ns.sub.example.", Type::A)].iter() {
+        assert!(!sigs.iter().any(|s| s.0 == owner && s.1 == rtype), "{} {} is signed", owner, rtype);
+    }
+    assert!(signed.iter().any(|rr| rr.rname == name("ns.sub.example.")));
+
+    // The NSEC chain covers the authoritative names, with the TTL of the
+    // SOA minimum
+    let nsecs = signed.iter().filter(|rr| rr.rtype == Type::NSEC).cloned().collect::<Vec<_>>();
+    assert_eq!(nsecs.len(), 7);
+    assert!(nsecs.iter().all(|rr| rr.rttl == 300));
+    assert_eq!(verify_nsec(&nsecs, &name("nope.example."), Denial::NxDomain), ValidationStatus::Secure);
+    assert_eq!(verify_nsec(&nsecs, &name("y.example."), Denial::NoData(Type::A)), ValidationStatus::Secure);
+    assert_eq!(verify_nsec(&nsecs, &name("insecure.example."), Denial::NoData(Type::DS)), ValidationStatus::Secure);
+    assert_eq!(verify_nsec(&nsecs, &name("a.w.example."), Denial::WildcardAnswer(2)), ValidationStatus::Secure);
+
+    // The DS records lead to the zone's keys
+    let ds = signer.ds_records(86400, DIGEST_SHA256).ok().unwrap();
+    assert_eq!(ds.len(), 1);
+    assert_eq!(ds[0].rdata().ok().unwrap().to_string().split(' ').next(), Some(ksk.to_string().as_str()));
+    let mut anchors = TrustAnchors::new();
+    anchors.add(ds[0].clone()).ok().unwrap();
+    let a = answer(&signed, "www.example.", Type::A);
+    assert_eq!(validate_chain(&anchors, &signed, &a, INCEPTION + 1), ValidationStatus::Secure);
+}
+
+#[test]
+fn test_sign_zone_nsec3() {
+    let mut signer = signer(ECDSAP256SHA256, None);
+    let params = Nsec3Params { salt: vec![0xAA, 0xBB, 0xCC, 0xDD], iterations: 5, opt_out: false };
+    signer.set_nsec3(Some(params.clone()));
+    let signed = signer.sign_zone(&records(UNSIGNED)).ok().unwrap();
+    // A single key signs everything
+    let tag = signer.keys()[0].key_tag();
+    assert!(verify_signed(&signed, INCEPTION).iter().all(|s| s.2 == tag));
+    assert!(signed.iter().all(|rr| rr.rtype != Type::NSEC));
+    let param = answer(&signed, "example.", Type::NSEC3PARAM);
+    assert_eq!(param[0].rdata().ok().unwrap().to_string(), "1 0 5 AABBCCDD");
+
+    // The chain also covers the empty non-terminals y.example. and
+    // w.example.
+    let nsec3s = signed.iter().filter(|rr| rr.rtype == Type::NSEC3).cloned().collect::<Vec<_>>();
+    assert_eq!(nsec3s.len(), 9);
+    let check = |nsec3s: &[OwnedResourceRecord], qname: &str, denial: Denial| verify_nsec3(nsec3s, &name(qname), denial, MAX_NSEC3_ITERATIONS);
+    assert_eq!(check(&nsec3s, "nope.example.", Denial::NxDomain), ValidationStatus::Secure);
+    assert_eq!(check(&nsec3s, "y.example.", Denial::NoData(Type::A)), ValidationStatus::Secure);
+    assert_eq!(check(&nsec3s, "insecure.example.", Denial::NoData(Type::DS)), ValidationStatus::Secure);
+    assert_eq!(check(&nsec3s, "a.w.example.", Denial::WildcardAnswer(2)), ValidationStatus::Secure);
+
+    // With Opt-Out the unsigned delegation is left out, and only
+    // insecurely denied
+    signer.set_nsec3(Some(Nsec3Params { opt_out: true, ..params }));
+    let signed = signer.sign_zone(&records(UNSIGNED)).ok().unwrap();
+    verify_signed(&signed, INCEPTION);
+    let nsec3s = signed.iter().filter(|rr| rr.rtype == Type::NSEC3).cloned().collect::<Vec<_>>();
+    assert_eq!(nsec3s.len(), 8);
+    assert_eq!(check(&nsec3s, "insecure.example.", Denial::NoData(Type::DS)), ValidationStatus::Insecure);
+    assert_eq!(check(&nsec3s, "sub.example.", Denial::NoData(Type::DS)), ValidationStatus::Bogus(DnssecError::TypeExistsError(Type::DS)));
+    assert_eq!(check(&nsec3s, "y.example.", Denial::NoData(Type::A)), ValidationStatus::Secure);
+}
+
+#[test]
+fn test_resign_zone() {
+    let old = signer(ECDSAP256SHA256, Some(ECDSAP256SHA256));
+    let signed = old.sign_zone(&records(UNSIGNED)).ok().unwrap();
+
+    // A later signer with the same keys only re-signs what changed
+    let mut new = ZoneSigner::new(name("example.").into_owned(), INCEPTION + 1000, EXPIRATION + 1000);
+    for key in old.keys() {
+        new.add_key(SigningKey::from_pkcs8(key.algorithm(), key.flags(), key.pkcs8()).ok().unwrap());
+    }
+    let changed = UNSIGNED.replace("192.0.2.3", "192.0.2.5");
+    let resigned = new.resign_zone(&signed, &records(&changed)).ok().unwrap();
+    verify_signed(&resigned, INCEPTION + 1000);
+    let rrsig = |signed: &[OwnedResourceRecord], owner: &str, rtype: Type| -> Vec<OwnedResourceRecord> {
+        answer(signed, owner, Type::RRSIG).into_iter().filter(|rr| covered(rr).0 == rtype).collect()
+    };
+    assert_ne!(rrsig(&signed, "www.example.", Type::A), rrsig(&resigned, "www.example.", Type::A));
+    for &(owner, rtype) in [("example.", Type::SOA), ("example.", Type::DNSKEY), ("ns1.example.", Type::A), ("www.example.", Type::NSEC)].iter() {
+        assert_eq!(rrsig(&signed, owner, rtype), rrsig(&resigned, owner, rtype));
+    }
+
+    // Adding a name changes the NSEC before it
+    let added = format!("{}new.example. 3600 IN A 192.0.2.6\n", changed);
+    let resigned = new.resign_zone(&resigned, &records(&added)).ok().unwrap();
+    verify_signed(&resigned, INCEPTION + 1000);
+    assert_eq!(rrsig(&resigned, "new.example.", Type::NSEC).len(), 1);
+    assert_ne!(rrsig(&signed, "insecure.example.", Type::NSEC), rrsig(&resigned, "insecure.example.", Type::NSEC));
+    assert_eq!(rrsig(&signed, "ns1.example.", Type::NSEC), rrsig(&resigned, "ns1.example.", Type::NSEC));
+
+    // RRSIGs about to expire are replaced
+    new.set_refresh(EXPIRATION - INCEPTION);
+    let refreshed = new.resign_zone(&resigned, &records(&added)).ok().unwrap();
+    assert_ne!(rrsig(&resigned, "example.", Type::SOA), rrsig(&refreshed, "example.", Type::SOA));
+    // As are those by keys no longer in use
+    let mut rolled = ZoneSigner::new(name("example.").into_owned(), INCEPTION, EXPIRATION);
+    rolled.add_key(SigningKey::generate(ECDSAP256SHA256, ZONE_KEY | SECURE_ENTRY_POINT).ok().unwrap());
+    let tag = rolled.keys()[0].key_tag();
+    let rolled = rolled.resign_zone(&resigned, &records(&added)).ok().unwrap();
+    assert!(verify_signed(&rolled, INCEPTION + 1000).iter().all(|s| s.2 == tag));
+}
+
+#[test]
+fn test_sign_zone_jitter() {
+    let mut signer = signer(15, None);
+    signer.set_jitter(86400);
+    let signed = signer.sign_zone(&records(UNSIGNED)).ok().unwrap();
+    verify_signed(&signed, INCEPTION);
+    let expirations = signed.iter().filter(|rr| rr.rtype == Type::RRSIG).map(|rr| match rr.rdata().ok().unwrap() {
+        RData::RRSIG { expiration, .. } => expiration,
+        _ => unreachable!(),
+    }).collect::<Vec<u32>>();
+    assert!(expirations.iter().all(|&e| e > EXPIRATION - 86400 && e <= EXPIRATION));
+    assert!(expirations.iter().any(|&e| e != expirations[0]));
+    // The jitter is the same each time
+    assert_eq!(signer.sign_zone(&records(UNSIGNED)).ok().unwrap(), signed);
+}
+
+#[test]
+fn test_signer_errors() {
+    let mut signer = ZoneSigner::new(name("example.").into_owned(), INCEPTION, EXPIRATION);
+    assert_eq!(signer.sign_zone(&records(UNSIGNED)), Err(DnssecError::NoSigningKeyError));
+    signer.add_key(SigningKey::generate(15, ZONE_KEY).ok().unwrap());
+    assert_eq!(signer.sign_zone(&records("www.example.com. 3600 IN A 192.0.2.1")), Err(DnssecError::OutOfZoneError));
+    assert_eq!(signer.sign_zone(&records("www.example. 3600 IN A 192.0.2.1")), Err(DnssecError::MissingRRsetError(Type::SOA)));
+
+    assert_eq!(SigningKey::generate(8, ZONE_KEY).err(), Some(DnssecError::UnsupportedAlgorithmError(8)));
+    assert_eq!(SigningKey::from_pkcs8(15, ZONE_KEY, &[0x30, 0x00]).err(), Some(DnssecError::PrivateKeyError));
+    let p256 = SigningKey::generate(ECDSAP256SHA256, ZONE_KEY).ok().unwrap();
+    assert_eq!(SigningKey::from_pkcs8(15, ZONE_KEY, p256.pkcs8()).err(), Some(DnssecError::PrivateKeyError));
+    assert_eq!(p256.public_key().len(), 64);
+}
+
+fn sig0_message(id: u16, flags: u16) -> Vec<u8> {
+    let mut msg = Message::new(id, flags);
+    msg.questions.push(Question { qname: name("example."), qtype: Type::SOA, qclass: Class::IN });
+    write_dns_message(&msg).ok().unwrap()
+}
+
+#[test]
+fn test_sig0() {
+    let now = INCEPTION + 60;
+    for alg in [ED25519, ECDSAP256SHA256].iter() {
+        let key = SigningKey::generate(*alg, 0x0200).ok().unwrap();
+        let key_rr = ResourceRecord::new(name("Client.Example."), Type::KEY, Class::IN, 300, write_dns_rdata(&key.key()).ok().unwrap());
+        let keys = vec![key_rr.clone()];
+
+        let request = sig0_message(1, 0x2800);
+        let signed = sign_sig0(&request, &key, &name("client.example."), INCEPTION, INCEPTION + 300, None).ok().unwrap();
+        assert_eq!(verify_sig0(&signed, &keys, now, None), Ok(()));
+
+        let msg = read_dns_message(&signed).ok().unwrap();
+        assert_eq!(msg.additionals.len(), 1);
+        assert_eq!((msg.additionals[0].rtype, msg.additionals[0].rclass, msg.additionals[0].rttl), (Type::SIG, Class::ANY, 0));
+        match msg.additionals[0].rdata().ok().unwrap() {
+            RData::SIG { type_covered, key_tag, .. } => assert_eq!((type_covered, key_tag), (Type::from(0), key.key_tag())),
+            r => panic!("unexpected RDATA {:?}", r),
+        }
+
+        let mut tampered = signed.clone();
+        tampered[13] ^= 0x01;
+        assert_eq!(verify_sig0(&tampered, &keys, now, None), Err(DnssecError::BadSignatureError));
+        assert_eq!(verify_sig0(&signed, &keys, INCEPTION + 301, None), Err(DnssecError::ExpiredError(INCEPTION + 300)));
+        assert_eq!(verify_sig0(&signed, &keys, INCEPTION - 1, None), Err(DnssecError::NotYetValidError(INCEPTION)));
+        assert_eq!(verify_sig0(&request, &keys, now, None), Err(DnssecError::MissingSignatureError(Type::SIG)));
+
+        // Responses cover the request they answer
+        let response = sig0_message(1, 0xA800);
+        let signed_response = sign_sig0(&response, &key, &name("client.example."), INCEPTION, INCEPTION + 300, Some(&signed)).ok().unwrap();
+        assert_eq!(verify_sig0(&signed_response, &keys, now, Some(&signed)), Ok(()));
+        assert_eq!(verify_sig0(&signed_response, &keys, now, None), Err(DnssecError::BadSignatureError));
+    }
+
+    let key = SigningKey::generate(ED25519, 0x0200).ok().unwrap();
+    let signed = sign_sig0(&sig0_message(2, 0x2800), &key, &name("client.example."), INCEPTION, EXPIRATION, None).ok().unwrap();
+    let other = SigningKey::generate(ED25519, 0x0200).ok().unwrap();
+    let key_rr = |owner: &'static str, k: &SigningKey| {
+        ResourceRecord::new(name(owner), Type::KEY, Class::IN, 300, write_dns_rdata(&k.key()).ok().unwrap())
+    };
+    assert_eq!(verify_sig0(&signed, &[key_rr("other.example.", &key)], INCEPTION, None), Err(DnssecError::SignerMismatchError));
+    let tag = key.key_tag();
+    assert_eq!(verify_sig0(&signed, &[key_rr("client.example.", &other)], INCEPTION, None),
+               if other.key_tag() == tag { Err(DnssecError::BadSignatureError) } else { Err(DnssecError::KeyTagMismatchError(tag)) });
+    // The signing key is found among several
+    assert_eq!(verify_sig0(&signed, &[key_rr("client.example.", &other), key_rr("client.example.", &key)], INCEPTION, None), Ok(()));
+
+    // Keys flagged as holding no key are refused
+    let no_key = SigningKey::from_pkcs8(ED25519, 0xC200, key.pkcs8()).ok().unwrap();
+    let signed = sign_sig0(&sig0_message(3, 0x2800), &no_key, &name("client.example."), INCEPTION, EXPIRATION, None).ok().unwrap();
+    assert_eq!(verify_sig0(&signed, &[key_rr("client.example.", &no_key)], INCEPTION, None), Err(DnssecError::InvalidKeyError));
+
+    // The SIG(0) must be the last record
+    let mut misplaced = sign_sig0(&sig0_message(4, 0x2800), &key, &name("client.example."), INCEPTION, EXPIRATION, None).ok().unwrap();
+    misplaced[7] = 1;
+    misplaced[11] = 0;
+    assert_eq!(verify_sig0(&misplaced, &[key_rr("client.example.", &key)], INCEPTION, None), Err(DnssecError::TransactionSignatureError));
+}
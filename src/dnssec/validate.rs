@@ -0,0 +1,170 @@
+use super::{Type,Name,Label,RData,ResourceRecord};
+use super::algorithm::{verify_signature,is_supported,ZONE_KEY,DNSKEY_PROTOCOL};
+use super::errors::DnssecError;
+use super::super::encoding::serial_cmp;
+use super::super::writer::{MessageWriter,write_dns_rdata_canonical,_write_be_u16,_write_be_u32};
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// The value of the RRSIG labels field for `name`: its number of
+/// labels, not counting the root or a leading wildcard label, as per
+/// [RFC4034§3.1.3](https://tools.ietf.org/html/rfc4034#section-3.1.3).
+pub fn rrsig_labels(name: &Name) -> u8 {
+    match name.labels.first() {
+        Some(l) if l.as_bytes() == b"*" => (name.labels.len() - 1) as u8,
+        _ => name.labels.len() as u8,
+    }
+}
+
+/// Write `name` in canonical form: uncompressed and lowercase.
+pub fn write_canonical_name(buf: &mut Vec<u8>, name: &Name) -> Result<(), DnssecError> {
+    let mut w = MessageWriter::new();
+    w.set_canonical(true);
+    w.write_name(name)?;
+    buf.extend_from_slice(&w.into_bytes());
+    Ok(())
+}
+
+/// The owner name an RRset was signed under: the owner itself, or the
+/// wildcard it was expanded from if the RRSIG has fewer labels, as per
+/// [RFC4035§5.3.2](https://tools.ietf.org/html/rfc4035#section-5.3.2).
+fn signed_owner<'n>(owner: &Name<'n>, labels: u8) -> Result<Name<'n>, DnssecError> {
+    let count = owner.labels.len();
+    let labels = labels as usize;
+    if labels > count {
+        return Err(DnssecError::LabelsError(labels as u8));
+    }
+    if labels == count {
+        return Ok(owner.clone());
+    }
+    let mut wildcard = vec![Label::from_slice(b"*")?];
+    wildcard.extend(owner.labels[count - labels..].iter().cloned());
+    Ok(Name { labels: wildcard })
+}
+
+/// Build the data covered by an RRSIG, as per
+/// [RFC4034§3.1.8.1](https://tools.ietf.org/html/rfc4034#section-3.1.8.1):
+/// the RRSIG RDATA without its signature, followed by every record of
+/// the RRset in the canonical form and order of
+/// [RFC4034§6](https://tools.ietf.org/html/rfc4034#section-6).
+///
+/// Owner names are lowercased and expanded back to the wildcard named
+/// by the labels field, TTLs are replaced by the original TTL, and
+/// duplicate records are dropped. The RRset is not otherwise checked.
+pub fn signed_data(rrset: &[ResourceRecord], rrsig: &RData) -> Result<Vec<u8>, DnssecError> {
+    let first = rrset.first().ok_or(DnssecError::EmptyRRsetError)?;
+    let (labels, original_ttl, unsigned) = match *rrsig {
+        RData::RRSIG { type_covered, algorithm, labels, original_ttl, expiration, inception, key_tag, ref signer_name, .. } => (labels, original_ttl, RData::RRSIG {
+            type_covered,
+            algorithm,
+            labels,
+            original_ttl,
+            expiration,
+            inception,
+            key_tag,
+            signer_name: signer_name.clone(),
+            signature: Cow::Borrowed(&[]),
+        }),
+        _ => return Err(DnssecError::RecordTypeError(Type::RRSIG)),
+    };
+    let mut data = write_dns_rdata_canonical(&unsigned)?;
+
+    // Every record shares the owner, type and class, so only the RDATA
+    // decides the order
+    let mut prefix = Vec::new();
+    write_canonical_name(&mut prefix, &signed_owner(&first.rname, labels)?)?;
+    _write_be_u16(&mut prefix, first.rtype.to_u16());
+    _write_be_u16(&mut prefix, first.rclass.to_u16());
+    _write_be_u32(&mut prefix, original_ttl);
+
+    let mut rdatas = rrset.iter()
+        .map(|rr| Ok(write_dns_rdata_canonical(&rr.rdata()?)?))
+        .collect::<Result<Vec<Vec<u8>>, DnssecError>>()?;
+    rdatas.sort();
+    rdatas.dedup();
+    for rdata in rdatas.iter() {
+        data.extend_from_slice(&prefix);
+        _write_be_u16(&mut data, rdata.len() as u16);
+        data.extend_from_slice(rdata);
+    }
+    Ok(data)
+}
+
+/// Validate the signature of an RRset with one of its RRSIGs and the
+/// DNSKEY it names, at time `now` in seconds since the epoch modulo
+/// 2^32.
+///
+/// This performs the checks of
+/// [RFC4035§5.3](https://tools.ietf.org/html/rfc4035#section-5.3): the
+/// records must form an RRset covered by the RRSIG, the DNSKEY must be
+/// a zone key of the signer matching the RRSIG's algorithm and key
+/// tag, the RRSIG must be within its validity period, and the signature
+/// must verify over the canonical RRset. The first failed check is
+/// returned.
+pub fn verify_rrsig(rrset: &[ResourceRecord], rrsig: &ResourceRecord, dnskey: &ResourceRecord, now: u32) -> Result<(), DnssecError> {
+    let first = rrset.first().ok_or(DnssecError::EmptyRRsetError)?;
+    if rrset.iter().any(|rr| rr.rname != first.rname || rr.rtype != first.rtype || rr.rclass != first.rclass) {
+        return Err(DnssecError::RRsetMismatchError);
+    }
+    if rrsig.rtype != Type::RRSIG {
+        return Err(DnssecError::RecordTypeError(rrsig.rtype));
+    }
+    if dnskey.rtype != Type::DNSKEY {
+        return Err(DnssecError::RecordTypeError(dnskey.rtype));
+    }
+
+    let sig = rrsig.rdata()?;
+    let (type_covered, algorithm, labels, expiration, inception, key_tag, signer_name, signature) = match sig {
+        RData::RRSIG { type_covered, algorithm, labels, expiration, inception, key_tag, ref signer_name, ref signature, .. } =>
+            (type_covered, algorithm, labels, expiration, inception, key_tag, signer_name, signature),
+        _ => return Err(DnssecError::RecordTypeError(rrsig.rtype)),
+    };
+    let key = dnskey.rdata()?;
+    let (flags, protocol, key_algorithm, public_key) = match key {
+        RData::DNSKEY { flags, protocol, algorithm, ref public_key } => (flags, protocol, algorithm, public_key),
+        _ => return Err(DnssecError::RecordTypeError(dnskey.rtype)),
+    };
+
+    if type_covered != first.rtype {
+        return Err(DnssecError::TypeCoveredError(type_covered));
+    }
+    if rrsig.rname != first.rname || rrsig.rclass != first.rclass {
+        return Err(DnssecError::OwnerMismatchError);
+    }
+    if labels > rrsig_labels(&first.rname) {
+        return Err(DnssecError::LabelsError(labels));
+    }
+    if *signer_name != dnskey.rname || dnskey.rclass != first.rclass {
+        return Err(DnssecError::SignerMismatchError);
+    }
+    if !first.rname.is_subdomain_of(signer_name) {
+        return Err(DnssecError::SignerScopeError);
+    }
+    if algorithm != key_algorithm {
+        return Err(DnssecError::AlgorithmMismatchError(algorithm));
+    }
+    if Some(key_tag) != key.key_tag() {
+        return Err(DnssecError::KeyTagMismatchError(key_tag));
+    }
+    if protocol != DNSKEY_PROTOCOL {
+        return Err(DnssecError::ProtocolError(protocol));
+    }
+    if flags & ZONE_KEY == 0 {
+        return Err(DnssecError::NotZoneKeyError);
+    }
+    match serial_cmp(now, expiration) {
+        Some(Ordering::Greater) | None => return Err(DnssecError::ExpiredError(expiration)),
+        _ => {},
+    }
+    match serial_cmp(now, inception) {
+        Some(Ordering::Less) | None => return Err(DnssecError::NotYetValidError(inception)),
+        _ => {},
+    }
+    if !is_supported(algorithm) {
+        return Err(DnssecError::UnsupportedAlgorithmError(algorithm));
+    }
+
+    let data = signed_data(rrset, &sig)?;
+    verify_signature(algorithm, public_key, &data, signature)
+}
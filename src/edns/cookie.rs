@@ -0,0 +1,201 @@
+use super::{Edns,EdnsOption,EDNS0OptionCode};
+use super::errors::EdnsError;
+
+use siphasher::sip::SipHasher24;
+
+use std::hash::Hasher;
+use std::net::IpAddr;
+
+/// Length of a client cookie.
+pub const CLIENT_COOKIE_LENGTH: usize = 8;
+/// Length of a server cookie made by `ServerCookies`.
+pub const SERVER_COOKIE_LENGTH: usize = 16;
+/// Version of the server cookie construction of
+/// [RFC9018§4](https://tools.ietf.org/html/rfc9018#section-4).
+pub const SERVER_COOKIE_VERSION: u8 = 1;
+
+// Ages of a server cookie, in seconds, as per RFC9018§4.3
+const COOKIE_RENEW_AGE: i32 = 1800;
+const COOKIE_MAX_AGE: i32 = 3600;
+const COOKIE_MAX_SKEW: i32 = 300;
+
+/// The COOKIE option of
+/// [RFC7873§4](https://tools.ietf.org/html/rfc7873#section-4): a client
+/// cookie, followed by a server cookie of 8 to 32 octets once the client
+/// has learned one.
+#[derive(PartialEq,Eq,Debug,Clone)]
+pub struct Cookie {
+    pub client: [u8; CLIENT_COOKIE_LENGTH],
+    pub server: Option<Vec<u8>>,
+}
+
+/// The result of checking the server cookie of a request.
+#[derive(PartialEq,Eq,Debug,Clone,Copy)]
+pub enum CookieStatus {
+    /// The request has only a client cookie.
+    Missing,
+    /// The server cookie was not made by this server for this client,
+    /// or is too old or too far in the future.
+    Bad,
+    /// The server cookie is valid.
+    Good,
+    /// The server cookie is valid but old enough that a fresh one
+    /// should be returned.
+    Renew,
+}
+
+fn hash(key: &[u8; 16], parts: &[&[u8]]) -> [u8; 8] {
+    let mut h = SipHasher24::new_with_key(key);
+    for p in parts.iter() {
+        h.write(p);
+    }
+    h.finish().to_le_bytes()
+}
+
+fn ip_octets(ip: &IpAddr) -> Vec<u8> {
+    match *ip {
+        IpAddr::V4(a) => a.octets().to_vec(),
+        IpAddr::V6(a) => a.octets().to_vec(),
+    }
+}
+
+/// Generate the client cookie to send to `server_ip`.
+///
+/// The cookie is SipHash-2-4 of the server address keyed with the
+/// client secret. As recommended by
+/// [RFC9018§3](https://tools.ietf.org/html/rfc9018#section-3) the client
+/// address is left out; clients should change the secret whenever
+/// their own address changes so that they cannot be tracked.
+pub fn client_cookie(secret: &[u8; 16], server_ip: &IpAddr) -> [u8; CLIENT_COOKIE_LENGTH] {
+    hash(secret, &[&ip_octets(server_ip)])
+}
+
+impl Cookie {
+    /// Create the option for a client that has not yet learned a server
+    /// cookie.
+    pub fn new(client: [u8; CLIENT_COOKIE_LENGTH]) -> Cookie {
+        Cookie {
+            client,
+            server: None,
+        }
+    }
+
+    /// Decode the option data.
+    pub fn from_option(option: &EdnsOption) -> Result<Cookie, EdnsError> {
+        if option.code != EDNS0OptionCode::Cookie {
+            return Err(EdnsError::OptionCodeError(option.code.to_u16()));
+        }
+        let data = &option.data[..];
+        let server = match data.len() {
+            8 => None,
+            16..=40 => Some(data[8..].to_vec()),
+            x => return Err(EdnsError::OptionLengthError(x)),
+        };
+        let mut client = [0u8; CLIENT_COOKIE_LENGTH];
+        client.copy_from_slice(&data[..8]);
+        Ok(Cookie {
+            client,
+            server,
+        })
+    }
+
+    /// Encode the option.
+    pub fn to_option(&self) -> Result<EdnsOption<'static>, EdnsError> {
+        let mut data = self.client.to_vec();
+        if let Some(ref server) = self.server {
+            if server.len() < 8 || server.len() > 32 {
+                return Err(EdnsError::OptionLengthError(8 + server.len()));
+            }
+            data.extend_from_slice(server);
+        }
+        Ok(EdnsOption::new(EDNS0OptionCode::Cookie, data))
+    }
+}
+
+/// Creates and checks server cookies with the interoperable SipHash-2-4
+/// construction of
+/// [RFC9018§4](https://tools.ietf.org/html/rfc9018#section-4), so that
+/// the servers of an anycast set sharing a secret accept each other's
+/// cookies.
+///
+/// Timestamps are seconds since the epoch modulo 2^32 and are compared
+/// using serial number arithmetic.
+pub struct ServerCookies {
+    secret: [u8; 16],
+}
+
+impl ServerCookies {
+    pub fn new(secret: [u8; 16]) -> ServerCookies {
+        ServerCookies {
+            secret,
+        }
+    }
+
+    /// Create the server cookie for `client` at `client_ip`.
+    pub fn create(&self, client: &[u8; CLIENT_COOKIE_LENGTH], client_ip: &IpAddr, timestamp: u32) -> [u8; SERVER_COOKIE_LENGTH] {
+        let mut cookie = [0u8; SERVER_COOKIE_LENGTH];
+        cookie[0] = SERVER_COOKIE_VERSION;
+        cookie[4..8].copy_from_slice(&timestamp.to_be_bytes());
+        let h = hash(&self.secret, &[client, &cookie[..8], &ip_octets(client_ip)]);
+        cookie[8..].copy_from_slice(&h);
+        cookie
+    }
+
+    /// Check the server cookie of a request from `client_ip` at time
+    /// `now`.
+    pub fn check(&self, cookie: &Cookie, client_ip: &IpAddr, now: u32) -> CookieStatus {
+        let server = match cookie.server {
+            Some(ref s) => s,
+            None => return CookieStatus::Missing,
+        };
+        if server.len() != SERVER_COOKIE_LENGTH || server[0] != SERVER_COOKIE_VERSION {
+            return CookieStatus::Bad;
+        }
+        let h = hash(&self.secret, &[&cookie.client, &server[..8], &ip_octets(client_ip)]);
+        if h[..] != server[8..] {
+            return CookieStatus::Bad;
+        }
+        let timestamp = u32::from_be_bytes([server[4], server[5], server[6], server[7]]);
+        let age = now.wrapping_sub(timestamp) as i32;
+        if !(-COOKIE_MAX_SKEW..=COOKIE_MAX_AGE).contains(&age) {
+            CookieStatus::Bad
+        } else if age > COOKIE_RENEW_AGE {
+            CookieStatus::Renew
+        } else {
+            CookieStatus::Good
+        }
+    }
+
+    /// Check the cookie of a request and build the cookie to return in
+    /// the response: the client cookie echoed with the request's server
+    /// cookie if it is good, or a fresh one otherwise.
+    ///
+    /// A server that requires cookies should answer a request whose
+    /// status is `Bad` or `Missing` with `RCode::BADCOOKIE` and the
+    /// returned cookie, as per
+    /// [RFC7873§5.2](https://tools.ietf.org/html/rfc7873#section-5.2).
+    pub fn respond(&self, request: &Cookie, client_ip: &IpAddr, now: u32) -> (CookieStatus, Cookie) {
+        let status = self.check(request, client_ip, now);
+        let server = match status {
+            CookieStatus::Good => request.server.clone(),
+            _ => Some(self.create(&request.client, client_ip, now).to_vec()),
+        };
+        (status, Cookie {
+            client: request.client,
+            server,
+        })
+    }
+}
+
+impl<'e> Edns<'e> {
+    /// Decode the first COOKIE option, if there is one.
+    pub fn cookie(&self) -> Result<Option<Cookie>, EdnsError> {
+        self.option(EDNS0OptionCode::Cookie).map(Cookie::from_option).transpose()
+    }
+
+    /// Replace any COOKIE option with `cookie`.
+    pub fn set_cookie(&mut self, cookie: &Cookie) -> Result<(), EdnsError> {
+        self.set_option(cookie.to_option()?);
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use super::{Edns,EdnsOption,EDNS0OptionCode,ExtendedErrorCode};
+use super::errors::EdnsError;
+use super::super::parser::{_read_be_u16};
+use super::super::writer::{_write_be_u16};
+
+use std::fmt;
+use std::str;
+
+/// The Extended DNS Error option of
+/// [RFC8914§2](https://tools.ietf.org/html/rfc8914#section-2), giving
+/// the reason for a response alongside its RCODE.
+#[derive(PartialEq,Eq,Debug,Clone)]
+pub struct ExtendedError {
+    pub code: ExtendedErrorCode,
+    /// Free-form text for a human reader, empty if none was given.
+    pub text: String,
+}
+
+impl ExtendedError {
+    pub fn new(code: ExtendedErrorCode, text: &str) -> ExtendedError {
+        ExtendedError {
+            code,
+            text: text.to_string(),
+        }
+    }
+
+    /// Decode the option data. The extra text must be UTF-8.
+    pub fn from_option(option: &EdnsOption) -> Result<ExtendedError, EdnsError> {
+        if option.code != EDNS0OptionCode::ExtendedDnsError {
+            return Err(EdnsError::OptionCodeError(option.code.to_u16()));
+        }
+        let data = &option.data[..];
+        if data.len() < 2 {
+            return Err(EdnsError::OptionLengthError(data.len()));
+        }
+        let mut i = 0;
+        let code = ExtendedErrorCode::from(_read_be_u16(data, &mut i));
+        let text = str::from_utf8(&data[2..]).map_err(|_| EdnsError::TextEncodingError)?;
+        Ok(ExtendedError::new(code, text))
+    }
+
+    /// Encode the option.
+    pub fn to_option(&self) -> Result<EdnsOption<'static>, EdnsError> {
+        if self.text.len() > 65533 {
+            return Err(EdnsError::OptionLengthError(2 + self.text.len()));
+        }
+        let mut data = Vec::with_capacity(2 + self.text.len());
+        _write_be_u16(&mut data, self.code.to_u16());
+        data.extend_from_slice(self.text.as_bytes());
+        Ok(EdnsOption::new(EDNS0OptionCode::ExtendedDnsError, data))
+    }
+}
+
+impl fmt::Display for ExtendedError {
+    /// Formats an error as its code's description and number, followed
+    /// by the extra text if there is any.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.code, self.code.to_u16())?;
+        if !self.text.is_empty() {
+            write!(f, ": {}", self.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'e> Edns<'e> {
+    /// Decode every Extended DNS Error option, in order. A response may
+    /// carry more than one.
+    pub fn extended_errors(&self) -> Result<Vec<ExtendedError>, EdnsError> {
+        self.options.iter()
+            .filter(|o| o.code == EDNS0OptionCode::ExtendedDnsError)
+            .map(ExtendedError::from_option)
+            .collect()
+    }
+
+    /// Append an Extended DNS Error option.
+    pub fn add_extended_error(&mut self, error: &ExtendedError) -> Result<(), EdnsError> {
+        let option = error.to_option()?;
+        self.options.push(option);
+        Ok(())
+    }
+}
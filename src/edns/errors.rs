@@ -0,0 +1,51 @@
+use std::fmt;
+use std::error;
+use super::ReadError;
+
+/// An error decoding or encoding the data of an EDNS option.
+#[derive(PartialEq,Copy,Clone,Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum EdnsError {
+    OptionCodeError(u16),
+    OptionLengthError(usize),
+    AddressFamilyError(u16),
+    PrefixLengthError(u8),
+    AddressMaskError,
+    TextEncodingError,
+    InvalidNameError(ReadError),
+}
+
+impl error::Error for EdnsError {
+    fn description(&self) -> &str {
+        match *self {
+            EdnsError::OptionCodeError(_) => "Option has the wrong code",
+            EdnsError::OptionLengthError(_) => "Option data has an invalid length",
+            EdnsError::AddressFamilyError(_) => "Unsupported address family",
+            EdnsError::PrefixLengthError(_) => "Prefix length too long for address family",
+            EdnsError::AddressMaskError => "Address has bits set beyond the source prefix",
+            EdnsError::TextEncodingError => "Option text is not valid UTF-8",
+            EdnsError::InvalidNameError(_) => "Option has an invalid name",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match *self {
+            EdnsError::InvalidNameError(ref err) => Some(err as &dyn error::Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EdnsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EdnsError::OptionCodeError(x) => write!(f, "Option has the wrong code: {}", x),
+            EdnsError::OptionLengthError(x) => write!(f, "Option data has an invalid length: {}", x),
+            EdnsError::AddressFamilyError(x) => write!(f, "Unsupported address family: {}", x),
+            EdnsError::PrefixLengthError(x) => write!(f, "Prefix length too long for address family: {}", x),
+            EdnsError::AddressMaskError => write!(f, "Address has bits set beyond the source prefix"),
+            EdnsError::TextEncodingError => write!(f, "Option text is not valid UTF-8"),
+            EdnsError::InvalidNameError(x) => write!(f, "Option has an invalid name: {}", x),
+        }
+    }
+}
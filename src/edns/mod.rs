@@ -0,0 +1,24 @@
+pub use super::iana::types::Type;
+pub use super::iana::classes::Class;
+pub use super::iana::edns0codes::EDNS0OptionCode;
+pub use super::iana::edecodes::ExtendedErrorCode;
+pub use super::parser::{Message,Name,OwnedName,ResourceRecord,OwnedResourceRecord,ReadError};
+pub use super::writer::WriteError;
+
+pub use super::edns::cookie::{Cookie,CookieStatus,ServerCookies,client_cookie,CLIENT_COOKIE_LENGTH,SERVER_COOKIE_LENGTH,SERVER_COOKIE_VERSION};
+pub use super::edns::ede::ExtendedError;
+pub use super::edns::errors::EdnsError;
+pub use super::edns::opt::{Edns,EdnsOption,OwnedEdns,DEFAULT_UDP_PAYLOAD_SIZE};
+pub use super::edns::options::{Nsid,TcpKeepalive,Chain,KeyTags};
+pub use super::edns::padding::{Padding,PaddingPolicy,QUERY_BLOCK_SIZE,RESPONSE_BLOCK_SIZE};
+pub use super::edns::subnet::{ClientSubnet,SubnetCacheKey,FAMILY_IPV4,FAMILY_IPV6};
+
+mod cookie;
+mod ede;
+mod errors;
+mod opt;
+mod options;
+mod padding;
+mod subnet;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,162 @@
+use super::{Type,Class,Name,ResourceRecord,OwnedResourceRecord,EDNS0OptionCode,ReadError,WriteError};
+use super::super::parser::{_read_be_u16};
+use super::super::writer::{_write_be_u16};
+
+use std::borrow::Cow;
+
+/// UDP payload size advertised by `Edns::new`, as recommended by DNS
+/// Flag Day 2020 to avoid IP fragmentation.
+pub const DEFAULT_UDP_PAYLOAD_SIZE: u16 = 1232;
+
+const DO: u16 = 0x8000;
+
+/// An `Edns` that does not borrow from a buffer.
+pub type OwnedEdns = Edns<'static>;
+
+/// A decoded view of an OPT pseudo-record, as per
+/// [RFC6891§6.1](https://tools.ietf.org/html/rfc6891#section-6.1).
+///
+/// The OPT record repurposes the CLASS field as the requestor's UDP
+/// payload size and the TTL field as the upper 8 bits of the extended
+/// RCODE, the EDNS version and flags. Its RDATA is a list of options.
+#[derive(PartialEq,Debug,Clone)]
+pub struct Edns<'e> {
+    pub udp_payload_size: u16,
+    /// The upper 8 bits of the 12-bit extended RCODE. The lower 4 bits
+    /// are in the message header.
+    pub extended_rcode: u8,
+    pub version: u8,
+    /// The DNSSEC OK flag of
+    /// [RFC3225§3](https://tools.ietf.org/html/rfc3225#section-3).
+    pub dnssec_ok: bool,
+    /// The remaining 15 flag bits, which must be zero when sending.
+    pub z: u16,
+    pub options: Vec<EdnsOption<'e>>,
+}
+
+/// A single EDNS option, kept as raw octets.
+#[derive(PartialEq,Debug,Clone)]
+pub struct EdnsOption<'e> {
+    pub code: EDNS0OptionCode,
+    pub data: Cow<'e, [u8]>,
+}
+
+impl<'e> EdnsOption<'e> {
+    pub fn new<D: Into<Cow<'e, [u8]>>>(code: EDNS0OptionCode, data: D) -> EdnsOption<'e> {
+        EdnsOption {
+            code,
+            data: data.into(),
+        }
+    }
+
+    /// Convert this option into one that owns its data.
+    pub fn into_owned(self) -> EdnsOption<'static> {
+        EdnsOption {
+            code: self.code,
+            data: Cow::Owned(self.data.into_owned()),
+        }
+    }
+}
+
+impl<'e> Edns<'e> {
+    /// Create an EDNS version 0 view with the default UDP payload size
+    /// and no flags or options.
+    pub fn new() -> Edns<'e> {
+        Edns {
+            udp_payload_size: DEFAULT_UDP_PAYLOAD_SIZE,
+            extended_rcode: 0,
+            version: 0,
+            dnssec_ok: false,
+            z: 0,
+            options: Vec::new(),
+        }
+    }
+
+    /// Decode an OPT record. Options borrow from the record's RDATA.
+    pub fn from_record(rr: &'e ResourceRecord) -> Result<Edns<'e>, ReadError> {
+        if !rr.rname.labels.is_empty() {
+            return Err(ReadError::OptOwnerError);
+        }
+        let ttl = rr.rttl as u32;
+        let flags = ttl as u16;
+        let mut edns = Edns {
+            udp_payload_size: rr.rclass.to_u16(),
+            extended_rcode: (ttl >> 24) as u8,
+            version: (ttl >> 16) as u8,
+            dnssec_ok: flags & DO != 0,
+            z: flags & !DO,
+            options: Vec::new(),
+        };
+
+        let buf = &rr.context[..];
+        let end = rr.rdata + rr.rdlen as usize;
+        if end > buf.len() {
+            return Err(ReadError::IndexOutOfRangeError(end, buf.len()));
+        }
+        let mut i = rr.rdata;
+        while i < end {
+            if i + 4 > end {
+                return Err(ReadError::IndexOutOfRangeError(i + 4, end));
+            }
+            let code = EDNS0OptionCode::from(_read_be_u16(buf, &mut i));
+            let len = _read_be_u16(buf, &mut i) as usize;
+            if i + len > end {
+                return Err(ReadError::IndexOutOfRangeError(i + len, end));
+            }
+            edns.options.push(EdnsOption::new(code, &buf[i..i+len]));
+            i += len;
+        }
+        Ok(edns)
+    }
+
+    /// Encode this view as an OPT record.
+    pub fn to_record(&self) -> Result<OwnedResourceRecord, WriteError> {
+        let mut rdata = Vec::new();
+        for o in self.options.iter() {
+            if o.data.len() > 65535 {
+                return Err(WriteError::RDataTooLongError(o.data.len()));
+            }
+            _write_be_u16(&mut rdata, o.code.to_u16());
+            _write_be_u16(&mut rdata, o.data.len() as u16);
+            rdata.extend_from_slice(&o.data);
+        }
+        if rdata.len() > 65535 {
+            return Err(WriteError::RDataTooLongError(rdata.len()));
+        }
+        let mut flags = self.z & !DO;
+        if self.dnssec_ok {
+            flags |= DO;
+        }
+        let ttl = (self.extended_rcode as u32) << 24 | (self.version as u32) << 16 | flags as u32;
+        Ok(ResourceRecord::new(Name { labels: Vec::new() }, Type::OPT, Class::from(self.udp_payload_size), ttl as i32, rdata))
+    }
+
+    /// The first option with the given code, if any.
+    pub fn option(&self, code: EDNS0OptionCode) -> Option<&EdnsOption<'e>> {
+        self.options.iter().find(|o| o.code == code)
+    }
+
+    /// Replace any options with the same code as `option` with it.
+    pub fn set_option(&mut self, option: EdnsOption<'e>) {
+        self.options.retain(|o| o.code != option.code);
+        self.options.push(option);
+    }
+
+    /// Convert this view into one that owns its options.
+    pub fn into_owned(self) -> OwnedEdns {
+        Edns {
+            udp_payload_size: self.udp_payload_size,
+            extended_rcode: self.extended_rcode,
+            version: self.version,
+            dnssec_ok: self.dnssec_ok,
+            z: self.z,
+            options: self.options.into_iter().map(|o| o.into_owned()).collect(),
+        }
+    }
+}
+
+impl<'e> Default for Edns<'e> {
+    fn default() -> Edns<'e> {
+        Edns::new()
+    }
+}
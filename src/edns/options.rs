@@ -0,0 +1,153 @@
+use super::{Edns,EdnsOption,EDNS0OptionCode,OwnedName,ReadError};
+use super::errors::EdnsError;
+use super::super::parser::{_read_be_u16,read_dns_name};
+use super::super::writer::{_write_be_u16};
+
+/// The Name Server Identifier option of
+/// [RFC5001§2.3](https://tools.ietf.org/html/rfc5001#section-2.3).
+/// Queries carry an empty identifier to request one.
+#[derive(PartialEq,Eq,Debug,Clone)]
+pub struct Nsid {
+    pub id: Vec<u8>,
+}
+
+/// The edns-tcp-keepalive option of
+/// [RFC7828§3.1](https://tools.ietf.org/html/rfc7828#section-3.1).
+#[derive(PartialEq,Eq,Debug,Clone,Copy)]
+pub struct TcpKeepalive {
+    /// Idle timeout in units of 100 milliseconds, sent only by servers.
+    pub timeout: Option<u16>,
+}
+
+/// The CHAIN option of
+/// [RFC7901§4](https://tools.ietf.org/html/rfc7901#section-4), naming
+/// the closest trust point the client already has.
+#[derive(PartialEq,Debug,Clone)]
+pub struct Chain {
+    pub closest_trust_point: OwnedName,
+}
+
+/// The edns-key-tag option of
+/// [RFC8145§4](https://tools.ietf.org/html/rfc8145#section-4), listing
+/// the key tags of the trust anchors a resolver uses for a zone.
+#[derive(PartialEq,Eq,Debug,Clone)]
+pub struct KeyTags {
+    pub tags: Vec<u16>,
+}
+
+fn check_code(option: &EdnsOption, code: EDNS0OptionCode) -> Result<(), EdnsError> {
+    if option.code != code {
+        return Err(EdnsError::OptionCodeError(option.code.to_u16()));
+    }
+    Ok(())
+}
+
+impl Nsid {
+    pub fn from_option(option: &EdnsOption) -> Result<Nsid, EdnsError> {
+        check_code(option, EDNS0OptionCode::NSID)?;
+        Ok(Nsid { id: option.data.to_vec() })
+    }
+
+    pub fn to_option(&self) -> Result<EdnsOption<'static>, EdnsError> {
+        Ok(EdnsOption::new(EDNS0OptionCode::NSID, self.id.clone()))
+    }
+}
+
+impl TcpKeepalive {
+    pub fn from_option(option: &EdnsOption) -> Result<TcpKeepalive, EdnsError> {
+        check_code(option, EDNS0OptionCode::TcpKeepalive)?;
+        let timeout = match option.data.len() {
+            0 => None,
+            2 => Some(_read_be_u16(&option.data, &mut 0)),
+            x => return Err(EdnsError::OptionLengthError(x)),
+        };
+        Ok(TcpKeepalive { timeout })
+    }
+
+    pub fn to_option(&self) -> Result<EdnsOption<'static>, EdnsError> {
+        let mut data = Vec::new();
+        if let Some(t) = self.timeout {
+            _write_be_u16(&mut data, t);
+        }
+        Ok(EdnsOption::new(EDNS0OptionCode::TcpKeepalive, data))
+    }
+}
+
+impl Chain {
+    /// Decode the option data, which must be a single uncompressed,
+    /// fully qualified name.
+    pub fn from_option(option: &EdnsOption) -> Result<Chain, EdnsError> {
+        check_code(option, EDNS0OptionCode::Chain)?;
+        let name = read_dns_name(&option.data, &mut 0).map_err(EdnsError::InvalidNameError)?;
+        // A compressed name or trailing data leaves the lengths unequal
+        let len = name.labels.iter().map(|l| l.as_bytes().len() + 1).sum::<usize>() + 1;
+        if len != option.data.len() {
+            return Err(EdnsError::OptionLengthError(option.data.len()));
+        }
+        Ok(Chain { closest_trust_point: name.into_owned() })
+    }
+
+    pub fn to_option(&self) -> Result<EdnsOption<'static>, EdnsError> {
+        let mut data = Vec::new();
+        for l in self.closest_trust_point.labels.iter() {
+            data.push(l.as_bytes().len() as u8);
+            data.extend_from_slice(l.as_bytes());
+        }
+        data.push(0);
+        if data.len() > 255 {
+            return Err(EdnsError::InvalidNameError(ReadError::NameTooLongError(data.len())));
+        }
+        Ok(EdnsOption::new(EDNS0OptionCode::Chain, data))
+    }
+}
+
+impl KeyTags {
+    /// Decode the option data, which must hold at least one key tag.
+    pub fn from_option(option: &EdnsOption) -> Result<KeyTags, EdnsError> {
+        check_code(option, EDNS0OptionCode::KeyTag)?;
+        let data = &option.data[..];
+        if data.is_empty() || data.len() % 2 != 0 {
+            return Err(EdnsError::OptionLengthError(data.len()));
+        }
+        let mut i = 0;
+        let mut tags = Vec::with_capacity(data.len() / 2);
+        while i < data.len() {
+            tags.push(_read_be_u16(data, &mut i));
+        }
+        Ok(KeyTags { tags })
+    }
+
+    pub fn to_option(&self) -> Result<EdnsOption<'static>, EdnsError> {
+        if self.tags.is_empty() || self.tags.len() > 32767 {
+            return Err(EdnsError::OptionLengthError(self.tags.len() * 2));
+        }
+        let mut data = Vec::with_capacity(self.tags.len() * 2);
+        for t in self.tags.iter() {
+            _write_be_u16(&mut data, *t);
+        }
+        Ok(EdnsOption::new(EDNS0OptionCode::KeyTag, data))
+    }
+}
+
+impl<'e> Edns<'e> {
+    /// Decode the NSID option, if there is one.
+    pub fn nsid(&self) -> Result<Option<Nsid>, EdnsError> {
+        self.option(EDNS0OptionCode::NSID).map(Nsid::from_option).transpose()
+    }
+
+    /// Decode the edns-tcp-keepalive option, if there is one.
+    pub fn tcp_keepalive(&self) -> Result<Option<TcpKeepalive>, EdnsError> {
+        self.option(EDNS0OptionCode::TcpKeepalive).map(TcpKeepalive::from_option).transpose()
+    }
+
+    /// Decode the CHAIN option, if there is one.
+    pub fn chain(&self) -> Result<Option<Chain>, EdnsError> {
+        self.option(EDNS0OptionCode::Chain).map(Chain::from_option).transpose()
+    }
+
+    /// Decode the edns-key-tag option, if there is one.
+    pub fn key_tags(&self) -> Result<Option<KeyTags>, EdnsError> {
+        self.option(EDNS0OptionCode::KeyTag).map(KeyTags::from_option).transpose()
+    }
+}
+
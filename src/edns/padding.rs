@@ -0,0 +1,102 @@
+use super::{Edns,EdnsOption,EDNS0OptionCode,Message,WriteError};
+use super::errors::EdnsError;
+use super::super::writer::write_dns_message;
+
+/// Block size for padding queries recommended by
+/// [RFC8467§4.1](https://tools.ietf.org/html/rfc8467#section-4.1).
+pub const QUERY_BLOCK_SIZE: usize = 128;
+/// Block size for padding responses recommended by
+/// [RFC8467§4.1](https://tools.ietf.org/html/rfc8467#section-4.1).
+pub const RESPONSE_BLOCK_SIZE: usize = 468;
+
+/// The Padding option of
+/// [RFC7830§3](https://tools.ietf.org/html/rfc7830#section-3). Padding
+/// is written as zero octets, but any content is accepted.
+#[derive(PartialEq,Eq,Debug,Clone,Copy)]
+pub struct Padding {
+    pub length: usize,
+}
+
+impl Padding {
+    pub fn from_option(option: &EdnsOption) -> Result<Padding, EdnsError> {
+        if option.code != EDNS0OptionCode::Padding {
+            return Err(EdnsError::OptionCodeError(option.code.to_u16()));
+        }
+        Ok(Padding { length: option.data.len() })
+    }
+
+    pub fn to_option(&self) -> Result<EdnsOption<'static>, EdnsError> {
+        if self.length > 65535 {
+            return Err(EdnsError::OptionLengthError(self.length));
+        }
+        Ok(EdnsOption::new(EDNS0OptionCode::Padding, vec![0u8; self.length]))
+    }
+}
+
+/// The Block-Length Padding strategy of
+/// [RFC8467§4.1](https://tools.ietf.org/html/rfc8467#section-4.1):
+/// messages are padded so that their length is a multiple of the block
+/// size, hiding their exact size on encrypted transports.
+///
+/// Padding is meant for encrypted transports only. As per
+/// [RFC7830§4](https://tools.ietf.org/html/rfc7830#section-4) a server
+/// should pad a response only if the query was padded.
+#[derive(PartialEq,Eq,Debug,Clone,Copy)]
+pub struct PaddingPolicy {
+    pub block_size: usize,
+    /// Messages are never padded beyond this length, such as the UDP
+    /// payload size of the peer.
+    pub max_size: usize,
+}
+
+impl PaddingPolicy {
+    pub fn new(block_size: usize) -> PaddingPolicy {
+        PaddingPolicy {
+            block_size,
+            max_size: 65535,
+        }
+    }
+
+    /// The recommended policy for queries.
+    pub fn queries() -> PaddingPolicy {
+        PaddingPolicy::new(QUERY_BLOCK_SIZE)
+    }
+
+    /// The recommended policy for responses.
+    pub fn responses() -> PaddingPolicy {
+        PaddingPolicy::new(RESPONSE_BLOCK_SIZE)
+    }
+
+    /// Pad `msg`, replacing any Padding option it has and adding an OPT
+    /// record if it has none. The length is that of the message as
+    /// written by `write_dns_message`, with name compression.
+    pub fn pad(&self, msg: &mut Message) -> Result<(), WriteError> {
+        let mut edns = match msg.edns()? {
+            Some(e) => e.into_owned(),
+            None => Edns::new(),
+        };
+        edns.set_option(EdnsOption::new(EDNS0OptionCode::Padding, Vec::new()));
+        msg.set_edns(&edns)?;
+        if self.block_size <= 1 {
+            return Ok(());
+        }
+
+        let len = write_dns_message(msg)?.len();
+        let mut pad = (self.block_size - len % self.block_size) % self.block_size;
+        if len + pad > self.max_size {
+            pad = self.max_size.saturating_sub(len);
+        }
+        if pad > 0 {
+            edns.set_option(EdnsOption::new(EDNS0OptionCode::Padding, vec![0u8; pad]));
+            msg.set_edns(&edns)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'e> Edns<'e> {
+    /// Decode the Padding option, if there is one.
+    pub fn padding(&self) -> Result<Option<Padding>, EdnsError> {
+        self.option(EDNS0OptionCode::Padding).map(Padding::from_option).transpose()
+    }
+}
@@ -0,0 +1,218 @@
+use super::{Edns,EdnsOption,EDNS0OptionCode};
+use super::errors::EdnsError;
+use super::super::parser::{_read_be_u16};
+use super::super::writer::{_write_be_u16};
+
+use std::net::{IpAddr,Ipv4Addr,Ipv6Addr};
+
+/// Address family number for IPv4, from the IANA Address Family Numbers
+/// registry.
+pub const FAMILY_IPV4: u16 = 1;
+/// Address family number for IPv6.
+pub const FAMILY_IPV6: u16 = 2;
+
+/// The EDNS Client Subnet option of
+/// [RFC7871§6](https://tools.ietf.org/html/rfc7871#section-6).
+///
+/// `address` always has every bit beyond `source_prefix` cleared, so
+/// only the significant octets are sent on the wire.
+#[derive(PartialEq,Eq,Hash,Debug,Clone,Copy)]
+pub struct ClientSubnet {
+    pub source_prefix: u8,
+    pub scope_prefix: u8,
+    pub address: IpAddr,
+}
+
+/// The subnet a cached response applies to, as per
+/// [RFC7871§7.3.1](https://tools.ietf.org/html/rfc7871#section-7.3.1).
+#[derive(PartialEq,Eq,Hash,Debug,Clone,Copy)]
+pub struct SubnetCacheKey {
+    pub prefix: u8,
+    pub address: IpAddr,
+}
+
+fn family(address: &IpAddr) -> u16 {
+    match *address {
+        IpAddr::V4(_) => FAMILY_IPV4,
+        IpAddr::V6(_) => FAMILY_IPV6,
+    }
+}
+
+fn max_prefix(address: &IpAddr) -> u8 {
+    match *address {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn octets(address: &IpAddr) -> Vec<u8> {
+    match *address {
+        IpAddr::V4(a) => a.octets().to_vec(),
+        IpAddr::V6(a) => a.octets().to_vec(),
+    }
+}
+
+/// Clear every bit of `octets` beyond the first `prefix` bits.
+fn mask(octets: &mut [u8], prefix: u8) {
+    let prefix = prefix as usize;
+    for (i, o) in octets.iter_mut().enumerate() {
+        if i * 8 >= prefix {
+            *o = 0;
+        } else if (i + 1) * 8 > prefix {
+            *o &= 0xFFu8 << (8 - (prefix - i * 8));
+        }
+    }
+}
+
+fn masked(address: &IpAddr, prefix: u8) -> IpAddr {
+    let mut o = octets(address);
+    mask(&mut o, prefix);
+    match *address {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::new(o[0], o[1], o[2], o[3])),
+        IpAddr::V6(_) => {
+            let mut a = [0u8; 16];
+            a.copy_from_slice(&o);
+            IpAddr::V6(Ipv6Addr::from(a))
+        },
+    }
+}
+
+impl ClientSubnet {
+    /// Create the option a client sends for `address`, truncated to
+    /// `source_prefix` bits, with a scope prefix of 0.
+    pub fn new(address: IpAddr, source_prefix: u8) -> Result<ClientSubnet, EdnsError> {
+        if source_prefix > max_prefix(&address) {
+            return Err(EdnsError::PrefixLengthError(source_prefix));
+        }
+        Ok(ClientSubnet {
+            source_prefix,
+            scope_prefix: 0,
+            address: masked(&address, source_prefix),
+        })
+    }
+
+    /// The address family number of this option.
+    pub fn family(&self) -> u16 {
+        family(&self.address)
+    }
+
+    /// Decode the option data. The address must be exactly as long as
+    /// the source prefix requires and have no bits set beyond it.
+    pub fn from_option(option: &EdnsOption) -> Result<ClientSubnet, EdnsError> {
+        if option.code != EDNS0OptionCode::EdnsClientSubnet {
+            return Err(EdnsError::OptionCodeError(option.code.to_u16()));
+        }
+        let data = &option.data[..];
+        if data.len() < 4 {
+            return Err(EdnsError::OptionLengthError(data.len()));
+        }
+        let mut i = 0;
+        let family = _read_be_u16(data, &mut i);
+        let source_prefix = data[2];
+        let scope_prefix = data[3];
+        let mut octets = match family {
+            FAMILY_IPV4 => vec![0u8; 4],
+            FAMILY_IPV6 => vec![0u8; 16],
+            _ => return Err(EdnsError::AddressFamilyError(family)),
+        };
+        let max = (octets.len() * 8) as u8;
+        if source_prefix > max {
+            return Err(EdnsError::PrefixLengthError(source_prefix));
+        }
+        if scope_prefix > max {
+            return Err(EdnsError::PrefixLengthError(scope_prefix));
+        }
+        let len = (source_prefix as usize + 7) / 8;
+        if data.len() != 4 + len {
+            return Err(EdnsError::OptionLengthError(data.len()));
+        }
+        octets[..len].copy_from_slice(&data[4..]);
+        let unmasked = octets.clone();
+        mask(&mut octets, source_prefix);
+        if octets != unmasked {
+            return Err(EdnsError::AddressMaskError);
+        }
+        let address = if family == FAMILY_IPV4 {
+            IpAddr::V4(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))
+        } else {
+            let mut a = [0u8; 16];
+            a.copy_from_slice(&octets);
+            IpAddr::V6(Ipv6Addr::from(a))
+        };
+        Ok(ClientSubnet {
+            source_prefix,
+            scope_prefix,
+            address,
+        })
+    }
+
+    /// Encode the option, sending only the significant address octets.
+    pub fn to_option(&self) -> Result<EdnsOption<'static>, EdnsError> {
+        let max = max_prefix(&self.address);
+        if self.source_prefix > max {
+            return Err(EdnsError::PrefixLengthError(self.source_prefix));
+        }
+        if self.scope_prefix > max {
+            return Err(EdnsError::PrefixLengthError(self.scope_prefix));
+        }
+        if masked(&self.address, self.source_prefix) != self.address {
+            return Err(EdnsError::AddressMaskError);
+        }
+        let len = (self.source_prefix as usize + 7) / 8;
+        let mut data = Vec::with_capacity(4 + len);
+        _write_be_u16(&mut data, self.family());
+        data.push(self.source_prefix);
+        data.push(self.scope_prefix);
+        data.extend_from_slice(&octets(&self.address)[..len]);
+        Ok(EdnsOption::new(EDNS0OptionCode::EdnsClientSubnet, data))
+    }
+
+    /// Create the option a server returns for this query, answering
+    /// for `scope_prefix` bits of the client's address.
+    pub fn response(&self, scope_prefix: u8) -> ClientSubnet {
+        ClientSubnet {
+            source_prefix: self.source_prefix,
+            scope_prefix,
+            address: self.address,
+        }
+    }
+
+    /// The key under which to cache the answer to this query, given the
+    /// scope prefix from the response. A scope longer than the query's
+    /// source prefix is limited to the source prefix, and a scope of 0
+    /// means the answer is valid for every client.
+    pub fn cache_key(&self, scope_prefix: u8) -> SubnetCacheKey {
+        let prefix = scope_prefix.min(self.source_prefix);
+        SubnetCacheKey {
+            prefix,
+            address: masked(&self.address, prefix),
+        }
+    }
+}
+
+impl SubnetCacheKey {
+    /// Whether an answer cached under this key may be used for a query
+    /// carrying `query`. The query must be at least as specific as the
+    /// key and agree with it on the key's prefix.
+    pub fn matches(&self, query: &ClientSubnet) -> bool {
+        if self.prefix == 0 {
+            return true;
+        }
+        family(&self.address) == query.family()
+            && query.source_prefix >= self.prefix
+            && masked(&query.address, self.prefix) == self.address
+    }
+}
+
+impl<'e> Edns<'e> {
+    /// Decode the first Client Subnet option, if there is one.
+    pub fn client_subnet(&self) -> Result<Option<ClientSubnet>, EdnsError> {
+        self.option(EDNS0OptionCode::EdnsClientSubnet).map(ClientSubnet::from_option).transpose()
+    }
+
+    /// Replace any Client Subnet option with `ecs`.
+    pub fn set_client_subnet(&mut self, ecs: &ClientSubnet) -> Result<(), EdnsError> {
+        self.set_option(ecs.to_option()?);
+        Ok(())
+    }
+}
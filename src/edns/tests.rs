@@ -0,0 +1,385 @@
+use super::{Edns,EdnsOption,Message,Name,ResourceRecord,Type,Class,EDNS0OptionCode,ReadError,DEFAULT_UDP_PAYLOAD_SIZE};
+use super::{ExtendedError,ExtendedErrorCode};
+use super::{Nsid,TcpKeepalive,Chain,KeyTags,Padding,PaddingPolicy};
+use super::super::parser::Question;
+use super::{ClientSubnet,Cookie,CookieStatus,ServerCookies,EdnsError,client_cookie};
+use super::super::encoding::decode_hex;
+use super::super::iana::rcodes::RCode;
+use super::super::parser::{RData,read_dns_message};
+use super::super::writer::{write_dns_message,write_dns_rdata};
+
+use std::borrow::Cow;
+use std::net::IpAddr;
+
+#[test]
+fn test_edns_record_round_trip() {
+    let mut edns = Edns::new();
+    edns.udp_payload_size = 4096;
+    edns.dnssec_ok = true;
+    edns.options.push(EdnsOption::new(EDNS0OptionCode::NSID, &b""[..]));
+    edns.options.push(EdnsOption::new(EDNS0OptionCode::Unknown(65002), &b"\x01\x02"[..]));
+
+    let rr = edns.to_record().ok().unwrap();
+    assert_eq!(rr.rname, Name { labels: Vec::new() });
+    assert_eq!(rr.rtype, Type::OPT);
+    assert_eq!(rr.rclass.to_u16(), 4096);
+    assert_eq!(rr.rttl as u32, 0x0000_8000);
+    assert_eq!(&rr.context[..], b"\x00\x03\x00\x00\xfd\xea\x00\x02\x01\x02");
+
+    assert_eq!(Edns::from_record(&rr).ok().unwrap(), edns);
+    assert_eq!(edns.option(EDNS0OptionCode::NSID).map(|o| &o.data[..]), Some(&b""[..]));
+    assert!(edns.option(EDNS0OptionCode::DAU).is_none());
+}
+
+#[test]
+fn test_edns_in_message() {
+    let mut msg = Message::new(0x1234, 0x0100);
+    assert!(msg.edns().ok().unwrap().is_none());
+
+    let mut edns = Edns::new();
+    edns.dnssec_ok = true;
+    msg.set_edns(&edns).ok().unwrap();
+    // Setting again replaces rather than adds
+    msg.set_edns(&edns).ok().unwrap();
+    assert_eq!(msg.additionals.len(), 1);
+
+    let buf = write_dns_message(&msg).ok().unwrap();
+    let parsed = read_dns_message(&buf).ok().unwrap();
+    let got = parsed.edns().ok().unwrap().unwrap();
+    assert_eq!(got.udp_payload_size, DEFAULT_UDP_PAYLOAD_SIZE);
+    assert!(got.dnssec_ok);
+    assert!(got.options.is_empty());
+
+    msg.clear_edns();
+    assert!(msg.additionals.is_empty());
+
+    // A TSIG or SIG(0) record stays last
+    let name = Name::from_str("key.").ok().unwrap();
+    msg.additionals.push(ResourceRecord::new(name.clone(), Type::TSIG, Class::ANY, 0, &b""[..]));
+    msg.set_edns(&edns).ok().unwrap();
+    assert_eq!(msg.additionals.iter().map(|rr| rr.rtype).collect::<Vec<_>>(), vec![Type::OPT, Type::TSIG]);
+    msg.additionals.pop();
+    msg.clear_edns();
+    let sig0 = write_dns_rdata(&RData::SIG {
+        type_covered: Type::from(0),
+        algorithm: 13,
+        labels: 0,
+        original_ttl: 0,
+        expiration: 2,
+        inception: 1,
+        key_tag: 1,
+        signer_name: name.clone(),
+        signature: Cow::Borrowed(&[0; 64]),
+    }).ok().unwrap();
+    msg.additionals.push(ResourceRecord::new(Name { labels: Vec::new() }, Type::SIG, Class::ANY, 0, sig0));
+    msg.set_rcode(RCode::BADVERS).ok().unwrap();
+    assert_eq!(msg.additionals.iter().map(|rr| rr.rtype).collect::<Vec<_>>(), vec![Type::OPT, Type::SIG]);
+}
+
+#[test]
+fn test_trailing_empty_opt() {
+    // Query with a single OPT record of RDLENGTH 0 ending the packet
+    let buf = b"\x00\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x01\
+                \x00\x00\x29\x04\xd0\x00\x00\x00\x00\x00\x00";
+    let msg = read_dns_message(buf).ok().unwrap();
+    let edns = msg.edns().ok().unwrap().unwrap();
+    assert_eq!(edns.udp_payload_size, 1232);
+    assert!(!edns.dnssec_ok);
+    assert!(edns.options.is_empty());
+}
+
+#[test]
+fn test_extended_rcode() {
+    let mut msg = Message::new(1, 0x8000);
+    msg.set_rcode(RCode::BADVERS).ok().unwrap();
+    assert_eq!(msg.header().rcode, 0);
+    assert_eq!(msg.edns().ok().unwrap().unwrap().extended_rcode, 1);
+
+    let buf = write_dns_message(&msg).ok().unwrap();
+    let parsed = read_dns_message(&buf).ok().unwrap();
+    assert_eq!(parsed.rcode().ok().unwrap(), RCode::BADVERS);
+
+    // Codes that fit in the header need no OPT record
+    let mut msg = Message::new(1, 0x8000);
+    msg.set_rcode(RCode::NXDomain).ok().unwrap();
+    assert!(msg.additionals.is_empty());
+    assert_eq!(msg.rcode().ok().unwrap(), RCode::NXDomain);
+}
+
+#[test]
+fn test_bad_opt() {
+    let mut msg = Message::new(1, 0);
+    let rr = Edns::new().to_record().ok().unwrap();
+    msg.additionals.push(rr.clone());
+    msg.additionals.push(rr);
+    assert_eq!(msg.edns(), Err(ReadError::DuplicateOptError));
+
+    let owner = Name::from_str("example.com").ok().unwrap();
+    let rr = ResourceRecord::new(owner, Type::OPT, Class::from(512), 0, Vec::new());
+    assert_eq!(Edns::from_record(&rr), Err(ReadError::OptOwnerError));
+
+    // Option length runs past the end of the RDATA
+    let rr = ResourceRecord::new(Name { labels: Vec::new() }, Type::OPT, Class::from(512), 0, &b"\x00\x03\x00\x04\x01"[..]);
+    assert!(Edns::from_record(&rr).is_err());
+}
+
+fn ecs(data: &[u8]) -> Result<ClientSubnet, EdnsError> {
+    ClientSubnet::from_option(&EdnsOption::new(EDNS0OptionCode::EdnsClientSubnet, data))
+}
+
+#[test]
+fn test_client_subnet() {
+    let addr = "192.0.2.77".parse::<IpAddr>().unwrap();
+    let subnet = ClientSubnet::new(addr, 24).ok().unwrap();
+    assert_eq!(subnet.address, "192.0.2.0".parse::<IpAddr>().unwrap());
+    let option = subnet.to_option().ok().unwrap();
+    assert_eq!(&option.data[..], b"\x00\x01\x18\x00\xc0\x00\x02");
+    assert_eq!(ClientSubnet::from_option(&option), Ok(subnet));
+
+    // Prefix lengths that are not a multiple of 8
+    let addr = "2001:db8:ffff::1".parse::<IpAddr>().unwrap();
+    let subnet = ClientSubnet::new(addr, 36).ok().unwrap();
+    assert_eq!(subnet.address, "2001:db8:f000::".parse::<IpAddr>().unwrap());
+    let option = subnet.to_option().ok().unwrap();
+    assert_eq!(&option.data[..], b"\x00\x02\x24\x00\x20\x01\x0d\xb8\xf0");
+    assert_eq!(ClientSubnet::from_option(&option), Ok(subnet));
+
+    // A zero source prefix carries no address octets
+    assert_eq!(ecs(b"\x00\x01\x00\x00").ok().unwrap().address, "0.0.0.0".parse::<IpAddr>().unwrap());
+
+    let mut edns = Edns::new();
+    assert_eq!(edns.client_subnet(), Ok(None));
+    edns.set_client_subnet(&subnet).ok().unwrap();
+    edns.set_client_subnet(&subnet).ok().unwrap();
+    assert_eq!(edns.options.len(), 1);
+    assert_eq!(edns.client_subnet(), Ok(Some(subnet)));
+}
+
+#[test]
+fn test_client_subnet_invalid() {
+    // Bits set beyond the source prefix
+    assert_eq!(ecs(b"\x00\x01\x17\x00\xc0\x00\x03"), Err(EdnsError::AddressMaskError));
+    // Too many and too few address octets
+    assert_eq!(ecs(b"\x00\x01\x10\x00\xc0\x00\x00"), Err(EdnsError::OptionLengthError(7)));
+    assert_eq!(ecs(b"\x00\x01\x18\x00\xc0\x00"), Err(EdnsError::OptionLengthError(6)));
+    assert_eq!(ecs(b"\x00\x01\x18"), Err(EdnsError::OptionLengthError(3)));
+    assert_eq!(ecs(b"\x00\x01\x21\x00\xc0\x00\x02\x00\x00"), Err(EdnsError::PrefixLengthError(33)));
+    assert_eq!(ecs(b"\x00\x01\x08\x21\xc0"), Err(EdnsError::PrefixLengthError(33)));
+    assert_eq!(ecs(b"\x00\x03\x00\x00"), Err(EdnsError::AddressFamilyError(3)));
+
+    let other = EdnsOption::new(EDNS0OptionCode::NSID, &b""[..]);
+    assert_eq!(ClientSubnet::from_option(&other), Err(EdnsError::OptionCodeError(3)));
+
+    let addr = "192.0.2.1".parse::<IpAddr>().unwrap();
+    assert_eq!(ClientSubnet::new(addr, 33), Err(EdnsError::PrefixLengthError(33)));
+    let unmasked = ClientSubnet { source_prefix: 24, scope_prefix: 0, address: addr };
+    assert_eq!(unmasked.to_option(), Err(EdnsError::AddressMaskError));
+}
+
+#[test]
+fn test_client_subnet_cache_key() {
+    let query = ClientSubnet::new("198.51.100.200".parse::<IpAddr>().unwrap(), 24).ok().unwrap();
+    let key = query.cache_key(16);
+    assert_eq!(key.prefix, 16);
+    assert_eq!(key.address, "198.51.0.0".parse::<IpAddr>().unwrap());
+
+    // The scope cannot be wider than what the client revealed
+    assert_eq!(query.cache_key(32).prefix, 24);
+
+    let near = ClientSubnet::new("198.51.7.1".parse::<IpAddr>().unwrap(), 24).ok().unwrap();
+    let far = ClientSubnet::new("198.52.7.1".parse::<IpAddr>().unwrap(), 24).ok().unwrap();
+    let vague = ClientSubnet::new("198.51.7.1".parse::<IpAddr>().unwrap(), 8).ok().unwrap();
+    let v6 = ClientSubnet::new("2001:db8::".parse::<IpAddr>().unwrap(), 56).ok().unwrap();
+    assert!(key.matches(&near));
+    assert!(!key.matches(&far));
+    assert!(!key.matches(&vague));
+    assert!(!key.matches(&v6));
+
+    let global = query.cache_key(0);
+    assert!(global.matches(&far));
+    assert!(global.matches(&v6));
+}
+
+fn hex<T: Default + AsMut<[u8]>>(s: &str) -> T {
+    let mut out = T::default();
+    out.as_mut().copy_from_slice(&decode_hex(s.as_bytes()).unwrap());
+    out
+}
+
+#[test]
+fn test_server_cookie_vectors() {
+    // Examples from RFC 9018 Appendix A
+    let client_ip = "198.51.100.100".parse::<IpAddr>().unwrap();
+    let server = ServerCookies::new(hex("e5e973e5a6b2a43f48e7dc849e37bfcf"));
+    let client: [u8; 8] = hex("2464c4abcf10c957");
+    let cookie = server.create(&client, &client_ip, 1559731985);
+    assert_eq!(cookie, hex::<[u8; 16]>("010000005cf79f111f8130c3eee29480"));
+
+    let cookie = server.create(&client, &client_ip, 1559734385);
+    assert_eq!(cookie, hex::<[u8; 16]>("010000005cf7a871d4a564a1442aca77"));
+}
+
+#[test]
+fn test_server_cookie_check() {
+    let client_ip = "2001:db8::53".parse::<IpAddr>().unwrap();
+    let other_ip = "2001:db8::54".parse::<IpAddr>().unwrap();
+    let server = ServerCookies::new(hex("445536bcd2513298075a5d379663c962"));
+    let client = client_cookie(&hex("000102030405060708090a0b0c0d0e0f"), &"192.0.2.53".parse::<IpAddr>().unwrap());
+    let now = 1559741961u32;
+
+    let request = Cookie::new(client);
+    let (status, response) = server.respond(&request, &client_ip, now);
+    assert_eq!(status, CookieStatus::Missing);
+    assert_eq!(response.client, client);
+    assert_eq!(server.check(&response, &client_ip, now), CookieStatus::Good);
+
+    // The server cookie is only good for the client and time it was made for
+    assert_eq!(server.check(&response, &other_ip, now), CookieStatus::Bad);
+    let mut wrong = response.clone();
+    wrong.client[0] ^= 1;
+    assert_eq!(server.check(&wrong, &client_ip, now), CookieStatus::Bad);
+    assert_eq!(server.check(&response, &client_ip, now + 1801), CookieStatus::Renew);
+    assert_eq!(server.check(&response, &client_ip, now + 3601), CookieStatus::Bad);
+    assert_eq!(server.check(&response, &client_ip, now - 300), CookieStatus::Good);
+    assert_eq!(server.check(&response, &client_ip, now - 301), CookieStatus::Bad);
+
+    // Timestamps wrap around
+    let late = server.create(&client, &client_ip, 0xFFFF_FFF0);
+    let cookie = Cookie { client, server: Some(late.to_vec()) };
+    assert_eq!(server.check(&cookie, &client_ip, 0x10), CookieStatus::Good);
+
+    // A good cookie is echoed and a renewed one replaced
+    let (status, echoed) = server.respond(&response, &client_ip, now + 10);
+    assert_eq!(status, CookieStatus::Good);
+    assert_eq!(echoed, response);
+    let (status, renewed) = server.respond(&response, &client_ip, now + 2000);
+    assert_eq!(status, CookieStatus::Renew);
+    assert!(renewed != response);
+    assert_eq!(server.check(&renewed, &client_ip, now + 2000), CookieStatus::Good);
+}
+
+#[test]
+fn test_cookie_option() {
+    let client: [u8; 8] = hex("2464c4abcf10c957");
+    let server: [u8; 16] = hex("010000005cf79f111f8130c3eee29480");
+    let cookie = Cookie { client, server: Some(server.to_vec()) };
+
+    let mut edns = Edns::new();
+    edns.set_cookie(&Cookie::new(client)).ok().unwrap();
+    edns.set_cookie(&cookie).ok().unwrap();
+    assert_eq!(edns.options.len(), 1);
+    assert_eq!(edns.options[0].code, EDNS0OptionCode::Cookie);
+    assert_eq!(edns.options[0].data.len(), 24);
+    assert_eq!(edns.cookie(), Ok(Some(cookie)));
+
+    for &len in [0usize, 7, 9, 15, 41].iter() {
+        let option = EdnsOption::new(EDNS0OptionCode::Cookie, vec![0u8; len]);
+        assert_eq!(Cookie::from_option(&option), Err(EdnsError::OptionLengthError(len)));
+    }
+    let short = Cookie { client, server: Some(vec![0u8; 7]) };
+    assert_eq!(short.to_option(), Err(EdnsError::OptionLengthError(15)));
+}
+
+#[test]
+fn test_extended_error() {
+    let mut msg = Message::new(7, 0x8180);
+    msg.set_rcode(RCode::ServFail).ok().unwrap();
+    let mut edns = Edns::new();
+    let bogus = ExtendedError::new(ExtendedErrorCode::DnssecBogus, "signature by 12345 is invalid");
+    edns.add_extended_error(&bogus).ok().unwrap();
+    edns.add_extended_error(&ExtendedError::new(ExtendedErrorCode::Unknown(50000), "")).ok().unwrap();
+    msg.set_edns(&edns).ok().unwrap();
+
+    let buf = write_dns_message(&msg).ok().unwrap();
+    let parsed = read_dns_message(&buf).ok().unwrap();
+    let errors = parsed.edns().ok().unwrap().unwrap().extended_errors().ok().unwrap();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0], bogus);
+    assert_eq!(errors[0].to_string(), "DNSSEC Bogus (6): signature by 12345 is invalid");
+    assert_eq!(errors[1].to_string(), "INFO-CODE 50000 (50000)");
+
+    let option = bogus.to_option().ok().unwrap();
+    assert_eq!(&option.data[..2], b"\x00\x06");
+
+    let bad = EdnsOption::new(EDNS0OptionCode::ExtendedDnsError, &b"\x00\x06\xff"[..]);
+    assert_eq!(ExtendedError::from_option(&bad), Err(EdnsError::TextEncodingError));
+    let short = EdnsOption::new(EDNS0OptionCode::ExtendedDnsError, &b"\x00"[..]);
+    assert_eq!(ExtendedError::from_option(&short), Err(EdnsError::OptionLengthError(1)));
+}
+
+#[test]
+fn test_simple_options() {
+    let mut edns = Edns::new();
+    assert_eq!(edns.nsid(), Ok(None));
+    edns.set_option(Nsid { id: Vec::new() }.to_option().ok().unwrap());
+    edns.set_option(Nsid { id: b"ns1.lax".to_vec() }.to_option().ok().unwrap());
+    edns.set_option(TcpKeepalive { timeout: Some(1200) }.to_option().ok().unwrap());
+    let chain = Chain { closest_trust_point: Name::from_str("example.com.").ok().unwrap().into_owned() };
+    edns.set_option(chain.to_option().ok().unwrap());
+    edns.set_option(KeyTags { tags: vec![20326, 38696] }.to_option().ok().unwrap());
+    assert_eq!(edns.options.len(), 4);
+
+    let rr = edns.to_record().ok().unwrap();
+    let edns = Edns::from_record(&rr).ok().unwrap();
+    assert_eq!(edns.nsid(), Ok(Some(Nsid { id: b"ns1.lax".to_vec() })));
+    assert_eq!(edns.tcp_keepalive(), Ok(Some(TcpKeepalive { timeout: Some(1200) })));
+    assert_eq!(edns.chain(), Ok(Some(chain)));
+    assert_eq!(edns.key_tags(), Ok(Some(KeyTags { tags: vec![20326, 38696] })));
+    assert_eq!(&edns.option(EDNS0OptionCode::KeyTag).unwrap().data[..], b"\x4f\x66\x97\x28");
+    assert_eq!(&edns.option(EDNS0OptionCode::Chain).unwrap().data[..], b"\x07example\x03com\x00");
+
+    let query = TcpKeepalive { timeout: None }.to_option().ok().unwrap();
+    assert!(query.data.is_empty());
+    assert_eq!(TcpKeepalive::from_option(&query), Ok(TcpKeepalive { timeout: None }));
+}
+
+#[test]
+fn test_simple_options_invalid() {
+    let option = EdnsOption::new(EDNS0OptionCode::TcpKeepalive, &b"\x00"[..]);
+    assert_eq!(TcpKeepalive::from_option(&option), Err(EdnsError::OptionLengthError(1)));
+    let option = EdnsOption::new(EDNS0OptionCode::KeyTag, &b""[..]);
+    assert_eq!(KeyTags::from_option(&option), Err(EdnsError::OptionLengthError(0)));
+    let option = EdnsOption::new(EDNS0OptionCode::KeyTag, &b"\x01\x02\x03"[..]);
+    assert_eq!(KeyTags::from_option(&option), Err(EdnsError::OptionLengthError(3)));
+    assert_eq!(KeyTags { tags: Vec::new() }.to_option(), Err(EdnsError::OptionLengthError(0)));
+
+    // CHAIN names must not be followed by other data
+    let option = EdnsOption::new(EDNS0OptionCode::Chain, &b"\x03com\x00\x07example\xc0\x00"[..]);
+    assert_eq!(Chain::from_option(&option), Err(EdnsError::OptionLengthError(15)));
+    let option = EdnsOption::new(EDNS0OptionCode::Chain, &b"\x03com"[..]);
+    assert!(Chain::from_option(&option).is_err());
+    let option = EdnsOption::new(EDNS0OptionCode::Padding, &b""[..]);
+    assert_eq!(Nsid::from_option(&option), Err(EdnsError::OptionCodeError(12)));
+}
+
+#[test]
+fn test_padding_policy() {
+    let name = Name::from_str("www.example.com.").ok().unwrap();
+    let mut msg = Message::new(0xbeef, 0x0100);
+    msg.questions.push(Question { qname: name, qtype: Type::AAAA, qclass: Class::IN });
+
+    PaddingPolicy::queries().pad(&mut msg).ok().unwrap();
+    let buf = write_dns_message(&msg).ok().unwrap();
+    assert_eq!(buf.len(), 128);
+    let parsed = read_dns_message(&buf).ok().unwrap();
+    let padding = parsed.edns().ok().unwrap().unwrap().padding().ok().unwrap().unwrap();
+    assert_eq!(padding.length, 128 - 12 - 21 - 11 - 4);
+
+    // Padding again replaces the old option
+    PaddingPolicy::responses().pad(&mut msg).ok().unwrap();
+    assert_eq!(write_dns_message(&msg).ok().unwrap().len(), 468);
+    assert_eq!(msg.additionals.len(), 1);
+
+    let mut policy = PaddingPolicy::new(1000);
+    policy.max_size = 512;
+    policy.pad(&mut msg).ok().unwrap();
+    assert_eq!(write_dns_message(&msg).ok().unwrap().len(), 512);
+
+    // A message already at a block boundary gets an empty option
+    PaddingPolicy::new(256).pad(&mut msg).ok().unwrap();
+    PaddingPolicy::new(256).pad(&mut msg).ok().unwrap();
+    assert_eq!(write_dns_message(&msg).ok().unwrap().len(), 256);
+
+    let option = EdnsOption::new(EDNS0OptionCode::Padding, &b"\x01\x02"[..]);
+    assert_eq!(Padding::from_option(&option), Ok(Padding { length: 2 }));
+}
@@ -0,0 +1,89 @@
+static ALPHABET: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+/// Encode octets as unpadded, lowercase base32 with the extended hex
+/// alphabet of [RFC4648§7](https://tools.ietf.org/html/rfc4648#section-7),
+/// the presentation format of NSEC3 hashed owner names given in
+/// [RFC5155§3.3](https://tools.ietf.org/html/rfc5155#section-3.3).
+pub fn encode_base32hex(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() + 4) / 5 * 8);
+    let mut bits = 0u32;
+    let mut n = 0u32;
+    for &b in data.iter() {
+        n = n << 8 | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[(n >> bits & 0x1F) as usize] as char);
+        }
+        n &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        out.push(ALPHABET[(n << (5 - bits) & 0x1F) as usize] as char);
+    }
+    out
+}
+
+/// Decode unpadded base32hex, in either case. Returns `None` if `text`
+/// contains anything other than base32hex characters, has a length that
+/// no number of octets encodes to, or has non-zero trailing bits.
+pub fn decode_base32hex(text: &[u8]) -> Option<Vec<u8>> {
+    if [1, 3, 6].contains(&(text.len() % 8)) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let mut bits = 0u32;
+    let mut n = 0u32;
+    for &c in text.iter() {
+        let v = match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'v' => c - b'a' + 10,
+            b'A'..=b'V' => c - b'A' + 10,
+            _ => return None,
+        };
+        n = n << 5 | v as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((n >> bits) as u8);
+            n &= (1 << bits) - 1;
+        }
+    }
+    if n != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod test_base32 {
+    use super::{encode_base32hex,decode_base32hex};
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let vectors: [(&str, &str); 7] = [
+            ("", ""),
+            ("f", "co"),
+            ("fo", "cpng"),
+            ("foo", "cpnmu"),
+            ("foob", "cpnmuog"),
+            ("fooba", "cpnmuoj1"),
+            ("foobar", "cpnmuoj1e8"),
+        ];
+        for &(plain, encoded) in vectors.iter() {
+            assert_eq!(encode_base32hex(plain.as_bytes()), encoded);
+            assert_eq!(decode_base32hex(encoded.as_bytes()).unwrap(), plain.as_bytes());
+            assert_eq!(decode_base32hex(encoded.to_uppercase().as_bytes()).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_invalid_base32hex() {
+        assert_eq!(decode_base32hex(b"c"), None);
+        assert_eq!(decode_base32hex(b"cpn"), None);
+        assert_eq!(decode_base32hex(b"cpnmuo"), None);
+        assert_eq!(decode_base32hex(b"cw"), None);
+        assert_eq!(decode_base32hex(b"co======"), None);
+        // Trailing bits must be zero
+        assert_eq!(decode_base32hex(b"cp"), None);
+    }
+}
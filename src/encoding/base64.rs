@@ -0,0 +1,90 @@
+static ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode octets as padded base64, as per
+/// [RFC4648§4](https://tools.ietf.org/html/rfc4648#section-4).
+pub fn encode_base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode padded base64. Returns `None` if `text` contains anything
+/// other than base64 characters and trailing padding, or if its
+/// length is not a multiple of 4.
+pub fn decode_base64(text: &[u8]) -> Option<Vec<u8>> {
+    if text.len() % 4 != 0 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for (i, chunk) in text.chunks(4).enumerate() {
+        let last = i == text.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in chunk[..4 - padding].iter() {
+            let v = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => return None,
+            };
+            n = n << 6 | v as u32;
+        }
+        n <<= 6 * padding;
+        let octets = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        out.extend_from_slice(&octets[..3 - padding]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod test_base64 {
+    use super::{encode_base64,decode_base64};
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let vectors: [(&str, &str); 7] = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for &(plain, encoded) in vectors.iter() {
+            assert_eq!(encode_base64(plain.as_bytes()), encoded);
+            assert_eq!(decode_base64(encoded.as_bytes()).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(decode_base64(b"Zm9"), None);
+        assert_eq!(decode_base64(b"Zm9v!A=="), None);
+        assert_eq!(decode_base64(b"Zg==Zm9v"), None);
+        assert_eq!(decode_base64(b"Z==="), None);
+    }
+
+    #[test]
+    fn test_all_octets() {
+        let data: Vec<u8> = (0..=255u8).collect();
+        for i in 0..data.len() {
+            assert_eq!(decode_base64(encode_base64(&data[i..]).as_bytes()).unwrap(), &data[i..]);
+        }
+    }
+}
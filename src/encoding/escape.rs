@@ -0,0 +1,37 @@
+/// Decode the `\X` or `\DDD` escape sequence of
+/// [RFC1035§5.1](https://tools.ietf.org/html/rfc1035#section-5.1) that
+/// starts with the backslash at `text[idx]`. Returns the octet and the
+/// length of the sequence, or `None` if the sequence is truncated or
+/// `DDD` is greater than 255.
+pub fn decode_escape(text: &[u8], idx: usize) -> Option<(u8, usize)> {
+    let c = *text.get(idx + 1)?;
+    if !c.is_ascii_digit() {
+        return Some((c, 2));
+    }
+    let digits = text.get(idx+1..idx+4)?;
+    if !digits.iter().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let value = digits.iter().fold(0u32, |acc, c| acc * 10 + (c - b'0') as u32);
+    if value > 255 {
+        return None;
+    }
+    Some((value as u8, 4))
+}
+
+#[cfg(test)]
+mod test_escape {
+    use super::decode_escape;
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(decode_escape(b"\\.", 0), Some((b'.', 2)));
+        assert_eq!(decode_escape(b"a\\\\b", 1), Some((b'\\', 2)));
+        assert_eq!(decode_escape(b"\\000", 0), Some((0, 4)));
+        assert_eq!(decode_escape(b"\\2551", 0), Some((255, 4)));
+        assert_eq!(decode_escape(b"\\256", 0), None);
+        assert_eq!(decode_escape(b"\\25", 0), None);
+        assert_eq!(decode_escape(b"\\2a5", 0), None);
+        assert_eq!(decode_escape(b"\\", 0), None);
+    }
+}
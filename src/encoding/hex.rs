@@ -0,0 +1,37 @@
+/// Encode octets as uppercase hexadecimal.
+pub fn encode_hex(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() * 2);
+    for b in data.iter() {
+        out.push_str(&format!("{:02X}", b));
+    }
+    out
+}
+
+/// Decode hexadecimal digit pairs, in either case. Returns `None` if
+/// `text` contains anything else or has an odd length.
+pub fn decode_hex(text: &[u8]) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 2);
+    for pair in text.chunks(2) {
+        let hi = (pair[0] as char).to_digit(16)?;
+        let lo = (pair[1] as char).to_digit(16)?;
+        out.push((hi << 4 | lo) as u8);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod test_hex {
+    use super::{encode_hex,decode_hex};
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(encode_hex(&[0x00, 0xDE, 0xad, 0x0F]), "00DEAD0F");
+        assert_eq!(decode_hex(b"00DEad0f").unwrap(), vec![0x00, 0xDE, 0xAD, 0x0F]);
+        assert_eq!(decode_hex(b"").unwrap(), Vec::<u8>::new());
+        assert_eq!(decode_hex(b"0"), None);
+        assert_eq!(decode_hex(b"0g"), None);
+    }
+}
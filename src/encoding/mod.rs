@@ -0,0 +1,11 @@
+pub use self::base32::{encode_base32hex,decode_base32hex};
+pub use self::base64::{encode_base64,decode_base64};
+pub use self::escape::decode_escape;
+pub use self::hex::{encode_hex,decode_hex};
+pub use self::time::{format_timestamp,parse_timestamp,serial_cmp};
+
+mod base32;
+mod base64;
+mod escape;
+mod hex;
+mod time;
@@ -0,0 +1,117 @@
+use std::cmp::Ordering;
+
+/// Days since 1970-01-01 of the given proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// The proleptic Gregorian date of the given number of days since
+/// 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (if month <= 2 { yoe + era * 400 + 1 } else { yoe + era * 400 }, month, day)
+}
+
+/// Format seconds since the epoch as `YYYYMMDDHHmmSS` in UTC, the
+/// presentation format of RRSIG timestamps given in
+/// [RFC4034§3.2](https://tools.ietf.org/html/rfc4034#section-3.2).
+pub fn format_timestamp(secs: u32) -> String {
+    let secs = secs as i64;
+    let (year, month, day) = civil_from_days(secs / 86400);
+    let rem = secs % 86400;
+    format!("{:04}{:02}{:02}{:02}{:02}{:02}", year, month, day, rem / 3600, rem / 60 % 60, rem % 60)
+}
+
+/// Parse a `YYYYMMDDHHmmSS` UTC timestamp into seconds since the epoch.
+/// Returns `None` if the text is malformed or the time does not fit in
+/// 32 bits.
+pub fn parse_timestamp(text: &[u8]) -> Option<u32> {
+    if text.len() != 14 || !text.iter().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let field = |from: usize, to: usize| text[from..to].iter().fold(0i64, |acc, c| acc * 10 + (c - b'0') as i64);
+    let (year, month, day) = (field(0, 4), field(4, 6), field(6, 8));
+    let (hour, minute, second) = (field(8, 10), field(10, 12), field(12, 14));
+    if !(1..=12).contains(&month) || day < 1 || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    // Reject days past the end of the month
+    let next_month = if month == 12 { days_from_civil(year + 1, 1, 1) } else { days_from_civil(year, month + 1, 1) };
+    let days = days_from_civil(year, month, day);
+    if days >= next_month {
+        return None;
+    }
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    if !(0..=0xFFFFFFFF).contains(&secs) {
+        return None;
+    }
+    Some(secs as u32)
+}
+
+/// Compare two 32-bit timestamps or serial numbers using the serial
+/// number arithmetic of
+/// [RFC1982§3.2](https://tools.ietf.org/html/rfc1982#section-3.2), as
+/// required for RRSIG validity periods by
+/// [RFC4034§3.1.5](https://tools.ietf.org/html/rfc4034#section-3.1.5).
+/// Returns `None` for the undefined case of values exactly 2^31 apart.
+pub fn serial_cmp(a: u32, b: u32) -> Option<Ordering> {
+    match a.wrapping_sub(b) {
+        0 => Some(Ordering::Equal),
+        0x8000_0000 => None,
+        d if d < 0x8000_0000 => Some(Ordering::Greater),
+        _ => Some(Ordering::Less),
+    }
+}
+
+#[cfg(test)]
+mod test_time {
+    use super::{format_timestamp,parse_timestamp,serial_cmp};
+    use std::cmp::Ordering;
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(format_timestamp(0), "19700101000000");
+        assert_eq!(format_timestamp(1078358400), "20040304000000");
+        assert_eq!(format_timestamp(951782400), "20000229000000");
+        assert_eq!(format_timestamp(0xFFFFFFFF), "21060207062815");
+        assert_eq!(parse_timestamp(b"20040304000000"), Some(1078358400));
+        assert_eq!(parse_timestamp(b"21060207062815"), Some(0xFFFFFFFF));
+        for &t in [0u32, 1, 59, 86399, 951782400, 1234567890, 0xFFFFFFFF].iter() {
+            assert_eq!(parse_timestamp(format_timestamp(t).as_bytes()), Some(t));
+        }
+    }
+
+    #[test]
+    fn test_invalid_timestamps() {
+        assert_eq!(parse_timestamp(b"21060207062816"), None);
+        assert_eq!(parse_timestamp(b"19691231235959"), None);
+        assert_eq!(parse_timestamp(b"20010229000000"), None);
+        assert_eq!(parse_timestamp(b"20011301000000"), None);
+        assert_eq!(parse_timestamp(b"20010101240000"), None);
+        assert_eq!(parse_timestamp(b"2001010100000"), None);
+        assert_eq!(parse_timestamp(b"2001010100000a"), None);
+    }
+
+    #[test]
+    fn test_serial_cmp() {
+        assert_eq!(serial_cmp(5, 5), Some(Ordering::Equal));
+        assert_eq!(serial_cmp(6, 5), Some(Ordering::Greater));
+        assert_eq!(serial_cmp(5, 6), Some(Ordering::Less));
+        assert_eq!(serial_cmp(0, 0xFFFF_FFFF), Some(Ordering::Greater));
+        assert_eq!(serial_cmp(0x7FFF_FFFF, 0), Some(Ordering::Greater));
+        assert_eq!(serial_cmp(0x8000_0001, 0), Some(Ordering::Less));
+        assert_eq!(serial_cmp(0x8000_0000, 0), None);
+    }
+}
@@ -1,7 +1,12 @@
 pub use super::IdentifierError;
 
+use std::cmp;
+use std::convert;
+use std::fmt;
+use std::hash;
+
 #[repr(u16)]
-#[derive(PartialEq,Debug,Clone)]
+#[derive(Eq,Debug,Copy,Clone)]
 pub enum Class {
     IN = 1,
     CH = 3,
@@ -9,9 +14,27 @@ pub enum Class {
     NONE = 254,
     ANY = 255,
 
+    /// A code without a variant of its own, kept as is so that
+    /// records using it can be handled as per RFC 3597. It compares and
+    /// hashes equal to the variant of the same code, but patterns only
+    /// match it as `Unknown`; `From<u16>` never builds one for a code
+    /// that has a variant.
+    Unknown(u16),
 }
 
 impl Class {
+    pub fn to_u16(&self) -> u16 {
+        match *self {
+            Class::IN => 1,
+            Class::CH => 3,
+            Class::HS => 4,
+            Class::NONE => 254,
+            Class::ANY => 255,
+
+            Class::Unknown(x) => x,
+        }
+    }
+
     pub fn from_u16(value: u16) ->  Result<Class, IdentifierError> {
         match value {
             1 => Ok(Class::IN),
@@ -20,16 +43,81 @@ impl Class {
             254 => Ok(Class::NONE),
             255 => Ok(Class::ANY),
 
-            x @ 65280...65534 => Err(IdentifierError::PrivateUseIdentifierError(x as i64)),
+            x @ 65280..=65534 => Err(IdentifierError::PrivateUseIdentifierError(x as i64)),
 
-            0 => Err(IdentifierError::ReservedIdentifierError(0 as i64)),
-            65535 => Err(IdentifierError::ReservedIdentifierError(65535 as i64)),
+            0 => Err(IdentifierError::ReservedIdentifierError(0)),
+            65535 => Err(IdentifierError::ReservedIdentifierError(65535)),
 
-            2 => Err(IdentifierError::UnassignedIdentifierError(2 as i64)),
-            x @ 5...253 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
-            x @ 256...65279 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            2 => Err(IdentifierError::UnassignedIdentifierError(2)),
+            x @ 5..=253 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            x @ 256..=65279 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+        }
+    }
+
+    /// The presentation-format mnemonic of this class, if it has one.
+    pub fn mnemonic(&self) -> Option<&'static str> {
+        match *self {
+            Class::IN => Some("IN"),
+            Class::CH => Some("CH"),
+            Class::HS => Some("HS"),
+            Class::NONE => Some("NONE"),
+            Class::ANY => Some("ANY"),
 
-            x @ _ => Err(IdentifierError::UnknownIdentifierError(x as i64)),
+            Class::Unknown(_) => None,
+        }
+    }
+
+    /// Look up a class by its presentation-format mnemonic, such as
+    /// `"IN"`, or by the generic `CLASS###` form of
+    /// [RFC3597§5](https://tools.ietf.org/html/rfc3597#section-5).
+    /// Matching is case-insensitive.
+    pub fn from_mnemonic(s: &str) -> Option<Class> {
+        let upper = s.to_ascii_uppercase();
+        if upper.starts_with("CLASS") && upper.len() > 5 && upper[5..].bytes().all(|c| c.is_ascii_digit()) {
+            return upper[5..].parse::<u16>().ok().map(Class::from);
+        }
+        match &upper[..] {
+            "IN" => Some(Class::IN),
+            "CH" => Some(Class::CH),
+            "HS" => Some(Class::HS),
+            "NONE" => Some(Class::NONE),
+            "ANY" => Some(Class::ANY),
+            _ => None,
+        }
+    }
+}
+
+impl convert::From<u16> for Class {
+    /// Convert a code to a `Class`, mapping codes that `from_u16` would
+    /// reject to `Class::Unknown`.
+    fn from(value: u16) -> Class {
+        match Class::from_u16(value) {
+            Ok(x) => x,
+            Err(_) => Class::Unknown(value),
+        }
+    }
+}
+
+impl cmp::PartialEq for Class {
+    /// Codes are compared by value, so `Class::Unknown` built by hand
+    /// equals the variant of its code.
+    fn eq(&self, other: &Class) -> bool {
+        self.to_u16() == other.to_u16()
+    }
+}
+
+impl hash::Hash for Class {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.to_u16().hash(state);
+    }
+}
+
+impl fmt::Display for Class {
+    /// Formats a class as its mnemonic, or as `CLASS###` if it has none.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mnemonic() {
+            Some(s) => write!(f, "{}", s),
+            None => write!(f, "CLASS{}", self.to_u16()),
         }
     }
 }
@@ -48,6 +136,46 @@ mod test_class {
 
     }
 
+    #[test]
+    fn test_unknown_normalisation() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash,Hasher};
+        let hash = |x: Class| {
+            let mut h = DefaultHasher::new();
+            x.hash(&mut h);
+            h.finish()
+        };
+        // Unknown built by hand equals the variant of its code
+        assert_eq!(Class::Unknown(1), Class::IN);
+        assert_eq!(hash(Class::Unknown(1)), hash(Class::IN));
+        assert!(Class::Unknown(1) != Class::Unknown(65001));
+        assert!(matches!(Class::from(1), Class::IN));
+    }
+
+    #[test]
+    fn test_unknown_identity() {
+        assert_eq!(Class::IN, Class::from(1));
+        assert_eq!(Class::Unknown(65280), Class::from(65280));
+        for i in 0..65536usize {
+            assert_eq!(Class::from(i as u16).to_u16(), i as u16);
+            assert_eq!(Class::from_u16(i as u16).is_ok(), !matches!(Class::from(i as u16), Class::Unknown(_)));
+        }
+    }
+
+    #[test]
+    fn test_mnemonic() {
+        assert_eq!(Some(Class::IN), Class::from_mnemonic("IN"));
+        assert_eq!(Some(Class::CH), Class::from_mnemonic("ch"));
+        assert_eq!(Some(Class::Unknown(2)), Class::from_mnemonic("CLASS2"));
+        assert_eq!(None, Class::from_mnemonic("CLASS"));
+        assert_eq!(None, Class::from_mnemonic("MX"));
+        for i in 0..65536usize {
+            let c = Class::from(i as u16);
+            assert_eq!(Some(c), Class::from_mnemonic(&c.to_string()));
+        }
+        assert_eq!(Class::Unknown(2).to_string(), "CLASS2");
+    }
+
     #[test]
     fn test_range_privateuse_identity() {
         for i in 65280..(65534u64+1) {
@@ -0,0 +1,259 @@
+pub use super::IdentifierError;
+
+use std::convert;
+use std::fmt;
+
+/// INFO-CODE values of the Extended DNS Error option of
+/// [RFC8914§4](https://tools.ietf.org/html/rfc8914#section-4).
+#[repr(u16)]
+#[derive(PartialEq,Eq,Hash,Debug,Copy,Clone)]
+pub enum ExtendedErrorCode {
+    OtherError = 0,
+    UnsupportedDnskeyAlgorithm = 1,
+    UnsupportedDsDigestType = 2,
+    StaleAnswer = 3,
+    ForgedAnswer = 4,
+    DnssecIndeterminate = 5,
+    DnssecBogus = 6,
+    SignatureExpired = 7,
+    SignatureNotYetValid = 8,
+    DnskeyMissing = 9,
+    RrsigsMissing = 10,
+    NoZoneKeyBitSet = 11,
+    NsecMissing = 12,
+    CachedError = 13,
+    NotReady = 14,
+    Blocked = 15,
+    Censored = 16,
+    Filtered = 17,
+    Prohibited = 18,
+    StaleNxdomainAnswer = 19,
+    NotAuthoritative = 20,
+    NotSupported = 21,
+    NoReachableAuthority = 22,
+    NetworkError = 23,
+    InvalidData = 24,
+    SignatureExpiredBeforeValid = 25,
+    TooEarly = 26,
+    UnsupportedNsec3IterationsValue = 27,
+    UnableToConformToPolicy = 28,
+    Synthesized = 29,
+    InvalidQueryType = 30,
+
+    /// A code without a variant of its own, kept as is so that
+    /// options using it can be passed through.
+    Unknown(u16),
+}
+
+impl ExtendedErrorCode {
+    pub fn to_u16(&self) -> u16 {
+        match *self {
+            ExtendedErrorCode::OtherError => 0,
+            ExtendedErrorCode::UnsupportedDnskeyAlgorithm => 1,
+            ExtendedErrorCode::UnsupportedDsDigestType => 2,
+            ExtendedErrorCode::StaleAnswer => 3,
+            ExtendedErrorCode::ForgedAnswer => 4,
+            ExtendedErrorCode::DnssecIndeterminate => 5,
+            ExtendedErrorCode::DnssecBogus => 6,
+            ExtendedErrorCode::SignatureExpired => 7,
+            ExtendedErrorCode::SignatureNotYetValid => 8,
+            ExtendedErrorCode::DnskeyMissing => 9,
+            ExtendedErrorCode::RrsigsMissing => 10,
+            ExtendedErrorCode::NoZoneKeyBitSet => 11,
+            ExtendedErrorCode::NsecMissing => 12,
+            ExtendedErrorCode::CachedError => 13,
+            ExtendedErrorCode::NotReady => 14,
+            ExtendedErrorCode::Blocked => 15,
+            ExtendedErrorCode::Censored => 16,
+            ExtendedErrorCode::Filtered => 17,
+            ExtendedErrorCode::Prohibited => 18,
+            ExtendedErrorCode::StaleNxdomainAnswer => 19,
+            ExtendedErrorCode::NotAuthoritative => 20,
+            ExtendedErrorCode::NotSupported => 21,
+            ExtendedErrorCode::NoReachableAuthority => 22,
+            ExtendedErrorCode::NetworkError => 23,
+            ExtendedErrorCode::InvalidData => 24,
+            ExtendedErrorCode::SignatureExpiredBeforeValid => 25,
+            ExtendedErrorCode::TooEarly => 26,
+            ExtendedErrorCode::UnsupportedNsec3IterationsValue => 27,
+            ExtendedErrorCode::UnableToConformToPolicy => 28,
+            ExtendedErrorCode::Synthesized => 29,
+            ExtendedErrorCode::InvalidQueryType => 30,
+
+            ExtendedErrorCode::Unknown(x) => x,
+        }
+    }
+
+    pub fn from_u16(value: u16) ->  Result<ExtendedErrorCode, IdentifierError> {
+        match value {
+            0 => Ok(ExtendedErrorCode::OtherError),
+            1 => Ok(ExtendedErrorCode::UnsupportedDnskeyAlgorithm),
+            2 => Ok(ExtendedErrorCode::UnsupportedDsDigestType),
+            3 => Ok(ExtendedErrorCode::StaleAnswer),
+            4 => Ok(ExtendedErrorCode::ForgedAnswer),
+            5 => Ok(ExtendedErrorCode::DnssecIndeterminate),
+            6 => Ok(ExtendedErrorCode::DnssecBogus),
+            7 => Ok(ExtendedErrorCode::SignatureExpired),
+            8 => Ok(ExtendedErrorCode::SignatureNotYetValid),
+            9 => Ok(ExtendedErrorCode::DnskeyMissing),
+            10 => Ok(ExtendedErrorCode::RrsigsMissing),
+            11 => Ok(ExtendedErrorCode::NoZoneKeyBitSet),
+            12 => Ok(ExtendedErrorCode::NsecMissing),
+            13 => Ok(ExtendedErrorCode::CachedError),
+            14 => Ok(ExtendedErrorCode::NotReady),
+            15 => Ok(ExtendedErrorCode::Blocked),
+            16 => Ok(ExtendedErrorCode::Censored),
+            17 => Ok(ExtendedErrorCode::Filtered),
+            18 => Ok(ExtendedErrorCode::Prohibited),
+            19 => Ok(ExtendedErrorCode::StaleNxdomainAnswer),
+            20 => Ok(ExtendedErrorCode::NotAuthoritative),
+            21 => Ok(ExtendedErrorCode::NotSupported),
+            22 => Ok(ExtendedErrorCode::NoReachableAuthority),
+            23 => Ok(ExtendedErrorCode::NetworkError),
+            24 => Ok(ExtendedErrorCode::InvalidData),
+            25 => Ok(ExtendedErrorCode::SignatureExpiredBeforeValid),
+            26 => Ok(ExtendedErrorCode::TooEarly),
+            27 => Ok(ExtendedErrorCode::UnsupportedNsec3IterationsValue),
+            28 => Ok(ExtendedErrorCode::UnableToConformToPolicy),
+            29 => Ok(ExtendedErrorCode::Synthesized),
+            30 => Ok(ExtendedErrorCode::InvalidQueryType),
+
+            x @ 49152..=65535 => Err(IdentifierError::PrivateUseIdentifierError(x as i64)),
+
+            x @ 31..=49151 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+        }
+    }
+
+    /// The description of this code in the IANA registry.
+    pub fn description(&self) -> Option<&'static str> {
+        match *self {
+            ExtendedErrorCode::OtherError => Some("Other Error"),
+            ExtendedErrorCode::UnsupportedDnskeyAlgorithm => Some("Unsupported DNSKEY Algorithm"),
+            ExtendedErrorCode::UnsupportedDsDigestType => Some("Unsupported DS Digest Type"),
+            ExtendedErrorCode::StaleAnswer => Some("Stale Answer"),
+            ExtendedErrorCode::ForgedAnswer => Some("Forged Answer"),
+            ExtendedErrorCode::DnssecIndeterminate => Some("DNSSEC Indeterminate"),
+            ExtendedErrorCode::DnssecBogus => Some("DNSSEC Bogus"),
+            ExtendedErrorCode::SignatureExpired => Some("Signature Expired"),
+            ExtendedErrorCode::SignatureNotYetValid => Some("Signature Not Yet Valid"),
+            ExtendedErrorCode::DnskeyMissing => Some("DNSKEY Missing"),
+            ExtendedErrorCode::RrsigsMissing => Some("RRSIGs Missing"),
+            ExtendedErrorCode::NoZoneKeyBitSet => Some("No Zone Key Bit Set"),
+            ExtendedErrorCode::NsecMissing => Some("NSEC Missing"),
+            ExtendedErrorCode::CachedError => Some("Cached Error"),
+            ExtendedErrorCode::NotReady => Some("Not Ready"),
+            ExtendedErrorCode::Blocked => Some("Blocked"),
+            ExtendedErrorCode::Censored => Some("Censored"),
+            ExtendedErrorCode::Filtered => Some("Filtered"),
+            ExtendedErrorCode::Prohibited => Some("Prohibited"),
+            ExtendedErrorCode::StaleNxdomainAnswer => Some("Stale NXDOMAIN Answer"),
+            ExtendedErrorCode::NotAuthoritative => Some("Not Authoritative"),
+            ExtendedErrorCode::NotSupported => Some("Not Supported"),
+            ExtendedErrorCode::NoReachableAuthority => Some("No Reachable Authority"),
+            ExtendedErrorCode::NetworkError => Some("Network Error"),
+            ExtendedErrorCode::InvalidData => Some("Invalid Data"),
+            ExtendedErrorCode::SignatureExpiredBeforeValid => Some("Signature Expired before Valid"),
+            ExtendedErrorCode::TooEarly => Some("Too Early"),
+            ExtendedErrorCode::UnsupportedNsec3IterationsValue => Some("Unsupported NSEC3 Iterations Value"),
+            ExtendedErrorCode::UnableToConformToPolicy => Some("Unable to conform to policy"),
+            ExtendedErrorCode::Synthesized => Some("Synthesized"),
+            ExtendedErrorCode::InvalidQueryType => Some("Invalid Query Type"),
+
+            ExtendedErrorCode::Unknown(_) => None,
+        }
+    }
+}
+
+impl convert::From<u16> for ExtendedErrorCode {
+    /// Convert a code to an `ExtendedErrorCode`, mapping codes that
+    /// `from_u16` would reject to `ExtendedErrorCode::Unknown`.
+    fn from(value: u16) -> ExtendedErrorCode {
+        match ExtendedErrorCode::from_u16(value) {
+            Ok(x) => x,
+            Err(_) => ExtendedErrorCode::Unknown(value),
+        }
+    }
+}
+
+impl fmt::Display for ExtendedErrorCode {
+    /// Formats a code as its description, or as `INFO-CODE ###` if it
+    /// has none.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.description() {
+            Some(s) => write!(f, "{}", s),
+            None => write!(f, "INFO-CODE {}", self.to_u16()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_extendederrorcode {
+    use super::ExtendedErrorCode;
+    use super::IdentifierError;
+    #[test]
+    fn test_variant_identity() {
+        assert_eq!(ExtendedErrorCode::OtherError, ExtendedErrorCode::from_u16(0).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::UnsupportedDnskeyAlgorithm, ExtendedErrorCode::from_u16(1).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::UnsupportedDsDigestType, ExtendedErrorCode::from_u16(2).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::StaleAnswer, ExtendedErrorCode::from_u16(3).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::ForgedAnswer, ExtendedErrorCode::from_u16(4).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::DnssecIndeterminate, ExtendedErrorCode::from_u16(5).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::DnssecBogus, ExtendedErrorCode::from_u16(6).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::SignatureExpired, ExtendedErrorCode::from_u16(7).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::SignatureNotYetValid, ExtendedErrorCode::from_u16(8).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::DnskeyMissing, ExtendedErrorCode::from_u16(9).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::RrsigsMissing, ExtendedErrorCode::from_u16(10).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::NoZoneKeyBitSet, ExtendedErrorCode::from_u16(11).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::NsecMissing, ExtendedErrorCode::from_u16(12).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::CachedError, ExtendedErrorCode::from_u16(13).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::NotReady, ExtendedErrorCode::from_u16(14).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::Blocked, ExtendedErrorCode::from_u16(15).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::Censored, ExtendedErrorCode::from_u16(16).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::Filtered, ExtendedErrorCode::from_u16(17).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::Prohibited, ExtendedErrorCode::from_u16(18).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::StaleNxdomainAnswer, ExtendedErrorCode::from_u16(19).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::NotAuthoritative, ExtendedErrorCode::from_u16(20).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::NotSupported, ExtendedErrorCode::from_u16(21).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::NoReachableAuthority, ExtendedErrorCode::from_u16(22).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::NetworkError, ExtendedErrorCode::from_u16(23).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::InvalidData, ExtendedErrorCode::from_u16(24).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::SignatureExpiredBeforeValid, ExtendedErrorCode::from_u16(25).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::TooEarly, ExtendedErrorCode::from_u16(26).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::UnsupportedNsec3IterationsValue, ExtendedErrorCode::from_u16(27).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::UnableToConformToPolicy, ExtendedErrorCode::from_u16(28).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::Synthesized, ExtendedErrorCode::from_u16(29).ok().unwrap());
+        assert_eq!(ExtendedErrorCode::InvalidQueryType, ExtendedErrorCode::from_u16(30).ok().unwrap());
+
+    }
+
+    #[test]
+    fn test_unknown_identity() {
+        for i in 0..65536usize {
+            assert_eq!(ExtendedErrorCode::from(i as u16).to_u16(), i as u16);
+        }
+        assert_eq!(ExtendedErrorCode::Unknown(49152), ExtendedErrorCode::from(49152));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(ExtendedErrorCode::DnssecBogus.to_string(), "DNSSEC Bogus");
+        assert_eq!(ExtendedErrorCode::StaleNxdomainAnswer.to_string(), "Stale NXDOMAIN Answer");
+        assert_eq!(ExtendedErrorCode::Unknown(1000).to_string(), "INFO-CODE 1000");
+    }
+
+    #[test]
+    fn test_range_privateuse_identity() {
+        for i in 49152..(65535u64+1) {
+            assert_eq!(IdentifierError::PrivateUseIdentifierError(i as i64), ExtendedErrorCode::from_u16(i as u16).err().unwrap());
+        }
+
+    }
+    #[test]
+    fn test_range_unassigned_identity() {
+        for i in 31..(49151u64+1) {
+            assert_eq!(IdentifierError::UnassignedIdentifierError(i as i64), ExtendedErrorCode::from_u16(i as u16).err().unwrap());
+        }
+
+    }
+
+}
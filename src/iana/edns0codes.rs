@@ -1,7 +1,11 @@
 pub use super::IdentifierError;
 
+use std::cmp;
+use std::convert;
+use std::hash;
+
 #[repr(u16)]
-#[derive(PartialEq,Debug,Clone)]
+#[derive(Eq,Debug,Copy,Clone)]
 pub enum EDNS0OptionCode {
     LLQ = 1,
     UL = 2,
@@ -11,10 +15,43 @@ pub enum EDNS0OptionCode {
     N3U = 7,
     EdnsClientSubnet = 8,
     EDNS = 9,
+    Cookie = 10,
+    TcpKeepalive = 11,
+    Padding = 12,
+    Chain = 13,
+    KeyTag = 14,
+    ExtendedDnsError = 15,
 
+    /// A code without a variant of its own, kept as is so that
+    /// options using it can be passed through. It compares and hashes
+    /// equal to the variant of the same code, but patterns only match it
+    /// as `Unknown`; `From<u16>` never builds one for a code that has a
+    /// variant.
+    Unknown(u16),
 }
 
 impl EDNS0OptionCode {
+    pub fn to_u16(&self) -> u16 {
+        match *self {
+            EDNS0OptionCode::LLQ => 1,
+            EDNS0OptionCode::UL => 2,
+            EDNS0OptionCode::NSID => 3,
+            EDNS0OptionCode::DAU => 5,
+            EDNS0OptionCode::DHU => 6,
+            EDNS0OptionCode::N3U => 7,
+            EDNS0OptionCode::EdnsClientSubnet => 8,
+            EDNS0OptionCode::EDNS => 9,
+            EDNS0OptionCode::Cookie => 10,
+            EDNS0OptionCode::TcpKeepalive => 11,
+            EDNS0OptionCode::Padding => 12,
+            EDNS0OptionCode::Chain => 13,
+            EDNS0OptionCode::KeyTag => 14,
+            EDNS0OptionCode::ExtendedDnsError => 15,
+
+            EDNS0OptionCode::Unknown(x) => x,
+        }
+    }
+
     pub fn from_u16(value: u16) ->  Result<EDNS0OptionCode, IdentifierError> {
         match value {
             1 => Ok(EDNS0OptionCode::LLQ),
@@ -25,19 +62,48 @@ impl EDNS0OptionCode {
             7 => Ok(EDNS0OptionCode::N3U),
             8 => Ok(EDNS0OptionCode::EdnsClientSubnet),
             9 => Ok(EDNS0OptionCode::EDNS),
+            10 => Ok(EDNS0OptionCode::Cookie),
+            11 => Ok(EDNS0OptionCode::TcpKeepalive),
+            12 => Ok(EDNS0OptionCode::Padding),
+            13 => Ok(EDNS0OptionCode::Chain),
+            14 => Ok(EDNS0OptionCode::KeyTag),
+            15 => Ok(EDNS0OptionCode::ExtendedDnsError),
 
-            0 => Err(IdentifierError::ReservedIdentifierError(0 as i64)),
-            4 => Err(IdentifierError::ReservedIdentifierError(4 as i64)),
-            x @ 65001...65534 => Err(IdentifierError::ReservedIdentifierError(x as i64)),
-            65535 => Err(IdentifierError::ReservedIdentifierError(65535 as i64)),
+            0 => Err(IdentifierError::ReservedIdentifierError(0)),
+            4 => Err(IdentifierError::ReservedIdentifierError(4)),
+            x @ 65001..=65534 => Err(IdentifierError::ReservedIdentifierError(x as i64)),
+            65535 => Err(IdentifierError::ReservedIdentifierError(65535)),
 
-            x @ 10...65000 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            x @ 16..=65000 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+        }
+    }
+}
 
-            x @ _ => Err(IdentifierError::UnknownIdentifierError(x as i64)),
+impl convert::From<u16> for EDNS0OptionCode {
+    /// Convert a code to an `EDNS0OptionCode`, mapping codes that
+    /// `from_u16` would reject to `EDNS0OptionCode::Unknown`.
+    fn from(value: u16) -> EDNS0OptionCode {
+        match EDNS0OptionCode::from_u16(value) {
+            Ok(x) => x,
+            Err(_) => EDNS0OptionCode::Unknown(value),
         }
     }
 }
 
+impl cmp::PartialEq for EDNS0OptionCode {
+    /// Codes are compared by value, so `EDNS0OptionCode::Unknown` built
+    /// by hand equals the variant of its code.
+    fn eq(&self, other: &EDNS0OptionCode) -> bool {
+        self.to_u16() == other.to_u16()
+    }
+}
+
+impl hash::Hash for EDNS0OptionCode {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.to_u16().hash(state);
+    }
+}
+
 #[cfg(test)]
 mod test_edns0optioncode {
     use super::EDNS0OptionCode;
@@ -52,7 +118,39 @@ mod test_edns0optioncode {
         assert_eq!(EDNS0OptionCode::N3U, EDNS0OptionCode::from_u16(7).ok().unwrap());
         assert_eq!(EDNS0OptionCode::EdnsClientSubnet, EDNS0OptionCode::from_u16(8).ok().unwrap());
         assert_eq!(EDNS0OptionCode::EDNS, EDNS0OptionCode::from_u16(9).ok().unwrap());
+        assert_eq!(EDNS0OptionCode::Cookie, EDNS0OptionCode::from_u16(10).ok().unwrap());
+        assert_eq!(EDNS0OptionCode::TcpKeepalive, EDNS0OptionCode::from_u16(11).ok().unwrap());
+        assert_eq!(EDNS0OptionCode::Padding, EDNS0OptionCode::from_u16(12).ok().unwrap());
+        assert_eq!(EDNS0OptionCode::Chain, EDNS0OptionCode::from_u16(13).ok().unwrap());
+        assert_eq!(EDNS0OptionCode::KeyTag, EDNS0OptionCode::from_u16(14).ok().unwrap());
+        assert_eq!(EDNS0OptionCode::ExtendedDnsError, EDNS0OptionCode::from_u16(15).ok().unwrap());
+
+    }
 
+    #[test]
+    fn test_unknown_normalisation() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash,Hasher};
+        let hash = |x: EDNS0OptionCode| {
+            let mut h = DefaultHasher::new();
+            x.hash(&mut h);
+            h.finish()
+        };
+        // Unknown built by hand equals the variant of its code
+        assert_eq!(EDNS0OptionCode::Unknown(3), EDNS0OptionCode::NSID);
+        assert_eq!(hash(EDNS0OptionCode::Unknown(3)), hash(EDNS0OptionCode::NSID));
+        assert!(EDNS0OptionCode::Unknown(3) != EDNS0OptionCode::Unknown(65001));
+        assert!(matches!(EDNS0OptionCode::from(3), EDNS0OptionCode::NSID));
+    }
+
+    #[test]
+    fn test_unknown_identity() {
+        assert_eq!(EDNS0OptionCode::NSID, EDNS0OptionCode::from(3));
+        assert_eq!(EDNS0OptionCode::Unknown(65001), EDNS0OptionCode::from(65001));
+        for i in 0..65536usize {
+            assert_eq!(EDNS0OptionCode::from(i as u16).to_u16(), i as u16);
+            assert_eq!(EDNS0OptionCode::from_u16(i as u16).is_ok(), !matches!(EDNS0OptionCode::from(i as u16), EDNS0OptionCode::Unknown(_)));
+        }
     }
 
     #[test]
@@ -67,7 +165,7 @@ mod test_edns0optioncode {
     }
     #[test]
     fn test_range_unassigned_identity() {
-        for i in 10..(65000u64+1) {
+        for i in 16..(65000u64+1) {
             assert_eq!(IdentifierError::UnassignedIdentifierError(i as i64), EDNS0OptionCode::from_u16(i as u16).err().unwrap());
         }
 
@@ -2,6 +2,7 @@ use std::error;
 use std::fmt;
 
 #[derive(PartialEq,Debug,Copy,Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum IdentifierError {
     ReservedIdentifierError(i64),
     UnassignedIdentifierError(i64),
@@ -19,10 +20,8 @@ impl error::Error for IdentifierError {
         }
     }
 
-    fn cause(&self) -> Option<&error::Error> {
-        match *self {
-            _ => None,
-        }
+    fn cause(&self) -> Option<&dyn error::Error> {
+        None
     }
 }
 
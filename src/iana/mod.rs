@@ -5,10 +5,12 @@ pub use self::opcodes::OpCode;
 pub use self::rcodes::RCode;
 pub use self::types::Type;
 pub use self::edns0codes::EDNS0OptionCode;
+pub use self::edecodes::ExtendedErrorCode;
 
 pub mod errors;
 
 pub mod classes;
+pub mod edecodes;
 pub mod edns0codes;
 pub mod opcodes;
 pub mod rcodes;
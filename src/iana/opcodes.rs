@@ -12,6 +12,10 @@ pub enum OpCode {
 }
 
 impl OpCode {
+    pub fn to_u8(&self) -> u8 {
+        self.clone() as u8
+    }
+
     pub fn from_u8(value: u8) ->  Result<OpCode, IdentifierError> {
         match value {
             0 => Ok(OpCode::Query),
@@ -20,10 +24,10 @@ impl OpCode {
             4 => Ok(OpCode::Notify),
             5 => Ok(OpCode::Update),
 
-            3 => Err(IdentifierError::UnassignedIdentifierError(3 as i64)),
-            x @ 6...15 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            3 => Err(IdentifierError::UnassignedIdentifierError(3)),
+            x @ 6..=15 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
 
-            x @ _ => Err(IdentifierError::UnknownIdentifierError(x as i64)),
+            x => Err(IdentifierError::UnknownIdentifierError(x as i64)),
         }
     }
 }
@@ -21,10 +21,21 @@ pub enum RCode {
     BADNAME = 20,
     BADALG = 21,
     BADTRUNC = 22,
+    BADCOOKIE = 23,
 
 }
 
 impl RCode {
+    /// TSIG signature failure, as per
+    /// [RFC8945§5.3.2](https://tools.ietf.org/html/rfc8945#section-5.3.2).
+    /// It shares its value with `BADVERS`, which is only used in OPT
+    /// records.
+    pub const BADSIG: RCode = RCode::BADVERS;
+
+    pub fn to_u16(&self) -> u16 {
+        self.clone() as u16
+    }
+
     pub fn from_u16(value: u16) ->  Result<RCode, IdentifierError> {
         match value {
             0 => Ok(RCode::NoError),
@@ -45,16 +56,15 @@ impl RCode {
             20 => Ok(RCode::BADNAME),
             21 => Ok(RCode::BADALG),
             22 => Ok(RCode::BADTRUNC),
+            23 => Ok(RCode::BADCOOKIE),
 
-            x @ 3841...4095 => Err(IdentifierError::PrivateUseIdentifierError(x as i64)),
-
-            65535 => Err(IdentifierError::ReservedIdentifierError(65535 as i64)),
+            x @ 3841..=4095 => Err(IdentifierError::PrivateUseIdentifierError(x as i64)),
 
-            x @ 11...15 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
-            x @ 23...3840 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
-            x @ 4096...65534 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            65535 => Err(IdentifierError::ReservedIdentifierError(65535)),
 
-            x @ _ => Err(IdentifierError::UnknownIdentifierError(x as i64)),
+            x @ 11..=15 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            x @ 24..=3840 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            x @ 4096..=65534 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
         }
     }
 }
@@ -77,12 +87,14 @@ mod test_rcode {
         assert_eq!(RCode::NotAuth, RCode::from_u16(9).ok().unwrap());
         assert_eq!(RCode::NotZone, RCode::from_u16(10).ok().unwrap());
         assert_eq!(RCode::BADVERS, RCode::from_u16(16).ok().unwrap());
+        assert_eq!(RCode::BADSIG, RCode::from_u16(16).ok().unwrap());
         assert_eq!(RCode::BADKEY, RCode::from_u16(17).ok().unwrap());
         assert_eq!(RCode::BADTIME, RCode::from_u16(18).ok().unwrap());
         assert_eq!(RCode::BADMODE, RCode::from_u16(19).ok().unwrap());
         assert_eq!(RCode::BADNAME, RCode::from_u16(20).ok().unwrap());
         assert_eq!(RCode::BADALG, RCode::from_u16(21).ok().unwrap());
         assert_eq!(RCode::BADTRUNC, RCode::from_u16(22).ok().unwrap());
+        assert_eq!(RCode::BADCOOKIE, RCode::from_u16(23).ok().unwrap());
 
     }
 
@@ -103,7 +115,7 @@ mod test_rcode {
         for i in 11..(15u64+1) {
             assert_eq!(IdentifierError::UnassignedIdentifierError(i as i64), RCode::from_u16(i as u16).err().unwrap());
         }
-        for i in 23..(3840u64+1) {
+        for i in 24..(3840u64+1) {
             assert_eq!(IdentifierError::UnassignedIdentifierError(i as i64), RCode::from_u16(i as u16).err().unwrap());
         }
         for i in 4096..(65534u64+1) {
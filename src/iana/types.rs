@@ -1,7 +1,12 @@
 pub use super::IdentifierError;
 
+use std::cmp;
+use std::convert;
+use std::fmt;
+use std::hash;
+
 #[repr(u16)]
-#[derive(PartialEq,Debug,Clone)]
+#[derive(Eq,Debug,Copy,Clone)]
 pub enum Type {
     A = 1,
     NS = 2,
@@ -86,9 +91,104 @@ pub enum Type {
     TA = 32768,
     DLV = 32769,
 
+    /// A code without a variant of its own, kept as is so that
+    /// records using it can be handled as per RFC 3597. It compares and
+    /// hashes equal to the variant of the same code, but patterns only
+    /// match it as `Unknown`; `From<u16>` never builds one for a code
+    /// that has a variant.
+    Unknown(u16),
 }
 
 impl Type {
+    pub fn to_u16(&self) -> u16 {
+        match *self {
+            Type::A => 1,
+            Type::NS => 2,
+            Type::MD => 3,
+            Type::MF => 4,
+            Type::CNAME => 5,
+            Type::SOA => 6,
+            Type::MB => 7,
+            Type::MG => 8,
+            Type::MR => 9,
+            Type::NULL => 10,
+            Type::WKS => 11,
+            Type::PTR => 12,
+            Type::HINFO => 13,
+            Type::MINFO => 14,
+            Type::MX => 15,
+            Type::TXT => 16,
+            Type::RP => 17,
+            Type::AFSDB => 18,
+            Type::X25 => 19,
+            Type::ISDN => 20,
+            Type::RT => 21,
+            Type::NSAP => 22,
+            Type::NSAPPTR => 23,
+            Type::SIG => 24,
+            Type::KEY => 25,
+            Type::PX => 26,
+            Type::GPOS => 27,
+            Type::AAAA => 28,
+            Type::LOC => 29,
+            Type::NXT => 30,
+            Type::EID => 31,
+            Type::NIMLOC => 32,
+            Type::SRV => 33,
+            Type::ATMA => 34,
+            Type::NAPTR => 35,
+            Type::KX => 36,
+            Type::CERT => 37,
+            Type::A6 => 38,
+            Type::DNAME => 39,
+            Type::SINK => 40,
+            Type::OPT => 41,
+            Type::APL => 42,
+            Type::DS => 43,
+            Type::SSHFP => 44,
+            Type::IPSECKEY => 45,
+            Type::RRSIG => 46,
+            Type::NSEC => 47,
+            Type::DNSKEY => 48,
+            Type::DHCID => 49,
+            Type::NSEC3 => 50,
+            Type::NSEC3PARAM => 51,
+            Type::TLSA => 52,
+            Type::HIP => 55,
+            Type::NINFO => 56,
+            Type::RKEY => 57,
+            Type::TALINK => 58,
+            Type::CDS => 59,
+            Type::CDNSKEY => 60,
+            Type::OPENPGPKEY => 61,
+            Type::CSYNC => 62,
+            Type::SPF => 99,
+            Type::UINFO => 100,
+            Type::UID => 101,
+            Type::GID => 102,
+            Type::UNSPEC => 103,
+            Type::NID => 104,
+            Type::L32 => 105,
+            Type::L64 => 106,
+            Type::LP => 107,
+            Type::EUI48 => 108,
+            Type::EUI64 => 109,
+            Type::TKEY => 249,
+            Type::TSIG => 250,
+            Type::IXFR => 251,
+            Type::AXFR => 252,
+            Type::MAILB => 253,
+            Type::MAILA => 254,
+            Type::STAR => 255,
+            Type::URI => 256,
+            Type::CAA => 257,
+            Type::TA => 32768,
+            Type::DLV => 32769,
+
+            Type::Unknown(x) => x,
+        }
+    }
+
     pub fn from_u16(value: u16) ->  Result<Type, IdentifierError> {
         match value {
             1 => Ok(Type::A),
@@ -174,17 +274,238 @@ impl Type {
             32768 => Ok(Type::TA),
             32769 => Ok(Type::DLV),
 
-            x @ 65280...65534 => Err(IdentifierError::PrivateUseIdentifierError(x as i64)),
+            x @ 65280..=65534 => Err(IdentifierError::PrivateUseIdentifierError(x as i64)),
+
+            65535 => Err(IdentifierError::ReservedIdentifierError(65535)),
+
+            x @ 53..=54 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            x @ 63..=98 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            x @ 110..=248 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            x @ 258..=32767 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+            x @ 32770..=65279 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+
+            x => Err(IdentifierError::UnknownIdentifierError(x as i64)),
+        }
+    }
+
+    /// The presentation-format mnemonic of this type, if it has one.
+    pub fn mnemonic(&self) -> Option<&'static str> {
+        match *self {
+            Type::A => Some("A"),
+            Type::NS => Some("NS"),
+            Type::MD => Some("MD"),
+            Type::MF => Some("MF"),
+            Type::CNAME => Some("CNAME"),
+            Type::SOA => Some("SOA"),
+            Type::MB => Some("MB"),
+            Type::MG => Some("MG"),
+            Type::MR => Some("MR"),
+            Type::NULL => Some("NULL"),
+            Type::WKS => Some("WKS"),
+            Type::PTR => Some("PTR"),
+            Type::HINFO => Some("HINFO"),
+            Type::MINFO => Some("MINFO"),
+            Type::MX => Some("MX"),
+            Type::TXT => Some("TXT"),
+            Type::RP => Some("RP"),
+            Type::AFSDB => Some("AFSDB"),
+            Type::X25 => Some("X25"),
+            Type::ISDN => Some("ISDN"),
+            Type::RT => Some("RT"),
+            Type::NSAP => Some("NSAP"),
+            Type::NSAPPTR => Some("NSAP-PTR"),
+            Type::SIG => Some("SIG"),
+            Type::KEY => Some("KEY"),
+            Type::PX => Some("PX"),
+            Type::GPOS => Some("GPOS"),
+            Type::AAAA => Some("AAAA"),
+            Type::LOC => Some("LOC"),
+            Type::NXT => Some("NXT"),
+            Type::EID => Some("EID"),
+            Type::NIMLOC => Some("NIMLOC"),
+            Type::SRV => Some("SRV"),
+            Type::ATMA => Some("ATMA"),
+            Type::NAPTR => Some("NAPTR"),
+            Type::KX => Some("KX"),
+            Type::CERT => Some("CERT"),
+            Type::A6 => Some("A6"),
+            Type::DNAME => Some("DNAME"),
+            Type::SINK => Some("SINK"),
+            Type::OPT => Some("OPT"),
+            Type::APL => Some("APL"),
+            Type::DS => Some("DS"),
+            Type::SSHFP => Some("SSHFP"),
+            Type::IPSECKEY => Some("IPSECKEY"),
+            Type::RRSIG => Some("RRSIG"),
+            Type::NSEC => Some("NSEC"),
+            Type::DNSKEY => Some("DNSKEY"),
+            Type::DHCID => Some("DHCID"),
+            Type::NSEC3 => Some("NSEC3"),
+            Type::NSEC3PARAM => Some("NSEC3PARAM"),
+            Type::TLSA => Some("TLSA"),
+            Type::HIP => Some("HIP"),
+            Type::NINFO => Some("NINFO"),
+            Type::RKEY => Some("RKEY"),
+            Type::TALINK => Some("TALINK"),
+            Type::CDS => Some("CDS"),
+            Type::CDNSKEY => Some("CDNSKEY"),
+            Type::OPENPGPKEY => Some("OPENPGPKEY"),
+            Type::CSYNC => Some("CSYNC"),
+            Type::SPF => Some("SPF"),
+            Type::UINFO => Some("UINFO"),
+            Type::UID => Some("UID"),
+            Type::GID => Some("GID"),
+            Type::UNSPEC => Some("UNSPEC"),
+            Type::NID => Some("NID"),
+            Type::L32 => Some("L32"),
+            Type::L64 => Some("L64"),
+            Type::LP => Some("LP"),
+            Type::EUI48 => Some("EUI48"),
+            Type::EUI64 => Some("EUI64"),
+            Type::TKEY => Some("TKEY"),
+            Type::TSIG => Some("TSIG"),
+            Type::IXFR => Some("IXFR"),
+            Type::AXFR => Some("AXFR"),
+            Type::MAILB => Some("MAILB"),
+            Type::MAILA => Some("MAILA"),
+            Type::STAR => Some("ANY"),
+            Type::URI => Some("URI"),
+            Type::CAA => Some("CAA"),
+            Type::TA => Some("TA"),
+            Type::DLV => Some("DLV"),
+
+            Type::Unknown(_) => None,
+        }
+    }
+
+    /// Look up a type by its presentation-format mnemonic, such as
+    /// `"MX"`, or by the generic `TYPE###` form of
+    /// [RFC3597§5](https://tools.ietf.org/html/rfc3597#section-5).
+    /// Matching is case-insensitive.
+    pub fn from_mnemonic(s: &str) -> Option<Type> {
+        let upper = s.to_ascii_uppercase();
+        if upper.starts_with("TYPE") && upper.len() > 4 && upper[4..].bytes().all(|c| c.is_ascii_digit()) {
+            return upper[4..].parse::<u16>().ok().map(Type::from);
+        }
+        match &upper[..] {
+            "A" => Some(Type::A),
+            "NS" => Some(Type::NS),
+            "MD" => Some(Type::MD),
+            "MF" => Some(Type::MF),
+            "CNAME" => Some(Type::CNAME),
+            "SOA" => Some(Type::SOA),
+            "MB" => Some(Type::MB),
+            "MG" => Some(Type::MG),
+            "MR" => Some(Type::MR),
+            "NULL" => Some(Type::NULL),
+            "WKS" => Some(Type::WKS),
+            "PTR" => Some(Type::PTR),
+            "HINFO" => Some(Type::HINFO),
+            "MINFO" => Some(Type::MINFO),
+            "MX" => Some(Type::MX),
+            "TXT" => Some(Type::TXT),
+            "RP" => Some(Type::RP),
+            "AFSDB" => Some(Type::AFSDB),
+            "X25" => Some(Type::X25),
+            "ISDN" => Some(Type::ISDN),
+            "RT" => Some(Type::RT),
+            "NSAP" => Some(Type::NSAP),
+            "NSAP-PTR" => Some(Type::NSAPPTR),
+            "SIG" => Some(Type::SIG),
+            "KEY" => Some(Type::KEY),
+            "PX" => Some(Type::PX),
+            "GPOS" => Some(Type::GPOS),
+            "AAAA" => Some(Type::AAAA),
+            "LOC" => Some(Type::LOC),
+            "NXT" => Some(Type::NXT),
+            "EID" => Some(Type::EID),
+            "NIMLOC" => Some(Type::NIMLOC),
+            "SRV" => Some(Type::SRV),
+            "ATMA" => Some(Type::ATMA),
+            "NAPTR" => Some(Type::NAPTR),
+            "KX" => Some(Type::KX),
+            "CERT" => Some(Type::CERT),
+            "A6" => Some(Type::A6),
+            "DNAME" => Some(Type::DNAME),
+            "SINK" => Some(Type::SINK),
+            "OPT" => Some(Type::OPT),
+            "APL" => Some(Type::APL),
+            "DS" => Some(Type::DS),
+            "SSHFP" => Some(Type::SSHFP),
+            "IPSECKEY" => Some(Type::IPSECKEY),
+            "RRSIG" => Some(Type::RRSIG),
+            "NSEC" => Some(Type::NSEC),
+            "DNSKEY" => Some(Type::DNSKEY),
+            "DHCID" => Some(Type::DHCID),
+            "NSEC3" => Some(Type::NSEC3),
+            "NSEC3PARAM" => Some(Type::NSEC3PARAM),
+            "TLSA" => Some(Type::TLSA),
+            "HIP" => Some(Type::HIP),
+            "NINFO" => Some(Type::NINFO),
+            "RKEY" => Some(Type::RKEY),
+            "TALINK" => Some(Type::TALINK),
+            "CDS" => Some(Type::CDS),
+            "CDNSKEY" => Some(Type::CDNSKEY),
+            "OPENPGPKEY" => Some(Type::OPENPGPKEY),
+            "CSYNC" => Some(Type::CSYNC),
+            "SPF" => Some(Type::SPF),
+            "UINFO" => Some(Type::UINFO),
+            "UID" => Some(Type::UID),
+            "GID" => Some(Type::GID),
+            "UNSPEC" => Some(Type::UNSPEC),
+            "NID" => Some(Type::NID),
+            "L32" => Some(Type::L32),
+            "L64" => Some(Type::L64),
+            "LP" => Some(Type::LP),
+            "EUI48" => Some(Type::EUI48),
+            "EUI64" => Some(Type::EUI64),
+            "TKEY" => Some(Type::TKEY),
+            "TSIG" => Some(Type::TSIG),
+            "IXFR" => Some(Type::IXFR),
+            "AXFR" => Some(Type::AXFR),
+            "MAILB" => Some(Type::MAILB),
+            "MAILA" => Some(Type::MAILA),
+            "ANY" => Some(Type::STAR),
+            "URI" => Some(Type::URI),
+            "CAA" => Some(Type::CAA),
+            "TA" => Some(Type::TA),
+            "DLV" => Some(Type::DLV),
+            _ => None,
+        }
+    }
+}
+
+impl convert::From<u16> for Type {
+    /// Convert a code to a `Type`, mapping codes that `from_u16` would
+    /// reject to `Type::Unknown`.
+    fn from(value: u16) -> Type {
+        match Type::from_u16(value) {
+            Ok(x) => x,
+            Err(_) => Type::Unknown(value),
+        }
+    }
+}
 
-            65535 => Err(IdentifierError::ReservedIdentifierError(65535 as i64)),
+impl cmp::PartialEq for Type {
+    /// Codes are compared by value, so `Type::Unknown` built by hand
+    /// equals the variant of its code.
+    fn eq(&self, other: &Type) -> bool {
+        self.to_u16() == other.to_u16()
+    }
+}
 
-            x @ 53...54 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
-            x @ 63...98 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
-            x @ 110...248 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
-            x @ 258...32767 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
-            x @ 32770...65279 => Err(IdentifierError::UnassignedIdentifierError(x as i64)),
+impl hash::Hash for Type {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.to_u16().hash(state);
+    }
+}
 
-            x @ _ => Err(IdentifierError::UnknownIdentifierError(x as i64)),
+impl fmt::Display for Type {
+    /// Formats a type as its mnemonic, or as `TYPE###` if it has none.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mnemonic() {
+            Some(s) => write!(f, "{}", s),
+            None => write!(f, "TYPE{}", self.to_u16()),
         }
     }
 }
@@ -280,6 +601,50 @@ mod test_type {
 
     }
 
+    #[test]
+    fn test_unknown_normalisation() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash,Hasher};
+        let hash = |x: Type| {
+            let mut h = DefaultHasher::new();
+            x.hash(&mut h);
+            h.finish()
+        };
+        // Unknown built by hand equals the variant of its code
+        assert_eq!(Type::Unknown(1), Type::A);
+        assert_eq!(hash(Type::Unknown(1)), hash(Type::A));
+        assert!(Type::Unknown(1) != Type::Unknown(65001));
+        assert!(matches!(Type::from(1), Type::A));
+    }
+
+    #[test]
+    fn test_unknown_identity() {
+        assert_eq!(Type::A, Type::from(1));
+        assert_eq!(Type::Unknown(65280), Type::from(65280));
+        for i in 0..65536usize {
+            assert_eq!(Type::from(i as u16).to_u16(), i as u16);
+            assert_eq!(Type::from_u16(i as u16).is_ok(), !matches!(Type::from(i as u16), Type::Unknown(_)));
+        }
+    }
+
+    #[test]
+    fn test_mnemonic() {
+        assert_eq!(Some(Type::MX), Type::from_mnemonic("MX"));
+        assert_eq!(Some(Type::NSEC3PARAM), Type::from_mnemonic("nsec3param"));
+        assert_eq!(Some(Type::NSAPPTR), Type::from_mnemonic("NSAP-PTR"));
+        assert_eq!(Some(Type::A), Type::from_mnemonic("TYPE1"));
+        assert_eq!(Some(Type::Unknown(65280)), Type::from_mnemonic("type65280"));
+        assert_eq!(None, Type::from_mnemonic("TYPE65536"));
+        assert_eq!(None, Type::from_mnemonic("TYPE"));
+        assert_eq!(None, Type::from_mnemonic("BOGUS"));
+        for i in 0..65536usize {
+            let t = Type::from(i as u16);
+            assert_eq!(Some(t), Type::from_mnemonic(&t.to_string()));
+        }
+        assert_eq!(Type::NSAPPTR.to_string(), "NSAP-PTR");
+        assert_eq!(Type::Unknown(65280).to_string(), "TYPE65280");
+    }
+
     #[test]
     fn test_range_privateuse_identity() {
         for i in 65280..(65534u64+1) {
@@ -1,6 +1,17 @@
 #![crate_type = "lib"]
 #![crate_name = "dns"]
 
+extern crate md5;
+extern crate num_bigint;
+extern crate ring;
+extern crate siphasher;
+
+pub mod dnssec;
+pub mod edns;
+pub mod encoding;
 pub mod iana;
 pub mod proto;
 pub mod parser;
+pub mod tsig;
+pub mod writer;
+pub mod zone;
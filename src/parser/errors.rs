@@ -4,11 +4,21 @@ use std::error;
 use super::IdentifierError;
 
 #[derive(PartialEq,Copy,Clone,Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum ReadError {
     InvalidIdentifierError(super::IdentifierError),
     IndexOutOfRangeError(usize, usize),
     LabelTooLongError(usize),
     LabelZeroLengthError,
+    RDataLengthError(u16),
+    PointerLoopError(usize),
+    BadPointerError(usize),
+    NameTooLongError(usize),
+    LabelTypeError(u8),
+    InvalidEscapeError(usize),
+    OptOwnerError,
+    DuplicateOptError,
+    TypeBitmapError(usize),
 }
 
 impl error::Error for ReadError {
@@ -18,12 +28,21 @@ impl error::Error for ReadError {
             ReadError::IndexOutOfRangeError(_, _) => "Index out of range",
             ReadError::LabelTooLongError(_) => "Label was too long",
             ReadError::LabelZeroLengthError => "Label has zero length",
+            ReadError::RDataLengthError(_) => "RDATA length did not match its contents",
+            ReadError::PointerLoopError(_) => "Compression pointer loop",
+            ReadError::BadPointerError(_) => "Compression pointer does not point backwards",
+            ReadError::NameTooLongError(_) => "Name was too long",
+            ReadError::LabelTypeError(_) => "Unsupported label type",
+            ReadError::InvalidEscapeError(_) => "Invalid escape sequence",
+            ReadError::OptOwnerError => "OPT record owner is not the root",
+            ReadError::DuplicateOptError => "Message has more than one OPT record",
+            ReadError::TypeBitmapError(_) => "Invalid type bitmap",
         }
     }
 
-    fn cause(&self) -> Option<&error::Error> {
+    fn cause(&self) -> Option<&dyn error::Error> {
         match *self {
-            ReadError::InvalidIdentifierError(ref err) => Some(err as &error::Error),
+            ReadError::InvalidIdentifierError(ref err) => Some(err as &dyn error::Error),
             _ => None,
         }
     }
@@ -42,6 +61,15 @@ impl convert::From<IdentifierError> for ReadError {
             ReadError::IndexOutOfRangeError(x, y) => write!(f, "Index out of range: {} > {}", x, y),
             ReadError::LabelTooLongError(x) => write!(f, "Label was too long: {} > 63", x),
             ReadError::LabelZeroLengthError => write!(f, "Label has zero length"),
+            ReadError::RDataLengthError(x) => write!(f, "RDATA length did not match its contents: {}", x),
+            ReadError::PointerLoopError(x) => write!(f, "Compression pointer loop at offset {}", x),
+            ReadError::BadPointerError(x) => write!(f, "Compression pointer does not point backwards at offset {}", x),
+            ReadError::NameTooLongError(x) => write!(f, "Name was too long: {} > 255", x),
+            ReadError::LabelTypeError(x) => write!(f, "Unsupported label type: 0x{:02x}", x & 0xC0),
+            ReadError::InvalidEscapeError(x) => write!(f, "Invalid escape sequence at offset {}", x),
+            ReadError::OptOwnerError => write!(f, "OPT record owner is not the root"),
+            ReadError::DuplicateOptError => write!(f, "Message has more than one OPT record"),
+            ReadError::TypeBitmapError(x) => write!(f, "Invalid type bitmap window at offset {}", x),
         }
     }
 }
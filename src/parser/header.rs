@@ -0,0 +1,84 @@
+use super::{OpCode,RCode,IdentifierError};
+
+const QR: u16 = 0x8000;
+const OPCODE: u16 = 0x7800;
+const AA: u16 = 0x0400;
+const TC: u16 = 0x0200;
+const RD: u16 = 0x0100;
+const RA: u16 = 0x0080;
+const Z: u16 = 0x0040;
+const AD: u16 = 0x0020;
+const CD: u16 = 0x0010;
+const RCODE: u16 = 0x000F;
+
+/// A decoded view of the flags field of a DNS message header.
+///
+/// The OPCODE and RCODE fields are kept as raw values so that headers
+/// carrying unassigned codes can still be decoded and re-encoded; use
+/// `opcode()` and `rcode()` to interpret them.
+#[derive(PartialEq,Eq,Debug,Copy,Clone,Default)]
+pub struct Header {
+    pub qr: bool,
+    pub opcode: u8,
+    pub aa: bool,
+    pub tc: bool,
+    pub rd: bool,
+    pub ra: bool,
+    pub z: bool,
+    pub ad: bool,
+    pub cd: bool,
+    pub rcode: u8,
+}
+
+impl Header {
+    /// Decode a header from the 16-bit flags field of a message.
+    pub fn from_flags(flags: u16) -> Header {
+        Header {
+            qr: flags & QR != 0,
+            opcode: ((flags & OPCODE) >> 11) as u8,
+            aa: flags & AA != 0,
+            tc: flags & TC != 0,
+            rd: flags & RD != 0,
+            ra: flags & RA != 0,
+            z: flags & Z != 0,
+            ad: flags & AD != 0,
+            cd: flags & CD != 0,
+            rcode: (flags & RCODE) as u8,
+        }
+    }
+
+    /// Encode this header into the 16-bit flags field of a message.
+    pub fn to_flags(&self) -> u16 {
+        let mut flags = ((self.opcode as u16) << 11) & OPCODE;
+        flags |= (self.rcode as u16) & RCODE;
+        if self.qr { flags |= QR; }
+        if self.aa { flags |= AA; }
+        if self.tc { flags |= TC; }
+        if self.rd { flags |= RD; }
+        if self.ra { flags |= RA; }
+        if self.z { flags |= Z; }
+        if self.ad { flags |= AD; }
+        if self.cd { flags |= CD; }
+        flags
+    }
+
+    pub fn opcode(&self) -> Result<OpCode, IdentifierError> {
+        OpCode::from_u8(self.opcode)
+    }
+
+    pub fn set_opcode(&mut self, opcode: OpCode) {
+        self.opcode = opcode.to_u8();
+    }
+
+    /// The RCODE carried in the header. Only the low four bits of an
+    /// extended RCODE fit in the header; the rest live in the OPT
+    /// record.
+    pub fn rcode(&self) -> Result<RCode, IdentifierError> {
+        RCode::from_u16(self.rcode as u16)
+    }
+
+    /// Set the header RCODE to the low four bits of `rcode`.
+    pub fn set_rcode(&mut self, rcode: RCode) {
+        self.rcode = (rcode.to_u16() & RCODE) as u8;
+    }
+}
@@ -1,13 +1,37 @@
 use super::Type;
 use super::Class;
 use super::errors;
+use super::RCode;
+use super::header::Header;
+use super::super::edns::Edns;
+use super::rdata::{RData,read_dns_rdata};
+use super::super::writer::{WriteError,write_dns_rdata,write_dns_rdata_canonical};
+use super::super::encoding::decode_escape;
 
 use super::{_read_be_u16,_read_be_i32};
 
+use std::borrow::Cow;
 use std::fmt;
 use std::cmp;
 use std::cmp::Ordering;
-
+use std::hash;
+
+/// A `Message` that does not borrow from a buffer.
+pub type OwnedMessage = Message<'static>;
+/// A `Question` that does not borrow from a buffer.
+pub type OwnedQuestion = Question<'static>;
+/// A `ResourceRecord` that does not borrow from a buffer.
+pub type OwnedResourceRecord = ResourceRecord<'static>;
+/// A `Name` that does not borrow from a buffer.
+pub type OwnedName = Name<'static>;
+/// A `Label` that does not borrow from a buffer.
+pub type OwnedLabel = Label<'static>;
+
+/// A DNS message.
+///
+/// Parsed messages borrow names and RDATA from the buffer they were read
+/// from. Use `into_owned` to get an `OwnedMessage` that can outlive it.
+#[derive(Debug,Clone)]
 pub struct Message<'n> {
     pub id: u16,
     pub flags: u16,
@@ -17,6 +41,125 @@ pub struct Message<'n> {
     pub additionals: Vec<ResourceRecord<'n>>,
 }
 
+/// Whether `rr` is a TSIG or SIG(0) record, which signs the whole
+/// message it ends.
+fn is_transaction_signature(rr: &ResourceRecord) -> bool {
+    match rr.rtype {
+        Type::TSIG => true,
+        Type::SIG => matches!(rr.rdata(), Ok(RData::SIG { type_covered, .. }) if type_covered == Type::from(0)),
+        _ => false,
+    }
+}
+
+impl<'n> Message<'n> {
+    /// Create an empty message with the given ID and header flags.
+    pub fn new(id: u16, flags: u16) -> Message<'n> {
+        Message {
+            id,
+            flags,
+            questions: Vec::new(),
+            answers: Vec::new(),
+            nameservers: Vec::new(),
+            additionals: Vec::new(),
+        }
+    }
+
+    /// Decode the header flags of this message.
+    pub fn header(&self) -> Header {
+        Header::from_flags(self.flags)
+    }
+
+    /// Re-encode the header flags of this message from `header`.
+    pub fn set_header(&mut self, header: &Header) {
+        self.flags = header.to_flags();
+    }
+
+    /// Decode the OPT pseudo-record of this message, if it has one.
+    /// A message with more than one OPT record is malformed as per
+    /// [RFC6891§6.1.1](https://tools.ietf.org/html/rfc6891#section-6.1.1).
+    pub fn edns(&self) -> Result<Option<Edns<'_>>, errors::ReadError> {
+        let mut opts = self.additionals.iter().filter(|r| r.rtype == Type::OPT);
+        let edns = match opts.next() {
+            Some(rr) => Edns::from_record(rr)?,
+            None => return Ok(None),
+        };
+        if opts.next().is_some() {
+            return Err(errors::ReadError::DuplicateOptError);
+        }
+        Ok(Some(edns))
+    }
+
+    /// Replace any OPT pseudo-record of this message with one encoding
+    /// `edns`, at the end of the additional section but before any TSIG
+    /// or SIG(0) record, which must stay last as per
+    /// [RFC8945§5.1](https://tools.ietf.org/html/rfc8945#section-5.1) and
+    /// [RFC2931§3](https://tools.ietf.org/html/rfc2931#section-3).
+    pub fn set_edns(&mut self, edns: &Edns) -> Result<(), WriteError> {
+        let rr = edns.to_record()?;
+        self.clear_edns();
+        let at = match self.additionals.last() {
+            Some(last) if is_transaction_signature(last) => self.additionals.len() - 1,
+            _ => self.additionals.len(),
+        };
+        self.additionals.insert(at, rr);
+        Ok(())
+    }
+
+    /// Remove any OPT pseudo-record from this message.
+    pub fn clear_edns(&mut self) {
+        self.additionals.retain(|r| r.rtype != Type::OPT);
+    }
+
+    /// The RCODE of this message, combining the 4 bits in the header
+    /// with the upper 8 bits in the OPT record, if there is one, into
+    /// the 12-bit extended RCODE.
+    pub fn rcode(&self) -> Result<RCode, errors::ReadError> {
+        let upper = match self.edns()? {
+            Some(edns) => edns.extended_rcode as u16,
+            None => 0,
+        };
+        Ok(RCode::from_u16(upper << 4 | self.header().rcode as u16)?)
+    }
+
+    /// Set the RCODE of this message. The upper 8 bits of an extended
+    /// RCODE are stored in the OPT record, which is added with default
+    /// settings if it is needed and missing.
+    pub fn set_rcode(&mut self, rcode: RCode) -> Result<(), WriteError> {
+        let code = rcode.to_u16();
+        let mut header = self.header();
+        header.rcode = (code & 0x0F) as u8;
+        self.set_header(&header);
+
+        let edns = self.edns()?.map(|e| e.into_owned());
+        let edns = match edns {
+            Some(e) => Some(e),
+            None if code > 0x0F => Some(Edns::new()),
+            None => None,
+        };
+        if let Some(mut edns) = edns {
+            edns.extended_rcode = (code >> 4) as u8;
+            self.set_edns(&edns)?;
+        }
+        Ok(())
+    }
+
+    /// Convert this message into one that owns all of its data.
+    pub fn into_owned(self) -> Result<OwnedMessage, WriteError> {
+        Ok(Message {
+            id: self.id,
+            flags: self.flags,
+            questions: self.questions.into_iter().map(|q| q.into_owned()).collect(),
+            answers: owned_records(self.answers)?,
+            nameservers: owned_records(self.nameservers)?,
+            additionals: owned_records(self.additionals)?,
+        })
+    }
+}
+
+fn owned_records(records: Vec<ResourceRecord>) -> Result<Vec<OwnedResourceRecord>, WriteError> {
+    records.into_iter().map(|r| r.into_owned()).collect()
+}
+
 #[derive(PartialEq,Debug,Clone)]
 pub struct Question<'n> {
     pub qname: Name<'n>,
@@ -24,7 +167,18 @@ pub struct Question<'n> {
     pub qclass: Class,
 }
 
-#[derive(PartialEq,Debug,Clone)]
+impl<'n> Question<'n> {
+    /// Convert this question into one that owns its name.
+    pub fn into_owned(self) -> OwnedQuestion {
+        Question {
+            qname: self.qname.into_owned(),
+            qtype: self.qtype,
+            qclass: self.qclass,
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
 pub struct ResourceRecord<'n> {
     pub rname: Name<'n>,
     pub rtype: Type,
@@ -32,7 +186,87 @@ pub struct ResourceRecord<'n> {
     pub rttl: i32,
     pub rdlen: u16,
     pub rdata: usize,
-    pub context: &'n [u8],
+    pub context: Cow<'n, [u8]>,
+}
+
+impl<'n> ResourceRecord<'n> {
+    /// Create a resource record from uncompressed wire-format RDATA,
+    /// such as that produced by `writer::write_dns_rdata`.
+    pub fn new<R: Into<Cow<'n, [u8]>>>(rname: Name<'n>, rtype: Type, rclass: Class, rttl: i32, rdata: R) -> ResourceRecord<'n> {
+        let rdata = rdata.into();
+        ResourceRecord {
+            rname,
+            rtype,
+            rclass,
+            rttl,
+            rdlen: rdata.len() as u16,
+            rdata: 0,
+            context: rdata,
+        }
+    }
+
+    /// Decode the RDATA of this record according to its type. Names
+    /// in the RDATA are read from `context`, so compression pointers
+    /// into the rest of the message are followed.
+    pub fn rdata(&self) -> Result<RData<'_>, errors::ReadError> {
+        let mut i = self.rdata;
+        read_dns_rdata(&self.rtype, &self.context, &mut i, self.rdlen)
+    }
+
+    /// Convert this record into one that owns its name and RDATA. Names
+    /// in the RDATA are decompressed, so the owned record no longer
+    /// depends on the message it was read from.
+    pub fn into_owned(self) -> Result<OwnedResourceRecord, WriteError> {
+        let rdata = write_dns_rdata(&self.rdata()?)?;
+        Ok(ResourceRecord::new(self.rname.into_owned(), self.rtype, self.rclass, self.rttl, rdata))
+    }
+}
+
+impl<'n> ResourceRecord<'n> {
+    /// The RDATA of this record in canonical form, with names
+    /// decompressed, or as is if it cannot be decoded.
+    fn canonical_rdata(&self) -> Cow<'_, [u8]> {
+        match self.rdata().map(|rdata| write_dns_rdata_canonical(&rdata)) {
+            Ok(Ok(rdata)) => Cow::Owned(rdata),
+            _ => {
+                let end = cmp::min(self.rdata + self.rdlen as usize, self.context.len());
+                Cow::Borrowed(self.context.get(self.rdata..end).unwrap_or(&[]))
+            },
+        }
+    }
+}
+
+impl<'n, 'm> cmp::PartialEq<ResourceRecord<'m>> for ResourceRecord<'n> {
+
+    /// Records are equal if their owner, type, class, TTL and RDATA are.
+    /// RDATA is compared in canonical form, so a record equals its
+    /// `into_owned` copy whatever compression the message it was read
+    /// from used.
+    fn eq(&self, other: &ResourceRecord<'m>) -> bool {
+        self.rname == other.rname
+            && self.rtype == other.rtype
+            && self.rclass == other.rclass
+            && self.rttl == other.rttl
+            && self.canonical_rdata() == other.canonical_rdata()
+    }
+}
+
+impl<'n> fmt::Display for ResourceRecord<'n> {
+
+    /// Formats a record in master file presentation format, as
+    /// `owner TTL class type rdata`. RDATA that cannot be decoded is
+    /// shown in the generic `\# <length> <hex>` form.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} {} ", self.rname, self.rttl, self.rclass, self.rtype)?;
+        match self.rdata() {
+            Ok(rdata) => write!(f, "{}", rdata),
+            Err(_) => {
+                let end = cmp::min(self.rdata + self.rdlen as usize, self.context.len());
+                let data = self.context.get(self.rdata..end).unwrap_or(&[]);
+                write!(f, "{}", RData::Opaque(Cow::Borrowed(data)))
+            },
+        }
+    }
 }
 
 /// A DNS domain name.
@@ -47,32 +281,141 @@ pub struct Name<'n> {
     pub labels: Vec<Label<'n>>,
 }
 impl<'n> Name<'n> {
-    #[inline]
-    pub fn to_string(&self) -> String {
-        format!("{}", self)
-    }
-
     /// Parse a DNS name from the RDATA section of a DNS resource record.
     /// The `context` field in the `ResourceRecord` struct exists so that
     /// this function can properly follow pointers.
     pub fn from_rdata<'r>(rr: &'r ResourceRecord) -> Result<Name<'r>, errors::ReadError> {
         let mut i = rr.rdata;
-        read_dns_name(rr.context, &mut i)
+        read_dns_name(&rr.context, &mut i)
     }
-    pub fn from_str(s: &'n str) -> Result<Name, errors::ReadError> {
-        let mut n = Name { labels: Vec::new() };
-        for x in s.split('.') {
-            if x.len() == 0 { continue; }
-            n.labels.push(try!(Label::from_slice(x.as_bytes())));
+
+    /// Convert this name into one that owns its labels.
+    pub fn into_owned(self) -> OwnedName {
+        Name { labels: self.labels.into_iter().map(|l| l.into_owned()).collect() }
+    }
+    /// Parse a name in presentation format, such as `www.example.com.`,
+    /// as per [RFC1035§5.1](https://tools.ietf.org/html/rfc1035#section-5.1).
+    ///
+    /// This is the inverse of `Display`: `\X` and `\DDD` escapes are
+    /// decoded, and an unescaped `.` separates labels. The name is
+    /// treated as fully qualified whether or not it ends in `.`; use
+    /// `from_str_relative` to resolve relative names. Labels borrow
+    /// from `s` unless they contain escapes.
+    ///
+    /// The empty string is the root, like `.`. Empty labels, as in
+    /// `a..b` or `.a`, are rejected with `LabelZeroLengthError`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &'n str) -> Result<Name<'n>, errors::ReadError> {
+        if s.is_empty() {
+            return Ok(Name { labels: Vec::new() });
         }
-        Ok(n)
+        Ok(Name::from_presentation(s.as_bytes())?.0)
+    }
+
+    /// Parse a name in presentation format, appending `origin` unless
+    /// the name ends in an unescaped `.`. The name `@` is `origin`
+    /// itself.
+    pub fn from_str_relative(s: &'n str, origin: &Name<'n>) -> Result<Name<'n>, errors::ReadError> {
+        if s == "@" {
+            return Ok(origin.clone());
+        }
+        let (mut name, absolute) = Name::from_presentation(s.as_bytes())?;
+        if !absolute {
+            name.labels.extend(origin.labels.iter().cloned());
+            name.check_length()?;
+        }
+        Ok(name)
+    }
+
+    /// Parse presentation-format octets, returning the name and whether
+    /// it is absolute, i.e. ends in an unescaped `.`. The name `.` is
+    /// the root, which is absolute.
+    pub fn from_presentation(text: &'n [u8]) -> Result<(Name<'n>, bool), errors::ReadError> {
+        if text == b"." {
+            return Ok((Name { labels: Vec::new() }, true));
+        }
+
+        let mut labels = Vec::new();
+        let mut start = 0;
+        // The decoded octets of the current label, once it is known to
+        // contain an escape
+        let mut unescaped: Option<Vec<u8>> = None;
+        let mut i = 0;
+        while i < text.len() {
+            match text[i] {
+                b'\\' => {
+                    let (c, len) = decode_escape(text, i).ok_or(errors::ReadError::InvalidEscapeError(i))?;
+                    unescaped.get_or_insert_with(|| text[start..i].to_vec()).push(c);
+                    i += len;
+                },
+                b'.' => {
+                    labels.push(match unescaped.take() {
+                        Some(v) => Label::from_cow(Cow::Owned(v))?,
+                        None => Label::from_slice(&text[start..i])?,
+                    });
+                    i += 1;
+                    start = i;
+                },
+                c => {
+                    if let Some(ref mut v) = unescaped {
+                        v.push(c);
+                    }
+                    i += 1;
+                },
+            }
+        }
+
+        // A name ending in a label separator is absolute; otherwise the
+        // last label has not been added yet
+        let absolute = !text.is_empty() && start == text.len() && unescaped.is_none();
+        if !absolute {
+            labels.push(match unescaped {
+                Some(v) => Label::from_cow(Cow::Owned(v))?,
+                None => Label::from_slice(&text[start..])?,
+            });
+        }
+
+        let name = Name { labels };
+        name.check_length()?;
+        Ok((name, absolute))
+    }
+
+    /// Check that this name is no longer than 255 octets in wire format.
+    fn check_length(&self) -> Result<(), errors::ReadError> {
+        let len = self.labels.iter().fold(1, |acc, l| acc + 1 + l.label.len());
+        if len > 255 {
+            return Err(errors::ReadError::NameTooLongError(len));
+        }
+        Ok(())
+    }
+}
+impl<'n> Name<'n> {
+    /// Compare names in the canonical order of
+    /// [RFC4034§6.1](https://tools.ietf.org/html/rfc4034#section-6.1):
+    /// labels are compared from the root down as case-insensitive octet
+    /// strings, and a name sorts before the names below it.
+    pub fn canonical_cmp(&self, other: &Name) -> Ordering {
+        for (a, b) in self.labels.iter().rev().zip(other.labels.iter().rev()) {
+            match a.cmp(b) {
+                Ordering::Equal => continue,
+                x => return x,
+            }
+        }
+        self.labels.len().cmp(&other.labels.len())
+    }
+
+    /// Whether this name is `zone` or a name below it, comparing labels
+    /// case-insensitively. Every name is a subdomain of the root.
+    pub fn is_subdomain_of(&self, zone: &Name) -> bool {
+        self.labels.len() >= zone.labels.len()
+            && self.labels.iter().rev().zip(zone.labels.iter().rev()).all(|(a, b)| a == b)
     }
 }
 impl<'n> fmt::Display for Name<'n> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.labels.len() > 0 {
+        if !self.labels.is_empty() {
             for l in self.labels.iter() {
-                try!(write!(f, "{}.", l));
+                write!(f, "{}.", l)?;
             }
             write!(f, "")
         } else {
@@ -82,32 +425,14 @@ impl<'n> fmt::Display for Name<'n> {
 }
 impl<'n> cmp::PartialOrd for Name<'n> {
     fn partial_cmp(&self, other: &Name<'n>) -> Option<Ordering> {
-        let sl = self.labels.len();
-        let ol = other.labels.len();
-        let mut case = Ordering::Equal;
-        let max_depth = cmp::min(sl, ol);
-        for i in 1..(max_depth + 1) {
-            if self.labels[sl - i] == other.labels[ol - i] {
-                if self.labels[sl - i] < other.labels[ol - i] {
-                    case = Ordering::Less;
-                } else if self.labels[sl - i] > other.labels[ol - i] {
-                    case = Ordering::Greater;
-                } else {
-                    continue;
-                }
-            } else if self.labels[sl - i] < other.labels[ol - i] {
-                return Some(Ordering::Less);
-            } else if self.labels[sl - i] > other.labels[ol - i] {
-                return Some(Ordering::Greater);
-            }
-        }
-        if sl > ol {
-            Some(Ordering::Greater)
-        } else if sl < ol {
-            Some(Ordering::Less)
-        } else {
-            Some(case)
-        }
+        Some(self.cmp(other))
+    }
+}
+impl<'n> cmp::Ord for Name<'n> {
+
+    /// Names are ordered canonically, as by `canonical_cmp`.
+    fn cmp(&self, other: &Name<'n>) -> Ordering {
+        self.canonical_cmp(other)
     }
 }
 
@@ -115,16 +440,35 @@ impl<'n> cmp::PartialOrd for Name<'n> {
 ///
 /// A label may consist of between 1 and 63 octets of any value `0x00`
 /// to `0xFF`
-#[derive(Eq,Hash,Debug,Clone)]
+#[derive(Eq,Debug,Clone)]
 pub struct Label<'l> {
-    label: &'l [u8],
+    label: Cow<'l, [u8]>,
 }
 
 impl<'l> Label<'l> {
-    pub fn from_slice(slice: &'l [u8]) -> Result<Label, errors::ReadError> {
-        if slice.len() > 63 { return Err(errors::ReadError::LabelTooLongError(slice.len())); }
-        if slice.len() == 0 { return Err(errors::ReadError::LabelZeroLengthError); }
-        Ok(Label { label: slice, })
+    pub fn from_slice(slice: &'l [u8]) -> Result<Label<'l>, errors::ReadError> {
+        Label::from_cow(Cow::Borrowed(slice))
+    }
+
+    /// Create a label that owns its octets.
+    pub fn from_vec(vec: Vec<u8>) -> Result<OwnedLabel, errors::ReadError> {
+        Label::from_cow(Cow::Owned(vec))
+    }
+
+    fn from_cow(label: Cow<'l, [u8]>) -> Result<Label<'l>, errors::ReadError> {
+        if label.len() > 63 { return Err(errors::ReadError::LabelTooLongError(label.len())); }
+        if label.is_empty() { return Err(errors::ReadError::LabelZeroLengthError); }
+        Ok(Label { label, })
+    }
+
+    /// The raw octets of this label.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.label
+    }
+
+    /// Convert this label into one that owns its octets.
+    pub fn into_owned(self) -> OwnedLabel {
+        Label { label: Cow::Owned(self.label.into_owned()) }
     }
 }
 impl<'l> fmt::Display for Label<'l> {
@@ -136,15 +480,17 @@ impl<'l> fmt::Display for Label<'l> {
             match *c {
                 // Non-printing character ranges are shown as \XXX
                 // where XXX is the char's zero-padded decimal repr
-                x @ 0x00...0x20 | x @ 0x7F...0xFF | x @ 0x2E => {
-                    try!(write!(f, "\\{:03}", x));
+                x @ 0x00..=0x20 | x @ 0x7F..=0xFF | x @ 0x2E => {
+                    write!(f, "\\{:03}", x)?;
                 },
-                // Printing character ranges are shown as is
-                x @ 0x21...0x2D | x @ 0x2F...0x7E => {
-                    try!(write!(f, "{}", x as char));
+                // Characters with a special meaning in master files
+                // are shown as \X
+                x @ b'"' | x @ b'(' | x @ b')' | x @ b';' | x @ b'\\' | x @ b'@' | x @ b'$' => {
+                    write!(f, "\\{}", x as char)?;
                 },
-                _ => {
-                    return Err(fmt::Error);
+                // Printing character ranges are shown as is
+                x => {
+                    write!(f, "{}", x as char)?;
                 },
             }
         }
@@ -154,22 +500,22 @@ impl<'l> fmt::Display for Label<'l> {
 impl<'l> cmp::PartialEq for Label<'l> {
 
     /// Domain names are compared for equality case-insensitively for
-    /// alphabetic characters (`0x41...0x5A`, `0x61...0x71`).
+    /// alphabetic characters (`0x41..=0x5A`, `0x61..=0x71`).
     fn eq(&self, other: &Label) -> bool {
         let sl = self.label.len();
         let ol = other.label.len();
         if sl != ol { return false; }
         for i in 0..cmp::min(sl, ol) {
             match self.label[i] {
-                x @ 0x41...0x5A => {
+                x @ 0x41..=0x5A => {
                     // Compare uppercase letters case-insensitively
                     if x == other.label[i] || (x + 32) == other.label[i] { continue; } else { return false; }
                 },
-                x @ 0x61...0x7A => {
+                x @ 0x61..=0x7A => {
                     // Compare lowercase letters case-insensitively
                     if x == other.label[i] || (x - 32) == other.label[i] { continue; } else { return false; }
                 },
-                x @ _ => {
+                x => {
                     // Direct equality for non-alphabetic characters
                     if x == other.label[i] { continue; } else { return false; }
                 }
@@ -178,50 +524,54 @@ impl<'l> cmp::PartialEq for Label<'l> {
         true
     }
 }
-impl<'l> cmp::PartialOrd for Label<'l> {
+impl<'l> hash::Hash for Label<'l> {
 
-    /// Domain names are compared for ordering based on the ordinal
-    /// values of characters (i.e., 'A' is hex `0x41`, and 'a' is hex `0x61`,
-    /// so 'A' orders before 'a')
-    fn partial_cmp(&self, other: &Label) -> Option<Ordering> {
-        let sl = self.label.len();
-        let ol = other.label.len();
-        let mut caps = Ordering::Equal;
-
-        // Don't try to compare outside bounds if one label is shorter
-        for i in 0..cmp::min(sl, ol) {
-            let sli = self.label[i];
-            let oli = other.label[i];
-
-            // Strip casing from [a-z] characters for initial comparison
-            let sll = if 0x61 <= sli && sli <= 0x7A { sli - 32 } else { sli };
-            let oll = if 0x61 <= oli && oli <= 0x7A { oli - 32 } else { oli };
-
-            // Compare case-insensitively first
-            if sll < oll {
-                return Some(Ordering::Less);
-            } else if sll > oll {
-                return Some(Ordering::Greater);
-            } else {
-                // Compare case-sensitively second, if equal
-                if caps == Ordering::Equal {
-                    if sli < oli {
-                        caps = Ordering::Less;
-                    } else if sli > oli {
-                        caps = Ordering::Greater;
-                    }
-                }
-            }
+    /// Labels hash consistently with their case-insensitive equality by
+    /// folding `0x41...0x5A` to lowercase before hashing.
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        state.write_usize(self.label.len());
+        for c in self.label.iter() {
+            state.write_u8(c.to_ascii_lowercase());
         }
-        // Resolve length-sensitive ordering before case-sensitive
-        // (probably more efficient in
-        if sl > ol { Some(Ordering::Greater) }
-        else if sl < ol { Some(Ordering::Less) }
-        else { Some(caps) }
+    }
+}
+impl<'l> cmp::PartialOrd for Label<'l> {
+    fn partial_cmp(&self, other: &Label) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<'l> cmp::Ord for Label<'l> {
+
+    /// Labels are ordered as in
+    /// [RFC4034§6.1](https://tools.ietf.org/html/rfc4034#section-6.1):
+    /// as octet strings with uppercase letters folded to lowercase, so
+    /// the order agrees with case-insensitive equality.
+    fn cmp(&self, other: &Label) -> Ordering {
+        let a = self.label.iter().map(|c| c.to_ascii_lowercase());
+        let b = other.label.iter().map(|c| c.to_ascii_lowercase());
+        a.cmp(b)
     }
 }
 
+/// Whether `response` answers `query`: it must be a response with the
+/// same ID and echo the question section, as per
+/// [RFC5452§9.1](https://tools.ietf.org/html/rfc5452#section-9.1).
+/// Names are compared case-insensitively, since servers need not
+/// preserve the case of the query.
+pub fn is_response_to(response: &Message, query: &Message) -> bool {
+    response.id == query.id
+        && response.header().qr
+        && response.questions.len() == query.questions.len()
+        && response.questions.iter().zip(query.questions.iter()).all(|(r, q)| {
+            r.qtype == q.qtype && r.qclass == q.qclass && r.qname.canonical_cmp(&q.qname) == Ordering::Equal
+        })
+}
+
 /// Read a DNS message from a `&[u8]` buffer.
+///
+/// Types and classes without a variant of their own are read as
+/// `Unknown`, so messages containing them parse losslessly as per
+/// [RFC3597](https://tools.ietf.org/html/rfc3597).
 pub fn read_dns_message<'b>(buf: &'b [u8]) -> Result<Message<'b>, errors::ReadError> {
     // Validate minimum length
     if buf.len() < 12 {
@@ -238,8 +588,8 @@ pub fn read_dns_message<'b>(buf: &'b [u8]) -> Result<Message<'b>, errors::ReadEr
     let adcount = _read_be_u16(buf, &mut i);
 
     let mut msg = Message {
-        id: id,
-        flags: flags,
+        id,
+        flags,
         questions: Vec::new(),
         answers: Vec::new(),
         nameservers: Vec::new(),
@@ -248,26 +598,41 @@ pub fn read_dns_message<'b>(buf: &'b [u8]) -> Result<Message<'b>, errors::ReadEr
 
     // Read questions, answers, nameservers, and additional records
     for _ in 0..qdcount {
-        msg.questions.push(try!(read_dns_question(buf, &mut i)));
+        msg.questions.push(read_dns_question(buf, &mut i)?);
     }
     for _ in 0..ancount {
-        msg.answers.push(try!(read_dns_resource_record(buf, &mut i)));
+        msg.answers.push(read_dns_resource_record(buf, &mut i)?);
     }
     for _ in 0..nscount {
-        msg.nameservers.push(try!(read_dns_resource_record(buf, &mut i)));
+        msg.nameservers.push(read_dns_resource_record(buf, &mut i)?);
     }
     for _ in 0..adcount {
-        msg.additionals.push(try!(read_dns_resource_record(buf, &mut i)));
+        msg.additionals.push(read_dns_resource_record(buf, &mut i)?);
     }
 
     Ok(msg)
 }
 
+/// Read a DNS message from a `&[u8]` buffer, rejecting any question or
+/// record whose type or class is reserved, unassigned or private-use.
+pub fn read_dns_message_strict<'b>(buf: &'b [u8]) -> Result<Message<'b>, errors::ReadError> {
+    let msg = read_dns_message(buf)?;
+    for q in msg.questions.iter() {
+        Type::from_u16(q.qtype.to_u16())?;
+        Class::from_u16(q.qclass.to_u16())?;
+    }
+    for r in msg.answers.iter().chain(msg.nameservers.iter()).chain(msg.additionals.iter()) {
+        Type::from_u16(r.rtype.to_u16())?;
+        Class::from_u16(r.rclass.to_u16())?;
+    }
+    Ok(msg)
+}
+
 /// Read a single DNS question from a buffer
 #[inline(always)]
 pub fn read_dns_question<'b>(buf: &'b [u8], idx: &mut usize) -> Result<Question<'b>, errors::ReadError> {
     let mut q = Question {
-        qname: try!(read_dns_name(buf, idx)),
+        qname: read_dns_name(buf, idx)?,
         qtype: Type::A,
         qclass: Class::IN,
     };
@@ -275,8 +640,8 @@ pub fn read_dns_question<'b>(buf: &'b [u8], idx: &mut usize) -> Result<Question<
     if *idx + 4 > buf.len() {
         return Err(errors::ReadError::IndexOutOfRangeError(*idx + 4, buf.len()));
     }
-    q.qtype = try!(Type::from_u16(_read_be_u16(buf, idx)));
-    q.qclass = try!(Class::from_u16(_read_be_u16(buf, idx)));
+    q.qtype = Type::from(_read_be_u16(buf, idx));
+    q.qclass = Class::from(_read_be_u16(buf, idx));
     Ok(q)
 }
 
@@ -284,20 +649,20 @@ pub fn read_dns_question<'b>(buf: &'b [u8], idx: &mut usize) -> Result<Question<
 #[inline(always)]
 pub fn read_dns_resource_record<'b>(buf: &'b [u8], idx: &mut usize) -> Result<ResourceRecord<'b>, errors::ReadError> {
     let mut r = ResourceRecord {
-        rname: try!(read_dns_name(buf, idx)),
+        rname: read_dns_name(buf, idx)?,
         rtype: Type::A,
         rclass: Class::IN,
         rttl: 0,
         rdlen: 0,
         rdata: *idx,
-        context: buf,
+        context: Cow::Borrowed(buf),
     };
     // Check bounds before reading fixed-length RR data
-    if *idx + 10 >= buf.len() {
+    if *idx + 10 > buf.len() {
         return Err(errors::ReadError::IndexOutOfRangeError(*idx + 10, buf.len()));
     }
-    r.rtype = try!(Type::from_u16(_read_be_u16(buf, idx)));
-    r.rclass = try!(Class::from_u16(_read_be_u16(buf, idx)));
+    r.rtype = Type::from(_read_be_u16(buf, idx));
+    r.rclass = Class::from(_read_be_u16(buf, idx));
     r.rttl = _read_be_i32(buf, idx);
     r.rdlen = _read_be_u16(buf, idx);
     r.rdata = *idx;
@@ -311,6 +676,10 @@ pub fn read_dns_resource_record<'b>(buf: &'b [u8], idx: &mut usize) -> Result<Re
 }
 
 /// Read a single DNS name from a `&[u8]` buffer
+///
+/// Compression pointers are followed as long as each one points
+/// strictly before the start of the run of labels containing it, which
+/// rules out forward pointers and guarantees that reading terminates.
 #[inline(always)]
 pub fn read_dns_name<'b>(buf: &'b [u8], idx: &mut usize) -> Result<Name<'b>, errors::ReadError> {
     // Pre-check bounds (min 1 byte for root label)
@@ -322,29 +691,39 @@ pub fn read_dns_name<'b>(buf: &'b [u8], idx: &mut usize) -> Result<Name<'b>, err
 
     let mut follow = false;
     let mut return_to = 0usize;
-    let mut pcount = 0usize;
+    // Start of the current run of labels; pointers must target
+    // offsets before it
+    let mut run_start = *idx;
+    // Length of the name in wire format, including the root label
+    let mut wire_len = 1usize;
     let blen = buf.len();
     // Read in labels
     loop {
         // Check bounds for next label
-        if *idx + 1 > buf.len() {
-            return Err(errors::ReadError::IndexOutOfRangeError(*idx + 1, buf.len()));
+        if *idx + 1 > blen {
+            return Err(errors::ReadError::IndexOutOfRangeError(*idx + 1, blen));
         }
         // Get the next label's size
         let llen = buf[*idx];
-        // Get offset (clear upper 2 bits of label size)
-        let offset = 1 + ((llen & 0x3F) as usize);
 
-        if llen == 0 || pcount > 255 {
+        if llen == 0 {
             // Zero length labels are the root, so we're done
-
-            // If we've gone through more than 255 pointers something
-            // isn't right and we should bail.
-
             break;
-        } else if (llen & 0xC0) == 0xC0 && (*idx + 1) < blen {
+        } else if (llen & 0xC0) == 0xC0 {
             // Labels with the two high order bits set (0xC0)
-            // are pointers.
+            // are pointers, with a 14-bit offset.
+            if *idx + 2 > blen {
+                return Err(errors::ReadError::IndexOutOfRangeError(*idx + 2, blen));
+            }
+            let target = (((llen & 0x3F) as usize) << 8) | (buf[*idx+1] as usize);
+
+            if target >= *idx {
+                // Pointers to the current or a later position
+                return Err(errors::ReadError::BadPointerError(*idx));
+            } else if target >= run_start {
+                // Pointers back into labels already read for this name
+                return Err(errors::ReadError::PointerLoopError(*idx));
+            }
 
             // If this is the first pointer encountered, store
             // the current reader location to restore later.
@@ -352,18 +731,27 @@ pub fn read_dns_name<'b>(buf: &'b [u8], idx: &mut usize) -> Result<Name<'b>, err
                 return_to = *idx+2;
                 follow = true;
             }
-            pcount += 1;
 
             // Seek to the pointer location
-            *idx = buf[*idx+1] as usize;
+            *idx = target;
+            run_start = target;
             continue;
-        } else if (*idx + offset) >= blen {
+        } else if (llen & 0xC0) != 0 {
+            // The 0x40 and 0x80 label types are not in use
+            return Err(errors::ReadError::LabelTypeError(llen));
+        }
+
+        let offset = 1 + llen as usize;
+        if (*idx + offset) >= blen {
             return Err(errors::ReadError::IndexOutOfRangeError(*idx + offset, blen));
-        } else {
-            let new_label = &buf[*idx+1..*idx+offset];
-            *idx += offset;
-            labels.push(try!(Label::from_slice(new_label)));
         }
+        wire_len += offset;
+        if wire_len > 255 {
+            return Err(errors::ReadError::NameTooLongError(wire_len));
+        }
+        let new_label = &buf[*idx+1..*idx+offset];
+        *idx += offset;
+        labels.push(Label::from_slice(new_label)?);
     }
 
     if follow {
@@ -375,5 +763,5 @@ pub fn read_dns_name<'b>(buf: &'b [u8], idx: &mut usize) -> Result<Name<'b>, err
         *idx += 1;
     }
 
-    Ok(Name { labels: labels })
+    Ok(Name { labels })
 }
@@ -4,12 +4,18 @@ pub use super::iana::rcodes::RCode;
 pub use super::iana::opcodes::OpCode;
 pub use super::iana::errors::IdentifierError;
 
+pub use super::parser::header::Header;
 pub use super::parser::message::{Message,Question,ResourceRecord,Name,Label};
-pub use super::parser::message::{read_dns_message,read_dns_question,read_dns_resource_record,read_dns_name};
-pub use super::parser::util::{_read_be_u16,_read_be_i32};
+pub use super::parser::message::{OwnedMessage,OwnedQuestion,OwnedResourceRecord,OwnedName,OwnedLabel};
+pub use super::parser::message::{is_response_to,read_dns_message,read_dns_message_strict,read_dns_question,read_dns_resource_record,read_dns_name};
+pub use super::parser::errors::ReadError;
+pub use super::parser::rdata::{RData,OwnedRData,read_dns_rdata,key_tag};
+pub use super::parser::util::{_read_be_u16,_read_be_u32,_read_be_i32};
 
 mod errors;
+mod header;
 mod message;
+mod rdata;
 mod util;
 #[cfg(test)]
 mod tests;
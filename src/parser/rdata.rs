@@ -0,0 +1,794 @@
+use super::Type;
+use super::errors;
+use super::message::{Name,read_dns_name};
+
+use super::{_read_be_u16,_read_be_u32};
+use super::super::encoding::{encode_base32hex,encode_base64,encode_hex,format_timestamp};
+
+use std::borrow::Cow;
+use std::fmt;
+use std::net::{Ipv4Addr,Ipv6Addr};
+
+/// An `RData` that does not borrow from a buffer.
+pub type OwnedRData = RData<'static>;
+
+/// Decoded RDATA of a DNS resource record.
+///
+/// Names embedded in RDATA borrow from the message buffer the record
+/// was read from, so compression pointers are followed transparently.
+/// Record types without a dedicated decoder are returned as `Opaque`
+/// bytes rather than failing.
+#[derive(PartialEq,Debug,Clone)]
+pub enum RData<'r> {
+    A(Ipv4Addr),
+    NS(Name<'r>),
+    CNAME(Name<'r>),
+    SOA {
+        mname: Name<'r>,
+        rname: Name<'r>,
+        serial: u32,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: u32,
+    },
+    MB(Name<'r>),
+    MG(Name<'r>),
+    MR(Name<'r>),
+    PTR(Name<'r>),
+    HINFO {
+        cpu: Cow<'r, [u8]>,
+        os: Cow<'r, [u8]>,
+    },
+    MINFO {
+        rmailbx: Name<'r>,
+        emailbx: Name<'r>,
+    },
+    MX {
+        preference: u16,
+        exchange: Name<'r>,
+    },
+    TXT(Vec<Cow<'r, [u8]>>),
+    RP {
+        mbox: Name<'r>,
+        txt: Name<'r>,
+    },
+    AFSDB {
+        subtype: u16,
+        hostname: Name<'r>,
+    },
+    AAAA(Ipv6Addr),
+    SRV {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: Name<'r>,
+    },
+    NAPTR {
+        order: u16,
+        preference: u16,
+        flags: Cow<'r, [u8]>,
+        services: Cow<'r, [u8]>,
+        regexp: Cow<'r, [u8]>,
+        replacement: Name<'r>,
+    },
+    DNAME(Name<'r>),
+    /// The original DNSSEC signature record, now used only for SIG(0)
+    /// transaction signatures as per
+    /// [RFC2931](https://tools.ietf.org/html/rfc2931). Its RDATA has the
+    /// format of RRSIG.
+    SIG {
+        type_covered: Type,
+        algorithm: u8,
+        labels: u8,
+        original_ttl: u32,
+        expiration: u32,
+        inception: u32,
+        key_tag: u16,
+        signer_name: Name<'r>,
+        signature: Cow<'r, [u8]>,
+    },
+    RRSIG {
+        type_covered: Type,
+        algorithm: u8,
+        labels: u8,
+        original_ttl: u32,
+        expiration: u32,
+        inception: u32,
+        key_tag: u16,
+        signer_name: Name<'r>,
+        signature: Cow<'r, [u8]>,
+    },
+    /// A public key for SIG(0), as per
+    /// [RFC2535§3](https://tools.ietf.org/html/rfc2535#section-3) and
+    /// [RFC3445](https://tools.ietf.org/html/rfc3445). Its RDATA has the
+    /// format of DNSKEY.
+    KEY {
+        flags: u16,
+        protocol: u8,
+        algorithm: u8,
+        public_key: Cow<'r, [u8]>,
+    },
+    DNSKEY {
+        flags: u16,
+        protocol: u8,
+        algorithm: u8,
+        public_key: Cow<'r, [u8]>,
+    },
+    DS {
+        key_tag: u16,
+        algorithm: u8,
+        digest_type: u8,
+        digest: Cow<'r, [u8]>,
+    },
+    NSEC {
+        next_domain: Name<'r>,
+        types: Vec<Type>,
+    },
+    NSEC3 {
+        hash_algorithm: u8,
+        flags: u8,
+        iterations: u16,
+        salt: Cow<'r, [u8]>,
+        next_hashed_owner: Cow<'r, [u8]>,
+        types: Vec<Type>,
+    },
+    NSEC3PARAM {
+        hash_algorithm: u8,
+        flags: u8,
+        iterations: u16,
+        salt: Cow<'r, [u8]>,
+    },
+    /// Child copy of a DS record, as per
+    /// [RFC7344§3.1](https://tools.ietf.org/html/rfc7344#section-3.1).
+    CDS {
+        key_tag: u16,
+        algorithm: u8,
+        digest_type: u8,
+        digest: Cow<'r, [u8]>,
+    },
+    /// Child copy of a DNSKEY record, as per
+    /// [RFC7344§3.2](https://tools.ietf.org/html/rfc7344#section-3.2).
+    CDNSKEY {
+        flags: u16,
+        protocol: u8,
+        algorithm: u8,
+        public_key: Cow<'r, [u8]>,
+    },
+    /// A DNSSEC trust anchor in the format of a DS record, from the
+    /// DNSSEC Trust Authorities proposal.
+    TA {
+        key_tag: u16,
+        algorithm: u8,
+        digest_type: u8,
+        digest: Cow<'r, [u8]>,
+    },
+    /// DNSSEC Lookaside Validation record, as per
+    /// [RFC4431](https://tools.ietf.org/html/rfc4431). DLV has been
+    /// retired by [RFC8749](https://tools.ietf.org/html/rfc8749) and is
+    /// only decoded.
+    DLV {
+        key_tag: u16,
+        algorithm: u8,
+        digest_type: u8,
+        digest: Cow<'r, [u8]>,
+    },
+    /// Transaction signature, as per
+    /// [RFC8945§4.2](https://tools.ietf.org/html/rfc8945#section-4.2).
+    /// `time_signed` is 48 bits on the wire.
+    TSIG {
+        algorithm: Name<'r>,
+        time_signed: u64,
+        fudge: u16,
+        mac: Cow<'r, [u8]>,
+        original_id: u16,
+        error: u16,
+        other: Cow<'r, [u8]>,
+    },
+    /// Transaction key establishment, as per
+    /// [RFC2930§2](https://tools.ietf.org/html/rfc2930#section-2).
+    TKEY {
+        algorithm: Name<'r>,
+        inception: u32,
+        expiration: u32,
+        mode: u16,
+        error: u16,
+        key: Cow<'r, [u8]>,
+        other: Cow<'r, [u8]>,
+    },
+    SPF(Vec<Cow<'r, [u8]>>),
+    Opaque(Cow<'r, [u8]>),
+}
+
+/// Convert borrowed octets into owned ones.
+#[inline(always)]
+fn owned(b: Cow<[u8]>) -> Cow<'static, [u8]> {
+    Cow::Owned(b.into_owned())
+}
+
+impl<'r> RData<'r> {
+    /// Convert this RDATA into one that owns all of its names and octets.
+    pub fn into_owned(self) -> OwnedRData {
+        match self {
+            RData::A(a) => RData::A(a),
+            RData::NS(n) => RData::NS(n.into_owned()),
+            RData::CNAME(n) => RData::CNAME(n.into_owned()),
+            RData::SOA { mname, rname, serial, refresh, retry, expire, minimum } => RData::SOA {
+                mname: mname.into_owned(),
+                rname: rname.into_owned(),
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+            },
+            RData::MB(n) => RData::MB(n.into_owned()),
+            RData::MG(n) => RData::MG(n.into_owned()),
+            RData::MR(n) => RData::MR(n.into_owned()),
+            RData::PTR(n) => RData::PTR(n.into_owned()),
+            RData::HINFO { cpu, os } => RData::HINFO { cpu: owned(cpu), os: owned(os) },
+            RData::MINFO { rmailbx, emailbx } => RData::MINFO {
+                rmailbx: rmailbx.into_owned(),
+                emailbx: emailbx.into_owned(),
+            },
+            RData::MX { preference, exchange } => RData::MX { preference, exchange: exchange.into_owned() },
+            RData::TXT(strings) => RData::TXT(strings.into_iter().map(owned).collect()),
+            RData::RP { mbox, txt } => RData::RP { mbox: mbox.into_owned(), txt: txt.into_owned() },
+            RData::AFSDB { subtype, hostname } => RData::AFSDB { subtype, hostname: hostname.into_owned() },
+            RData::AAAA(a) => RData::AAAA(a),
+            RData::SRV { priority, weight, port, target } => RData::SRV {
+                priority,
+                weight,
+                port,
+                target: target.into_owned(),
+            },
+            RData::NAPTR { order, preference, flags, services, regexp, replacement } => RData::NAPTR {
+                order,
+                preference,
+                flags: owned(flags),
+                services: owned(services),
+                regexp: owned(regexp),
+                replacement: replacement.into_owned(),
+            },
+            RData::DNAME(n) => RData::DNAME(n.into_owned()),
+            RData::SIG { type_covered, algorithm, labels, original_ttl, expiration, inception, key_tag, signer_name, signature } => RData::SIG {
+                type_covered,
+                algorithm,
+                labels,
+                original_ttl,
+                expiration,
+                inception,
+                key_tag,
+                signer_name: signer_name.into_owned(),
+                signature: owned(signature),
+            },
+            RData::RRSIG { type_covered, algorithm, labels, original_ttl, expiration, inception, key_tag, signer_name, signature } => RData::RRSIG {
+                type_covered,
+                algorithm,
+                labels,
+                original_ttl,
+                expiration,
+                inception,
+                key_tag,
+                signer_name: signer_name.into_owned(),
+                signature: owned(signature),
+            },
+            RData::KEY { flags, protocol, algorithm, public_key } => RData::KEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key: owned(public_key),
+            },
+            RData::DNSKEY { flags, protocol, algorithm, public_key } => RData::DNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key: owned(public_key),
+            },
+            RData::DS { key_tag, algorithm, digest_type, digest } => RData::DS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest: owned(digest),
+            },
+            RData::NSEC { next_domain, types } => RData::NSEC { next_domain: next_domain.into_owned(), types },
+            RData::NSEC3 { hash_algorithm, flags, iterations, salt, next_hashed_owner, types } => RData::NSEC3 {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt: owned(salt),
+                next_hashed_owner: owned(next_hashed_owner),
+                types,
+            },
+            RData::NSEC3PARAM { hash_algorithm, flags, iterations, salt } => RData::NSEC3PARAM {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt: owned(salt),
+            },
+            RData::CDS { key_tag, algorithm, digest_type, digest } => RData::CDS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest: owned(digest),
+            },
+            RData::CDNSKEY { flags, protocol, algorithm, public_key } => RData::CDNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key: owned(public_key),
+            },
+            RData::TA { key_tag, algorithm, digest_type, digest } => RData::TA {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest: owned(digest),
+            },
+            RData::DLV { key_tag, algorithm, digest_type, digest } => RData::DLV {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest: owned(digest),
+            },
+            RData::TSIG { algorithm, time_signed, fudge, mac, original_id, error, other } => RData::TSIG {
+                algorithm: algorithm.into_owned(),
+                time_signed,
+                fudge,
+                mac: owned(mac),
+                original_id,
+                error,
+                other: owned(other),
+            },
+            RData::TKEY { algorithm, inception, expiration, mode, error, key, other } => RData::TKEY {
+                algorithm: algorithm.into_owned(),
+                inception,
+                expiration,
+                mode,
+                error,
+                key: owned(key),
+                other: owned(other),
+            },
+            RData::SPF(strings) => RData::SPF(strings.into_iter().map(owned).collect()),
+            RData::Opaque(data) => RData::Opaque(owned(data)),
+        }
+    }
+
+    /// The key tag of DNSKEY, CDNSKEY or KEY RDATA, computed as per
+    /// [RFC4034 Appendix B](https://tools.ietf.org/html/rfc4034#appendix-B).
+    /// Returns `None` for other types.
+    pub fn key_tag(&self) -> Option<u16> {
+        match *self {
+            RData::DNSKEY { flags, protocol, algorithm, ref public_key } |
+            RData::CDNSKEY { flags, protocol, algorithm, ref public_key } |
+            RData::KEY { flags, protocol, algorithm, ref public_key } => {
+                Some(key_tag(flags, protocol, algorithm, public_key))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Compute the key tag of a DNSKEY from its fields.
+pub fn key_tag(flags: u16, protocol: u8, algorithm: u8, public_key: &[u8]) -> u16 {
+    // Algorithm 1 (RSA/MD5) uses the third to last two octets of the
+    // modulus instead, as per RFC4034 Appendix B.1
+    if algorithm == 1 {
+        let len = public_key.len();
+        if len < 3 {
+            return 0;
+        }
+        return (public_key[len - 3] as u16) << 8 | public_key[len - 2] as u16;
+    }
+    let mut ac = flags as u32 + ((protocol as u32) << 8 | algorithm as u32);
+    for (i, &b) in public_key.iter().enumerate() {
+        ac += if i % 2 == 0 { (b as u32) << 8 } else { b as u32 };
+    }
+    ac += ac >> 16 & 0xFFFF;
+    ac as u16
+}
+
+impl<'r> fmt::Display for RData<'r> {
+
+    /// Formats RDATA in the presentation format of its type. Opaque
+    /// RDATA, and TXT or SPF RDATA without any strings, is shown in
+    /// the generic `\# <length> <hex>` form of
+    /// [RFC3597§5](https://tools.ietf.org/html/rfc3597#section-5).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RData::A(ref a) => write!(f, "{}", a),
+            RData::AAAA(ref a) => write!(f, "{}", a),
+            RData::NS(ref n) | RData::CNAME(ref n) | RData::MB(ref n) | RData::MG(ref n) |
+            RData::MR(ref n) | RData::PTR(ref n) | RData::DNAME(ref n) => write!(f, "{}", n),
+            RData::SOA { ref mname, ref rname, serial, refresh, retry, expire, minimum } => {
+                write!(f, "{} {} {} {} {} {} {}", mname, rname, serial, refresh, retry, expire, minimum)
+            },
+            RData::HINFO { ref cpu, ref os } => {
+                fmt_character_string(f, cpu)?;
+                write!(f, " ")?;
+                fmt_character_string(f, os)
+            },
+            RData::MINFO { ref rmailbx, ref emailbx } => write!(f, "{} {}", rmailbx, emailbx),
+            RData::MX { preference, ref exchange } => write!(f, "{} {}", preference, exchange),
+            RData::TXT(ref strings) | RData::SPF(ref strings) => {
+                if strings.is_empty() {
+                    return write!(f, "\\# 0");
+                }
+                for (i, s) in strings.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    fmt_character_string(f, s)?;
+                }
+                Ok(())
+            },
+            RData::RP { ref mbox, ref txt } => write!(f, "{} {}", mbox, txt),
+            RData::AFSDB { subtype, ref hostname } => write!(f, "{} {}", subtype, hostname),
+            RData::SRV { priority, weight, port, ref target } => write!(f, "{} {} {} {}", priority, weight, port, target),
+            RData::NAPTR { order, preference, ref flags, ref services, ref regexp, ref replacement } => {
+                write!(f, "{} {} ", order, preference)?;
+                fmt_character_string(f, flags)?;
+                write!(f, " ")?;
+                fmt_character_string(f, services)?;
+                write!(f, " ")?;
+                fmt_character_string(f, regexp)?;
+                write!(f, " {}", replacement)
+            },
+            RData::SIG { type_covered, algorithm, labels, original_ttl, expiration, inception, key_tag, ref signer_name, ref signature } |
+            RData::RRSIG { type_covered, algorithm, labels, original_ttl, expiration, inception, key_tag, ref signer_name, ref signature } => {
+                write!(f, "{} {} {} {} {} {} {} {} {}", type_covered, algorithm, labels, original_ttl,
+                       format_timestamp(expiration), format_timestamp(inception), key_tag, signer_name,
+                       encode_base64(signature))
+            },
+            RData::DNSKEY { flags, protocol, algorithm, ref public_key } |
+            RData::CDNSKEY { flags, protocol, algorithm, ref public_key } |
+            RData::KEY { flags, protocol, algorithm, ref public_key } => {
+                write!(f, "{} {} {} {}", flags, protocol, algorithm, encode_base64(public_key))
+            },
+            RData::DS { key_tag, algorithm, digest_type, ref digest } |
+            RData::CDS { key_tag, algorithm, digest_type, ref digest } |
+            RData::TA { key_tag, algorithm, digest_type, ref digest } |
+            RData::DLV { key_tag, algorithm, digest_type, ref digest } => {
+                write!(f, "{} {} {} {}", key_tag, algorithm, digest_type, encode_hex(digest))
+            },
+            RData::TSIG { ref algorithm, time_signed, fudge, ref mac, original_id, error, ref other } => {
+                write!(f, "{} {} {} {}", algorithm, time_signed, fudge, mac.len())?;
+                if !mac.is_empty() {
+                    write!(f, " {}", encode_base64(mac))?;
+                }
+                write!(f, " {} {} {}", original_id, error, other.len())?;
+                if !other.is_empty() {
+                    write!(f, " {}", encode_base64(other))?;
+                }
+                Ok(())
+            },
+            RData::TKEY { ref algorithm, inception, expiration, mode, error, ref key, ref other } => {
+                write!(f, "{} {} {} {} {} {}", algorithm, format_timestamp(inception), format_timestamp(expiration),
+                       mode, error, key.len())?;
+                if !key.is_empty() {
+                    write!(f, " {}", encode_base64(key))?;
+                }
+                write!(f, " {}", other.len())?;
+                if !other.is_empty() {
+                    write!(f, " {}", encode_base64(other))?;
+                }
+                Ok(())
+            },
+            RData::NSEC { ref next_domain, ref types } => {
+                write!(f, "{}", next_domain)?;
+                fmt_types(f, types)
+            },
+            RData::NSEC3 { hash_algorithm, flags, iterations, ref salt, ref next_hashed_owner, ref types } => {
+                write!(f, "{} {} {} ", hash_algorithm, flags, iterations)?;
+                fmt_salt(f, salt)?;
+                write!(f, " {}", encode_base32hex(next_hashed_owner))?;
+                fmt_types(f, types)
+            },
+            RData::NSEC3PARAM { hash_algorithm, flags, iterations, ref salt } => {
+                write!(f, "{} {} {} ", hash_algorithm, flags, iterations)?;
+                fmt_salt(f, salt)
+            },
+            RData::Opaque(ref data) => {
+                write!(f, "\\# {}", data.len())?;
+                if !data.is_empty() {
+                    write!(f, " {}", encode_hex(data))?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Format a `<character-string>` as a quoted string, escaping `"` and
+/// `\` and showing non-printing characters as `\DDD`.
+fn fmt_character_string(f: &mut fmt::Formatter, s: &[u8]) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.iter() {
+        match *c {
+            x @ 0x00..=0x1F | x @ 0x7F..=0xFF => write!(f, "\\{:03}", x)?,
+            x @ b'"' | x @ b'\\' => write!(f, "\\{}", x as char)?,
+            x => write!(f, "{}", x as char)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Format the types of an NSEC or NSEC3 type bitmap, each preceded by
+/// a space.
+fn fmt_types(f: &mut fmt::Formatter, types: &[Type]) -> fmt::Result {
+    for t in types.iter() {
+        write!(f, " {}", t)?;
+    }
+    Ok(())
+}
+
+/// Format an NSEC3 salt as hex, or `-` if it is empty.
+fn fmt_salt(f: &mut fmt::Formatter, salt: &[u8]) -> fmt::Result {
+    if salt.is_empty() {
+        write!(f, "-")
+    } else {
+        write!(f, "{}", encode_hex(salt))
+    }
+}
+
+/// Check that `len` more octets of RDATA are available before `end`.
+#[inline(always)]
+fn check_rdata_bounds(idx: usize, len: usize, end: usize) -> Result<(), errors::ReadError> {
+    if idx + len > end {
+        return Err(errors::ReadError::IndexOutOfRangeError(idx + len, end));
+    }
+    Ok(())
+}
+
+/// Read a single `<character-string>` from a `&[u8]` buffer.
+#[inline(always)]
+fn read_character_string<'b>(buf: &'b [u8], idx: &mut usize, end: usize) -> Result<Cow<'b, [u8]>, errors::ReadError> {
+    check_rdata_bounds(*idx, 1, end)?;
+    let len = buf[*idx] as usize;
+    check_rdata_bounds(*idx + 1, len, end)?;
+    let s = &buf[*idx+1..*idx+1+len];
+    *idx += 1 + len;
+    Ok(Cow::Borrowed(s))
+}
+
+/// Read octets prefixed by a 16-bit length from a `&[u8]` buffer.
+fn read_u16_octets<'b>(buf: &'b [u8], idx: &mut usize, end: usize) -> Result<Cow<'b, [u8]>, errors::ReadError> {
+    check_rdata_bounds(*idx, 2, end)?;
+    let len = _read_be_u16(buf, idx) as usize;
+    check_rdata_bounds(*idx, len, end)?;
+    let s = &buf[*idx..*idx+len];
+    *idx += len;
+    Ok(Cow::Borrowed(s))
+}
+
+/// Read the type bitmap of an NSEC or NSEC3 record, as per
+/// [RFC4034§4.1.2](https://tools.ietf.org/html/rfc4034#section-4.1.2).
+/// Windows must be in increasing order and 1 to 32 octets long.
+fn read_type_bitmap(buf: &[u8], idx: &mut usize, end: usize) -> Result<Vec<Type>, errors::ReadError> {
+    let mut types = Vec::new();
+    let mut last_window: Option<u8> = None;
+    while *idx < end {
+        check_rdata_bounds(*idx, 2, end)?;
+        let window = buf[*idx];
+        let len = buf[*idx+1] as usize;
+        if last_window.is_some_and(|w| window <= w) || len == 0 || len > 32 {
+            return Err(errors::ReadError::TypeBitmapError(*idx));
+        }
+        check_rdata_bounds(*idx + 2, len, end)?;
+        for (i, &octet) in buf[*idx+2..*idx+2+len].iter().enumerate() {
+            for bit in 0..8 {
+                if octet & (0x80 >> bit) != 0 {
+                    types.push(Type::from((window as u16) << 8 | (i * 8 + bit) as u16));
+                }
+            }
+        }
+        last_window = Some(window);
+        *idx += 2 + len;
+    }
+    Ok(types)
+}
+
+/// Read a DNS name from RDATA, making sure its uncompressed portion
+/// does not run past the end of the RDATA section.
+#[inline(always)]
+fn read_rdata_name<'b>(buf: &'b [u8], idx: &mut usize, end: usize) -> Result<Name<'b>, errors::ReadError> {
+    read_dns_name(&buf[..end], idx)
+}
+
+/// Read the RDATA of a resource record of type `rtype` from a `&[u8]`
+/// buffer. `buf` should be the entire message so that compression
+/// pointers inside the RDATA can be followed.
+pub fn read_dns_rdata<'b>(rtype: &Type, buf: &'b [u8], idx: &mut usize, rdlen: u16) -> Result<RData<'b>, errors::ReadError> {
+    let end = *idx + rdlen as usize;
+    if end > buf.len() {
+        return Err(errors::ReadError::IndexOutOfRangeError(end, buf.len()));
+    }
+
+    let rdata = match *rtype {
+        Type::A => {
+            check_rdata_bounds(*idx, 4, end)?;
+            let a = Ipv4Addr::new(buf[*idx], buf[*idx+1], buf[*idx+2], buf[*idx+3]);
+            *idx += 4;
+            RData::A(a)
+        },
+        Type::AAAA => {
+            check_rdata_bounds(*idx, 16, end)?;
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&buf[*idx..*idx+16]);
+            *idx += 16;
+            RData::AAAA(Ipv6Addr::from(octets))
+        },
+        Type::NS => RData::NS(read_rdata_name(buf, idx, end)?),
+        Type::CNAME => RData::CNAME(read_rdata_name(buf, idx, end)?),
+        Type::MB => RData::MB(read_rdata_name(buf, idx, end)?),
+        Type::MG => RData::MG(read_rdata_name(buf, idx, end)?),
+        Type::MR => RData::MR(read_rdata_name(buf, idx, end)?),
+        Type::PTR => RData::PTR(read_rdata_name(buf, idx, end)?),
+        Type::DNAME => RData::DNAME(read_rdata_name(buf, idx, end)?),
+        Type::SOA => {
+            let mname = read_rdata_name(buf, idx, end)?;
+            let rname = read_rdata_name(buf, idx, end)?;
+            check_rdata_bounds(*idx, 20, end)?;
+            RData::SOA {
+                mname,
+                rname,
+                serial: _read_be_u32(buf, idx),
+                refresh: _read_be_u32(buf, idx),
+                retry: _read_be_u32(buf, idx),
+                expire: _read_be_u32(buf, idx),
+                minimum: _read_be_u32(buf, idx),
+            }
+        },
+        Type::HINFO => {
+            let cpu = read_character_string(buf, idx, end)?;
+            let os = read_character_string(buf, idx, end)?;
+            RData::HINFO { cpu, os }
+        },
+        Type::MINFO => {
+            let rmailbx = read_rdata_name(buf, idx, end)?;
+            let emailbx = read_rdata_name(buf, idx, end)?;
+            RData::MINFO { rmailbx, emailbx }
+        },
+        Type::MX => {
+            check_rdata_bounds(*idx, 2, end)?;
+            let preference = _read_be_u16(buf, idx);
+            let exchange = read_rdata_name(buf, idx, end)?;
+            RData::MX { preference, exchange }
+        },
+        Type::TXT | Type::SPF => {
+            let mut strings = Vec::new();
+            while *idx < end {
+                strings.push(read_character_string(buf, idx, end)?);
+            }
+            if *rtype == Type::TXT { RData::TXT(strings) } else { RData::SPF(strings) }
+        },
+        Type::RP => {
+            let mbox = read_rdata_name(buf, idx, end)?;
+            let txt = read_rdata_name(buf, idx, end)?;
+            RData::RP { mbox, txt }
+        },
+        Type::AFSDB => {
+            check_rdata_bounds(*idx, 2, end)?;
+            let subtype = _read_be_u16(buf, idx);
+            let hostname = read_rdata_name(buf, idx, end)?;
+            RData::AFSDB { subtype, hostname }
+        },
+        Type::SRV => {
+            check_rdata_bounds(*idx, 6, end)?;
+            let priority = _read_be_u16(buf, idx);
+            let weight = _read_be_u16(buf, idx);
+            let port = _read_be_u16(buf, idx);
+            let target = read_rdata_name(buf, idx, end)?;
+            RData::SRV { priority, weight, port, target }
+        },
+        Type::NAPTR => {
+            check_rdata_bounds(*idx, 4, end)?;
+            let order = _read_be_u16(buf, idx);
+            let preference = _read_be_u16(buf, idx);
+            let flags = read_character_string(buf, idx, end)?;
+            let services = read_character_string(buf, idx, end)?;
+            let regexp = read_character_string(buf, idx, end)?;
+            let replacement = read_rdata_name(buf, idx, end)?;
+            RData::NAPTR { order, preference, flags, services, regexp, replacement }
+        },
+        Type::RRSIG | Type::SIG => {
+            check_rdata_bounds(*idx, 18, end)?;
+            let type_covered = Type::from(_read_be_u16(buf, idx));
+            let algorithm = buf[*idx];
+            let labels = buf[*idx+1];
+            *idx += 2;
+            let original_ttl = _read_be_u32(buf, idx);
+            let expiration = _read_be_u32(buf, idx);
+            let inception = _read_be_u32(buf, idx);
+            let key_tag = _read_be_u16(buf, idx);
+            let signer_name = read_rdata_name(buf, idx, end)?;
+            let signature = Cow::Borrowed(&buf[*idx..end]);
+            *idx = end;
+            if *rtype == Type::RRSIG {
+                RData::RRSIG { type_covered, algorithm, labels, original_ttl, expiration, inception, key_tag, signer_name, signature }
+            } else {
+                RData::SIG { type_covered, algorithm, labels, original_ttl, expiration, inception, key_tag, signer_name, signature }
+            }
+        },
+        Type::DNSKEY | Type::CDNSKEY | Type::KEY => {
+            check_rdata_bounds(*idx, 4, end)?;
+            let flags = _read_be_u16(buf, idx);
+            let protocol = buf[*idx];
+            let algorithm = buf[*idx+1];
+            let public_key = Cow::Borrowed(&buf[*idx+2..end]);
+            *idx = end;
+            match *rtype {
+                Type::DNSKEY => RData::DNSKEY { flags, protocol, algorithm, public_key },
+                Type::CDNSKEY => RData::CDNSKEY { flags, protocol, algorithm, public_key },
+                _ => RData::KEY { flags, protocol, algorithm, public_key },
+            }
+        },
+        Type::DS | Type::CDS | Type::TA | Type::DLV => {
+            check_rdata_bounds(*idx, 4, end)?;
+            let key_tag = _read_be_u16(buf, idx);
+            let algorithm = buf[*idx];
+            let digest_type = buf[*idx+1];
+            let digest = Cow::Borrowed(&buf[*idx+2..end]);
+            *idx = end;
+            match *rtype {
+                Type::DS => RData::DS { key_tag, algorithm, digest_type, digest },
+                Type::CDS => RData::CDS { key_tag, algorithm, digest_type, digest },
+                Type::TA => RData::TA { key_tag, algorithm, digest_type, digest },
+                _ => RData::DLV { key_tag, algorithm, digest_type, digest },
+            }
+        },
+        Type::NSEC => {
+            let next_domain = read_rdata_name(buf, idx, end)?;
+            let types = read_type_bitmap(buf, idx, end)?;
+            RData::NSEC { next_domain, types }
+        },
+        Type::NSEC3 | Type::NSEC3PARAM => {
+            check_rdata_bounds(*idx, 4, end)?;
+            let hash_algorithm = buf[*idx];
+            let flags = buf[*idx+1];
+            *idx += 2;
+            let iterations = _read_be_u16(buf, idx);
+            let salt = read_character_string(buf, idx, end)?;
+            if *rtype == Type::NSEC3PARAM {
+                RData::NSEC3PARAM { hash_algorithm, flags, iterations, salt }
+            } else {
+                let next_hashed_owner = read_character_string(buf, idx, end)?;
+                let types = read_type_bitmap(buf, idx, end)?;
+                RData::NSEC3 { hash_algorithm, flags, iterations, salt, next_hashed_owner, types }
+            }
+        },
+        Type::TSIG => {
+            let algorithm = read_rdata_name(buf, idx, end)?;
+            check_rdata_bounds(*idx, 10, end)?;
+            let time_signed = ((_read_be_u16(buf, idx) as u64) << 32) | _read_be_u32(buf, idx) as u64;
+            let fudge = _read_be_u16(buf, idx);
+            let mac = read_u16_octets(buf, idx, end)?;
+            check_rdata_bounds(*idx, 4, end)?;
+            let original_id = _read_be_u16(buf, idx);
+            let error = _read_be_u16(buf, idx);
+            let other = read_u16_octets(buf, idx, end)?;
+            RData::TSIG { algorithm, time_signed, fudge, mac, original_id, error, other }
+        },
+        Type::TKEY => {
+            let algorithm = read_rdata_name(buf, idx, end)?;
+            check_rdata_bounds(*idx, 12, end)?;
+            let inception = _read_be_u32(buf, idx);
+            let expiration = _read_be_u32(buf, idx);
+            let mode = _read_be_u16(buf, idx);
+            let error = _read_be_u16(buf, idx);
+            let key = read_u16_octets(buf, idx, end)?;
+            let other = read_u16_octets(buf, idx, end)?;
+            RData::TKEY { algorithm, inception, expiration, mode, error, key, other }
+        },
+        _ => {
+            let data = &buf[*idx..end];
+            *idx = end;
+            RData::Opaque(Cow::Borrowed(data))
+        },
+    };
+
+    // Every decoder must consume exactly RDLENGTH octets
+    if *idx != end {
+        return Err(errors::ReadError::RDataLengthError(rdlen));
+    }
+    Ok(rdata)
+}
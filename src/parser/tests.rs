@@ -1,7 +1,19 @@
-use super::{Message,Name,Label};
-use super::{read_dns_message,read_dns_name};
+use super::{Message,Header,Name,Label,RData,Type,Class,OpCode,RCode,IdentifierError};
+use super::{OwnedMessage,OwnedName,OwnedLabel};
+use super::{read_dns_message,read_dns_message_strict,read_dns_name,read_dns_rdata,key_tag};
+use super::super::encoding::decode_base64;
+use super::errors::ReadError;
 
-static NET1_RS: &'static [u8] = include_bytes!("../../tests/packets/net1-rs.bin");
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash,Hasher};
+use std::net::Ipv4Addr;
+use std::thread;
+
+static NET1_RS: &[u8] = include_bytes!("../../tests/packets/net1-rs.bin");
+static FB1_RS: &[u8] = include_bytes!("../../tests/packets/fb1-rs.bin");
 
 fn check_std_response_norecurse(m: &Message, q: usize, a: usize, n: usize, x: usize) {
     assert_eq!(m.flags, 0x8000);
@@ -20,6 +32,31 @@ fn test_label_create() {
     assert!(Label::from_slice(l64).is_err());
 }
 
+#[test]
+fn test_label_hash() {
+    fn hash(label: &Label) -> u64 {
+        let mut h = DefaultHasher::new();
+        label.hash(&mut h);
+        h.finish()
+    }
+    // Labels equal case-insensitively hash equally
+    let l1 = Label::from_slice("cat".as_bytes()).ok().unwrap();
+    let l2 = Label::from_slice("CaT".as_bytes()).ok().unwrap();
+    let l3 = Label::from_slice("cat1".as_bytes()).ok().unwrap();
+    assert_eq!(l1, l2);
+    assert_eq!(hash(&l1), hash(&l2));
+    assert!(hash(&l1) != hash(&l3));
+
+    // Only ASCII letters fold: '[' is not '{'
+    let l4 = Label::from_slice("[".as_bytes()).ok().unwrap();
+    let l5 = Label::from_slice("{".as_bytes()).ok().unwrap();
+    assert!(l4 != l5);
+    assert!(hash(&l4) != hash(&l5));
+
+    let set: HashSet<Label> = vec![l1, l2, l3].into_iter().collect();
+    assert_eq!(set.len(), 2);
+}
+
 #[test]
 fn test_label_eq() {
     let l1 = Label::from_slice("cat".as_bytes()).ok().unwrap();
@@ -59,13 +96,18 @@ fn test_label_ord() {
     let l2 = Label::from_slice("cat1".as_bytes()).ok().unwrap();
     let l3 = Label::from_slice("cat2".as_bytes()).ok().unwrap();
 
-    // Test ordering (DNSSEC canonical)
-    assert!(l < l0);
+    // Test ordering (DNSSEC canonical), which ignores case like equality
+    assert_eq!(l.cmp(&l0), Ordering::Equal);
     assert!(l0 < l1);
-    assert!(l1 < l2);
+    assert_eq!(l1.cmp(&l2), Ordering::Equal);
     assert!(l2 < l3);
     assert!(l1 < l3);
 
+    // Letters are folded to lowercase, so '_' sorts before them
+    let u = Label::from_slice(b"_tcp").ok().unwrap();
+    assert!(u < Label::from_slice(b"A").ok().unwrap());
+    assert!(u < Label::from_slice(b"a").ok().unwrap());
+
     let b1 = [0x63u8, 0x61, 0x74, 0x00];
     let b2 = [0x63u8, 0x61, 0x74, 0x01];
     let b3 = [0x63u8, 0x61, 0x74, 0x02];
@@ -93,13 +135,13 @@ fn test_name_ord() {
     let n9 = "\x01*\x01z\x07example\x00".as_bytes();
     let v = vec!(n1, n2, n3, n4, n5, n6, n7, n8, n9);
     let mut v2 = v.iter().map( |x| -> Name {
-        read_dns_name(*x, &mut 0).ok().unwrap()
+        read_dns_name(x, &mut 0).ok().unwrap()
     }
     );
     let mut this = v2.next().unwrap();
     let mut that = v2.next().unwrap();
     assert!(this < that);
-    while let Some(next) = v2.next() {
+    for next in v2 {
         this = that;
         that = next;
         assert!(this < that);
@@ -115,13 +157,13 @@ fn test_name_ord() {
     let n9 = "\x07axample\x00".as_bytes();
     let v = vec!(n1, n2, n3, n4, n5, n6, n7, n8, n9);
     let mut v2 = v.iter().map( |x| -> Name {
-        read_dns_name(*x, &mut 0).ok().unwrap()
+        read_dns_name(x, &mut 0).ok().unwrap()
     }
     );
     let mut this = v2.next().unwrap();
     let mut that = v2.next().unwrap();
     assert!(this > that);
-    while let Some(next) = v2.next() {
+    for next in v2 {
         this = that;
         that = next;
         assert!(this > that);
@@ -129,14 +171,11 @@ fn test_name_ord() {
     // <--- End RFC4034 testcases
 
     let v3 = vec!(
-        "\x01A\x00".as_bytes(),
         "\x01a\x00".as_bytes(),
         "\x01b\x00".as_bytes(),
         "\x01c\x00".as_bytes(),
         "\x01d\x00".as_bytes(),
         "\x01c\x01d\x00".as_bytes(),
-        "\x01B\x01c\x01d\x00".as_bytes(),
-        "\x01b\x01C\x01d\x00".as_bytes(),
         "\x01b\x01c\x01d\x00".as_bytes(),
         "\x01\x10\x01b\x01c\x01d\x00".as_bytes(),
         "\x01\x11\x01b\x01c\x01d\x00".as_bytes(),
@@ -145,14 +184,14 @@ fn test_name_ord() {
         "\x01\x14\x01b\x01c\x01d\x00".as_bytes(),
         );
     let mut v4 = v3.iter().map( |x| -> Name {
-        //println!("{}", read_dns_name(*x, &mut 0));
-        read_dns_name(*x, &mut 0).ok().unwrap()
+        //println!("{}", read_dns_name(x, &mut 0));
+        read_dns_name(x, &mut 0).ok().unwrap()
     }
     );
     let mut this = v4.next().unwrap();
     let mut that = v4.next().unwrap();
     assert!(this < that);
-    while let Some(next) = v4.next() {
+    for next in v4 {
         this = that;
         that = next;
         assert!(this < that);
@@ -165,30 +204,36 @@ fn test_name_ord() {
         "\x01\x11\x01b\x01c\x01d\x00".as_bytes(),
         "\x01\x10\x01b\x01c\x01d\x00".as_bytes(),
         "\x01b\x01c\x01d\x00".as_bytes(),
-        "\x01b\x01C\x01d\x00".as_bytes(),
-        "\x01B\x01c\x01d\x00".as_bytes(),
         "\x01c\x01d\x00".as_bytes(),
         "\x01d\x00".as_bytes(),
         "\x01c\x00".as_bytes(),
         "\x01b\x00".as_bytes(),
         "\x01a\x00".as_bytes(),
-        "\x01A\x00".as_bytes(),
         );
     let mut v6 = v5.iter().map( |x| -> Name {
-        //println!("{}", read_dns_name(*x, &mut 0));
-        read_dns_name(*x, &mut 0).ok().unwrap()
+        //println!("{}", read_dns_name(x, &mut 0));
+        read_dns_name(x, &mut 0).ok().unwrap()
     }
     );
     let mut this = v6.next().unwrap();
     let mut that = v6.next().unwrap();
     assert!(this > that);
-    while let Some(next) = v6.next() {
+    for next in v6 {
         this = that;
         that = next;
         assert!(this > that);
     }
 
     read_dns_name(n1, &mut 0).ok().unwrap();
+
+    // Names differing only in case are equal in canonical order
+    let upper = read_dns_name("\x01B\x01c\x01d\x00".as_bytes(), &mut 0).ok().unwrap();
+    let mixed = read_dns_name("\x01b\x01C\x01d\x00".as_bytes(), &mut 0).ok().unwrap();
+    assert_eq!(upper.cmp(&mixed), Ordering::Equal);
+    assert_eq!(upper.partial_cmp(&mixed), Some(Ordering::Equal));
+    let mut names = vec![mixed.clone(), Name::from_str("a.d.").ok().unwrap(), Name::from_str("d.").ok().unwrap()];
+    names.sort();
+    assert_eq!(names, vec![Name::from_str("d.").ok().unwrap(), Name::from_str("a.d.").ok().unwrap(), mixed]);
 }
 
 #[test]
@@ -231,3 +276,470 @@ fn test_read_dns_message() {
 fn test_bounds_checks() {
     let _ = read_dns_message(&NET1_RS[0..NET1_RS.len()-2]).err();
 }
+
+#[test]
+fn test_rdata_from_message() {
+    let m = read_dns_message(NET1_RS).ok().unwrap();
+
+    for a in m.nameservers.iter() {
+        match a.rdata() {
+            Ok(RData::NS(n)) => assert!(n.to_string().ends_with(".gtld-servers.net.")),
+            x => panic!("unexpected rdata: {:?}", x),
+        }
+    }
+
+    let mut v4_ct = 0usize;
+    let mut v6_ct = 0usize;
+    for x in m.additionals.iter() {
+        match x.rdata().ok().unwrap() {
+            RData::A(_) => v4_ct += 1,
+            RData::AAAA(a) => {
+                assert_eq!(a.segments()[0], 0x2001);
+                v6_ct += 1;
+            },
+            x => panic!("unexpected rdata: {:?}", x),
+        }
+    }
+    assert_eq!(v4_ct, 13);
+    assert_eq!(v6_ct, 2);
+
+    let m = read_dns_message(FB1_RS).ok().unwrap();
+    assert_eq!(m.answers[0].rdata().ok().unwrap(), RData::A(Ipv4Addr::new(173, 252, 120, 6)));
+}
+
+#[test]
+fn test_rdata_typed() {
+    let buf = [
+        0x12u8, 0x34, 0x81, 0x80, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x00,
+        // example.com. MX 10 mail.example.com.
+        0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00,
+        0x00, 0x0f, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x09,
+        0x00, 0x0a, 0x04, 0x6d, 0x61, 0x69, 0x6c, 0xc0, 0x0c,
+        // example.com. TXT "hello" "world"
+        0xc0, 0x0c, 0x00, 0x10, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x0c,
+        0x05, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x05, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+        // example.com. SRV 1 2 53 example.com.
+        0xc0, 0x0c, 0x00, 0x21, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x08,
+        0x00, 0x01, 0x00, 0x02, 0x00, 0x35, 0xc0, 0x0c,
+        // example.com. SOA ns.example.com. host.example.com. 1 2 3 4 5
+        0xc0, 0x0c, 0x00, 0x06, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x20,
+        0x02, 0x6e, 0x73, 0xc0, 0x0c, 0x04, 0x68, 0x6f, 0x73, 0x74, 0xc0, 0x0c,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03,
+        0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x05,
+        // example.com. HINFO "cpu" "os"
+        0xc0, 0x0c, 0x00, 0x0d, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x07,
+        0x03, 0x63, 0x70, 0x75, 0x02, 0x6f, 0x73,
+        // example.com. NULL \# 3 010203
+        0xc0, 0x0c, 0x00, 0x0a, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x03,
+        0x01, 0x02, 0x03,
+    ];
+    let m = read_dns_message(&buf).ok().unwrap();
+    let rd: Vec<RData> = m.answers.iter().map(|x| x.rdata().ok().unwrap()).collect();
+
+    match rd[0] {
+        RData::MX { preference, ref exchange } => {
+            assert_eq!(preference, 10);
+            assert_eq!(exchange.to_string(), "mail.example.com.");
+        },
+        ref x => panic!("unexpected rdata: {:?}", x),
+    }
+    assert_eq!(rd[1], RData::TXT(vec!(Cow::Borrowed(&b"hello"[..]), Cow::Borrowed(&b"world"[..]))));
+    match rd[2] {
+        RData::SRV { priority, weight, port, ref target } => {
+            assert_eq!((priority, weight, port), (1, 2, 53));
+            assert_eq!(target.to_string(), "example.com.");
+        },
+        ref x => panic!("unexpected rdata: {:?}", x),
+    }
+    match rd[3] {
+        RData::SOA { ref mname, ref rname, serial, refresh, retry, expire, minimum } => {
+            assert_eq!(mname.to_string(), "ns.example.com.");
+            assert_eq!(rname.to_string(), "host.example.com.");
+            assert_eq!((serial, refresh, retry, expire, minimum), (1, 2, 3, 4, 5));
+        },
+        ref x => panic!("unexpected rdata: {:?}", x),
+    }
+    assert_eq!(rd[4], RData::HINFO { cpu: Cow::Borrowed(b"cpu"), os: Cow::Borrowed(b"os") });
+    assert_eq!(rd[5], RData::Opaque(Cow::Borrowed(&[1, 2, 3])));
+
+    // An A record with a short RDATA section is an error, not a panic
+    let mut rr = m.answers[5].clone();
+    rr.rtype = Type::A;
+    assert_eq!(rr.rdata(), Err(ReadError::IndexOutOfRangeError(rr.rdata + 4, rr.rdata + 3)));
+
+    // Trailing octets after a fully decoded RDATA are an error
+    let mut rr = m.answers[4].clone();
+    rr.rdlen += 1;
+    assert_eq!(rr.rdata(), Err(ReadError::RDataLengthError(8)));
+}
+
+#[test]
+fn test_header_flags() {
+    let m = read_dns_message(NET1_RS).ok().unwrap();
+    let h = m.header();
+    assert!(h.qr);
+    assert_eq!(h.opcode(), Ok(OpCode::Query));
+    assert!(!h.aa && !h.tc && !h.rd && !h.ra && !h.z && !h.ad && !h.cd);
+    assert_eq!(h.rcode(), Ok(RCode::NoError));
+
+    let m = read_dns_message(FB1_RS).ok().unwrap();
+    let h = m.header();
+    assert!(h.qr && h.rd && h.ra);
+    assert!(!h.aa && !h.tc);
+
+    // Every flags value survives a decode/encode round trip
+    for flags in 0..65536usize {
+        assert_eq!(Header::from_flags(flags as u16).to_flags(), flags as u16);
+    }
+}
+
+#[test]
+fn test_header_setters() {
+    let mut m = Message::new(1, 0);
+    let mut h = m.header();
+    h.qr = true;
+    h.aa = true;
+    h.ad = true;
+    h.set_opcode(OpCode::Notify);
+    h.set_rcode(RCode::NXDomain);
+    m.set_header(&h);
+    assert_eq!(m.flags, 0x8000 | 0x2000 | 0x0400 | 0x0020 | 0x0003);
+
+    // Extended RCODEs only keep their low four bits in the header
+    h.set_rcode(RCode::BADVERS);
+    assert_eq!(h.rcode, 0);
+}
+
+#[test]
+fn test_header_unassigned_codes() {
+    // OPCODE 3 and RCODE 11 are unassigned but still decode
+    let h = Header::from_flags(0x1800 | 0x000b);
+    assert_eq!(h.opcode, 3);
+    assert_eq!(h.rcode, 11);
+    assert!(h.opcode().is_err());
+    assert!(h.rcode().is_err());
+    assert_eq!(h.to_flags(), 0x180b);
+}
+
+#[test]
+fn test_unknown_type_and_class() {
+    let buf = [
+        0x00u8, 0x01, 0x80, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+        // example. TYPE65280 CLASS4096
+        0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x00, 0xff, 0x00, 0x10, 0x00,
+        // example. 60 CLASS4096 TYPE65280 \# 2 abcd
+        0xc0, 0x0c, 0xff, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x02,
+        0xab, 0xcd,
+    ];
+    let m = read_dns_message(&buf).ok().unwrap();
+    assert_eq!(m.questions[0].qtype, Type::Unknown(65280));
+    assert_eq!(m.questions[0].qclass, Class::Unknown(4096));
+    assert_eq!(m.answers[0].rtype, Type::Unknown(65280));
+    assert_eq!(m.answers[0].rclass, Class::Unknown(4096));
+    assert_eq!(m.answers[0].rdata(), Ok(RData::Opaque(Cow::Borrowed(&[0xab, 0xcd]))));
+
+    assert_eq!(read_dns_message_strict(&buf).err(),
+               Some(ReadError::InvalidIdentifierError(IdentifierError::PrivateUseIdentifierError(65280))));
+    assert!(read_dns_message_strict(NET1_RS).is_ok());
+}
+
+static LARGE1_RS: &[u8] = include_bytes!("../../tests/packets/large1-rs.bin");
+static LARGE2_RS: &[u8] = include_bytes!("../../tests/packets/large2-rs.bin");
+
+#[test]
+fn test_read_large_messages() {
+    // Glue owners beyond offset 255 are pointers whose first octet
+    // carries part of the offset (e.g. 0xc1 0x2a)
+    let m = read_dns_message(LARGE1_RS).ok().unwrap();
+    assert_eq!(m.answers.len(), 60);
+    assert_eq!(m.additionals.len(), 60);
+    for (i, (ns, glue)) in m.answers.iter().zip(m.additionals.iter()).enumerate() {
+        let n = format!("ns{:02}.big.example.com.", i);
+        assert_eq!(Name::from_rdata(ns).ok().unwrap().to_string(), n);
+        assert_eq!(glue.rname.to_string(), n);
+        assert_eq!(glue.rdata(), Ok(RData::A(Ipv4Addr::new(192, 0, 2, i as u8))));
+    }
+
+    // Owners in the second half point back at names up to 0x3fff;
+    // later ones are written out in full since they are out of reach
+    let m = read_dns_message(LARGE2_RS).ok().unwrap();
+    assert!(LARGE2_RS.len() > 0x4000);
+    assert_eq!(m.answers.len(), 360);
+    for (i, rr) in m.answers.iter().enumerate() {
+        let n = format!("t{:03}.txt.example.com.", i % 180);
+        assert_eq!(rr.rname, Name::from_str(&n).ok().unwrap());
+    }
+}
+
+#[test]
+fn test_bad_pointers() {
+    // A name that points at itself
+    let b = b"\x03www\xc0\x00";
+    assert_eq!(read_dns_name(b, &mut 0).err(), Some(ReadError::PointerLoopError(4)));
+    let b = b"\x00\x03www\xc0\x01";
+    assert_eq!(read_dns_name(b, &mut 1).err(), Some(ReadError::PointerLoopError(5)));
+
+    // A pointer to itself and a forward pointer
+    let b = b"\xc0\x00";
+    assert_eq!(read_dns_name(b, &mut 0).err(), Some(ReadError::BadPointerError(0)));
+    let b = b"\xc0\x02\x00";
+    assert_eq!(read_dns_name(b, &mut 0).err(), Some(ReadError::BadPointerError(0)));
+
+    // Two names that point at each other need a forward pointer
+    let b = b"\x01a\xc0\x06\x00\x00\x01b\xc0\x00";
+    assert_eq!(read_dns_name(b, &mut 6).err(), Some(ReadError::BadPointerError(2)));
+
+    // A truncated pointer
+    let b = b"\x01a\xc0";
+    assert_eq!(read_dns_name(b, &mut 0).err(), Some(ReadError::IndexOutOfRangeError(4, 3)));
+
+    // Unused label types
+    let b = b"\x41a\x00";
+    assert_eq!(read_dns_name(b, &mut 0).err(), Some(ReadError::LabelTypeError(0x41)));
+
+    // A chain of backwards pointers is fine
+    let b = b"\x03com\x00\x07example\xc0\x00\x03www\xc0\x05";
+    assert_eq!(read_dns_name(b, &mut 15).ok().unwrap().to_string(), "www.example.com.");
+}
+
+#[test]
+fn test_name_too_long() {
+    // 127 one-octet labels plus the root make a 255-octet name
+    let mut b = Vec::new();
+    for _ in 0..127 { b.extend_from_slice(b"\x01a"); }
+    b.push(0);
+    assert_eq!(read_dns_name(&b, &mut 0).ok().unwrap().labels.len(), 127);
+
+    let mut b2 = b"\x01a".to_vec();
+    b2.extend_from_slice(&b);
+    assert_eq!(read_dns_name(&b2, &mut 0).err(), Some(ReadError::NameTooLongError(257)));
+
+    // The limit also applies to names assembled through pointers
+    let mut b3 = b[2..].to_vec();
+    b3.extend_from_slice(b"\x01b\x01c\xc0\x00");
+    let mut i = b.len() - 2;
+    assert_eq!(read_dns_name(&b3, &mut i).err(), Some(ReadError::NameTooLongError(257)));
+}
+
+fn read_owned(buf: &[u8]) -> OwnedMessage {
+    // Copy the buffer so the parsed message cannot borrow the static data
+    let copy = buf.to_vec();
+    let m = read_dns_message(&copy).ok().unwrap();
+    m.into_owned().ok().unwrap()
+}
+
+#[test]
+fn test_owned_message() {
+    let owned = read_owned(NET1_RS);
+    let borrowed = read_dns_message(NET1_RS).ok().unwrap();
+
+    assert_eq!(owned.id, borrowed.id);
+    assert_eq!(owned.questions, borrowed.questions);
+    for (o, b) in owned.nameservers.iter().zip(borrowed.nameservers.iter()) {
+        assert_eq!(o.rname, b.rname);
+        assert_eq!(o.rdata().ok().unwrap(), b.rdata().ok().unwrap());
+        // Owned RDATA is decompressed, so it stands on its own
+        assert_eq!(o.rdata, 0);
+        assert_eq!(o.context.len(), o.rdlen as usize);
+        assert!(o.context.len() > b.rdlen as usize);
+    }
+
+    // Every owned record equals the borrowed record it came from
+    for packet in [NET1_RS, FB1_RS].iter() {
+        let borrowed = read_dns_message(packet).ok().unwrap();
+        let sections = [&borrowed.answers, &borrowed.nameservers, &borrowed.additionals];
+        for rr in sections.iter().flat_map(|s| s.iter()) {
+            let owned = rr.clone().into_owned().ok().unwrap();
+            assert_eq!(owned, *rr);
+            assert_eq!(*rr, owned);
+        }
+    }
+    let mut other = borrowed.nameservers[0].clone().into_owned().ok().unwrap();
+    assert!(other != borrowed.nameservers[1]);
+    other.rttl += 1;
+    assert!(other != borrowed.nameservers[0]);
+
+    // Owned messages can be moved to other threads
+    let t = thread::spawn(move || {
+        owned.nameservers.iter().map(|r| Name::from_rdata(r).ok().unwrap().to_string()).collect::<Vec<String>>()
+    });
+    let names = t.join().ok().unwrap();
+    assert_eq!(names.len(), 13);
+    assert!(names.contains(&"a.gtld-servers.net.".to_string()));
+}
+
+#[test]
+fn test_owned_name_semantics() {
+    let s = format!("{}.{}.", "WWW", "Example");
+    let owned: OwnedName = Name::from_str(&s).ok().unwrap().into_owned();
+    drop(s);
+
+    let borrowed = Name::from_str("www.example.").ok().unwrap();
+    assert_eq!(owned, borrowed);
+    assert_eq!(owned.to_string(), "WWW.Example.");
+    assert!(owned < Name::from_str("a.www.example.").ok().unwrap());
+    assert!(owned > Name::from_str("example.").ok().unwrap());
+
+    let label: OwnedLabel = Label::from_vec(b"www".to_vec()).ok().unwrap();
+    assert_eq!(label, owned.labels[0]);
+    assert!(Label::from_vec(vec!(0x61; 64)).is_err());
+    assert!(Label::from_vec(Vec::new()).is_err());
+}
+
+#[test]
+fn test_name_canonical_order() {
+    // The example ordering from RFC 4034 section 6.1
+    let labels: Vec<Vec<&[u8]>> = vec![
+        vec![b"example"],
+        vec![b"a", b"example"],
+        vec![b"yljkjljk", b"a", b"example"],
+        vec![b"Z", b"a", b"example"],
+        vec![b"zABC", b"a", b"EXAMPLE"],
+        vec![b"z", b"example"],
+        vec![b"\x01", b"z", b"example"],
+        vec![b"*", b"z", b"example"],
+        vec![b"\xc8", b"z", b"example"],
+    ];
+    let names: Vec<Name> = labels.iter().map(|ls| Name {
+        labels: ls.iter().map(|l| Label::from_slice(l).ok().unwrap()).collect(),
+    }).collect();
+    for (i, a) in names.iter().enumerate() {
+        for (j, b) in names.iter().enumerate() {
+            assert_eq!(a.canonical_cmp(b), i.cmp(&j), "{} <=> {}", a, b);
+        }
+    }
+    assert_eq!(names[8].to_string(), "\\200.z.example.");
+    assert_eq!(Name::from_str("A.EXAMPLE.").ok().unwrap().canonical_cmp(&names[1]), Ordering::Equal);
+}
+
+#[test]
+fn test_label_display_escapes() {
+    let l = Label::from_slice(b"a.b\"c(d)e;f\\g@h$i j\x7f").ok().unwrap();
+    assert_eq!(l.to_string(), r#"a\046b\"c\(d\)e\;f\\g\@h\$i\032j\127"#);
+}
+
+#[test]
+fn test_name_from_str_escapes() {
+    let n = Name::from_str(r"a\.b.c\\d.\065\000z.").ok().unwrap();
+    let labels: Vec<&[u8]> = n.labels.iter().map(|l| l.as_bytes()).collect();
+    assert_eq!(labels, vec![&b"a.b"[..], &b"c\\d"[..], &b"A\x00z"[..]]);
+    assert_eq!(n.to_string(), r"a\046b.c\\d.A\000z.");
+
+    // Labels without escapes borrow from the input
+    let s = "www.ex\\097mple.com";
+    let n = Name::from_str(s).ok().unwrap();
+    assert_eq!(n.labels[0].as_bytes().as_ptr(), s.as_ptr());
+    assert_eq!(n.labels[1].as_bytes(), b"example");
+    assert_eq!(n.labels[2].as_bytes().as_ptr(), s[15..].as_ptr());
+
+    assert_eq!(Name::from_str(".").ok().unwrap().labels.len(), 0);
+    assert_eq!(Name::from_str("a\\.").ok().unwrap().labels[0].as_bytes(), b"a.");
+    assert_eq!(Name::from_str("a\\256").err().unwrap(), ReadError::InvalidEscapeError(1));
+    assert_eq!(Name::from_str("a\\").err().unwrap(), ReadError::InvalidEscapeError(1));
+    // Empty labels are rejected rather than skipped
+    assert_eq!(Name::from_str("a..b").err().unwrap(), ReadError::LabelZeroLengthError);
+    assert_eq!(Name::from_str(".a").err().unwrap(), ReadError::LabelZeroLengthError);
+    // The empty string is the root
+    assert_eq!(Name::from_str("").ok().unwrap().labels.len(), 0);
+}
+
+#[test]
+fn test_name_from_str_relative() {
+    let origin = Name::from_str("example.com.").ok().unwrap();
+    assert!(!Name::from_presentation(b"www").ok().unwrap().1);
+    assert!(Name::from_presentation(b"www.").ok().unwrap().1);
+    assert!(!Name::from_presentation(b"www\\.").ok().unwrap().1);
+    assert!(Name::from_presentation(b".").ok().unwrap().1);
+    assert_eq!(Name::from_str_relative("www", &origin).ok().unwrap().to_string(), "www.example.com.");
+    assert_eq!(Name::from_str_relative("www.example.net.", &origin).ok().unwrap().to_string(), "www.example.net.");
+    assert_eq!(Name::from_str_relative("@", &origin).ok().unwrap(), origin);
+    assert_eq!(Name::from_str_relative(".", &origin).ok().unwrap().labels.len(), 0);
+}
+
+#[test]
+fn test_name_from_str_length() {
+    // 4 labels of 62 octets plus a label of 1 octet is 255 octets
+    let l62 = "a".repeat(62);
+    let max = format!("{0}.{0}.{0}.{0}.b.", l62);
+    assert!(Name::from_str(&max).is_ok());
+    let over = format!("{0}.{0}.{0}.{0}.bc.", l62);
+    assert_eq!(Name::from_str(&over).err().unwrap(), ReadError::NameTooLongError(256));
+
+    let relative = format!("{0}.{0}.{0}.{0}", l62);
+    let origin = Name::from_str("b.").ok().unwrap();
+    assert!(Name::from_str_relative(&relative, &origin).is_ok());
+    let origin = Name::from_str("bc.").ok().unwrap();
+    assert_eq!(Name::from_str_relative(&relative, &origin).err().unwrap(), ReadError::NameTooLongError(256));
+}
+
+#[test]
+fn test_name_display_parse_identity() {
+    // Format names with arbitrary label octets and parse them back,
+    // using a fixed xorshift sequence so failures are reproducible
+    let mut state = 0x2545F4914F6CDD1Du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for _ in 0..2000 {
+        let mut labels = Vec::new();
+        let mut len = 1;
+        for _ in 0..(next() % 6) {
+            let llen = (next() % 63 + 1) as usize;
+            if len + 1 + llen > 255 {
+                break;
+            }
+            len += 1 + llen;
+            // Bias towards the characters that need escaping
+            let bytes = (0..llen).map(|_| match next() % 4 {
+                0 => b".\\\"();@$ "[(next() % 9) as usize],
+                _ => next() as u8,
+            }).collect();
+            labels.push(Label::from_vec(bytes).ok().unwrap());
+        }
+        let name = Name { labels };
+        let s = name.to_string();
+        let parsed = Name::from_str(&s).ok().unwrap();
+        assert_eq!(parsed.labels.len(), name.labels.len(), "{}", s);
+        for (a, b) in parsed.labels.iter().zip(name.labels.iter()) {
+            assert_eq!(a.as_bytes(), b.as_bytes(), "{}", s);
+        }
+    }
+}
+
+#[test]
+fn test_type_bitmap_errors() {
+    let read = |rdata: &[u8]| {
+        let mut i = 0;
+        read_dns_rdata(&Type::NSEC, rdata, &mut i, rdata.len() as u16).err()
+    };
+    // Windows out of order, repeated, empty or too long
+    assert_eq!(read(b"\x00\x01\x01\x40\x00\x01\x40"), Some(ReadError::TypeBitmapError(4)));
+    assert_eq!(read(b"\x00\x00\x01\x40\x00\x01\x40"), Some(ReadError::TypeBitmapError(4)));
+    assert_eq!(read(b"\x00\x00\x00"), Some(ReadError::TypeBitmapError(1)));
+    let mut long = vec![0u8, 0, 33];
+    long.extend_from_slice(&[0xFF; 33]);
+    assert_eq!(read(&long), Some(ReadError::TypeBitmapError(1)));
+    assert_eq!(read(b"\x00\x00\x02\x40"), Some(ReadError::IndexOutOfRangeError(5, 4)));
+}
+
+#[test]
+fn test_key_tag() {
+    // RFC 4034 §2.3, whose key signs the RRSIG example of §3.3 with key tag 2642
+    let key = decode_base64(b"AQPSKmynfzW4kyBv015MUG2DeIQ3Cbl+BBZH4b/0PY1kxkmvHjcZc8no\
+                               kfzj31GajIQKY+5CptLr3buXA10hWqTkF7H6RfoRqXQeogmMHfpftf6z\
+                               Mv1LyBUgia7za6ZEzOJBOztyvhjL742iU/TpPSEDhm2SNKLijfUppn1U\
+                               aNvv4w==").unwrap();
+    let dnskey = RData::DNSKEY { flags: 256, protocol: 3, algorithm: 5, public_key: Cow::Borrowed(&key) };
+    assert_eq!(dnskey.key_tag(), Some(2642));
+    let cdnskey = RData::CDNSKEY { flags: 256, protocol: 3, algorithm: 5, public_key: Cow::Borrowed(&key) };
+    assert_eq!(cdnskey.key_tag(), Some(2642));
+    let k = RData::KEY { flags: 256, protocol: 3, algorithm: 5, public_key: Cow::Borrowed(&key) };
+    assert_eq!(k.key_tag(), Some(2642));
+    assert_eq!(RData::A(Ipv4Addr::new(192, 0, 2, 1)).key_tag(), None);
+
+    // RSA/MD5 keys use octets of the modulus instead
+    assert_eq!(key_tag(256, 3, 1, &[0x01, 0x02, 0xAB, 0xCD, 0xEF]), 0xABCD);
+}
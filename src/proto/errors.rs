@@ -0,0 +1,79 @@
+use std::fmt;
+use std::error;
+use std::convert;
+use std::io;
+use super::{ReadError,WriteError};
+
+/// An error exchanging messages with a server.
+#[derive(PartialEq,Copy,Clone,Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum ProtoError {
+    IoError(io::ErrorKind),
+    TimeoutError,
+    ConnectionClosedError,
+    MessageError(ReadError),
+    EncodingError(WriteError),
+    MessageTooLongError(usize),
+    ResponseMismatchError,
+    NoPendingQueryError,
+}
+
+impl error::Error for ProtoError {
+    fn description(&self) -> &str {
+        match *self {
+            ProtoError::IoError(_) => "Socket operation failed",
+            ProtoError::TimeoutError => "No response before the timeout",
+            ProtoError::ConnectionClosedError => "Connection closed by the server",
+            ProtoError::MessageError(_) => "Response is malformed",
+            ProtoError::EncodingError(_) => "Message cannot be written",
+            ProtoError::MessageTooLongError(_) => "Message too long for TCP framing",
+            ProtoError::ResponseMismatchError => "Response does not match the query",
+            ProtoError::NoPendingQueryError => "No such query is outstanding",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match *self {
+            ProtoError::MessageError(ref err) => Some(err as &dyn error::Error),
+            ProtoError::EncodingError(ref err) => Some(err as &dyn error::Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ProtoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProtoError::IoError(x) => write!(f, "Socket operation failed: {:?}", x),
+            ProtoError::TimeoutError => write!(f, "No response before the timeout"),
+            ProtoError::ConnectionClosedError => write!(f, "Connection closed by the server"),
+            ProtoError::MessageError(x) => write!(f, "Response is malformed: {}", x),
+            ProtoError::EncodingError(x) => write!(f, "Message cannot be written: {}", x),
+            ProtoError::MessageTooLongError(x) => write!(f, "Message too long for TCP framing: {}", x),
+            ProtoError::ResponseMismatchError => write!(f, "Response does not match the query"),
+            ProtoError::NoPendingQueryError => write!(f, "No such query is outstanding"),
+        }
+    }
+}
+
+impl convert::From<io::Error> for ProtoError {
+    fn from(err: io::Error) -> ProtoError {
+        match err.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => ProtoError::TimeoutError,
+            io::ErrorKind::UnexpectedEof => ProtoError::ConnectionClosedError,
+            kind => ProtoError::IoError(kind),
+        }
+    }
+}
+
+impl convert::From<ReadError> for ProtoError {
+    fn from(err: ReadError) -> ProtoError {
+        ProtoError::MessageError(err)
+    }
+}
+
+impl convert::From<WriteError> for ProtoError {
+    fn from(err: WriteError) -> ProtoError {
+        ProtoError::EncodingError(err)
+    }
+}
@@ -0,0 +1,16 @@
+pub use super::iana::types::Type;
+pub use super::iana::classes::Class;
+pub use super::parser::{Message,OwnedMessage,Question,ReadError,is_response_to};
+pub use super::writer::WriteError;
+
+pub use super::proto::errors::ProtoError;
+pub use super::proto::query::{new_query,random_id};
+pub use super::proto::tcp::{TcpClient,TcpConnection,DEFAULT_IDLE_TIMEOUT,read_tcp_message,write_tcp_message,tcp_exchange};
+pub use super::proto::udp::{UdpClient,DEFAULT_ATTEMPTS,DEFAULT_TIMEOUT};
+
+mod errors;
+mod query;
+mod tcp;
+mod udp;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,28 @@
+use super::{Message,Question};
+use super::super::edns::Edns;
+use super::super::writer::{WriteError,write_dns_message};
+
+use ring::rand::{SecureRandom,SystemRandom};
+
+const RD: u16 = 0x0100;
+
+/// A random message ID, as per
+/// [RFC5452§4.3](https://tools.ietf.org/html/rfc5452#section-4.3).
+pub fn random_id() -> u16 {
+    let mut id = [0u8; 2];
+    // Falls back to ID 0 only if the system has no randomness at all
+    let _ = SystemRandom::new().fill(&mut id);
+    (id[0] as u16) << 8 | id[1] as u16
+}
+
+/// Serialize a query for `question` with the given ID, asking for
+/// recursion if `recursion_desired` is set and adding an OPT record
+/// for `edns` if there is one.
+pub fn new_query(id: u16, question: &Question, recursion_desired: bool, edns: Option<&Edns>) -> Result<Vec<u8>, WriteError> {
+    let mut msg = Message::new(id, if recursion_desired { RD } else { 0 });
+    msg.questions.push(question.clone());
+    if let Some(edns) = edns {
+        msg.set_edns(edns)?;
+    }
+    write_dns_message(&msg)
+}
@@ -0,0 +1,339 @@
+use super::{OwnedMessage,ProtoError,Question};
+use super::is_response_to;
+use super::query::{new_query,random_id};
+use super::udp::DEFAULT_TIMEOUT;
+use super::super::edns::{Edns,OwnedEdns,TcpKeepalive};
+use super::super::parser::read_dns_message;
+
+use std::collections::HashMap;
+use std::io::{Read,Write};
+use std::net::{Shutdown,SocketAddr,TcpStream};
+use std::time::{Duration,Instant};
+
+/// Write a message with the 2-byte length prefix of
+/// [RFC1035§4.2.2](https://tools.ietf.org/html/rfc1035#section-4.2.2).
+/// The prefix and message go out in a single write, as
+/// [RFC7766§8](https://tools.ietf.org/html/rfc7766#section-8) recommends.
+pub fn write_tcp_message<W: Write>(w: &mut W, msg: &[u8]) -> Result<(), ProtoError> {
+    if msg.len() > 65535 {
+        return Err(ProtoError::MessageTooLongError(msg.len()));
+    }
+    let mut buf = Vec::with_capacity(msg.len() + 2);
+    buf.push((msg.len() >> 8) as u8);
+    buf.push(msg.len() as u8);
+    buf.extend_from_slice(msg);
+    w.write_all(&buf)?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Read a message with a 2-byte length prefix.
+pub fn read_tcp_message<R: Read>(r: &mut R) -> Result<Vec<u8>, ProtoError> {
+    let mut len = [0u8; 2];
+    r.read_exact(&mut len)?;
+    let mut msg = vec![0u8; (len[0] as usize) << 8 | len[1] as usize];
+    r.read_exact(&mut msg)?;
+    Ok(msg)
+}
+
+/// Send a single serialized query to `server` over a new TCP connection
+/// and return the first message it answers with.
+pub fn tcp_exchange(server: SocketAddr, query: &[u8], timeout: Duration) -> Result<Vec<u8>, ProtoError> {
+    let mut stream = TcpStream::connect_timeout(&server, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    stream.set_nodelay(true)?;
+    write_tcp_message(&mut stream, query)?;
+    read_tcp_message(&mut stream)
+}
+
+/// How long a connection may stay idle before it is no longer reused,
+/// unless the server advertises its own timeout with edns-tcp-keepalive.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A connection to a server carrying any number of queries, as per
+/// [RFC7766§6.2.1](https://tools.ietf.org/html/rfc7766#section-6.2.1).
+///
+/// Queries can be pipelined: several may be sent before any response
+/// is read. Responses are matched to their query by ID and may arrive
+/// in any order; those read while waiting for another are kept until
+/// asked for. Responses to no outstanding query are discarded.
+///
+/// Queries carry the edns-tcp-keepalive option of
+/// [RFC7828](https://tools.ietf.org/html/rfc7828) when EDNS is enabled,
+/// and the idle timeout the server answers with replaces the client's.
+#[derive(Debug)]
+pub struct TcpConnection {
+    stream: TcpStream,
+    idle_timeout: Duration,
+    server_idle_timeout: Option<Duration>,
+    recursion_desired: bool,
+    edns: Option<OwnedEdns>,
+    keepalive: bool,
+    pending: HashMap<u16, OwnedMessage>,
+    responses: HashMap<u16, OwnedMessage>,
+    last_used: Instant,
+    closed: bool,
+}
+
+impl TcpConnection {
+    /// Connect to `server`, waiting at most `timeout` for the connection
+    /// and for each read and write on it.
+    pub fn connect(server: SocketAddr, timeout: Duration) -> Result<TcpConnection, ProtoError> {
+        let stream = TcpStream::connect_timeout(&server, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        stream.set_nodelay(true)?;
+        Ok(TcpConnection {
+            stream,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            server_idle_timeout: None,
+            recursion_desired: true,
+            edns: Some(Edns::new()),
+            keepalive: true,
+            pending: HashMap::new(),
+            responses: HashMap::new(),
+            last_used: Instant::now(),
+            closed: false,
+        })
+    }
+
+    /// Set how long the connection may stay idle and still be reused.
+    pub fn set_idle_timeout(&mut self, timeout: Duration) {
+        self.idle_timeout = timeout;
+    }
+
+    /// Set or clear the RD flag of queries.
+    pub fn set_recursion_desired(&mut self, rd: bool) {
+        self.recursion_desired = rd;
+    }
+
+    /// Set the EDNS settings of queries, or send them without an OPT
+    /// record and so without edns-tcp-keepalive.
+    pub fn set_edns(&mut self, edns: Option<OwnedEdns>) {
+        self.edns = edns;
+    }
+
+    /// Enable or disable sending edns-tcp-keepalive with queries.
+    pub fn set_keepalive(&mut self, keepalive: bool) {
+        self.keepalive = keepalive;
+    }
+
+    /// The idle timeout in effect: the one the server last advertised,
+    /// or else the one set on the connection.
+    pub fn idle_timeout(&self) -> Duration {
+        self.server_idle_timeout.unwrap_or(self.idle_timeout)
+    }
+
+    /// The number of queries sent whose response has not been read.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether more queries can be sent on this connection: it must not
+    /// have failed or been closed, and must have queries outstanding or
+    /// have been idle for less than the idle timeout.
+    pub fn is_reusable(&self) -> bool {
+        !self.closed && (!self.pending.is_empty() || self.last_used.elapsed() < self.idle_timeout())
+    }
+
+    /// Send a query for `question`, returning its ID to pass to
+    /// `receive`.
+    pub fn send(&mut self, question: &Question) -> Result<u16, ProtoError> {
+        if self.closed {
+            return Err(ProtoError::ConnectionClosedError);
+        }
+        let mut id = random_id();
+        while self.pending.contains_key(&id) || self.responses.contains_key(&id) {
+            id = random_id();
+        }
+        let mut edns = self.edns.clone();
+        if let Some(ref mut edns) = edns {
+            if self.keepalive {
+                if let Ok(option) = (TcpKeepalive { timeout: None }).to_option() {
+                    edns.set_option(option);
+                }
+            }
+        }
+        let request = new_query(id, question, self.recursion_desired, edns.as_ref())?;
+        let query = read_dns_message(&request)?.into_owned()?;
+        if let Err(err) = write_tcp_message(&mut self.stream, &request) {
+            self.closed = true;
+            return Err(err);
+        }
+        self.pending.insert(id, query);
+        self.last_used = Instant::now();
+        Ok(id)
+    }
+
+    /// Wait for the response to the query with ID `id`.
+    pub fn receive(&mut self, id: u16) -> Result<OwnedMessage, ProtoError> {
+        if let Some(response) = self.responses.remove(&id) {
+            return Ok(response);
+        }
+        if !self.pending.contains_key(&id) {
+            return Err(ProtoError::NoPendingQueryError);
+        }
+        loop {
+            let response = self.read_response()?;
+            if response.id == id {
+                return Ok(response);
+            }
+            self.responses.insert(response.id, response);
+        }
+    }
+
+    /// Wait for the response to any outstanding query.
+    pub fn receive_any(&mut self) -> Result<OwnedMessage, ProtoError> {
+        if let Some(&id) = self.responses.keys().next() {
+            return self.receive(id);
+        }
+        if self.pending.is_empty() {
+            return Err(ProtoError::NoPendingQueryError);
+        }
+        self.read_response()
+    }
+
+    /// Send a query for `question` and wait for its response.
+    pub fn query(&mut self, question: &Question) -> Result<OwnedMessage, ProtoError> {
+        let id = self.send(question)?;
+        self.receive(id)
+    }
+
+    /// Close the connection. Outstanding queries are abandoned.
+    pub fn close(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+        self.closed = true;
+        self.pending.clear();
+    }
+
+    /// Read messages until one answers an outstanding query. Any failure
+    /// leaves the framing in an unknown state, so closes the connection.
+    fn read_response(&mut self) -> Result<OwnedMessage, ProtoError> {
+        loop {
+            let buf = match read_tcp_message(&mut self.stream) {
+                Ok(buf) => buf,
+                Err(err) => {
+                    self.close();
+                    return Err(err);
+                },
+            };
+            let response = match read_dns_message(&buf) {
+                Ok(msg) => msg,
+                Err(err) => {
+                    self.close();
+                    return Err(err.into());
+                },
+            };
+            let query = match self.pending.remove(&response.id) {
+                Some(query) => query,
+                None => continue,
+            };
+            if !is_response_to(&response, &query) {
+                self.close();
+                return Err(ProtoError::ResponseMismatchError);
+            }
+            // A malformed keepalive option is ignored
+            if let Ok(Some(edns)) = response.edns() {
+                if let Ok(Some(TcpKeepalive { timeout: Some(t) })) = edns.tcp_keepalive() {
+                    self.server_idle_timeout = Some(Duration::from_millis(t as u64 * 100));
+                }
+            }
+            self.last_used = Instant::now();
+            return Ok(response.into_owned()?);
+        }
+    }
+}
+
+/// A blocking client sending queries over TCP to one server, reusing
+/// its connection while the idle timeout allows, as per
+/// [RFC7766§6.2.1](https://tools.ietf.org/html/rfc7766#section-6.2.1).
+///
+/// A query that fails because the server closed a reused connection
+/// is retried once on a new one.
+#[derive(Debug)]
+pub struct TcpClient {
+    server: SocketAddr,
+    timeout: Duration,
+    idle_timeout: Duration,
+    recursion_desired: bool,
+    edns: Option<OwnedEdns>,
+    keepalive: bool,
+    connection: Option<TcpConnection>,
+}
+
+impl TcpClient {
+    /// Create a client for `server` that asks for recursion and sends
+    /// EDNS with edns-tcp-keepalive.
+    pub fn new(server: SocketAddr) -> TcpClient {
+        TcpClient {
+            server,
+            timeout: DEFAULT_TIMEOUT,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            recursion_desired: true,
+            edns: Some(Edns::new()),
+            keepalive: true,
+            connection: None,
+        }
+    }
+
+    /// Set the timeout of connecting and of each read and write.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Set how long a connection may stay idle and still be reused.
+    pub fn set_idle_timeout(&mut self, timeout: Duration) {
+        self.idle_timeout = timeout;
+    }
+
+    /// Set or clear the RD flag of queries.
+    pub fn set_recursion_desired(&mut self, rd: bool) {
+        self.recursion_desired = rd;
+    }
+
+    /// Set the EDNS settings of queries, or send them without an OPT
+    /// record.
+    pub fn set_edns(&mut self, edns: Option<OwnedEdns>) {
+        self.edns = edns;
+    }
+
+    /// Enable or disable sending edns-tcp-keepalive with queries.
+    pub fn set_keepalive(&mut self, keepalive: bool) {
+        self.keepalive = keepalive;
+    }
+
+    /// The current connection if it can be reused, or else a new one.
+    /// Use it directly to pipeline queries.
+    pub fn connection(&mut self) -> Result<&mut TcpConnection, ProtoError> {
+        if !self.connection.as_ref().is_some_and(|c| c.is_reusable()) {
+            self.connection = None;
+            let mut connection = TcpConnection::connect(self.server, self.timeout)?;
+            connection.set_idle_timeout(self.idle_timeout);
+            connection.set_recursion_desired(self.recursion_desired);
+            connection.set_edns(self.edns.clone());
+            connection.set_keepalive(self.keepalive);
+            self.connection = Some(connection);
+        }
+        Ok(self.connection.as_mut().unwrap())
+    }
+
+    /// Send a query for `question` and wait for its response.
+    pub fn query(&mut self, question: &Question) -> Result<OwnedMessage, ProtoError> {
+        let reused = self.connection.as_ref().is_some_and(|c| c.is_reusable());
+        match self.connection()?.query(question) {
+            Err(ProtoError::ConnectionClosedError) | Err(ProtoError::IoError(_)) if reused => {
+                self.connection = None;
+                self.connection()?.query(question)
+            },
+            result => result,
+        }
+    }
+
+    /// Close the current connection, if any.
+    pub fn close(&mut self) {
+        if let Some(mut connection) = self.connection.take() {
+            connection.close();
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use super::{Class,Message,Question,Type,ProtoError};
+use super::{TcpClient,TcpConnection,UdpClient,is_response_to,new_query,read_tcp_message,write_tcp_message};
+use super::super::edns::{Edns,TcpKeepalive};
+use super::super::parser::{Name,RData,ResourceRecord,read_dns_message};
+use super::super::writer::{write_dns_message,write_dns_rdata};
+
+use std::io::Cursor;
+use std::net::{Ipv4Addr,SocketAddr,TcpListener,TcpStream,UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::thread;
+use std::time::Duration;
+
+fn question(name: &str) -> Question<'_> {
+    Question {
+        qname: Name::from_str(name).ok().unwrap(),
+        qtype: Type::A,
+        qclass: Class::IN,
+    }
+}
+
+/// Answer `query` with one A record, or with none and TC set.
+fn answer(query: &[u8], truncated: bool) -> Vec<u8> {
+    let query = read_dns_message(query).ok().unwrap();
+    let mut msg = Message::new(query.id, 0x8180 | if truncated { 0x0200 } else { 0 });
+    msg.questions = query.questions.clone();
+    if !truncated {
+        let rdata = write_dns_rdata(&RData::A(Ipv4Addr::new(192, 0, 2, 1))).ok().unwrap();
+        msg.answers.push(ResourceRecord::new(query.questions[0].qname.clone(), Type::A, Class::IN, 300, rdata));
+    }
+    write_dns_message(&msg).ok().unwrap()
+}
+
+/// A loopback UDP server running `handler` on each datagram it gets.
+fn udp_server<F: FnMut(&UdpSocket, &[u8], SocketAddr) + Send + 'static>(mut handler: F) -> (UdpSocket, SocketAddr) {
+    let socket = UdpSocket::bind("127.0.0.1:0").ok().unwrap();
+    let addr = socket.local_addr().ok().unwrap();
+    let server = socket.try_clone().ok().unwrap();
+    thread::spawn(move || {
+        let mut buf = [0u8; 65535];
+        while let Ok((len, from)) = server.recv_from(&mut buf) {
+            handler(&server, &buf[..len], from);
+        }
+    });
+    (socket, addr)
+}
+
+fn client(addr: SocketAddr) -> UdpClient {
+    let mut client = UdpClient::new(addr);
+    client.set_timeout(Duration::from_millis(200));
+    client
+}
+
+#[test]
+fn test_query_validation() {
+    let q = question("example.");
+    let request = new_query(7, &q, true, None).ok().unwrap();
+    let query = read_dns_message(&request).ok().unwrap();
+    assert_eq!(query.flags, 0x0100);
+    assert!(query.additionals.is_empty());
+
+    let response = answer(&request, false);
+    assert!(is_response_to(&read_dns_message(&response).ok().unwrap(), &query));
+    // Questions echo case-insensitively
+    let mut msg = read_dns_message(&response).ok().unwrap();
+    msg.questions[0].qname = Name::from_str("EXAMPLE.").ok().unwrap();
+    assert!(is_response_to(&msg, &query));
+    msg.questions[0].qtype = Type::AAAA;
+    assert!(!is_response_to(&msg, &query));
+    let mut msg = read_dns_message(&response).ok().unwrap();
+    msg.id = 8;
+    assert!(!is_response_to(&msg, &query));
+    // A query is not its own response
+    assert!(!is_response_to(&query, &query));
+}
+
+#[test]
+fn test_udp_query() {
+    let (_socket, addr) = udp_server(|server, query, from| {
+        // Forged responses from another address, with another ID and for
+        // another question are ignored
+        let spoofer = UdpSocket::bind("127.0.0.1:0").ok().unwrap();
+        spoofer.send_to(&answer(query, false), from).ok().unwrap();
+        let mut wrong_id = answer(query, false);
+        wrong_id[1] ^= 1;
+        server.send_to(&wrong_id, from).ok().unwrap();
+        let mut msg = read_dns_message(query).ok().unwrap();
+        msg.questions[0].qname = Name::from_str("other.").ok().unwrap();
+        server.send_to(&answer(&write_dns_message(&msg).ok().unwrap(), false), from).ok().unwrap();
+        server.send_to(&answer(query, false), from).ok().unwrap();
+    });
+
+    let response = client(addr).query(&question("example.")).ok().unwrap();
+    assert_eq!(response.answers.len(), 1);
+    assert_eq!(response.answers[0].rdata().ok().unwrap(), RData::A(Ipv4Addr::new(192, 0, 2, 1)));
+}
+
+#[test]
+fn test_udp_retry() {
+    let mut count = 0;
+    let (_socket, addr) = udp_server(move |server, query, from| {
+        count += 1;
+        assert!(read_dns_message(query).ok().unwrap().edns().ok().unwrap().is_some());
+        if count > 1 {
+            server.send_to(&answer(query, false), from).ok().unwrap();
+        }
+    });
+    assert!(client(addr).query(&question("example.")).is_ok());
+
+    let (_socket, addr) = udp_server(|_, _, _| {});
+    let mut c = client(addr);
+    c.set_timeout(Duration::from_millis(20));
+    c.set_attempts(2);
+    assert_eq!(c.query(&question("example.")).err(), Some(ProtoError::TimeoutError));
+}
+
+#[test]
+fn test_udp_edns_fallback() {
+    // A server without EDNS support answers FORMERR with no question
+    let (_socket, addr) = udp_server(|server, query, from| {
+        let msg = read_dns_message(query).ok().unwrap();
+        if msg.edns().ok().unwrap().is_some() {
+            let formerr = Message::new(msg.id, 0x8001);
+            server.send_to(&write_dns_message(&formerr).ok().unwrap(), from).ok().unwrap();
+        } else {
+            server.send_to(&answer(query, false), from).ok().unwrap();
+        }
+    });
+    let mut c = client(addr);
+    c.set_attempts(1);
+    let response = c.query(&question("example.")).ok().unwrap();
+    assert_eq!(response.answers.len(), 1);
+    assert!(response.edns().ok().unwrap().is_none());
+
+    // Without EDNS such replies are not answers
+    let (_socket, addr) = udp_server(|server, query, from| {
+        let formerr = Message::new(read_dns_message(query).ok().unwrap().id, 0x8001);
+        server.send_to(&write_dns_message(&formerr).ok().unwrap(), from).ok().unwrap();
+    });
+    let mut c = client(addr);
+    c.set_timeout(Duration::from_millis(20));
+    c.set_attempts(1);
+    assert_eq!(c.query(&question("example.")).err(), Some(ProtoError::TimeoutError));
+}
+
+#[test]
+fn test_tcp_fallback() {
+    let (socket, addr) = udp_server(|server, query, from| {
+        server.send_to(&answer(query, true), from).ok().unwrap();
+    });
+    let listener = TcpListener::bind(addr).ok().unwrap();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().ok().unwrap();
+        let query = read_tcp_message(&mut stream).ok().unwrap();
+        write_tcp_message(&mut stream, &answer(&query, false)).ok().unwrap();
+    });
+
+    let response = client(addr).query(&question("example.")).ok().unwrap();
+    assert_eq!(response.flags & 0x0200, 0);
+    assert_eq!(response.answers.len(), 1);
+
+    let mut c = client(socket.local_addr().ok().unwrap());
+    c.set_tcp_fallback(false);
+    let response = c.query(&question("example.")).ok().unwrap();
+    assert_eq!(response.flags & 0x0200, 0x0200);
+    assert!(response.answers.is_empty());
+}
+
+#[test]
+fn test_tcp_framing() {
+    let mut buf = Vec::new();
+    write_tcp_message(&mut buf, b"first").ok().unwrap();
+    write_tcp_message(&mut buf, &[]).ok().unwrap();
+    assert_eq!(&buf[..7], b"\x00\x05first");
+    let mut r = Cursor::new(buf);
+    assert_eq!(read_tcp_message(&mut r).ok().unwrap(), b"first");
+    assert_eq!(read_tcp_message(&mut r).ok().unwrap(), b"");
+    assert_eq!(read_tcp_message(&mut r), Err(ProtoError::ConnectionClosedError));
+    assert_eq!(read_tcp_message(&mut Cursor::new(b"\x00\x05fir")), Err(ProtoError::ConnectionClosedError));
+    assert_eq!(write_tcp_message(&mut Vec::new(), &[0; 65536]), Err(ProtoError::MessageTooLongError(65536)));
+}
+
+/// Answer `query` with one A record and an edns-tcp-keepalive option
+/// advertising `keepalive`, after checking the query asked for it.
+fn answer_keepalive(query: &[u8], keepalive: u16) -> Vec<u8> {
+    let msg = read_dns_message(query).ok().unwrap();
+    let option = msg.edns().ok().unwrap().unwrap().tcp_keepalive().ok().unwrap();
+    assert_eq!(option, Some(TcpKeepalive { timeout: None }));
+
+    let response = answer(query, false);
+    let mut msg = read_dns_message(&response).ok().unwrap();
+    let mut edns = Edns::new();
+    edns.set_option(TcpKeepalive { timeout: Some(keepalive) }.to_option().ok().unwrap());
+    msg.set_edns(&edns).ok().unwrap();
+    write_dns_message(&msg).ok().unwrap()
+}
+
+/// A loopback TCP server running `handler` on each connection, and
+/// counting them.
+fn tcp_server<F: Fn(TcpStream) + Send + Sync + 'static>(handler: F) -> (SocketAddr, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").ok().unwrap();
+    let addr = listener.local_addr().ok().unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+    let accepted = count.clone();
+    let handler = Arc::new(handler);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            accepted.fetch_add(1, Ordering::SeqCst);
+            let handler = handler.clone();
+            thread::spawn(move || handler(stream.ok().unwrap()));
+        }
+    });
+    (addr, count)
+}
+
+#[test]
+fn test_tcp_pipelining() {
+    let (addr, _) = tcp_server(|mut stream| {
+        let queries: Vec<Vec<u8>> = (0..3).map(|_| read_tcp_message(&mut stream).ok().unwrap()).collect();
+        // A response to no outstanding query is discarded
+        let mut unsolicited = answer(&queries[0], false);
+        unsolicited[0] ^= 0xFF;
+        write_tcp_message(&mut stream, &unsolicited).ok().unwrap();
+        for query in queries.iter().rev() {
+            write_tcp_message(&mut stream, &answer(query, false)).ok().unwrap();
+        }
+    });
+
+    let mut c = TcpConnection::connect(addr, Duration::from_secs(1)).ok().unwrap();
+    let names = ["a.example.", "b.example.", "c.example."];
+    let ids: Vec<u16> = names.iter().map(|name| c.send(&question(name)).ok().unwrap()).collect();
+    assert_eq!(c.pending(), 3);
+
+    let first = c.receive(ids[0]).ok().unwrap();
+    assert_eq!(first.questions[0].qname, Name::from_str(names[0]).ok().unwrap());
+    assert_eq!(c.pending(), 0);
+    let any = c.receive_any().ok().unwrap();
+    assert!(any.id == ids[1] || any.id == ids[2]);
+    let last = if any.id == ids[1] { ids[2] } else { ids[1] };
+    assert_eq!(c.receive(last).ok().unwrap().id, last);
+
+    assert_eq!(c.receive(ids[0]).err(), Some(ProtoError::NoPendingQueryError));
+    assert_eq!(c.receive_any().err(), Some(ProtoError::NoPendingQueryError));
+    assert!(c.is_reusable());
+    c.close();
+    assert!(!c.is_reusable());
+    assert_eq!(c.send(&question("example.")).err(), Some(ProtoError::ConnectionClosedError));
+}
+
+#[test]
+fn test_tcp_keepalive() {
+    let (addr, _) = tcp_server(|mut stream| {
+        for keepalive in [1200, 0].iter() {
+            let query = read_tcp_message(&mut stream).ok().unwrap();
+            write_tcp_message(&mut stream, &answer_keepalive(&query, *keepalive)).ok().unwrap();
+        }
+    });
+
+    let mut c = TcpConnection::connect(addr, Duration::from_secs(1)).ok().unwrap();
+    c.set_idle_timeout(Duration::from_secs(1));
+    assert!(c.query(&question("example.")).is_ok());
+    assert_eq!(c.idle_timeout(), Duration::from_secs(120));
+    assert!(c.is_reusable());
+    // A timeout of zero asks the client to close once idle
+    assert!(c.query(&question("example.")).is_ok());
+    assert_eq!(c.idle_timeout(), Duration::from_secs(0));
+    assert!(!c.is_reusable());
+}
+
+#[test]
+fn test_tcp_reuse() {
+    let (addr, count) = tcp_server(|mut stream| {
+        while let Ok(query) = read_tcp_message(&mut stream) {
+            write_tcp_message(&mut stream, &answer(&query, false)).ok().unwrap();
+        }
+    });
+    let mut c = TcpClient::new(addr);
+    for _ in 0..3 {
+        assert_eq!(c.query(&question("example.")).ok().unwrap().answers.len(), 1);
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    // Idle connections are not reused
+    c.set_idle_timeout(Duration::from_secs(0));
+    c.close();
+    for _ in 0..2 {
+        assert!(c.query(&question("example.")).is_ok());
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_tcp_server_close() {
+    // The server closes each connection after one response
+    let (addr, count) = tcp_server(|mut stream| {
+        let query = read_tcp_message(&mut stream).ok().unwrap();
+        write_tcp_message(&mut stream, &answer(&query, false)).ok().unwrap();
+    });
+    let mut c = TcpClient::new(addr);
+    c.set_timeout(Duration::from_millis(500));
+    for _ in 0..3 {
+        assert!(c.query(&question("example.")).is_ok());
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+}
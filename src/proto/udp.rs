@@ -0,0 +1,157 @@
+use super::{Message,OwnedMessage,ProtoError};
+use super::is_response_to;
+use super::query::{new_query,random_id};
+use super::tcp::tcp_exchange;
+use super::super::edns::{Edns,OwnedEdns};
+use super::super::iana::rcodes::RCode;
+use super::super::parser::{Question,read_dns_message};
+
+use std::net::{Ipv4Addr,Ipv6Addr,SocketAddr,UdpSocket};
+use std::time::{Duration,Instant};
+
+/// Timeout of the first attempt of a query. Each retry doubles it.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+/// Number of times a query is sent before giving up.
+pub const DEFAULT_ATTEMPTS: u32 = 3;
+
+const QR: u16 = 0x8000;
+const TC: u16 = 0x0200;
+
+/// A blocking stub client sending queries over UDP to one server.
+///
+/// Datagrams that do not come from the server or do not answer the
+/// query are ignored, as per
+/// [RFC5452§9.1](https://tools.ietf.org/html/rfc5452#section-9.1).
+/// Truncated responses are retried over TCP, as per
+/// [RFC7766§5](https://tools.ietf.org/html/rfc7766#section-5).
+#[derive(Debug,Clone)]
+pub struct UdpClient {
+    server: SocketAddr,
+    timeout: Duration,
+    attempts: u32,
+    recursion_desired: bool,
+    edns: Option<OwnedEdns>,
+    tcp_fallback: bool,
+}
+
+impl UdpClient {
+    /// Create a client for `server` that asks for recursion and sends
+    /// EDNS with the default UDP payload size.
+    pub fn new(server: SocketAddr) -> UdpClient {
+        UdpClient {
+            server,
+            timeout: DEFAULT_TIMEOUT,
+            attempts: DEFAULT_ATTEMPTS,
+            recursion_desired: true,
+            edns: Some(Edns::new()),
+            tcp_fallback: true,
+        }
+    }
+
+    /// Set the timeout of the first attempt. Retries wait twice as
+    /// long as the attempt before them.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Set the number of times a query is sent, at least once.
+    pub fn set_attempts(&mut self, attempts: u32) {
+        self.attempts = attempts.max(1);
+    }
+
+    /// Set or clear the RD flag of queries.
+    pub fn set_recursion_desired(&mut self, rd: bool) {
+        self.recursion_desired = rd;
+    }
+
+    /// Set the EDNS settings of queries, or send them without an OPT
+    /// record.
+    pub fn set_edns(&mut self, edns: Option<OwnedEdns>) {
+        self.edns = edns;
+    }
+
+    /// Enable or disable retrying truncated responses over TCP. Without
+    /// it the truncated response is returned.
+    pub fn set_tcp_fallback(&mut self, fallback: bool) {
+        self.tcp_fallback = fallback;
+    }
+
+    /// Send a query for `question` and wait for the answer.
+    ///
+    /// If the server answers a query with EDNS with FORMERR or NOTIMP
+    /// and no question section, as servers without EDNS support do, the
+    /// query is sent again without an OPT record, as per
+    /// [RFC6891§7](https://tools.ietf.org/html/rfc6891#section-7).
+    pub fn query(&self, question: &Question) -> Result<OwnedMessage, ProtoError> {
+        match self.exchange(question, self.edns.as_ref())? {
+            Some(response) => Ok(response),
+            None => self.exchange(question, None)?.ok_or(ProtoError::ResponseMismatchError),
+        }
+    }
+
+    /// Send a query with `edns`, returning `None` if the server rejects
+    /// EDNS.
+    fn exchange(&self, question: &Question, edns: Option<&Edns>) -> Result<Option<OwnedMessage>, ProtoError> {
+        let request = new_query(random_id(), question, self.recursion_desired, edns)?;
+        let query = read_dns_message(&request)?;
+
+        let local: SocketAddr = match self.server {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(local)?;
+        let mut buf = vec![0u8; 65535];
+        let mut timeout = self.timeout;
+        for _ in 0..self.attempts {
+            socket.send_to(&request, self.server)?;
+            let deadline = Instant::now() + timeout;
+            while let Some(remaining) = deadline.checked_duration_since(Instant::now()).filter(|d| !d.is_zero()) {
+                socket.set_read_timeout(Some(remaining))?;
+                let (len, from) = match socket.recv_from(&mut buf) {
+                    Ok(x) => x,
+                    Err(err) => match ProtoError::from(err) {
+                        ProtoError::TimeoutError => break,
+                        err => return Err(err),
+                    },
+                };
+                if from != self.server {
+                    continue;
+                }
+                let response = match read_dns_message(&buf[..len]) {
+                    Ok(msg) => msg,
+                    Err(_) => continue,
+                };
+                if !is_response_to(&response, &query) {
+                    if edns.is_some() && rejects_edns(&response, &query) {
+                        return Ok(None);
+                    }
+                    continue;
+                }
+                if response.flags & TC != 0 && self.tcp_fallback {
+                    return self.query_tcp(&request, &query).map(Some);
+                }
+                return Ok(Some(response.into_owned()?));
+            }
+            timeout *= 2;
+        }
+        Err(ProtoError::TimeoutError)
+    }
+
+    fn query_tcp(&self, request: &[u8], query: &Message) -> Result<OwnedMessage, ProtoError> {
+        let buf = tcp_exchange(self.server, request, self.timeout)?;
+        let response = read_dns_message(&buf)?;
+        if !is_response_to(&response, query) {
+            return Err(ProtoError::ResponseMismatchError);
+        }
+        Ok(response.into_owned()?)
+    }
+}
+
+/// Whether `response` is the reply of a server without EDNS support to
+/// `query`: FORMERR or NOTIMP with the query's ID and no question.
+fn rejects_edns(response: &Message, query: &Message) -> bool {
+    response.id == query.id
+        && response.flags & QR != 0
+        && response.questions.is_empty()
+        && matches!(response.header().rcode(), Ok(RCode::FormErr) | Ok(RCode::NotImp))
+}
@@ -0,0 +1,90 @@
+use super::{Name,OwnedName};
+
+use md5::{Digest,Md5};
+use ring::hmac;
+
+use std::cmp::Ordering;
+
+/// A TSIG MAC algorithm from
+/// [RFC8945§6](https://tools.ietf.org/html/rfc8945#section-6).
+///
+/// HMAC-MD5 and HMAC-SHA1 are only for peers that support nothing
+/// better.
+#[derive(PartialEq,Eq,Copy,Clone,Debug)]
+pub enum TsigAlgorithm {
+    HmacMd5,
+    HmacSha1,
+    HmacSha256,
+    HmacSha384,
+    HmacSha512,
+}
+
+impl TsigAlgorithm {
+    /// The algorithm identified by `name`, compared case-insensitively.
+    pub fn from_name(name: &Name) -> Option<TsigAlgorithm> {
+        [TsigAlgorithm::HmacMd5, TsigAlgorithm::HmacSha1, TsigAlgorithm::HmacSha256,
+         TsigAlgorithm::HmacSha384, TsigAlgorithm::HmacSha512].iter()
+            .find(|alg| alg.name().canonical_cmp(name) == Ordering::Equal)
+            .cloned()
+    }
+
+    /// The name identifying the algorithm in TSIG and TKEY records.
+    pub fn name(&self) -> OwnedName {
+        let s = match *self {
+            TsigAlgorithm::HmacMd5 => "hmac-md5.sig-alg.reg.int.",
+            TsigAlgorithm::HmacSha1 => "hmac-sha1.",
+            TsigAlgorithm::HmacSha256 => "hmac-sha256.",
+            TsigAlgorithm::HmacSha384 => "hmac-sha384.",
+            TsigAlgorithm::HmacSha512 => "hmac-sha512.",
+        };
+        Name::from_str(s).ok().unwrap().into_owned()
+    }
+
+    /// The length of an untruncated MAC.
+    pub fn mac_len(&self) -> usize {
+        match *self {
+            TsigAlgorithm::HmacMd5 => 16,
+            TsigAlgorithm::HmacSha1 => 20,
+            TsigAlgorithm::HmacSha256 => 32,
+            TsigAlgorithm::HmacSha384 => 48,
+            TsigAlgorithm::HmacSha512 => 64,
+        }
+    }
+
+    /// The shortest truncated MAC that may be sent: the larger of 10
+    /// octets and half the MAC, as per
+    /// [RFC8945§5.2.2.1](https://tools.ietf.org/html/rfc8945#section-5.2.2.1).
+    pub fn min_mac_len(&self) -> usize {
+        ::std::cmp::max(10, self.mac_len() / 2)
+    }
+
+    /// The untruncated MAC of `data` under `secret`.
+    pub fn mac(&self, secret: &[u8], data: &[u8]) -> Vec<u8> {
+        let alg = match *self {
+            TsigAlgorithm::HmacMd5 => return hmac_md5(secret, data),
+            TsigAlgorithm::HmacSha1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            TsigAlgorithm::HmacSha256 => hmac::HMAC_SHA256,
+            TsigAlgorithm::HmacSha384 => hmac::HMAC_SHA384,
+            TsigAlgorithm::HmacSha512 => hmac::HMAC_SHA512,
+        };
+        hmac::sign(&hmac::Key::new(alg, secret), data).as_ref().to_vec()
+    }
+}
+
+/// HMAC-MD5 as per [RFC2104](https://tools.ietf.org/html/rfc2104),
+/// which ring does not provide.
+fn hmac_md5(secret: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut key = [0u8; 64];
+    if secret.len() > key.len() {
+        key[..16].copy_from_slice(&Md5::digest(secret));
+    } else {
+        key[..secret.len()].copy_from_slice(secret);
+    }
+    let mut inner = Md5::new();
+    inner.update(key.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+    inner.update(data);
+    let mut outer = Md5::new();
+    outer.update(key.iter().map(|b| b ^ 0x5C).collect::<Vec<u8>>());
+    outer.update(inner.finalize());
+    outer.finalize().to_vec()
+}
@@ -0,0 +1,132 @@
+use super::RData;
+use super::errors::TsigError;
+use super::super::parser::_read_be_u16;
+use super::super::writer::_write_be_u16;
+
+use num_bigint::BigUint;
+use ring::rand::{SecureRandom,SystemRandom};
+
+use std::borrow::Cow;
+
+/// The KEY algorithm number of Diffie-Hellman keys, as per
+/// [RFC2539](https://tools.ietf.org/html/rfc2539).
+pub const DIFFIE_HELLMAN: u8 = 2;
+
+/// The KEY flags of a Diffie-Hellman key: owned by an entity, usable
+/// for confidentiality only.
+const KEY_FLAGS: u16 = 0x8200;
+
+/// The prime of Oakley group 1, of
+/// [RFC2409§6.1](https://tools.ietf.org/html/rfc2409#section-6.1).
+const OAKLEY_GROUP_1: &str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74\
+                              020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437\
+                              4FE1356D6D51C245E485B576625E7EC6F44C42E9A63A3620FFFFFFFFFFFFFFFF";
+/// The prime of Oakley group 2, of
+/// [RFC2409§6.2](https://tools.ietf.org/html/rfc2409#section-6.2).
+const OAKLEY_GROUP_2: &str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74\
+                              020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437\
+                              4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED\
+                              EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE65381FFFFFFFFFFFFFFFF";
+
+/// The prime of a well-known group of
+/// [RFC2539§2](https://tools.ietf.org/html/rfc2539#section-2), whose
+/// generator is 2.
+fn well_known_prime(group: u16) -> Option<BigUint> {
+    let hex = match group {
+        1 => OAKLEY_GROUP_1,
+        2 => OAKLEY_GROUP_2,
+        _ => return None,
+    };
+    BigUint::parse_bytes(hex.as_bytes(), 16)
+}
+
+/// A Diffie-Hellman private key for TKEY, as per
+/// [RFC2930§4.1](https://tools.ietf.org/html/rfc2930#section-4.1).
+#[derive(Debug,Clone)]
+pub struct DhKey {
+    group: u16,
+    prime: BigUint,
+    private: BigUint,
+    public: BigUint,
+}
+
+impl DhKey {
+    /// Generate a key in well-known group 1 (768 bits) or 2 (1024 bits).
+    /// Group 2 should be preferred.
+    pub fn generate(group: u16) -> Result<DhKey, TsigError> {
+        let prime = well_known_prime(group).ok_or(TsigError::KeyExchangeError)?;
+        let mut random = vec![0u8; prime.bits() as usize / 8];
+        SystemRandom::new().fill(&mut random).map_err(|_| TsigError::KeyExchangeError)?;
+        // 1 <= private <= p - 2
+        let private = BigUint::from_bytes_be(&random) % (&prime - 2u32) + 1u32;
+        let public = BigUint::from(2u32).modpow(&private, &prime);
+        Ok(DhKey { group, prime, private, public })
+    }
+
+    /// The public key in the KEY format of
+    /// [RFC2539§2](https://tools.ietf.org/html/rfc2539#section-2), naming
+    /// the well-known group.
+    pub fn public_key(&self) -> Vec<u8> {
+        let mut key = Vec::new();
+        _write_be_u16(&mut key, 2);
+        _write_be_u16(&mut key, self.group);
+        _write_be_u16(&mut key, 0);
+        let public = self.public.to_bytes_be();
+        _write_be_u16(&mut key, public.len() as u16);
+        key.extend_from_slice(&public);
+        key
+    }
+
+    /// The KEY RDATA publishing this key.
+    pub fn key(&self) -> RData<'static> {
+        RData::KEY {
+            flags: KEY_FLAGS,
+            protocol: 3,
+            algorithm: DIFFIE_HELLMAN,
+            public_key: Cow::Owned(self.public_key()),
+        }
+    }
+
+    /// The shared DH value of this key and a peer's public key in KEY
+    /// format, which must be in the same group.
+    pub fn agree(&self, peer: &[u8]) -> Result<Vec<u8>, TsigError> {
+        let (prime, generator, public) = parse_public_key(peer)?;
+        if prime != self.prime || generator != BigUint::from(2u32) {
+            return Err(TsigError::KeyExchangeError);
+        }
+        // Reject 0, 1 and p - 1, which would force a known secret
+        if public <= BigUint::from(1u32) || public >= &self.prime - 1u32 {
+            return Err(TsigError::KeyExchangeError);
+        }
+        Ok(public.modpow(&self.private, &self.prime).to_bytes_be())
+    }
+}
+
+/// Split a Diffie-Hellman public key in KEY format into its prime,
+/// generator and public value. A prime of length 1 or 2 is the index of
+/// a well-known group.
+fn parse_public_key(key: &[u8]) -> Result<(BigUint, BigUint, BigUint), TsigError> {
+    let mut i = 0;
+    let mut field = || -> Result<&[u8], TsigError> {
+        if i + 2 > key.len() {
+            return Err(TsigError::KeyExchangeError);
+        }
+        let len = _read_be_u16(key, &mut i) as usize;
+        if i + len > key.len() {
+            return Err(TsigError::KeyExchangeError);
+        }
+        i += len;
+        Ok(&key[i - len..i])
+    };
+    let prime = field()?;
+    let generator = field()?;
+    let public = field()?;
+    let (prime, generator) = match prime.len() {
+        1 | 2 => {
+            let group = prime.iter().fold(0u16, |acc, &b| acc << 8 | b as u16);
+            (well_known_prime(group).ok_or(TsigError::KeyExchangeError)?, BigUint::from(2u32))
+        },
+        _ => (BigUint::from_bytes_be(prime), BigUint::from_bytes_be(generator)),
+    };
+    Ok((prime, generator, BigUint::from_bytes_be(public)))
+}
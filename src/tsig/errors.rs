@@ -0,0 +1,108 @@
+use std::fmt;
+use std::error;
+use std::convert;
+use super::{RCode,ReadError,WriteError};
+
+/// The reason a message could not be signed, failed TSIG
+/// verification, or a TKEY exchange failed.
+#[derive(PartialEq,Copy,Clone,Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum TsigError {
+    MessageError(ReadError),
+    EncodingError(WriteError),
+    FormatError,
+    MissingError,
+    UnknownKeyError,
+    AlgorithmError,
+    MacSizeError(usize),
+    BadSignatureError,
+    BadTimeError(u64),
+    BadTruncationError(usize),
+    PeerError(u16),
+    TooManyUnsignedError,
+    KeyExchangeError,
+    ResponseMismatchError,
+    KeyExistsError,
+}
+
+impl TsigError {
+    /// The RCODE to put in the TSIG record of the error response, as per
+    /// [RFC8945§5.2](https://tools.ietf.org/html/rfc8945#section-5.2).
+    /// The response's header carries NOTAUTH for every TSIG error except
+    /// FORMERR.
+    pub fn rcode(&self) -> RCode {
+        match *self {
+            TsigError::UnknownKeyError | TsigError::AlgorithmError | TsigError::KeyExchangeError => RCode::BADKEY,
+            TsigError::BadSignatureError => RCode::BADSIG,
+            TsigError::BadTimeError(_) => RCode::BADTIME,
+            TsigError::BadTruncationError(_) => RCode::BADTRUNC,
+            TsigError::PeerError(x) => RCode::from_u16(x).unwrap_or(RCode::ServFail),
+            TsigError::MacSizeError(_) | TsigError::TooManyUnsignedError => RCode::ServFail,
+            _ => RCode::FormErr,
+        }
+    }
+}
+
+impl error::Error for TsigError {
+    fn description(&self) -> &str {
+        match *self {
+            TsigError::MessageError(_) => "Message is malformed",
+            TsigError::EncodingError(_) => "Message cannot be written",
+            TsigError::FormatError => "TSIG record is malformed or misplaced",
+            TsigError::MissingError => "Message has no TSIG or TKEY record",
+            TsigError::UnknownKeyError => "TSIG key is not known",
+            TsigError::AlgorithmError => "TSIG algorithm differs from the key's",
+            TsigError::MacSizeError(_) => "Invalid truncated MAC size",
+            TsigError::BadSignatureError => "TSIG MAC does not verify",
+            TsigError::BadTimeError(_) => "TSIG time signed is outside the fudge",
+            TsigError::BadTruncationError(_) => "TSIG MAC is truncated more than allowed",
+            TsigError::PeerError(_) => "Peer reported a TSIG error",
+            TsigError::TooManyUnsignedError => "Too many unsigned messages in a row",
+            TsigError::KeyExchangeError => "Diffie-Hellman key exchange failed",
+            TsigError::ResponseMismatchError => "TKEY response does not match the query",
+            TsigError::KeyExistsError => "TSIG key already exists",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match *self {
+            TsigError::MessageError(ref err) => Some(err as &dyn error::Error),
+            TsigError::EncodingError(ref err) => Some(err as &dyn error::Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TsigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TsigError::MessageError(x) => write!(f, "Message is malformed: {}", x),
+            TsigError::EncodingError(x) => write!(f, "Message cannot be written: {}", x),
+            TsigError::FormatError => write!(f, "TSIG record is malformed or misplaced"),
+            TsigError::MissingError => write!(f, "Message has no TSIG or TKEY record"),
+            TsigError::UnknownKeyError => write!(f, "TSIG key is not known"),
+            TsigError::AlgorithmError => write!(f, "TSIG algorithm differs from the key's"),
+            TsigError::MacSizeError(x) => write!(f, "Invalid truncated MAC size: {}", x),
+            TsigError::BadSignatureError => write!(f, "TSIG MAC does not verify"),
+            TsigError::BadTimeError(x) => write!(f, "TSIG time signed is outside the fudge: {}", x),
+            TsigError::BadTruncationError(x) => write!(f, "TSIG MAC is truncated more than allowed: {}", x),
+            TsigError::PeerError(x) => write!(f, "Peer reported a TSIG error: {}", x),
+            TsigError::TooManyUnsignedError => write!(f, "Too many unsigned messages in a row"),
+            TsigError::KeyExchangeError => write!(f, "Diffie-Hellman key exchange failed"),
+            TsigError::ResponseMismatchError => write!(f, "TKEY response does not match the query"),
+            TsigError::KeyExistsError => write!(f, "TSIG key already exists"),
+        }
+    }
+}
+
+impl convert::From<ReadError> for TsigError {
+    fn from(err: ReadError) -> TsigError {
+        TsigError::MessageError(err)
+    }
+}
+
+impl convert::From<WriteError> for TsigError {
+    fn from(err: WriteError) -> TsigError {
+        TsigError::EncodingError(err)
+    }
+}
@@ -0,0 +1,89 @@
+use super::{Name,OwnedName};
+use super::algorithm::TsigAlgorithm;
+use super::errors::TsigError;
+
+use std::collections::BTreeMap;
+
+/// A shared secret for TSIG, as per
+/// [RFC8945§4](https://tools.ietf.org/html/rfc8945#section-4).
+///
+/// The MAC size is the length of the MACs sent with the key, and the
+/// shortest accepted from peers. It defaults to the untruncated MAC.
+#[derive(PartialEq,Debug,Clone)]
+pub struct TsigKey {
+    name: OwnedName,
+    algorithm: TsigAlgorithm,
+    secret: Vec<u8>,
+    mac_size: usize,
+}
+
+impl TsigKey {
+    pub fn new(name: Name, algorithm: TsigAlgorithm, secret: &[u8]) -> TsigKey {
+        TsigKey {
+            name: name.into_owned(),
+            algorithm,
+            secret: secret.to_vec(),
+            mac_size: algorithm.mac_len(),
+        }
+    }
+
+    pub fn name(&self) -> &OwnedName {
+        &self.name
+    }
+
+    pub fn algorithm(&self) -> TsigAlgorithm {
+        self.algorithm
+    }
+
+    pub fn secret(&self) -> &[u8] {
+        &self.secret
+    }
+
+    pub fn mac_size(&self) -> usize {
+        self.mac_size
+    }
+
+    /// Truncate MACs to `size` octets, as per
+    /// [RFC8945§5.2.2.1](https://tools.ietf.org/html/rfc8945#section-5.2.2.1).
+    /// The size may not be shorter than the algorithm's `min_mac_len`.
+    pub fn set_mac_size(&mut self, size: usize) -> Result<(), TsigError> {
+        if size < self.algorithm.min_mac_len() || size > self.algorithm.mac_len() {
+            return Err(TsigError::MacSizeError(size));
+        }
+        self.mac_size = size;
+        Ok(())
+    }
+}
+
+/// TSIG keys by name. Names are compared case-insensitively.
+#[derive(Debug,Clone,Default)]
+pub struct Keyring {
+    keys: BTreeMap<OwnedName, TsigKey>,
+}
+
+impl Keyring {
+    pub fn new() -> Keyring {
+        Keyring { keys: BTreeMap::new() }
+    }
+
+    /// Add `key`, returning the key it replaces if one had its name.
+    pub fn add(&mut self, key: TsigKey) -> Option<TsigKey> {
+        self.keys.insert(key.name.clone(), key)
+    }
+
+    pub fn get(&self, name: &Name) -> Option<&TsigKey> {
+        self.keys.get(&name.clone().into_owned())
+    }
+
+    pub fn remove(&mut self, name: &Name) -> Option<TsigKey> {
+        self.keys.remove(&name.clone().into_owned())
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
@@ -0,0 +1,24 @@
+pub use super::iana::types::Type;
+pub use super::iana::classes::Class;
+pub use super::iana::rcodes::RCode;
+pub use super::parser::{Name,OwnedName,RData,ResourceRecord,ReadError};
+pub use super::writer::WriteError;
+
+pub use super::tsig::algorithm::TsigAlgorithm;
+pub use super::tsig::dh::{DhKey,DIFFIE_HELLMAN};
+pub use super::tsig::errors::TsigError;
+pub use super::tsig::key::{Keyring,TsigKey};
+pub use super::tsig::record::Tsig;
+pub use super::tsig::tkey::{TKEY_SERVER_ASSIGNMENT,TKEY_DIFFIE_HELLMAN,TKEY_GSS_API,TKEY_RESOLVER_ASSIGNMENT,TKEY_DELETE};
+pub use super::tsig::tkey::{TkeyClient,TkeyServer,delete_query,process_delete_response};
+pub use super::tsig::sign::{DEFAULT_FUDGE,MAX_UNSIGNED_MESSAGES,TsigSigner,TsigVerifier,unsigned_error};
+
+mod algorithm;
+mod dh;
+mod errors;
+mod key;
+mod record;
+mod sign;
+mod tkey;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,102 @@
+use super::{Class,OwnedName,RData,ResourceRecord,Type};
+use super::errors::TsigError;
+use super::super::parser::{OwnedResourceRecord,ReadError,_read_be_u16,read_dns_question,read_dns_resource_record};
+use super::super::writer::write_dns_rdata;
+
+use std::borrow::Cow;
+
+/// The contents of a TSIG record, as per
+/// [RFC8945§4.2](https://tools.ietf.org/html/rfc8945#section-4.2).
+///
+/// The record's owner is the key name; its class is always ANY and its
+/// TTL 0.
+#[derive(PartialEq,Debug,Clone)]
+pub struct Tsig {
+    pub key_name: OwnedName,
+    pub algorithm: OwnedName,
+    /// Seconds since the epoch, in 48 bits.
+    pub time_signed: u64,
+    pub fudge: u16,
+    pub mac: Vec<u8>,
+    pub original_id: u16,
+    pub error: u16,
+    pub other: Vec<u8>,
+}
+
+impl Tsig {
+    /// Decode a TSIG record.
+    pub fn from_record(rr: &ResourceRecord) -> Result<Tsig, TsigError> {
+        if rr.rtype != Type::TSIG || rr.rclass != Class::ANY || rr.rttl != 0 {
+            return Err(TsigError::FormatError);
+        }
+        match rr.rdata()? {
+            RData::TSIG { algorithm, time_signed, fudge, mac, original_id, error, other } => Ok(Tsig {
+                key_name: rr.rname.clone().into_owned(),
+                algorithm: algorithm.into_owned(),
+                time_signed,
+                fudge,
+                mac: mac.into_owned(),
+                original_id,
+                error,
+                other: other.into_owned(),
+            }),
+            _ => Err(TsigError::FormatError),
+        }
+    }
+
+    /// Encode this as a TSIG record.
+    pub fn to_record(&self) -> Result<OwnedResourceRecord, TsigError> {
+        let rdata = write_dns_rdata(&RData::TSIG {
+            algorithm: self.algorithm.clone(),
+            time_signed: self.time_signed,
+            fudge: self.fudge,
+            mac: Cow::Borrowed(&self.mac),
+            original_id: self.original_id,
+            error: self.error,
+            other: Cow::Borrowed(&self.other),
+        })?;
+        Ok(ResourceRecord::new(self.key_name.clone(), Type::TSIG, Class::ANY, 0, rdata))
+    }
+
+    /// The TSIG record of a serialized message, or `None` if it is
+    /// unsigned.
+    pub fn from_message(msg: &[u8]) -> Result<Option<Tsig>, TsigError> {
+        Ok(find_tsig(msg)?.map(|(_, tsig)| tsig))
+    }
+}
+
+/// Find the TSIG record of a serialized message, with the offset at
+/// which it starts. The record must be the last in the additional
+/// section and the only one in the message, as per
+/// [RFC8945§5.1](https://tools.ietf.org/html/rfc8945#section-5.1).
+pub fn find_tsig(msg: &[u8]) -> Result<Option<(usize, Tsig)>, TsigError> {
+    if msg.len() < 12 {
+        return Err(TsigError::MessageError(ReadError::IndexOutOfRangeError(12, msg.len())));
+    }
+    let mut i = 4;
+    let qdcount = _read_be_u16(msg, &mut i);
+    let records = (0..3).fold(0usize, |acc, _| acc + _read_be_u16(msg, &mut i) as usize);
+    for _ in 0..qdcount {
+        read_dns_question(msg, &mut i)?;
+    }
+    let mut found = None;
+    for n in 0..records {
+        let start = i;
+        let rr = read_dns_resource_record(msg, &mut i)?;
+        if rr.rtype == Type::TSIG {
+            if n + 1 != records || !additional(msg, n) {
+                return Err(TsigError::FormatError);
+            }
+            found = Some((start, Tsig::from_record(&rr)?));
+        }
+    }
+    Ok(found)
+}
+
+/// Whether record `n` of a message, counting from the first answer, is
+/// in the additional section.
+fn additional(msg: &[u8], n: usize) -> bool {
+    let mut i = 6;
+    let before = _read_be_u16(msg, &mut i) as usize + _read_be_u16(msg, &mut i) as usize;
+    n >= before
+}
@@ -0,0 +1,355 @@
+use super::{Class,Name,OwnedName,RCode};
+use super::algorithm::TsigAlgorithm;
+use super::errors::TsigError;
+use super::key::{Keyring,TsigKey};
+use super::record::{Tsig,find_tsig};
+use super::super::parser::ReadError;
+use super::super::writer::{MessageWriter,WriteError,_write_be_u16,_write_be_u32};
+
+use std::cmp::Ordering;
+
+/// Default allowed difference between the signer's and verifier's
+/// clocks, in seconds, as recommended by
+/// [RFC8945§10](https://tools.ietf.org/html/rfc8945#section-10).
+pub const DEFAULT_FUDGE: u16 = 300;
+
+/// Most unsigned messages allowed in a row in a multi-message stream,
+/// as per [RFC8945§5.3.1](https://tools.ietf.org/html/rfc8945#section-5.3.1).
+pub const MAX_UNSIGNED_MESSAGES: usize = 99;
+
+/// The digest state of a transaction, shared by signing and
+/// verification.
+#[derive(Debug,Clone)]
+struct Transaction {
+    /// The MAC of the request or the previous signed message.
+    prior_mac: Option<Vec<u8>>,
+    /// Whether a message has been signed yet; later ones digest only
+    /// the timers.
+    started: bool,
+    unsigned: Vec<u8>,
+    unsigned_count: usize,
+}
+
+impl Transaction {
+    fn new(prior_mac: Option<&[u8]>) -> Transaction {
+        Transaction {
+            prior_mac: prior_mac.map(|mac| mac.to_vec()),
+            started: false,
+            unsigned: Vec::new(),
+            unsigned_count: 0,
+        }
+    }
+
+    /// Record an unsigned message of a stream.
+    fn add_unsigned(&mut self, msg: &[u8]) -> Result<(), TsigError> {
+        if !self.started {
+            return Err(TsigError::MissingError);
+        }
+        if self.unsigned_count == MAX_UNSIGNED_MESSAGES {
+            return Err(TsigError::TooManyUnsignedError);
+        }
+        self.unsigned.extend_from_slice(msg);
+        self.unsigned_count += 1;
+        Ok(())
+    }
+
+    /// The data covered by the MAC of `tsig` for `msg`, which is the
+    /// message without its TSIG record and with its original ID, as per
+    /// [RFC8945§4.3](https://tools.ietf.org/html/rfc8945#section-4.3).
+    fn digest(&self, msg: &[u8], tsig: &Tsig) -> Result<Vec<u8>, TsigError> {
+        let mut data = Vec::new();
+        if let Some(ref mac) = self.prior_mac {
+            _write_be_u16(&mut data, mac.len() as u16);
+            data.extend_from_slice(mac);
+        }
+        data.extend_from_slice(&self.unsigned);
+        let start = data.len();
+        data.extend_from_slice(msg);
+        data[start + ID] = (tsig.original_id >> 8) as u8;
+        data[start + ID + 1] = tsig.original_id as u8;
+        if self.started {
+            // Later messages of a stream digest only the timers, as per
+            // RFC8945§5.3.1
+            write_timers(&mut data, tsig);
+        } else {
+            data.extend_from_slice(&canonical_name(&tsig.key_name)?);
+            _write_be_u16(&mut data, Class::ANY.to_u16());
+            _write_be_u32(&mut data, 0);
+            data.extend_from_slice(&canonical_name(&tsig.algorithm)?);
+            write_timers(&mut data, tsig);
+            _write_be_u16(&mut data, tsig.error);
+            _write_be_u16(&mut data, tsig.other.len() as u16);
+            data.extend_from_slice(&tsig.other);
+        }
+        Ok(data)
+    }
+
+    /// Move on to the message after one with MAC `mac`.
+    fn advance(&mut self, mac: &[u8]) {
+        self.prior_mac = Some(mac.to_vec());
+        self.started = true;
+        self.unsigned.clear();
+        self.unsigned_count = 0;
+    }
+}
+
+fn canonical_name(name: &Name) -> Result<Vec<u8>, TsigError> {
+    let mut w = MessageWriter::new();
+    w.set_canonical(true);
+    w.write_name(name)?;
+    Ok(w.into_bytes())
+}
+
+fn write_timers(data: &mut Vec<u8>, tsig: &Tsig) {
+    _write_be_u16(data, (tsig.time_signed >> 32) as u16);
+    _write_be_u32(data, tsig.time_signed as u32);
+    _write_be_u16(data, tsig.fudge);
+}
+
+/// Offsets into a message header.
+const ID: usize = 0;
+const RCODE: usize = 3;
+const ARCOUNT: usize = 10;
+
+fn check_header(msg: &[u8]) -> Result<(), TsigError> {
+    if msg.len() < 12 {
+        return Err(TsigError::MessageError(ReadError::IndexOutOfRangeError(12, msg.len())));
+    }
+    Ok(())
+}
+
+/// Append `tsig` to `msg` as the last additional record.
+fn append(msg: &[u8], tsig: &Tsig) -> Result<Vec<u8>, TsigError> {
+    let arcount = ((msg[ARCOUNT] as usize) << 8) + msg[ARCOUNT + 1] as usize + 1;
+    if arcount > 65535 {
+        return Err(TsigError::EncodingError(WriteError::TooManyRecordsError(arcount)));
+    }
+    let rr = tsig.to_record()?;
+    let mut w = MessageWriter::new();
+    w.set_compression(false);
+    w.write_resource_record(&rr)?;
+    let mut out = msg.to_vec();
+    out[ARCOUNT] = (arcount >> 8) as u8;
+    out[ARCOUNT + 1] = arcount as u8;
+    out.extend_from_slice(&w.into_bytes());
+    Ok(out)
+}
+
+/// Set the RCODE of a message's header to NOTAUTH, for a TSIG error.
+fn set_notauth(msg: &mut [u8]) {
+    msg[RCODE] = (msg[RCODE] & 0xF0) | RCode::NotAuth.to_u16() as u8;
+}
+
+/// Compare MACs in time independent of where they differ.
+fn mac_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Signs the messages of a transaction with a TSIG key, as per
+/// [RFC8945§5.3](https://tools.ietf.org/html/rfc8945#section-5.3).
+///
+/// A request is signed by a signer made with `new`; its responses by
+/// one made with `for_response` from the request's MAC. Each call to
+/// `sign` after the first continues a multi-message stream, such as a
+/// zone transfer, in which up to 99 messages in a row may be left
+/// unsigned by passing them to `add_unsigned` instead.
+#[derive(Debug,Clone)]
+pub struct TsigSigner<'k> {
+    key: &'k TsigKey,
+    fudge: u16,
+    error: u16,
+    transaction: Transaction,
+}
+
+impl<'k> TsigSigner<'k> {
+    /// Sign a request.
+    pub fn new(key: &'k TsigKey) -> TsigSigner<'k> {
+        TsigSigner {
+            key,
+            fudge: DEFAULT_FUDGE,
+            error: 0,
+            transaction: Transaction::new(None),
+        }
+    }
+
+    /// Sign the responses to a request whose TSIG has `request_mac`.
+    pub fn for_response(key: &'k TsigKey, request_mac: &[u8]) -> TsigSigner<'k> {
+        TsigSigner {
+            transaction: Transaction::new(Some(request_mac)),
+            ..TsigSigner::new(key)
+        }
+    }
+
+    pub fn set_fudge(&mut self, fudge: u16) {
+        self.fudge = fudge;
+    }
+
+    /// Report a TSIG error in the next signed message, with a NOTAUTH
+    /// header. BADTIME errors carry the signer's clock, as per
+    /// [RFC8945§5.2.3](https://tools.ietf.org/html/rfc8945#section-5.2.3).
+    pub fn set_error(&mut self, error: RCode) {
+        self.error = error.to_u16();
+    }
+
+    /// The MAC of the last signed message.
+    pub fn mac(&self) -> Option<&[u8]> {
+        if self.transaction.started {
+            self.transaction.prior_mac.as_ref().map(|mac| &mac[..])
+        } else {
+            None
+        }
+    }
+
+    /// Sign `msg` at `now`, in seconds since the epoch, returning it
+    /// with a TSIG record appended.
+    pub fn sign(&mut self, msg: &[u8], now: u64) -> Result<Vec<u8>, TsigError> {
+        check_header(msg)?;
+        let mut msg = msg.to_vec();
+        let now = now & 0xFFFF_FFFF_FFFF;
+        let mut other = Vec::new();
+        if self.error != 0 {
+            set_notauth(&mut msg);
+            if self.error == RCode::BADTIME.to_u16() {
+                _write_be_u16(&mut other, (now >> 32) as u16);
+                _write_be_u32(&mut other, now as u32);
+            }
+        }
+        let mut tsig = Tsig {
+            key_name: self.key.name().clone(),
+            algorithm: self.key.algorithm().name(),
+            time_signed: now,
+            fudge: self.fudge,
+            mac: Vec::new(),
+            original_id: ((msg[ID] as u16) << 8) + msg[ID + 1] as u16,
+            error: self.error,
+            other,
+        };
+        let data = self.transaction.digest(&msg, &tsig)?;
+        tsig.mac = self.key.algorithm().mac(self.key.secret(), &data);
+        tsig.mac.truncate(self.key.mac_size());
+        self.transaction.advance(&tsig.mac);
+        append(&msg, &tsig)
+    }
+
+    /// Leave `msg` of a stream unsigned. It is covered by the MAC of
+    /// the next signed message.
+    pub fn add_unsigned(&mut self, msg: &[u8]) -> Result<(), TsigError> {
+        check_header(msg)?;
+        self.transaction.add_unsigned(msg)
+    }
+}
+
+/// Verifies the TSIG records of the messages of a transaction, as per
+/// [RFC8945§5.2](https://tools.ietf.org/html/rfc8945#section-5.2).
+///
+/// Keys are looked up in a keyring. Requests are verified by a verifier
+/// made with `new`; responses by one made with `for_response`, which
+/// also accepts unsigned messages in the middle of a stream.
+#[derive(Debug,Clone)]
+pub struct TsigVerifier<'k> {
+    keyring: &'k Keyring,
+    key_name: Option<OwnedName>,
+    transaction: Transaction,
+}
+
+impl<'k> TsigVerifier<'k> {
+    /// Verify a request.
+    pub fn new(keyring: &'k Keyring) -> TsigVerifier<'k> {
+        TsigVerifier {
+            keyring,
+            key_name: None,
+            transaction: Transaction::new(None),
+        }
+    }
+
+    /// Verify the responses to a request signed with the key named
+    /// `key_name` and `request_mac`.
+    pub fn for_response(keyring: &'k Keyring, key_name: &Name, request_mac: &[u8]) -> TsigVerifier<'k> {
+        TsigVerifier {
+            keyring,
+            key_name: Some(key_name.clone().into_owned()),
+            transaction: Transaction::new(Some(request_mac)),
+        }
+    }
+
+    /// Verify `msg` at `now`, in seconds since the epoch, returning its
+    /// TSIG record, or `None` for an unsigned message within a stream.
+    ///
+    /// Failures map to the error to return to the peer with
+    /// `TsigError::rcode`. A peer's own TSIG error is returned as
+    /// `PeerError`.
+    pub fn verify(&mut self, msg: &[u8], now: u64) -> Result<Option<Tsig>, TsigError> {
+        let (offset, tsig) = match find_tsig(msg)? {
+            Some(found) => found,
+            None => {
+                self.transaction.add_unsigned(msg)?;
+                return Ok(None);
+            },
+        };
+        if self.key_name.as_ref().is_some_and(|name| name.canonical_cmp(&tsig.key_name) != Ordering::Equal) {
+            return Err(TsigError::UnknownKeyError);
+        }
+        let key = self.keyring.get(&tsig.key_name).ok_or(TsigError::UnknownKeyError)?;
+        if TsigAlgorithm::from_name(&tsig.algorithm) != Some(key.algorithm()) {
+            return Err(TsigError::AlgorithmError);
+        }
+        // BADKEY and BADSIG errors come unsigned, as per RFC8945§5.3.2
+        if tsig.error != 0 && tsig.mac.is_empty() {
+            return Err(TsigError::PeerError(tsig.error));
+        }
+
+        let alg = key.algorithm();
+        if tsig.mac.len() > alg.mac_len() || tsig.mac.len() < alg.min_mac_len() {
+            return Err(TsigError::FormatError);
+        }
+        let mut stripped = msg[..offset].to_vec();
+        let arcount = ((stripped[ARCOUNT] as u16) << 8) + stripped[ARCOUNT + 1] as u16 - 1;
+        stripped[ARCOUNT] = (arcount >> 8) as u8;
+        stripped[ARCOUNT + 1] = arcount as u8;
+        let data = self.transaction.digest(&stripped, &tsig)?;
+        let mac = alg.mac(key.secret(), &data);
+        if !mac_eq(&mac[..tsig.mac.len()], &tsig.mac) {
+            return Err(TsigError::BadSignatureError);
+        }
+        self.transaction.advance(&tsig.mac);
+        self.key_name = Some(tsig.key_name.clone());
+
+        if tsig.error != 0 {
+            return Err(TsigError::PeerError(tsig.error));
+        }
+        if now.abs_diff(tsig.time_signed) > tsig.fudge as u64 {
+            return Err(TsigError::BadTimeError(tsig.time_signed));
+        }
+        if tsig.mac.len() < key.mac_size() {
+            return Err(TsigError::BadTruncationError(tsig.mac.len()));
+        }
+        Ok(Some(tsig))
+    }
+
+    /// Check that the last message of a stream was signed.
+    pub fn finish(&self) -> Result<(), TsigError> {
+        if !self.transaction.started || self.transaction.unsigned_count != 0 {
+            return Err(TsigError::MissingError);
+        }
+        Ok(())
+    }
+}
+
+/// Answer a request that failed with BADKEY or BADSIG, which cannot be
+/// signed, as per
+/// [RFC8945§5.3.2](https://tools.ietf.org/html/rfc8945#section-5.3.2):
+/// `msg` is returned with a NOTAUTH header and a copy of the request's
+/// TSIG with an empty MAC and the error.
+pub fn unsigned_error(msg: &[u8], request: &Tsig, error: RCode) -> Result<Vec<u8>, TsigError> {
+    check_header(msg)?;
+    let mut msg = msg.to_vec();
+    set_notauth(&mut msg);
+    let tsig = Tsig {
+        mac: Vec::new(),
+        original_id: ((msg[ID] as u16) << 8) + msg[ID + 1] as u16,
+        error: error.to_u16(),
+        other: Vec::new(),
+        ..request.clone()
+    };
+    append(&msg, &tsig)
+}
@@ -0,0 +1,402 @@
+use super::{Name,RCode,RData,ResourceRecord,Type};
+use super::{Keyring,Tsig,TsigAlgorithm,TsigError,TsigKey,TsigSigner,TsigVerifier,unsigned_error,MAX_UNSIGNED_MESSAGES};
+use super::{DhKey,TkeyClient,TkeyServer,delete_query,process_delete_response,TKEY_DELETE,TKEY_DIFFIE_HELLMAN};
+use super::super::encoding::decode_hex;
+use super::super::parser::{Message,Question,Class,read_dns_message};
+use super::super::writer::{MessageWriter,write_dns_message,write_dns_rdata};
+
+use std::borrow::Cow;
+
+const NOW: u64 = 1_700_000_000;
+const SECRET: &[u8] = b"secret-key-0123456789";
+
+fn key(alg: TsigAlgorithm) -> TsigKey {
+    TsigKey::new(Name::from_str("Test-Key.").ok().unwrap(), alg, SECRET)
+}
+
+fn keyring(key: &TsigKey) -> Keyring {
+    let mut keys = Keyring::new();
+    keys.add(key.clone());
+    keys
+}
+
+fn query(id: u16) -> Vec<u8> {
+    let mut msg = Message::new(id, 0x0100);
+    msg.questions.push(Question {
+        qname: Name::from_str("example.").ok().unwrap(),
+        qtype: Type::A,
+        qclass: Class::IN,
+    });
+    write_dns_message(&msg).ok().unwrap()
+}
+
+fn response(id: u16) -> Vec<u8> {
+    let mut msg = query(id);
+    msg[2] |= 0x80;
+    msg
+}
+
+#[test]
+fn test_algorithms() {
+    // RFC2104 and RFC2202 test cases
+    let md5 = TsigAlgorithm::HmacMd5;
+    assert_eq!(md5.mac(&[0x0b; 16], b"Hi There"), decode_hex("9294727a3638bb1c13f48ef8158bfc9d".as_bytes()).unwrap());
+    assert_eq!(md5.mac(&[0xaa; 80], b"Test Using Larger Than Block-Size Key - Hash Key First"),
+               decode_hex("6b1ab7fe4bd7bf8f0b62e6ce61b9d0cd".as_bytes()).unwrap());
+    // RFC4231 test case 1
+    assert_eq!(TsigAlgorithm::HmacSha256.mac(&[0x0b; 20], b"Hi There"),
+               decode_hex("b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7".as_bytes()).unwrap());
+
+    let name = Name::from_str("HMAC-MD5.SIG-ALG.REG.INT.").ok().unwrap();
+    assert_eq!(TsigAlgorithm::from_name(&name), Some(TsigAlgorithm::HmacMd5));
+    assert_eq!(TsigAlgorithm::from_name(&Name::from_str("hmac-sha512.").ok().unwrap()), Some(TsigAlgorithm::HmacSha512));
+    assert_eq!(TsigAlgorithm::from_name(&Name::from_str("gss-tsig.").ok().unwrap()), None);
+    assert_eq!(TsigAlgorithm::HmacMd5.min_mac_len(), 10);
+    assert_eq!(TsigAlgorithm::HmacSha384.min_mac_len(), 24);
+
+    let mut k = key(TsigAlgorithm::HmacSha256);
+    assert_eq!(k.mac_size(), 32);
+    assert_eq!(k.set_mac_size(15), Err(TsigError::MacSizeError(15)));
+    assert_eq!(k.set_mac_size(33), Err(TsigError::MacSizeError(33)));
+    assert!(k.set_mac_size(16).is_ok());
+
+    let keys = keyring(&k);
+    assert!(keys.get(&Name::from_str("test-key.").ok().unwrap()).is_some());
+    assert!(keys.get(&Name::from_str("other-key.").ok().unwrap()).is_none());
+}
+
+#[test]
+fn test_sign_request() {
+    let k = key(TsigAlgorithm::HmacSha256);
+    let mut signer = TsigSigner::new(&k);
+    let signed = signer.sign(&query(0x1234), NOW).ok().unwrap();
+    let mac = decode_hex("cf53af6d584de068f4cfc89e8866faf8b43014a9d51d909f5c086cac6b30dcf8".as_bytes()).unwrap();
+    assert_eq!(signer.mac(), Some(&mac[..]));
+
+    let msg = read_dns_message(&signed).ok().unwrap();
+    assert_eq!(msg.additionals.len(), 1);
+    let rr = &msg.additionals[0];
+    match rr.rdata().ok().unwrap() {
+        RData::TSIG { ref algorithm, time_signed, fudge, original_id, error, .. } => {
+            assert_eq!(*algorithm, Name::from_str("hmac-sha256.").ok().unwrap());
+            assert_eq!((time_signed, fudge, original_id, error), (NOW, 300, 0x1234, 0));
+        },
+        _ => panic!("not a TSIG record"),
+    }
+    assert_eq!(format!("{}", rr.rdata().ok().unwrap()),
+               "hmac-sha256. 1700000000 300 32 z1OvbVhN4Gj0z8ieiGb6+LQwFKnVHZCfXAhsrGsw3Pg= 4660 0 0");
+
+    let tsig = Tsig::from_record(rr).ok().unwrap();
+    assert_eq!(tsig.mac, mac);
+    assert_eq!(Tsig::from_record(&tsig.to_record().ok().unwrap()).ok().unwrap(), tsig);
+    assert_eq!(Tsig::from_message(&signed).ok().unwrap(), Some(tsig));
+    assert_eq!(Tsig::from_message(&query(1)).ok().unwrap(), None);
+}
+
+#[test]
+fn test_request_response() {
+    for alg in [TsigAlgorithm::HmacMd5, TsigAlgorithm::HmacSha1, TsigAlgorithm::HmacSha256,
+                TsigAlgorithm::HmacSha384, TsigAlgorithm::HmacSha512].iter() {
+        let k = key(*alg);
+        let keys = keyring(&k);
+        let request = TsigSigner::new(&k).sign(&query(7), NOW).ok().unwrap();
+
+        let tsig = TsigVerifier::new(&keys).verify(&request, NOW + 10).ok().unwrap().unwrap();
+        let mut signer = TsigSigner::for_response(&k, &tsig.mac);
+        let signed = signer.sign(&response(7), NOW + 10).ok().unwrap();
+
+        let mut verifier = TsigVerifier::for_response(&keys, k.name(), &tsig.mac);
+        assert!(verifier.verify(&signed, NOW).ok().unwrap().is_some());
+        assert!(verifier.finish().is_ok());
+
+        // A response is not valid for another request
+        let mut verifier = TsigVerifier::for_response(&keys, k.name(), &[0; 16]);
+        assert_eq!(verifier.verify(&signed, NOW), Err(TsigError::BadSignatureError));
+    }
+}
+
+#[test]
+fn test_original_id() {
+    // Forwarders may rewrite the ID of a signed message
+    let k = key(TsigAlgorithm::HmacSha256);
+    let keys = keyring(&k);
+    let mut signed = TsigSigner::new(&k).sign(&query(0x1234), NOW).ok().unwrap();
+    signed[0] = 0x56;
+    signed[1] = 0x78;
+    assert!(TsigVerifier::new(&keys).verify(&signed, NOW).is_ok());
+}
+
+#[test]
+fn test_verify_errors() {
+    let k = key(TsigAlgorithm::HmacSha256);
+    let keys = keyring(&k);
+    let signed = TsigSigner::new(&k).sign(&query(1), NOW).ok().unwrap();
+
+    let mut tampered = signed.clone();
+    tampered[13] ^= 0x20;
+    assert_eq!(TsigVerifier::new(&keys).verify(&tampered, NOW), Err(TsigError::BadSignatureError));
+    assert_eq!(TsigError::BadSignatureError.rcode(), RCode::BADSIG);
+
+    assert_eq!(TsigVerifier::new(&Keyring::new()).verify(&signed, NOW), Err(TsigError::UnknownKeyError));
+    let other = keyring(&key(TsigAlgorithm::HmacSha512));
+    assert_eq!(TsigVerifier::new(&other).verify(&signed, NOW), Err(TsigError::AlgorithmError));
+    assert_eq!(TsigError::AlgorithmError.rcode(), RCode::BADKEY);
+
+    assert_eq!(TsigVerifier::new(&keys).verify(&signed, NOW + 301), Err(TsigError::BadTimeError(NOW)));
+    assert_eq!(TsigVerifier::new(&keys).verify(&signed, NOW - 301), Err(TsigError::BadTimeError(NOW)));
+    assert!(TsigVerifier::new(&keys).verify(&signed, NOW - 300).is_ok());
+
+    // The TSIG record must be the last additional record
+    let mut misplaced = signed.clone();
+    misplaced[7] = 1;
+    misplaced[11] = 0;
+    assert_eq!(TsigVerifier::new(&keys).verify(&misplaced, NOW), Err(TsigError::FormatError));
+    assert_eq!(TsigVerifier::new(&keys).verify(&query(1), NOW), Err(TsigError::MissingError));
+}
+
+#[test]
+fn test_truncation() {
+    let mut short = key(TsigAlgorithm::HmacSha256);
+    short.set_mac_size(16).ok().unwrap();
+    let signed = TsigSigner::new(&short).sign(&query(1), NOW).ok().unwrap();
+    let tsig = Tsig::from_message(&signed).ok().unwrap().unwrap();
+    assert_eq!(tsig.mac.len(), 16);
+
+    // Accepted by a peer that allows 16-octet MACs, but not by one that
+    // wants them whole
+    assert!(TsigVerifier::new(&keyring(&short)).verify(&signed, NOW).is_ok());
+    let full = keyring(&key(TsigAlgorithm::HmacSha256));
+    assert_eq!(TsigVerifier::new(&full).verify(&signed, NOW), Err(TsigError::BadTruncationError(16)));
+    assert_eq!(TsigError::BadTruncationError(16).rcode(), RCode::BADTRUNC);
+
+    // MACs shorter than half the hash are malformed
+    let mut tsig = tsig;
+    tsig.mac.truncate(15);
+    let rr = tsig.to_record().ok().unwrap();
+    let mut w = MessageWriter::new();
+    w.set_compression(false);
+    w.write_resource_record(&rr).ok().unwrap();
+    let mut truncated = query(1);
+    truncated[11] = 1;
+    truncated.extend_from_slice(&w.into_bytes());
+    assert_eq!(TsigVerifier::new(&keyring(&short)).verify(&truncated, NOW), Err(TsigError::FormatError));
+}
+
+#[test]
+fn test_error_responses() {
+    let k = key(TsigAlgorithm::HmacSha256);
+    let keys = keyring(&k);
+    let request = TsigSigner::new(&k).sign(&query(9), NOW).ok().unwrap();
+    let tsig = Tsig::from_message(&request).ok().unwrap().unwrap();
+
+    // BADSIG responses are unsigned
+    let reply = unsigned_error(&response(9), &tsig, RCode::BADSIG).ok().unwrap();
+    assert_eq!(read_dns_message(&reply).ok().unwrap().rcode().ok().unwrap(), RCode::NotAuth);
+    let mut verifier = TsigVerifier::for_response(&keys, k.name(), &tsig.mac);
+    assert_eq!(verifier.verify(&reply, NOW), Err(TsigError::PeerError(16)));
+
+    // BADTIME responses are signed and carry the server's clock
+    let mut signer = TsigSigner::for_response(&k, &tsig.mac);
+    signer.set_error(RCode::BADTIME);
+    let reply = signer.sign(&response(9), NOW + 1000).ok().unwrap();
+    let mut verifier = TsigVerifier::for_response(&keys, k.name(), &tsig.mac);
+    assert_eq!(verifier.verify(&reply, NOW), Err(TsigError::PeerError(18)));
+    let reply = Tsig::from_message(&reply).ok().unwrap().unwrap();
+    assert_eq!(reply.other, decode_hex("00006553f4e8".as_bytes()).unwrap());
+
+    // Responses must use the request's key
+    let other = TsigKey::new(Name::from_str("other-key.").ok().unwrap(), TsigAlgorithm::HmacSha256, SECRET);
+    let mut keys = keys.clone();
+    keys.add(other.clone());
+    let reply = TsigSigner::for_response(&other, &tsig.mac).sign(&response(9), NOW).ok().unwrap();
+    let mut verifier = TsigVerifier::for_response(&keys, k.name(), &tsig.mac);
+    assert_eq!(verifier.verify(&reply, NOW), Err(TsigError::UnknownKeyError));
+}
+
+#[test]
+fn test_multi_message() {
+    let k = key(TsigAlgorithm::HmacSha256);
+    let keys = keyring(&k);
+    let request = TsigSigner::new(&k).sign(&query(3), NOW).ok().unwrap();
+    let request_mac = Tsig::from_message(&request).ok().unwrap().unwrap().mac;
+
+    let mut signer = TsigSigner::for_response(&k, &request_mac);
+    let mut verifier = TsigVerifier::for_response(&keys, k.name(), &request_mac);
+    let first = signer.sign(&response(3), NOW).ok().unwrap();
+    assert!(verifier.verify(&first, NOW).ok().unwrap().is_some());
+
+    for _ in 0..MAX_UNSIGNED_MESSAGES {
+        signer.add_unsigned(&response(3)).ok().unwrap();
+        assert_eq!(verifier.verify(&response(3), NOW), Ok(None));
+    }
+    assert!(verifier.finish().is_err());
+    assert_eq!(signer.add_unsigned(&response(3)), Err(TsigError::TooManyUnsignedError));
+
+    let mut verifier_copy = verifier.clone();
+    assert_eq!(verifier_copy.verify(&response(3), NOW), Err(TsigError::TooManyUnsignedError));
+
+    // Later messages cover the unsigned ones before them
+    let last = signer.sign(&response(3), NOW + 1).ok().unwrap();
+    assert!(verifier.verify(&last, NOW).ok().unwrap().is_some());
+    assert!(verifier.finish().is_ok());
+
+    let mut fresh = TsigVerifier::for_response(&keys, k.name(), &request_mac);
+    fresh.verify(&first, NOW).ok().unwrap();
+    assert_eq!(fresh.verify(&last, NOW), Err(TsigError::BadSignatureError));
+}
+
+/// The error field of the TKEY record of a TKEY response.
+fn tkey_error(response: &[u8]) -> u16 {
+    let msg = read_dns_message(response).ok().unwrap();
+    match msg.answers[0].rdata().ok().unwrap() {
+        RData::TKEY { error, .. } => error,
+        _ => panic!("not a TKEY record"),
+    }
+}
+
+#[test]
+fn test_tkey_rdata() {
+    let tkey = RData::TKEY {
+        algorithm: Name::from_str("hmac-sha256.").ok().unwrap(),
+        inception: NOW as u32,
+        expiration: NOW as u32 + 3600,
+        mode: TKEY_DIFFIE_HELLMAN,
+        error: 0,
+        key: Cow::Borrowed(b"nonce"),
+        other: Cow::Borrowed(&[]),
+    };
+    let rr = ResourceRecord::new(Name::from_str("key.").ok().unwrap(), Type::TKEY, Class::ANY, 0, write_dns_rdata(&tkey).ok().unwrap());
+    assert_eq!(rr.rdata().ok().unwrap(), tkey);
+    assert_eq!(format!("{}", tkey), "hmac-sha256. 20231114221320 20231114231320 2 0 5 bm9uY2U= 0");
+}
+
+#[test]
+fn test_tkey_diffie_hellman() {
+    let name = Name::from_str("client-key.example.").ok().unwrap();
+    let client = TkeyClient::new(&name, TsigAlgorithm::HmacSha256, NOW as u32, NOW as u32 + 3600).ok().unwrap();
+    let server = TkeyServer::new().ok().unwrap();
+    let mut client_keys = Keyring::new();
+    let mut server_keys = Keyring::new();
+
+    let request = client.query(1).ok().unwrap();
+    let signer = Name::from_str("bootstrap.example.").ok().unwrap();
+    let response = server.process(&request, Some(&signer), &mut server_keys, NOW as u32).ok().unwrap();
+    assert_eq!(tkey_error(&response), 0);
+
+    // Responses to another query or about another key are refused
+    assert_eq!(client.process_response(&client.query(2).ok().unwrap(), &response, &mut client_keys),
+               Err(TsigError::ResponseMismatchError));
+    let mut msg = read_dns_message(&response).ok().unwrap();
+    msg.answers[0].rname = Name::from_str("Test-Key.").ok().unwrap();
+    let spoofed = write_dns_message(&msg).ok().unwrap();
+    assert_eq!(client.process_response(&request, &spoofed, &mut client_keys), Err(TsigError::ResponseMismatchError));
+    assert!(client_keys.is_empty());
+
+    // Keys already in the keyring are not replaced, whatever the case of the name
+    let k = TsigKey::new(Name::from_str("Client-Key.Example.").ok().unwrap(), TsigAlgorithm::HmacSha256, SECRET);
+    let mut other_keys = keyring(&k);
+    assert_eq!(client.process_response(&request, &response, &mut other_keys), Err(TsigError::KeyExistsError));
+    assert_eq!(other_keys.len(), 1);
+    assert_eq!(other_keys.get(&name).unwrap().secret(), SECRET);
+
+    let key_name = client.process_response(&request, &response, &mut client_keys).ok().unwrap();
+    assert_eq!(key_name, name);
+
+    let client_key = client_keys.get(&name).unwrap();
+    let server_key = server_keys.get(&name).unwrap();
+    assert_eq!(client_key.secret(), server_key.secret());
+    assert_eq!(client_key.algorithm(), TsigAlgorithm::HmacSha256);
+
+    // The agreed key signs messages
+    let request = TsigSigner::new(client_key).sign(&query(2), NOW).ok().unwrap();
+    assert!(TsigVerifier::new(&server_keys).verify(&request, NOW).ok().unwrap().is_some());
+}
+
+#[test]
+fn test_tkey_errors() {
+    let name = Name::from_str("client-key.example.").ok().unwrap();
+    let signer = Name::from_str("bootstrap.example.").ok().unwrap();
+    let server = TkeyServer::new().ok().unwrap();
+    let mut keys = Keyring::new();
+
+    // Unsigned queries cannot agree on a key
+    let client = TkeyClient::new(&name, TsigAlgorithm::HmacSha256, NOW as u32, NOW as u32 + 3600).ok().unwrap();
+    let request = client.query(1).ok().unwrap();
+    let response = server.process(&request, None, &mut keys, NOW as u32).ok().unwrap();
+    assert_eq!(tkey_error(&response), RCode::BADKEY.to_u16());
+    assert!(keys.is_empty());
+
+    // Expired validity period
+    let client = TkeyClient::new(&name, TsigAlgorithm::HmacSha256, NOW as u32 - 7200, NOW as u32 - 3600).ok().unwrap();
+    let request = client.query(1).ok().unwrap();
+    let response = server.process(&request, Some(&signer), &mut keys, NOW as u32).ok().unwrap();
+    assert_eq!(tkey_error(&response), RCode::BADTIME.to_u16());
+    assert_eq!(client.process_response(&request, &response, &mut keys), Err(TsigError::PeerError(RCode::BADTIME.to_u16())));
+    assert!(keys.is_empty());
+
+    // Existing keys are not replaced, whatever the case of the name
+    let k = key(TsigAlgorithm::HmacSha256);
+    let mut server_keys = keyring(&k);
+    let client = TkeyClient::new(&Name::from_str("test-key.").ok().unwrap(), TsigAlgorithm::HmacSha256, NOW as u32, NOW as u32 + 3600).ok().unwrap();
+    let response = server.process(&client.query(1).ok().unwrap(), Some(&signer), &mut server_keys, NOW as u32).ok().unwrap();
+    assert_eq!(tkey_error(&response), RCode::BADNAME.to_u16());
+    assert_eq!(server_keys.len(), 1);
+    assert_eq!(server_keys.get(k.name()).unwrap().secret(), SECRET);
+
+    // Unsupported mode, expired and missing keys
+    let mut query = delete_query(1, &k, NOW as u32).ok().unwrap();
+    let mode = query.len() - 8;
+    assert_eq!(&query[mode..mode + 2], &[0, TKEY_DELETE as u8]);
+    query[mode + 1] = 9;
+    let response = server.process(&query, None, &mut keys, NOW as u32).ok().unwrap();
+    assert_eq!(tkey_error(&response), RCode::BADMODE.to_u16());
+    query[mode + 1] = TKEY_DIFFIE_HELLMAN as u8;
+    let response = server.process(&query, Some(&signer), &mut keys, NOW as u32).ok().unwrap();
+    assert_eq!(tkey_error(&response), RCode::BADTIME.to_u16());
+    // Without a Diffie-Hellman KEY record
+    query[mode - 2] += 1;
+    let response = server.process(&query, Some(&signer), &mut keys, NOW as u32).ok().unwrap();
+    assert_eq!(tkey_error(&response), RCode::BADKEY.to_u16());
+
+    // Diffie-Hellman values that would force a known secret
+    let dh = DhKey::generate(2).ok().unwrap();
+    let mut public = dh.public_key();
+    let len = public.len() - 128;
+    public.truncate(len - 2);
+    public.extend_from_slice(&[0, 1, 1]);
+    assert_eq!(dh.agree(&public), Err(TsigError::KeyExchangeError));
+    assert_eq!(dh.agree(&[0, 1]), Err(TsigError::KeyExchangeError));
+    assert!(dh.agree(&DhKey::generate(2).ok().unwrap().public_key()).is_ok());
+    assert_eq!(dh.agree(&DhKey::generate(1).ok().unwrap().public_key()), Err(TsigError::KeyExchangeError));
+}
+
+#[test]
+fn test_tkey_delete() {
+    let server = TkeyServer::new().ok().unwrap();
+    let k = key(TsigAlgorithm::HmacSha256);
+    let mut server_keys = keyring(&k);
+    let mut client_keys = keyring(&k);
+    let query = TsigSigner::new(&k).sign(&delete_query(1, &k, NOW as u32).ok().unwrap(), NOW).ok().unwrap();
+
+    // Deletion must be signed with the key itself
+    let response = server.process(&query, None, &mut server_keys, NOW as u32).ok().unwrap();
+    assert_eq!(tkey_error(&response), RCode::BADKEY.to_u16());
+    assert_eq!(process_delete_response(&query, &response, Some(k.name()), &mut client_keys), Err(TsigError::PeerError(RCode::BADKEY.to_u16())));
+    assert_eq!(server_keys.len(), 1);
+
+    let signer = TsigVerifier::new(&server_keys).verify(&query, NOW).ok().unwrap().unwrap().key_name;
+    let response = server.process(&query, Some(&signer), &mut server_keys, NOW as u32).ok().unwrap();
+    assert_eq!(tkey_error(&response), 0);
+    assert!(server_keys.is_empty());
+    // The confirmation must be signed with the key and answer the query
+    assert_eq!(process_delete_response(&query, &response, None, &mut client_keys), Err(TsigError::UnknownKeyError));
+    let other = delete_query(2, &k, NOW as u32).ok().unwrap();
+    assert_eq!(process_delete_response(&other, &response, Some(&signer), &mut client_keys), Err(TsigError::ResponseMismatchError));
+    assert_eq!(client_keys.len(), 1);
+    assert!(process_delete_response(&query, &response, Some(&signer), &mut client_keys).is_ok());
+    assert!(client_keys.is_empty());
+
+    // The key is gone
+    let response = server.process(&query, Some(&signer), &mut server_keys, NOW as u32).ok().unwrap();
+    assert_eq!(tkey_error(&response), RCode::BADNAME.to_u16());
+}
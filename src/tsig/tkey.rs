@@ -0,0 +1,308 @@
+use super::{Class,Name,OwnedName,RCode,RData,ResourceRecord,Type};
+use super::algorithm::TsigAlgorithm;
+use super::dh::{DhKey,DIFFIE_HELLMAN};
+use super::errors::TsigError;
+use super::key::{Keyring,TsigKey};
+use super::super::encoding::serial_cmp;
+use super::super::parser::{Message,OwnedResourceRecord,Question,is_response_to,read_dns_message};
+use super::super::writer::{write_dns_message,write_dns_rdata};
+
+use md5::{Digest,Md5};
+use ring::rand::{SecureRandom,SystemRandom};
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// TKEY modes, from
+/// [RFC2930§2.5](https://tools.ietf.org/html/rfc2930#section-2.5).
+pub const TKEY_SERVER_ASSIGNMENT: u16 = 1;
+pub const TKEY_DIFFIE_HELLMAN: u16 = 2;
+pub const TKEY_GSS_API: u16 = 3;
+pub const TKEY_RESOLVER_ASSIGNMENT: u16 = 4;
+pub const TKEY_DELETE: u16 = 5;
+
+/// Length of the nonces exchanged with Diffie-Hellman keys.
+const NONCE_LENGTH: usize = 32;
+
+fn tkey_record(name: &Name, rdata: &RData) -> Result<OwnedResourceRecord, TsigError> {
+    Ok(ResourceRecord::new(name.clone().into_owned(), Type::TKEY, Class::ANY, 0, write_dns_rdata(rdata)?))
+}
+
+/// A query for `name` of type TKEY with `records` as its additional
+/// section, as per
+/// [RFC2930§3](https://tools.ietf.org/html/rfc2930#section-3).
+fn tkey_query(id: u16, name: &Name, records: Vec<OwnedResourceRecord>) -> Result<Vec<u8>, TsigError> {
+    let mut msg = Message::new(id, 0);
+    msg.questions.push(Question { qname: name.clone(), qtype: Type::TKEY, qclass: Class::ANY });
+    msg.additionals = records;
+    Ok(write_dns_message(&msg)?)
+}
+
+/// The TKEY record of a message section and its owner.
+fn find_tkey<'a>(records: &'a [ResourceRecord]) -> Result<(&'a ResourceRecord<'a>, RData<'a>), TsigError> {
+    let rr = records.iter().find(|rr| rr.rtype == Type::TKEY).ok_or(TsigError::MissingError)?;
+    Ok((rr, rr.rdata()?))
+}
+
+/// The name a TKEY response is about, after checking that it answers
+/// `query`, the query as it was sent, and that `rr`, its TKEY record,
+/// is owned by that name.
+fn check_response(query: &[u8], response: &Message, rr: &ResourceRecord) -> Result<OwnedName, TsigError> {
+    let query = read_dns_message(query)?;
+    if !is_response_to(response, &query) {
+        return Err(TsigError::ResponseMismatchError);
+    }
+    let name = query.questions.first().ok_or(TsigError::FormatError)?.qname.clone().into_owned();
+    if rr.rname.canonical_cmp(&name) != Ordering::Equal {
+        return Err(TsigError::ResponseMismatchError);
+    }
+    Ok(name)
+}
+
+/// The public key of the first Diffie-Hellman KEY record of a message
+/// section.
+fn find_dh_key<'a>(records: &'a [ResourceRecord]) -> Option<Cow<'a, [u8]>> {
+    records.iter().filter(|rr| rr.rtype == Type::KEY).filter_map(|rr| match rr.rdata() {
+        Ok(RData::KEY { algorithm: DIFFIE_HELLMAN, public_key, .. }) => Some(public_key),
+        _ => None,
+    }).next()
+}
+
+/// Derive a shared secret from a DH value and the nonces of the query
+/// and response, as per
+/// [RFC2930§4.1](https://tools.ietf.org/html/rfc2930#section-4.1):
+/// the DH value XOR the MD5 of each nonce followed by the DH value,
+/// with the shorter operand padded with zeros on the right.
+fn keying_material(dh_value: &[u8], query_nonce: &[u8], server_nonce: &[u8]) -> Vec<u8> {
+    let mut digests = Vec::new();
+    for nonce in [query_nonce, server_nonce].iter() {
+        let mut md5 = Md5::new();
+        md5.update(nonce);
+        md5.update(dh_value);
+        digests.extend_from_slice(&md5.finalize());
+    }
+    let mut secret = dh_value.to_vec();
+    if secret.len() < digests.len() {
+        secret.resize(digests.len(), 0);
+    }
+    for (s, d) in secret.iter_mut().zip(digests.iter()) {
+        *s ^= d;
+    }
+    secret
+}
+
+fn nonce() -> Result<Vec<u8>, TsigError> {
+    let mut nonce = vec![0u8; NONCE_LENGTH];
+    SystemRandom::new().fill(&mut nonce).map_err(|_| TsigError::KeyExchangeError)?;
+    Ok(nonce)
+}
+
+/// The resolver side of a Diffie-Hellman TKEY exchange, as per
+/// [RFC2930§4.1](https://tools.ietf.org/html/rfc2930#section-4.1).
+///
+/// The query proposes a key name, algorithm and validity period and
+/// carries a fresh DH key and nonce. Once the response is processed the
+/// negotiated key is in the keyring, ready for `TsigSigner`.
+#[derive(Debug,Clone)]
+pub struct TkeyClient {
+    key_name: OwnedName,
+    algorithm: TsigAlgorithm,
+    inception: u32,
+    expiration: u32,
+    dh: DhKey,
+    nonce: Vec<u8>,
+}
+
+impl TkeyClient {
+    /// Prepare to negotiate a key named `key_name` valid from
+    /// `inception` to `expiration`, in seconds since the epoch modulo
+    /// 2^32.
+    pub fn new(key_name: &Name, algorithm: TsigAlgorithm, inception: u32, expiration: u32) -> Result<TkeyClient, TsigError> {
+        Ok(TkeyClient {
+            key_name: key_name.clone().into_owned(),
+            algorithm,
+            inception,
+            expiration,
+            dh: DhKey::generate(2)?,
+            nonce: nonce()?,
+        })
+    }
+
+    /// The TKEY query to send to the server.
+    pub fn query(&self, id: u16) -> Result<Vec<u8>, TsigError> {
+        let tkey = tkey_record(&self.key_name, &RData::TKEY {
+            algorithm: self.algorithm.name(),
+            inception: self.inception,
+            expiration: self.expiration,
+            mode: TKEY_DIFFIE_HELLMAN,
+            error: 0,
+            key: Cow::Borrowed(&self.nonce),
+            other: Cow::Borrowed(&[]),
+        })?;
+        let key = ResourceRecord::new(self.key_name.clone(), Type::KEY, Class::IN, 0, write_dns_rdata(&self.dh.key())?);
+        tkey_query(id, &self.key_name, vec![tkey, key])
+    }
+
+    /// Derive the key from the server's response to `query`, the query
+    /// as it was sent, and add it to `keyring`, returning its name. An
+    /// error reported by the server in the TKEY record is returned as
+    /// `PeerError`.
+    ///
+    /// The response must answer `query` and be about the proposed key
+    /// name. It must also have been verified with TSIG or SIG(0) before
+    /// it is passed here, since nothing else ties it to the server. A
+    /// key already in `keyring` under that name is never replaced:
+    /// `KeyExistsError` is returned instead.
+    pub fn process_response(&self, query: &[u8], response: &[u8], keyring: &mut Keyring) -> Result<OwnedName, TsigError> {
+        let msg = read_dns_message(response)?;
+        let (rr, tkey) = find_tkey(&msg.answers)?;
+        let name = check_response(query, &msg, rr)?;
+        if name.canonical_cmp(&self.key_name) != Ordering::Equal {
+            return Err(TsigError::ResponseMismatchError);
+        }
+        let (algorithm, mode, error, server_nonce) = match tkey {
+            RData::TKEY { ref algorithm, mode, error, ref key, .. } => (algorithm, mode, error, key),
+            _ => return Err(TsigError::FormatError),
+        };
+        if error != 0 {
+            return Err(TsigError::PeerError(error));
+        }
+        if mode != TKEY_DIFFIE_HELLMAN || TsigAlgorithm::from_name(algorithm) != Some(self.algorithm) {
+            return Err(TsigError::FormatError);
+        }
+        if keyring.get(&name).is_some() {
+            return Err(TsigError::KeyExistsError);
+        }
+        let server_key = find_dh_key(&msg.answers).ok_or(TsigError::KeyExchangeError)?;
+        let dh_value = self.dh.agree(&server_key)?;
+        let secret = keying_material(&dh_value, &self.nonce, server_nonce);
+        keyring.add(TsigKey::new(name.clone(), self.algorithm, &secret));
+        Ok(name)
+    }
+}
+
+/// The server side of TKEY, as per
+/// [RFC2930§4](https://tools.ietf.org/html/rfc2930#section-4),
+/// supporting Diffie-Hellman exchange and key deletion.
+///
+/// Keys are agreed for the validity period the resolver asks for, and
+/// are added to or removed from a keyring.
+#[derive(Debug,Clone)]
+pub struct TkeyServer {
+    dh: DhKey,
+}
+
+impl TkeyServer {
+    /// Create a server with a fresh Diffie-Hellman key.
+    pub fn new() -> Result<TkeyServer, TsigError> {
+        Ok(TkeyServer { dh: DhKey::generate(2)? })
+    }
+
+    /// Answer a TKEY query at time `now`, in seconds since the epoch
+    /// modulo 2^32. `signer` is the name of the TSIG key the query was
+    /// verified with, if any. Key agreement requires the query to be
+    /// signed by a known key, as
+    /// [RFC2930§4.1](https://tools.ietf.org/html/rfc2930#section-4.1)
+    /// leaves unauthenticated agreement open to impersonation; deletion
+    /// requires it to be signed with the key being deleted.
+    ///
+    /// Agreed keys never replace a key already in `keyring`: proposing
+    /// the name of one is refused with BADNAME.
+    ///
+    /// Refused queries are answered with the reason in the TKEY error
+    /// field. Malformed queries are returned as an error, to be
+    /// answered with FORMERR.
+    pub fn process(&self, request: &[u8], signer: Option<&Name>, keyring: &mut Keyring, now: u32) -> Result<Vec<u8>, TsigError> {
+        let msg = read_dns_message(request)?;
+        let (rr, tkey) = find_tkey(&msg.additionals)?;
+        let (algorithm, inception, expiration, mode, client_nonce) = match tkey {
+            RData::TKEY { ref algorithm, inception, expiration, mode, ref key, .. } => (algorithm, inception, expiration, mode, key),
+            _ => return Err(TsigError::FormatError),
+        };
+
+        let mut server_nonce = Vec::new();
+        let mut answers = Vec::new();
+        let error = match mode {
+            TKEY_DIFFIE_HELLMAN => match TsigAlgorithm::from_name(algorithm) {
+                _ if signer.is_none() => RCode::BADKEY,
+                None => RCode::BADALG,
+                Some(_) if serial_cmp(now, expiration) != Some(Ordering::Less) => RCode::BADTIME,
+                Some(_) if keyring.get(&rr.rname).is_some() => RCode::BADNAME,
+                Some(alg) => match find_dh_key(&msg.additionals).map(|key| self.dh.agree(&key)) {
+                    Some(Ok(dh_value)) => {
+                        server_nonce = nonce()?;
+                        let secret = keying_material(&dh_value, client_nonce, &server_nonce);
+                        keyring.add(TsigKey::new(rr.rname.clone(), alg, &secret));
+                        answers.push(ResourceRecord::new(rr.rname.clone().into_owned(), Type::KEY, Class::IN, 0, write_dns_rdata(&self.dh.key())?));
+                        RCode::NoError
+                    },
+                    _ => RCode::BADKEY,
+                },
+            },
+            TKEY_DELETE => {
+                if signer.map_or(true, |s| s.canonical_cmp(&rr.rname) != Ordering::Equal) {
+                    RCode::BADKEY
+                } else if keyring.remove(&rr.rname).is_none() {
+                    RCode::BADNAME
+                } else {
+                    RCode::NoError
+                }
+            },
+            _ => RCode::BADMODE,
+        };
+
+        let tkey = tkey_record(&rr.rname, &RData::TKEY {
+            algorithm: algorithm.clone(),
+            inception,
+            expiration,
+            mode,
+            error: error.to_u16(),
+            key: Cow::Owned(server_nonce),
+            other: Cow::Borrowed(&[]),
+        })?;
+        answers.insert(0, tkey);
+        let mut reply = Message::new(msg.id, msg.flags & 0x7900 | 0x8000);
+        reply.questions = msg.questions.clone();
+        reply.answers = answers;
+        Ok(write_dns_message(&reply)?)
+    }
+}
+
+/// A TKEY query to delete `key`, as per
+/// [RFC2930§4.2](https://tools.ietf.org/html/rfc2930#section-4.2).
+/// It must be signed with `key` itself before it is sent.
+pub fn delete_query(id: u16, key: &TsigKey, now: u32) -> Result<Vec<u8>, TsigError> {
+    let tkey = tkey_record(key.name(), &RData::TKEY {
+        algorithm: key.algorithm().name(),
+        inception: now,
+        expiration: now,
+        mode: TKEY_DELETE,
+        error: 0,
+        key: Cow::Borrowed(&[]),
+        other: Cow::Borrowed(&[]),
+    })?;
+    tkey_query(id, key.name(), vec![tkey])
+}
+
+/// Remove the key from `keyring` once the server has confirmed its
+/// deletion in its response to `query`, the query as it was sent. An
+/// error reported by the server is returned as `PeerError`.
+///
+/// `signer` is the name of the TSIG key the response was verified
+/// with, if any, which must be the key being deleted.
+pub fn process_delete_response(query: &[u8], response: &[u8], signer: Option<&Name>, keyring: &mut Keyring) -> Result<(), TsigError> {
+    let msg = read_dns_message(response)?;
+    let (rr, tkey) = find_tkey(&msg.answers)?;
+    let name = check_response(query, &msg, rr)?;
+    if signer.map_or(true, |s| s.canonical_cmp(&name) != Ordering::Equal) {
+        return Err(TsigError::UnknownKeyError);
+    }
+    match tkey {
+        RData::TKEY { mode: TKEY_DELETE, error: 0, .. } => {
+            keyring.remove(&name);
+            Ok(())
+        },
+        RData::TKEY { error, .. } if error != 0 => Err(TsigError::PeerError(error)),
+        _ => Err(TsigError::FormatError),
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+use std::convert;
+use std::error;
+use super::super::parser::ReadError;
+
+#[derive(PartialEq,Copy,Clone,Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum WriteError {
+    InvalidRDataError(ReadError),
+    TooManyRecordsError(usize),
+    NameTooLongError(usize),
+    RDataTooLongError(usize),
+    CharacterStringTooLongError(usize),
+}
+
+impl error::Error for WriteError {
+    fn description(&self) -> &str {
+        match *self {
+            WriteError::InvalidRDataError(_) => "Record has invalid RDATA",
+            WriteError::TooManyRecordsError(_) => "Too many records in section",
+            WriteError::NameTooLongError(_) => "Name was too long",
+            WriteError::RDataTooLongError(_) => "RDATA was too long",
+            WriteError::CharacterStringTooLongError(_) => "Character string was too long",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match *self {
+            WriteError::InvalidRDataError(ref err) => Some(err as &dyn error::Error),
+            _ => None,
+        }
+    }
+}
+
+impl convert::From<ReadError> for WriteError {
+    fn from(err: ReadError) -> WriteError {
+        WriteError::InvalidRDataError(err)
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WriteError::InvalidRDataError(x) => write!(f, "Record has invalid RDATA: {}", x),
+            WriteError::TooManyRecordsError(x) => write!(f, "Too many records in section: {} > 65535", x),
+            WriteError::NameTooLongError(x) => write!(f, "Name was too long: {} > 255", x),
+            WriteError::RDataTooLongError(x) => write!(f, "RDATA was too long: {} > 65535", x),
+            WriteError::CharacterStringTooLongError(x) => write!(f, "Character string was too long: {} > 255", x),
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use super::{Message,Question,ResourceRecord,Name,Label,RData,Type};
+use super::errors;
+
+use super::{_write_be_u16,_write_be_u32,_write_be_i32};
+
+use std::collections::HashMap;
+
+/// Default maximum number of name suffixes remembered for compression.
+pub const DEFAULT_COMPRESSION_LIMIT: usize = 1024;
+
+/// Serializes DNS messages into RFC 1035 wire format.
+///
+/// Offsets written by a `MessageWriter` are relative to the start of
+/// its buffer, which is expected to be the start of a DNS message.
+///
+/// Names are compressed as per
+/// [RFC1035§4.1.4](https://tools.ietf.org/html/rfc1035#section-4.1.4)
+/// unless compression is disabled. Names inside RDATA are only
+/// compressed for the well-known types listed in
+/// [RFC3597§4](https://tools.ietf.org/html/rfc3597#section-4).
+pub struct MessageWriter<'a> {
+    buf: Vec<u8>,
+    compress: bool,
+    compress_rdata: bool,
+    compression_limit: usize,
+    canonical: bool,
+    table: HashMap<Vec<Label<'a>>, u16>,
+}
+
+impl<'a> MessageWriter<'a> {
+    pub fn new() -> MessageWriter<'a> {
+        MessageWriter {
+            buf: Vec::with_capacity(512),
+            compress: true,
+            compress_rdata: true,
+            compression_limit: DEFAULT_COMPRESSION_LIMIT,
+            canonical: false,
+            table: HashMap::new(),
+        }
+    }
+
+    /// Enable or disable name compression entirely.
+    pub fn set_compression(&mut self, compress: bool) {
+        self.compress = compress;
+    }
+
+    /// Enable or disable compression of names inside RDATA. Names in
+    /// RDATA of types other than the RFC 3597 well-known types are
+    /// never compressed regardless of this setting.
+    pub fn set_rdata_compression(&mut self, compress: bool) {
+        self.compress_rdata = compress;
+    }
+
+    /// Limit the number of name suffixes remembered as compression
+    /// targets. Names written after the limit is reached can still be
+    /// compressed against earlier names but are not added themselves.
+    pub fn set_compression_limit(&mut self, limit: usize) {
+        self.compression_limit = limit;
+    }
+
+    /// Enable or disable writing names in the canonical form of
+    /// [RFC4034§6.2](https://tools.ietf.org/html/rfc4034#section-6.2):
+    /// uncompressed, with uppercase letters folded to lowercase.
+    pub fn set_canonical(&mut self, canonical: bool) {
+        self.canonical = canonical;
+    }
+
+    /// Consume the writer, returning the bytes written so far.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    /// Write a complete DNS message, including a header whose section
+    /// counts are derived from the message's record vectors.
+    pub fn write_message(&mut self, msg: &'a Message) -> Result<(), errors::WriteError> {
+        _write_be_u16(&mut self.buf, msg.id);
+        _write_be_u16(&mut self.buf, msg.flags);
+        _write_be_u16(&mut self.buf, section_count(msg.questions.len())?);
+        _write_be_u16(&mut self.buf, section_count(msg.answers.len())?);
+        _write_be_u16(&mut self.buf, section_count(msg.nameservers.len())?);
+        _write_be_u16(&mut self.buf, section_count(msg.additionals.len())?);
+
+        for q in msg.questions.iter() {
+            self.write_question(q)?;
+        }
+        for rr in msg.answers.iter().chain(msg.nameservers.iter()).chain(msg.additionals.iter()) {
+            self.write_resource_record(rr)?;
+        }
+        Ok(())
+    }
+
+    /// Write a single DNS question.
+    pub fn write_question(&mut self, q: &'a Question) -> Result<(), errors::WriteError> {
+        self.write_name(&q.qname)?;
+        _write_be_u16(&mut self.buf, q.qtype.to_u16());
+        _write_be_u16(&mut self.buf, q.qclass.to_u16());
+        Ok(())
+    }
+
+    /// Write a single DNS resource record. The RDATA is decoded and
+    /// re-encoded so that names inside it do not carry compression
+    /// pointers into the message the record was originally read from.
+    pub fn write_resource_record(&mut self, rr: &'a ResourceRecord) -> Result<(), errors::WriteError> {
+        let rdata = rr.rdata()?;
+        self.write_name(&rr.rname)?;
+        _write_be_u16(&mut self.buf, rr.rtype.to_u16());
+        _write_be_u16(&mut self.buf, rr.rclass.to_u16());
+        _write_be_i32(&mut self.buf, rr.rttl);
+
+        // Reserve RDLENGTH and fill it in once the RDATA is written
+        let rdlen_at = self.buf.len();
+        _write_be_u16(&mut self.buf, 0);
+        self.write_rdata(&rdata)?;
+        let rdlen = self.buf.len() - rdlen_at - 2;
+        if rdlen > 65535 {
+            return Err(errors::WriteError::RDataTooLongError(rdlen));
+        }
+        self.buf[rdlen_at] = (rdlen >> 8) as u8;
+        self.buf[rdlen_at + 1] = rdlen as u8;
+        Ok(())
+    }
+
+    /// Write a DNS name, compressing it against previously written
+    /// names if compression is enabled.
+    pub fn write_name(&mut self, name: &Name<'a>) -> Result<(), errors::WriteError> {
+        let compress = self.compress;
+        self.write_name_compressed(name, compress)
+    }
+
+    /// Write a DNS name found in RDATA of a type that permits
+    /// compression.
+    fn write_rdata_name(&mut self, name: &Name<'a>) -> Result<(), errors::WriteError> {
+        let compress = self.compress && self.compress_rdata;
+        self.write_name_compressed(name, compress)
+    }
+
+    fn write_name_compressed(&mut self, name: &Name<'a>, compress: bool) -> Result<(), errors::WriteError> {
+        let len = name.labels.iter().fold(1, |acc, l| acc + 1 + l.as_bytes().len());
+        if len > 255 {
+            return Err(errors::WriteError::NameTooLongError(len));
+        }
+        if self.canonical {
+            for l in name.labels.iter() {
+                self.buf.push(l.as_bytes().len() as u8);
+                self.buf.extend(l.as_bytes().iter().map(|c| c.to_ascii_lowercase()));
+            }
+            self.buf.push(0);
+            return Ok(());
+        }
+        for i in 0..name.labels.len() {
+            if compress {
+                let suffix = &name.labels[i..];
+                // Point at an earlier occurrence of this suffix if one
+                // exists, which also terminates the name.
+                if let Some(&offset) = self.table.get(suffix) {
+                    _write_be_u16(&mut self.buf, 0xC000 | offset);
+                    return Ok(());
+                }
+                // Remember this suffix if it is addressable by a 14-bit
+                // pointer and the table has room.
+                if self.buf.len() < 0x4000 && self.table.len() < self.compression_limit {
+                    self.table.insert(suffix.to_vec(), self.buf.len() as u16);
+                }
+            }
+            let b = name.labels[i].as_bytes();
+            self.buf.push(b.len() as u8);
+            self.buf.extend_from_slice(b);
+        }
+        self.buf.push(0);
+        Ok(())
+    }
+
+    /// Write the RDATA of a record, without its RDLENGTH prefix.
+    pub fn write_rdata(&mut self, rdata: &RData<'a>) -> Result<(), errors::WriteError> {
+        match *rdata {
+            RData::A(ref a) => self.buf.extend_from_slice(&a.octets()),
+            RData::AAAA(ref a) => self.buf.extend_from_slice(&a.octets()),
+            RData::NS(ref n) | RData::CNAME(ref n) | RData::MB(ref n) | RData::MG(ref n) |
+            RData::MR(ref n) | RData::PTR(ref n) => {
+                self.write_rdata_name(n)?;
+            },
+            RData::DNAME(ref n) => {
+                self.write_name_compressed(n, false)?;
+            },
+            RData::SOA { ref mname, ref rname, serial, refresh, retry, expire, minimum } => {
+                self.write_rdata_name(mname)?;
+                self.write_rdata_name(rname)?;
+                _write_be_u32(&mut self.buf, serial);
+                _write_be_u32(&mut self.buf, refresh);
+                _write_be_u32(&mut self.buf, retry);
+                _write_be_u32(&mut self.buf, expire);
+                _write_be_u32(&mut self.buf, minimum);
+            },
+            RData::HINFO { ref cpu, ref os } => {
+                self.write_character_string(cpu)?;
+                self.write_character_string(os)?;
+            },
+            RData::MINFO { ref rmailbx, ref emailbx } => {
+                self.write_rdata_name(rmailbx)?;
+                self.write_rdata_name(emailbx)?;
+            },
+            RData::MX { preference, ref exchange } => {
+                _write_be_u16(&mut self.buf, preference);
+                self.write_rdata_name(exchange)?;
+            },
+            RData::TXT(ref strings) | RData::SPF(ref strings) => {
+                for s in strings.iter() {
+                    self.write_character_string(s)?;
+                }
+            },
+            RData::RP { ref mbox, ref txt } => {
+                self.write_name_compressed(mbox, false)?;
+                self.write_name_compressed(txt, false)?;
+            },
+            RData::AFSDB { subtype, ref hostname } => {
+                _write_be_u16(&mut self.buf, subtype);
+                self.write_name_compressed(hostname, false)?;
+            },
+            RData::SRV { priority, weight, port, ref target } => {
+                _write_be_u16(&mut self.buf, priority);
+                _write_be_u16(&mut self.buf, weight);
+                _write_be_u16(&mut self.buf, port);
+                self.write_name_compressed(target, false)?;
+            },
+            RData::NAPTR { order, preference, ref flags, ref services, ref regexp, ref replacement } => {
+                _write_be_u16(&mut self.buf, order);
+                _write_be_u16(&mut self.buf, preference);
+                self.write_character_string(flags)?;
+                self.write_character_string(services)?;
+                self.write_character_string(regexp)?;
+                self.write_name_compressed(replacement, false)?;
+            },
+            RData::SIG { type_covered, algorithm, labels, original_ttl, expiration, inception, key_tag, ref signer_name, ref signature } |
+            RData::RRSIG { type_covered, algorithm, labels, original_ttl, expiration, inception, key_tag, ref signer_name, ref signature } => {
+                _write_be_u16(&mut self.buf, type_covered.to_u16());
+                self.buf.push(algorithm);
+                self.buf.push(labels);
+                _write_be_u32(&mut self.buf, original_ttl);
+                _write_be_u32(&mut self.buf, expiration);
+                _write_be_u32(&mut self.buf, inception);
+                _write_be_u16(&mut self.buf, key_tag);
+                self.write_name_compressed(signer_name, false)?;
+                self.buf.extend_from_slice(signature);
+            },
+            RData::DNSKEY { flags, protocol, algorithm, ref public_key } |
+            RData::CDNSKEY { flags, protocol, algorithm, ref public_key } |
+            RData::KEY { flags, protocol, algorithm, ref public_key } => {
+                _write_be_u16(&mut self.buf, flags);
+                self.buf.push(protocol);
+                self.buf.push(algorithm);
+                self.buf.extend_from_slice(public_key);
+            },
+            RData::DS { key_tag, algorithm, digest_type, ref digest } |
+            RData::CDS { key_tag, algorithm, digest_type, ref digest } |
+            RData::TA { key_tag, algorithm, digest_type, ref digest } |
+            RData::DLV { key_tag, algorithm, digest_type, ref digest } => {
+                _write_be_u16(&mut self.buf, key_tag);
+                self.buf.push(algorithm);
+                self.buf.push(digest_type);
+                self.buf.extend_from_slice(digest);
+            },
+            RData::NSEC { ref next_domain, ref types } => {
+                // The next domain name keeps its case in canonical form,
+                // as per RFC6840§5.1
+                self.write_name_verbatim(next_domain)?;
+                self.write_type_bitmap(types);
+            },
+            RData::NSEC3 { hash_algorithm, flags, iterations, ref salt, ref next_hashed_owner, ref types } => {
+                self.buf.push(hash_algorithm);
+                self.buf.push(flags);
+                _write_be_u16(&mut self.buf, iterations);
+                self.write_character_string(salt)?;
+                self.write_character_string(next_hashed_owner)?;
+                self.write_type_bitmap(types);
+            },
+            RData::NSEC3PARAM { hash_algorithm, flags, iterations, ref salt } => {
+                self.buf.push(hash_algorithm);
+                self.buf.push(flags);
+                _write_be_u16(&mut self.buf, iterations);
+                self.write_character_string(salt)?;
+            },
+            RData::TSIG { ref algorithm, time_signed, fudge, ref mac, original_id, error, ref other } => {
+                self.write_name_compressed(algorithm, false)?;
+                _write_be_u16(&mut self.buf, (time_signed >> 32) as u16);
+                _write_be_u32(&mut self.buf, time_signed as u32);
+                _write_be_u16(&mut self.buf, fudge);
+                self.write_u16_octets(mac)?;
+                _write_be_u16(&mut self.buf, original_id);
+                _write_be_u16(&mut self.buf, error);
+                self.write_u16_octets(other)?;
+            },
+            RData::TKEY { ref algorithm, inception, expiration, mode, error, ref key, ref other } => {
+                self.write_name_compressed(algorithm, false)?;
+                _write_be_u32(&mut self.buf, inception);
+                _write_be_u32(&mut self.buf, expiration);
+                _write_be_u16(&mut self.buf, mode);
+                _write_be_u16(&mut self.buf, error);
+                self.write_u16_octets(key)?;
+                self.write_u16_octets(other)?;
+            },
+            RData::Opaque(ref data) => self.buf.extend_from_slice(data),
+        }
+        Ok(())
+    }
+
+    /// Write a name uncompressed and exactly as given, even in
+    /// canonical form.
+    fn write_name_verbatim(&mut self, name: &Name<'a>) -> Result<(), errors::WriteError> {
+        let len = name.labels.iter().fold(1, |acc, l| acc + 1 + l.as_bytes().len());
+        if len > 255 {
+            return Err(errors::WriteError::NameTooLongError(len));
+        }
+        for l in name.labels.iter() {
+            self.buf.push(l.as_bytes().len() as u8);
+            self.buf.extend_from_slice(l.as_bytes());
+        }
+        self.buf.push(0);
+        Ok(())
+    }
+
+    /// Write an NSEC or NSEC3 type bitmap, as per
+    /// [RFC4034§4.1.2](https://tools.ietf.org/html/rfc4034#section-4.1.2).
+    /// Types may be given in any order and are written only once.
+    fn write_type_bitmap(&mut self, types: &[Type]) {
+        let mut codes: Vec<u16> = types.iter().map(|t| t.to_u16()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        let mut i = 0;
+        while i < codes.len() {
+            let window = (codes[i] >> 8) as u8;
+            let mut bitmap = [0u8; 32];
+            let mut len = 0;
+            while i < codes.len() && (codes[i] >> 8) as u8 == window {
+                let bit = (codes[i] & 0xFF) as usize;
+                bitmap[bit / 8] |= 0x80 >> (bit % 8);
+                len = bit / 8 + 1;
+                i += 1;
+            }
+            self.buf.push(window);
+            self.buf.push(len as u8);
+            self.buf.extend_from_slice(&bitmap[..len]);
+        }
+    }
+
+    /// Write a single length-prefixed `<character-string>`.
+    fn write_character_string(&mut self, s: &[u8]) -> Result<(), errors::WriteError> {
+        if s.len() > 255 {
+            return Err(errors::WriteError::CharacterStringTooLongError(s.len()));
+        }
+        self.buf.push(s.len() as u8);
+        self.buf.extend_from_slice(s);
+        Ok(())
+    }
+
+    /// Write octets prefixed by a 16-bit length.
+    fn write_u16_octets(&mut self, s: &[u8]) -> Result<(), errors::WriteError> {
+        if s.len() > 65535 {
+            return Err(errors::WriteError::RDataTooLongError(s.len()));
+        }
+        _write_be_u16(&mut self.buf, s.len() as u16);
+        self.buf.extend_from_slice(s);
+        Ok(())
+    }
+}
+
+impl<'a> Default for MessageWriter<'a> {
+    fn default() -> MessageWriter<'a> {
+        MessageWriter::new()
+    }
+}
+
+/// Check that a section's record count fits in its 16-bit header field.
+#[inline(always)]
+fn section_count(len: usize) -> Result<u16, errors::WriteError> {
+    if len > 65535 {
+        return Err(errors::WriteError::TooManyRecordsError(len));
+    }
+    Ok(len as u16)
+}
+
+/// Write a DNS message to a new `Vec<u8>` in wire format, with name
+/// compression enabled.
+pub fn write_dns_message(msg: &Message) -> Result<Vec<u8>, errors::WriteError> {
+    let mut w = MessageWriter::new();
+    w.write_message(msg)?;
+    Ok(w.into_bytes())
+}
+
+/// Write typed RDATA to a new `Vec<u8>` in canonical form, for use in
+/// DNSSEC ordering and signing.
+pub fn write_dns_rdata_canonical(rdata: &RData) -> Result<Vec<u8>, errors::WriteError> {
+    let mut w = MessageWriter::new();
+    w.set_canonical(true);
+    w.write_rdata(rdata)?;
+    Ok(w.into_bytes())
+}
+
+/// Write typed RDATA to a new `Vec<u8>` without compression, suitable
+/// for use with `ResourceRecord::new`.
+pub fn write_dns_rdata(rdata: &RData) -> Result<Vec<u8>, errors::WriteError> {
+    let mut w = MessageWriter::new();
+    w.set_compression(false);
+    w.write_rdata(rdata)?;
+    Ok(w.into_bytes())
+}
@@ -0,0 +1,13 @@
+pub use super::iana::types::Type;
+pub use super::iana::classes::Class;
+pub use super::parser::{Message,Question,ResourceRecord,Name,Label,RData};
+
+pub use super::writer::errors::WriteError;
+pub use super::writer::message::{MessageWriter,DEFAULT_COMPRESSION_LIMIT,write_dns_message,write_dns_rdata,write_dns_rdata_canonical};
+pub use super::writer::util::{_write_be_u16,_write_be_u32,_write_be_i32};
+
+mod errors;
+mod message;
+mod util;
+#[cfg(test)]
+mod tests;
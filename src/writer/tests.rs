@@ -0,0 +1,272 @@
+use super::{Message,Question,ResourceRecord,Name,RData,Type,Class};
+use super::{MessageWriter,write_dns_message,write_dns_rdata,write_dns_rdata_canonical};
+use super::super::parser::read_dns_message;
+
+use std::borrow::Cow;
+use std::net::Ipv4Addr;
+
+static PACKETS: [&[u8]; 8] = [
+    include_bytes!("../../tests/packets/comns1-rs.bin"),
+    include_bytes!("../../tests/packets/fb1-rq.bin"),
+    include_bytes!("../../tests/packets/fb1-rs.bin"),
+    include_bytes!("../../tests/packets/googns1-rs.bin"),
+    include_bytes!("../../tests/packets/large1-rs.bin"),
+    include_bytes!("../../tests/packets/large2-rs.bin"),
+    include_bytes!("../../tests/packets/net1-rq.bin"),
+    include_bytes!("../../tests/packets/net1-rs.bin"),
+];
+
+fn check_same_records(a: &[ResourceRecord], b: &[ResourceRecord]) {
+    assert_eq!(a.len(), b.len());
+    for (x, y) in a.iter().zip(b.iter()) {
+        assert_eq!(x.rname, y.rname);
+        assert_eq!(x.rtype, y.rtype);
+        assert_eq!(x.rclass, y.rclass);
+        assert_eq!(x.rttl, y.rttl);
+        assert_eq!(x.rdata().ok().unwrap(), y.rdata().ok().unwrap());
+    }
+}
+
+fn check_same_message(a: &Message, b: &Message) {
+    assert_eq!(a.id, b.id);
+    assert_eq!(a.flags, b.flags);
+    assert_eq!(a.questions, b.questions);
+    check_same_records(&a.answers, &b.answers);
+    check_same_records(&a.nameservers, &b.nameservers);
+    check_same_records(&a.additionals, &b.additionals);
+}
+
+#[test]
+fn test_round_trip_packets() {
+    for p in PACKETS.iter() {
+        let m1 = read_dns_message(p).ok().unwrap();
+        let w1 = write_dns_message(&m1).ok().unwrap();
+        let m2 = read_dns_message(&w1).ok().unwrap();
+        check_same_message(&m1, &m2);
+
+        // Writing a re-parsed message must give identical bytes
+        let w2 = write_dns_message(&m2).ok().unwrap();
+        assert_eq!(w1, w2);
+    }
+}
+
+#[test]
+fn test_write_query() {
+    let mut m = Message::new(0xe1c9, 0x0000);
+    m.questions.push(Question {
+        qname: Name::from_str("net.").ok().unwrap(),
+        qtype: Type::A,
+        qclass: Class::IN,
+    });
+    assert_eq!(&write_dns_message(&m).ok().unwrap()[..], PACKETS[6]);
+
+    let mut m = Message::new(0x0b8d, 0x0100);
+    m.questions.push(Question {
+        qname: Name::from_str("facebook.com").ok().unwrap(),
+        qtype: Type::A,
+        qclass: Class::IN,
+    });
+    assert_eq!(&write_dns_message(&m).ok().unwrap()[..], PACKETS[1]);
+}
+
+#[test]
+fn test_write_response() {
+    let rdata = write_dns_rdata(&RData::A(Ipv4Addr::new(173, 252, 120, 6))).ok().unwrap();
+    let mut m = Message::new(0x0b8d, 0x8180);
+    m.questions.push(Question {
+        qname: Name::from_str("facebook.com").ok().unwrap(),
+        qtype: Type::A,
+        qclass: Class::IN,
+    });
+    m.answers.push(ResourceRecord::new(Name::from_str("facebook.com").ok().unwrap(), Type::A, Class::IN, 816, &rdata));
+
+    let w = write_dns_message(&m).ok().unwrap();
+    let m2 = read_dns_message(&w).ok().unwrap();
+    check_same_message(&m, &m2);
+    check_same_message(&read_dns_message(PACKETS[2]).ok().unwrap(), &m2);
+}
+
+#[test]
+fn test_write_rdata_names() {
+    let mx = RData::MX { preference: 10, exchange: Name::from_str("mail.example.com.").ok().unwrap() };
+    let b = write_dns_rdata(&mx).ok().unwrap();
+    assert_eq!(&b[..], &b"\x00\x0a\x04mail\x07example\x03com\x00"[..]);
+
+    let rr = ResourceRecord::new(Name::from_str("example.com.").ok().unwrap(), Type::MX, Class::IN, 3600, &b);
+    assert_eq!(rr.rdata().ok().unwrap(), mx);
+
+    let txt = RData::TXT(vec!(Cow::Borrowed(&[0x61u8; 256][..])));
+    assert!(write_dns_rdata(&txt).is_err());
+}
+
+#[test]
+fn test_compression_matches_packets() {
+    // These responses were compressed by their servers exactly the way
+    // the writer compresses: each suffix points at its first occurrence.
+    for p in PACKETS.iter() {
+        let m = read_dns_message(p).ok().unwrap();
+        assert_eq!(&write_dns_message(&m).ok().unwrap()[..], *p);
+    }
+}
+
+fn rr_message<'a>(owners: &[&'a str], rtype: Type, rdata: &'a [u8]) -> Message<'a> {
+    let mut m = Message::new(1, 0x8000);
+    for o in owners.iter() {
+        m.answers.push(ResourceRecord::new(Name::from_str(o).ok().unwrap(), rtype, Class::IN, 60, rdata));
+    }
+    m
+}
+
+#[test]
+fn test_compression_case_insensitive() {
+    let a = [192u8, 0, 2, 1];
+    let m = rr_message(&["www.example.com.", "mail.EXAMPLE.com."], Type::A, &a);
+    let w = write_dns_message(&m).ok().unwrap();
+    // The second owner is "mail" followed by a pointer to "example.com"
+    // in the first owner, at offset 12 + 4.
+    assert_eq!(&w[43..50], &b"\x04mail\xc0\x10"[..]);
+    let m2 = read_dns_message(&w).ok().unwrap();
+    check_same_message(&m, &m2);
+}
+
+#[test]
+fn test_compression_rdata() {
+    let ns = write_dns_rdata(&RData::NS(Name::from_str("ns.example.com.").ok().unwrap())).ok().unwrap();
+    let m = rr_message(&["example.com."], Type::NS, &ns);
+    let w = write_dns_message(&m).ok().unwrap();
+    assert_eq!(&w[35..], &b"\x02ns\xc0\x0c"[..]);
+
+    let mut wr = MessageWriter::new();
+    wr.set_rdata_compression(false);
+    wr.write_message(&m).ok().unwrap();
+    assert_eq!(&wr.into_bytes()[35..], &ns[..]);
+
+    // SRV targets must never be compressed (RFC 2782, RFC 3597)
+    let srv = write_dns_rdata(&RData::SRV {
+        priority: 0,
+        weight: 0,
+        port: 53,
+        target: Name::from_str("ns.example.com.").ok().unwrap(),
+    }).ok().unwrap();
+    let m = rr_message(&["example.com."], Type::SRV, &srv);
+    let w = write_dns_message(&m).ok().unwrap();
+    assert_eq!(&w[35..], &srv[..]);
+}
+
+#[test]
+fn test_compression_disabled_and_limited() {
+    let a = [192u8, 0, 2, 1];
+    let m = rr_message(&["a.example.com.", "b.example.com.", "c.example.com."], Type::A, &a);
+    let compressed = write_dns_message(&m).ok().unwrap();
+
+    let mut wr = MessageWriter::new();
+    wr.set_compression(false);
+    wr.write_message(&m).ok().unwrap();
+    let plain = wr.into_bytes();
+    assert_eq!(plain.len(), compressed.len() + 2 * 11);
+
+    // Only "a.example.com" and "example.com" fit in the table, so both
+    // later names still compress against the first.
+    let mut wr = MessageWriter::new();
+    wr.set_compression_limit(2);
+    wr.write_message(&m).ok().unwrap();
+    assert_eq!(wr.into_bytes(), compressed);
+
+    let mut wr = MessageWriter::new();
+    wr.set_compression_limit(0);
+    wr.write_message(&m).ok().unwrap();
+    assert_eq!(wr.into_bytes(), plain);
+
+    check_same_message(&m, &read_dns_message(&plain).ok().unwrap());
+}
+
+#[test]
+fn test_round_trip_unknown_type() {
+    let buf = [
+        0x00u8, 0x01, 0x80, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+        // example. 60 CLASS4096 TYPE65280 \# 2 abcd
+        0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x00,
+        0xff, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x02, 0xab, 0xcd,
+        // example. 60 IN TYPE262 \# 3 c00c00, which must not be treated
+        // as a compression pointer
+        0xc0, 0x0c, 0x01, 0x06, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x03,
+        0xc0, 0x0c, 0x00,
+    ];
+    let m = read_dns_message(&buf).ok().unwrap();
+    assert_eq!(m.answers[1].rtype, Type::Unknown(262));
+    assert_eq!(write_dns_message(&m).ok().unwrap(), &buf[..]);
+}
+
+#[test]
+fn test_write_owned_messages() {
+    for p in PACKETS.iter() {
+        let owned = read_dns_message(p).ok().unwrap().into_owned().ok().unwrap();
+        assert_eq!(&write_dns_message(&owned).ok().unwrap()[..], *p);
+    }
+}
+
+#[test]
+fn test_write_rdata_dnssec() {
+    let dnskey = RData::DNSKEY {
+        flags: 257,
+        protocol: 3,
+        algorithm: 8,
+        public_key: Cow::Borrowed(&[1, 2, 3, 4]),
+    };
+    let b = write_dns_rdata(&dnskey).ok().unwrap();
+    assert_eq!(&b[..], &[0x01, 0x01, 3, 8, 1, 2, 3, 4][..]);
+
+    let rrsig = RData::RRSIG {
+        type_covered: Type::A,
+        algorithm: 8,
+        labels: 2,
+        original_ttl: 3600,
+        expiration: 0x5F5E1000,
+        inception: 0x5F000000,
+        key_tag: 12345,
+        signer_name: Name::from_str("Example.COM.").ok().unwrap(),
+        signature: Cow::Borrowed(&[0xAA; 8]),
+    };
+    let b = write_dns_rdata(&rrsig).ok().unwrap();
+    let rr = ResourceRecord::new(Name::from_str("example.com.").ok().unwrap(), Type::RRSIG, Class::IN, 3600, &b);
+    assert_eq!(rr.rdata().ok().unwrap(), rrsig);
+
+    // The canonical form lowercases the signer name
+    let c = write_dns_rdata_canonical(&rrsig).ok().unwrap();
+    assert_eq!(&c[18..31], &b"\x07example\x03com\x00"[..]);
+    assert_eq!(&b[18..31], &b"\x07Example\x03COM\x00"[..]);
+}
+
+#[test]
+fn test_write_nsec_bitmaps() {
+    let nsec = RData::NSEC {
+        next_domain: Name::from_str("Host.example.").ok().unwrap(),
+        types: vec![Type::NSEC, Type::A, Type::Unknown(65535), Type::A, Type::Unknown(512)],
+    };
+    let b = write_dns_rdata(&nsec).ok().unwrap();
+    assert_eq!(&b[14..], &b"\x00\x06\x40\x00\x00\x00\x00\x01\x02\x01\x80\xff\x20\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01"[..]);
+    let rr = ResourceRecord::new(Name::from_str("a.example.").ok().unwrap(), Type::NSEC, Class::IN, 60, &b);
+    match rr.rdata().ok().unwrap() {
+        RData::NSEC { types, .. } => assert_eq!(types, vec![Type::A, Type::NSEC, Type::Unknown(512), Type::Unknown(65535)]),
+        r => panic!("unexpected RDATA {:?}", r),
+    }
+
+    // The next domain name keeps its case in canonical form
+    let c = write_dns_rdata_canonical(&nsec).ok().unwrap();
+    assert_eq!(&c[..14], &b"\x04Host\x07example\x00"[..]);
+
+    let nsec3 = RData::NSEC3 {
+        hash_algorithm: 1,
+        flags: 0,
+        iterations: 0,
+        salt: Cow::Borrowed(&[]),
+        next_hashed_owner: Cow::Borrowed(&[0x11; 20]),
+        types: Vec::new(),
+    };
+    let b = write_dns_rdata(&nsec3).ok().unwrap();
+    assert_eq!(b.len(), 4 + 1 + 21);
+    let rr = ResourceRecord::new(Name::from_str("a.example.").ok().unwrap(), Type::NSEC3, Class::IN, 60, &b);
+    assert_eq!(rr.rdata().ok().unwrap(), nsec3);
+}
@@ -0,0 +1,47 @@
+#[inline(always)]
+pub fn _write_be_u16(buf: &mut Vec<u8>, val: u16) {
+    buf.push((val >> 8) as u8);
+    buf.push(val as u8);
+}
+#[inline(always)]
+pub fn _write_be_u32(buf: &mut Vec<u8>, val: u32) {
+    buf.push((val >> 24) as u8);
+    buf.push((val >> 16) as u8);
+    buf.push((val >> 8) as u8);
+    buf.push(val as u8);
+}
+#[inline(always)]
+pub fn _write_be_i32(buf: &mut Vec<u8>, val: i32) {
+    _write_be_u32(buf, val as u32);
+}
+
+#[cfg(test)]
+mod test_hp_support_functions {
+    use super::super::super::parser::{_read_be_u16,_read_be_u32,_read_be_i32};
+    #[test]
+    fn test_write_be_u16() {
+        for i in 0..65536usize {
+            let mut b = Vec::new();
+            super::_write_be_u16(&mut b, i as u16);
+            assert_eq!(_read_be_u16(&b, &mut 0), i as u16);
+        }
+    }
+    #[test]
+    fn test_write_be_u32() {
+        let test_vals = [0u32, 1, 255, 256, 65535, 65536, 20000000, 4294967295u32];
+        for &i in test_vals.iter() {
+            let mut b = Vec::new();
+            super::_write_be_u32(&mut b, i);
+            assert_eq!(_read_be_u32(&b, &mut 0), i);
+        }
+    }
+    #[test]
+    fn test_write_be_i32() {
+        let test_vals = [-2147483648i32,-20000000, -1, 0, 1, 20000000, 2147483647i32];
+        for &i in test_vals.iter() {
+            let mut b = Vec::new();
+            super::_write_be_i32(&mut b, i);
+            assert_eq!(_read_be_i32(&b, &mut 0), i);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::error;
+use std::io;
+use super::super::parser::ReadError;
+use super::super::writer::WriteError;
+
+/// The reason a zone file could not be read.
+#[derive(PartialEq,Copy,Clone,Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum ZoneErrorKind {
+    IoError(io::ErrorKind),
+    UnbalancedParenthesisError,
+    UnterminatedStringError,
+    EscapeError,
+    UnknownDirectiveError,
+    IncludeDepthError,
+    NoOriginError,
+    NoOwnerError,
+    NoTtlError,
+    TtlError,
+    UnknownClassError,
+    UnknownTypeError,
+    UnsupportedTypeError,
+    MissingFieldError,
+    ExtraFieldError,
+    NumberError,
+    AddressError,
+    HexError,
+    Base32Error,
+    Base64Error,
+    TimestampError,
+    InvalidNameError(ReadError),
+    InvalidRDataError(WriteError),
+}
+
+impl ZoneErrorKind {
+    fn description(&self) -> &str {
+        match *self {
+            ZoneErrorKind::IoError(_) => "I/O error",
+            ZoneErrorKind::UnbalancedParenthesisError => "Unbalanced parenthesis",
+            ZoneErrorKind::UnterminatedStringError => "Unterminated quoted string",
+            ZoneErrorKind::EscapeError => "Invalid escape sequence",
+            ZoneErrorKind::UnknownDirectiveError => "Unknown directive",
+            ZoneErrorKind::IncludeDepthError => "$INCLUDE nested too deeply",
+            ZoneErrorKind::NoOriginError => "Relative name with no origin",
+            ZoneErrorKind::NoOwnerError => "No previous owner name to inherit",
+            ZoneErrorKind::NoTtlError => "No TTL given and no default TTL",
+            ZoneErrorKind::TtlError => "Invalid TTL",
+            ZoneErrorKind::UnknownClassError => "Unknown class",
+            ZoneErrorKind::UnknownTypeError => "Unknown type",
+            ZoneErrorKind::UnsupportedTypeError => "Type has no presentation format support",
+            ZoneErrorKind::MissingFieldError => "Missing field",
+            ZoneErrorKind::ExtraFieldError => "Unexpected extra field",
+            ZoneErrorKind::NumberError => "Invalid number",
+            ZoneErrorKind::AddressError => "Invalid address",
+            ZoneErrorKind::HexError => "Invalid hex",
+            ZoneErrorKind::Base32Error => "Invalid base32hex",
+            ZoneErrorKind::Base64Error => "Invalid base64",
+            ZoneErrorKind::TimestampError => "Invalid timestamp",
+            ZoneErrorKind::InvalidNameError(_) => "Invalid name",
+            ZoneErrorKind::InvalidRDataError(_) => "Invalid RDATA",
+        }
+    }
+}
+
+impl fmt::Display for ZoneErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ZoneErrorKind::IoError(x) => write!(f, "I/O error: {:?}", x),
+            ZoneErrorKind::InvalidNameError(x) => write!(f, "Invalid name: {}", x),
+            ZoneErrorKind::InvalidRDataError(x) => write!(f, "Invalid RDATA: {}", x),
+            ref x => write!(f, "{}", x.description()),
+        }
+    }
+}
+
+/// An error encountered while reading a zone file, with the position
+/// of the offending text. Lines and columns are counted from 1; errors
+/// that are not tied to a position, such as failing to open the
+/// top-level file, have a line of 0.
+#[derive(PartialEq,Clone,Debug)]
+pub struct ZoneError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub kind: ZoneErrorKind,
+}
+
+impl ZoneError {
+    pub fn new(file: &str, line: usize, column: usize, kind: ZoneErrorKind) -> ZoneError {
+        ZoneError {
+            file: file.to_string(),
+            line,
+            column,
+            kind,
+        }
+    }
+}
+
+impl error::Error for ZoneError {
+    fn description(&self) -> &str {
+        self.kind.description()
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match self.kind {
+            ZoneErrorKind::InvalidNameError(ref err) => Some(err as &dyn error::Error),
+            ZoneErrorKind::InvalidRDataError(ref err) => Some(err as &dyn error::Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ZoneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.file, self.kind)
+        } else {
+            write!(f, "{}:{}:{}: {}", self.file, self.line, self.column, self.kind)
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use super::errors::{ZoneError,ZoneErrorKind};
+use super::super::encoding::decode_escape;
+
+/// A single field of a zone file entry.
+///
+/// Escape sequences are left in `text` as written so that callers can
+/// tell an escaped `.` in a name from a label separator.
+#[derive(PartialEq,Debug,Clone)]
+pub struct Token {
+    pub text: Vec<u8>,
+    pub quoted: bool,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Token {
+    /// Whether this token is the unquoted text `s`, ignoring case.
+    pub fn is(&self, s: &str) -> bool {
+        !self.quoted && self.text.eq_ignore_ascii_case(s.as_bytes())
+    }
+}
+
+/// A logical line of a zone file: a directive or a resource record,
+/// possibly continued over several physical lines with parentheses.
+#[derive(PartialEq,Debug,Clone)]
+pub struct Entry {
+    /// Whether the entry started with whitespace, meaning the owner
+    /// name is omitted.
+    pub blank_owner: bool,
+    pub tokens: Vec<Token>,
+    /// Position just past the last token, for reporting missing fields.
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+/// Splits zone file text into entries as per
+/// [RFC1035§5.1](https://tools.ietf.org/html/rfc1035#section-5.1).
+pub struct Lexer<'a> {
+    file: &'a str,
+    buf: &'a [u8],
+    pos: usize,
+    line: usize,
+    line_start: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(file: &'a str, buf: &'a [u8]) -> Lexer<'a> {
+        Lexer {
+            file,
+            buf,
+            pos: 0,
+            line: 1,
+            line_start: 0,
+        }
+    }
+
+    fn column(&self) -> usize {
+        self.pos - self.line_start + 1
+    }
+
+    fn error(&self, line: usize, column: usize, kind: ZoneErrorKind) -> ZoneError {
+        ZoneError::new(self.file, line, column, kind)
+    }
+
+    fn newline(&mut self) {
+        self.pos += 1;
+        self.line += 1;
+        self.line_start = self.pos;
+    }
+
+    /// Read the next non-empty entry, or `None` at the end of input.
+    pub fn next_entry(&mut self) -> Result<Option<Entry>, ZoneError> {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut blank_owner = false;
+        // Position of the outermost open parenthesis, if any
+        let mut open: Option<(usize, usize)> = None;
+        let mut depth = 0usize;
+
+        loop {
+            if self.pos >= self.buf.len() {
+                if let Some((line, column)) = open {
+                    return Err(self.error(line, column, ZoneErrorKind::UnbalancedParenthesisError));
+                }
+                break;
+            }
+            match self.buf[self.pos] {
+                b'\n' => {
+                    if depth == 0 && !tokens.is_empty() {
+                        break;
+                    }
+                    self.newline();
+                },
+                b' ' | b'\t' | b'\r' => self.pos += 1,
+                b';' => {
+                    // Comments run to the end of the line
+                    while self.pos < self.buf.len() && self.buf[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                },
+                b'(' => {
+                    if depth == 0 {
+                        open = Some((self.line, self.column()));
+                    }
+                    depth += 1;
+                    self.pos += 1;
+                },
+                b')' => {
+                    if depth == 0 {
+                        return Err(self.error(self.line, self.column(), ZoneErrorKind::UnbalancedParenthesisError));
+                    }
+                    depth -= 1;
+                    if depth == 0 {
+                        open = None;
+                    }
+                    self.pos += 1;
+                },
+                c => {
+                    if tokens.is_empty() {
+                        let first = self.buf[self.line_start];
+                        blank_owner = first == b' ' || first == b'\t';
+                    }
+                    let token = if c == b'"' { self.quoted()? } else { self.bare() };
+                    tokens.push(token);
+                },
+            }
+        }
+
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Entry {
+            blank_owner,
+            tokens,
+            end_line: self.line,
+            end_column: self.column(),
+        }))
+    }
+
+    /// Read an unquoted token, which ends at whitespace or any
+    /// character with special meaning unless it is escaped.
+    fn bare(&mut self) -> Token {
+        let line = self.line;
+        let column = self.column();
+        let start = self.pos;
+        while self.pos < self.buf.len() {
+            match self.buf[self.pos] {
+                b' ' | b'\t' | b'\r' | b'\n' | b';' | b'(' | b')' | b'"' => break,
+                b'\\' => self.pos = (self.pos + 2).min(self.buf.len()),
+                _ => self.pos += 1,
+            }
+        }
+        Token {
+            text: self.buf[start..self.pos].to_vec(),
+            quoted: false,
+            line,
+            column,
+        }
+    }
+
+    /// Read a quoted token, which may contain whitespace and special
+    /// characters but not an unescaped newline.
+    fn quoted(&mut self) -> Result<Token, ZoneError> {
+        let line = self.line;
+        let column = self.column();
+        self.pos += 1;
+        let start = self.pos;
+        loop {
+            if self.pos >= self.buf.len() || self.buf[self.pos] == b'\n' {
+                return Err(self.error(line, column, ZoneErrorKind::UnterminatedStringError));
+            }
+            match self.buf[self.pos] {
+                b'"' => break,
+                b'\\' => {
+                    if self.pos + 1 < self.buf.len() && self.buf[self.pos + 1] == b'\n' {
+                        return Err(self.error(line, column, ZoneErrorKind::UnterminatedStringError));
+                    }
+                    self.pos += 2;
+                },
+                _ => self.pos += 1,
+            }
+        }
+        let text = self.buf[start..self.pos].to_vec();
+        self.pos += 1;
+        Ok(Token {
+            text,
+            quoted: true,
+            line,
+            column,
+        })
+    }
+}
+
+/// Decode all `\X` and `\DDD` escapes in `text`.
+pub fn unescape(text: &[u8]) -> Result<Vec<u8>, ZoneErrorKind> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut i = 0;
+    while i < text.len() {
+        if text[i] == b'\\' {
+            let (c, len) = decode_escape(text, i).ok_or(ZoneErrorKind::EscapeError)?;
+            out.push(c);
+            i += len;
+        } else {
+            out.push(text[i]);
+            i += 1;
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,14 @@
+pub use super::iana::types::Type;
+pub use super::iana::classes::Class;
+pub use super::parser::{Name,Label,RData,ResourceRecord,OwnedName,OwnedRData,OwnedResourceRecord};
+
+pub use super::zone::errors::{ZoneError,ZoneErrorKind};
+pub use super::zone::reader::{ZoneReader,MAX_INCLUDE_DEPTH,read_zone_file,read_zone_str};
+pub use super::zone::writer::{ZoneWriter,write_zone_string};
+
+mod errors;
+mod lexer;
+mod reader;
+mod writer;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,604 @@
+use super::{Type,Class,Name,RData,ResourceRecord};
+use super::{OwnedName,OwnedRData,OwnedResourceRecord};
+use super::errors::{ZoneError,ZoneErrorKind};
+use super::lexer::{Lexer,Entry,Token,unescape};
+use super::super::encoding::{decode_base32hex,decode_base64,decode_hex,parse_timestamp};
+use super::super::parser::{ReadError,read_dns_rdata};
+use super::super::writer::{WriteError,write_dns_rdata};
+
+use std::borrow::Cow;
+use std::fs;
+use std::net::{Ipv4Addr,Ipv6Addr};
+use std::path::Path;
+use std::str;
+
+/// Maximum nesting depth of `$INCLUDE` directives.
+pub const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Reads resource records from RFC 1035 master files.
+///
+/// The format is described in
+/// [RFC1035§5](https://tools.ietf.org/html/rfc1035#section-5), with the
+/// `$TTL` directive of
+/// [RFC2308§4](https://tools.ietf.org/html/rfc2308#section-4) and the
+/// generic `\#` RDATA syntax of
+/// [RFC3597§5](https://tools.ietf.org/html/rfc3597#section-5).
+///
+/// An omitted owner, TTL or class is inherited from the previous
+/// record. A record without a TTL uses the `$TTL` default if one is
+/// set, and a record without a class defaults to `IN` if no earlier
+/// record gave one.
+pub struct ZoneReader {
+    origin: Option<OwnedName>,
+    default_ttl: Option<u32>,
+    last_owner: Option<OwnedName>,
+    last_ttl: Option<u32>,
+    last_class: Option<Class>,
+    depth: usize,
+    records: Vec<OwnedResourceRecord>,
+}
+
+impl ZoneReader {
+    pub fn new() -> ZoneReader {
+        ZoneReader {
+            origin: None,
+            default_ttl: None,
+            last_owner: None,
+            last_ttl: None,
+            last_class: None,
+            depth: 0,
+            records: Vec::new(),
+        }
+    }
+
+    /// Set the origin that relative names are resolved against until a
+    /// `$ORIGIN` directive changes it.
+    pub fn set_origin(&mut self, origin: OwnedName) {
+        self.origin = Some(origin);
+    }
+
+    /// Set the TTL of records without one, as if by a `$TTL` directive.
+    pub fn set_default_ttl(&mut self, ttl: u32) {
+        self.default_ttl = Some(ttl);
+    }
+
+    /// Consume the reader, returning the records read so far.
+    pub fn into_records(self) -> Vec<OwnedResourceRecord> {
+        self.records
+    }
+
+    /// Read a zone file. `$INCLUDE` paths are resolved relative to the
+    /// directory containing the file.
+    pub fn read_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ZoneError> {
+        let path = path.as_ref();
+        let file = path.display().to_string();
+        let buf = fs::read(path).map_err(|e| ZoneError::new(&file, 0, 0, ZoneErrorKind::IoError(e.kind())))?;
+        self.read_buf(&file, path.parent().unwrap_or_else(|| Path::new("")), &buf)
+    }
+
+    /// Read zone file text. `$INCLUDE` paths are resolved relative to
+    /// the current directory.
+    pub fn read_str(&mut self, s: &str) -> Result<(), ZoneError> {
+        self.read_buf("<string>", Path::new(""), s.as_bytes())
+    }
+
+    fn read_buf(&mut self, file: &str, dir: &Path, buf: &[u8]) -> Result<(), ZoneError> {
+        let mut lexer = Lexer::new(file, buf);
+        while let Some(entry) = lexer.next_entry()? {
+            let mut fields = Fields { file, entry: &entry, pos: 0 };
+            if !entry.blank_owner && entry.tokens[0].text.starts_with(b"$") {
+                self.read_directive(dir, &mut fields)?;
+            } else {
+                self.read_record(&mut fields)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_directive(&mut self, dir: &Path, fields: &mut Fields) -> Result<(), ZoneError> {
+        let directive = fields.next()?;
+        if directive.is("$ORIGIN") {
+            let origin = fields.name(self.origin.as_ref())?;
+            fields.finish()?;
+            self.origin = Some(origin);
+        } else if directive.is("$TTL") {
+            let ttl = fields.ttl()?;
+            fields.finish()?;
+            self.default_ttl = Some(ttl);
+        } else if directive.is("$INCLUDE") {
+            let path_token = fields.next()?;
+            let path = fields.string_token(path_token)?;
+            let origin = match fields.peek() {
+                Some(_) => Some(fields.name(self.origin.as_ref())?),
+                None => None,
+            };
+            fields.finish()?;
+            if self.depth >= MAX_INCLUDE_DEPTH {
+                return Err(fields.error(directive, ZoneErrorKind::IncludeDepthError));
+            }
+
+            let path = dir.join(String::from_utf8_lossy(&path).as_ref());
+            let file = path.display().to_string();
+            let buf = fs::read(&path).map_err(|e| fields.error(path_token, ZoneErrorKind::IoError(e.kind())))?;
+
+            // The included file may change the origin, but that must
+            // not affect the rest of this file
+            let saved = self.origin.clone();
+            if origin.is_some() {
+                self.origin = origin;
+            }
+            self.depth += 1;
+            let result = self.read_buf(&file, path.parent().unwrap_or_else(|| Path::new("")), &buf);
+            self.depth -= 1;
+            self.origin = saved;
+            result?;
+        } else {
+            return Err(fields.error(directive, ZoneErrorKind::UnknownDirectiveError));
+        }
+        Ok(())
+    }
+
+    fn read_record(&mut self, fields: &mut Fields) -> Result<(), ZoneError> {
+        let owner = if fields.entry.blank_owner {
+            match self.last_owner {
+                Some(ref owner) => owner.clone(),
+                None => return Err(fields.error(&fields.entry.tokens[0], ZoneErrorKind::NoOwnerError)),
+            }
+        } else {
+            fields.name(self.origin.as_ref())?
+        };
+
+        // The TTL and class may appear in either order before the type
+        let mut ttl = None;
+        let mut class = None;
+        let rtype;
+        loop {
+            let token = fields.next()?;
+            let text = mnemonic(token);
+            if ttl.is_none() && !token.quoted && token.text.first().is_some_and(|c| c.is_ascii_digit()) {
+                ttl = Some(parse_ttl(&token.text).map_err(|k| fields.error(token, k))?);
+            } else if let (None, Some(c)) = (class, text.and_then(Class::from_mnemonic)) {
+                class = Some(c);
+            } else if let Some(t) = text.and_then(Type::from_mnemonic) {
+                rtype = (t, token);
+                break;
+            } else if class.is_none() && text.is_some_and(|s| s.to_ascii_uppercase().starts_with("CLASS")) {
+                return Err(fields.error(token, ZoneErrorKind::UnknownClassError));
+            } else {
+                return Err(fields.error(token, ZoneErrorKind::UnknownTypeError));
+            }
+        }
+
+        let ttl = match ttl.or(self.default_ttl).or(self.last_ttl) {
+            Some(ttl) => ttl,
+            None => return Err(fields.error(rtype.1, ZoneErrorKind::NoTtlError)),
+        };
+        let class = class.or(self.last_class).unwrap_or(Class::IN);
+        let rdata = read_rdata(rtype.0, rtype.1, fields, self.origin.as_ref())?;
+
+        self.records.push(ResourceRecord::new(owner.clone(), rtype.0, class, ttl as i32, rdata));
+        self.last_owner = Some(owner);
+        self.last_ttl = Some(ttl);
+        self.last_class = Some(class);
+        Ok(())
+    }
+}
+
+impl Default for ZoneReader {
+    fn default() -> ZoneReader {
+        ZoneReader::new()
+    }
+}
+
+/// Read all records from a zone file, resolving relative names against
+/// `origin` until the file sets its own.
+pub fn read_zone_file<P: AsRef<Path>>(path: P, origin: Option<OwnedName>) -> Result<Vec<OwnedResourceRecord>, ZoneError> {
+    let mut reader = ZoneReader::new();
+    if let Some(origin) = origin {
+        reader.set_origin(origin);
+    }
+    reader.read_file(path)?;
+    Ok(reader.into_records())
+}
+
+/// Read all records from zone file text, resolving relative names
+/// against `origin` until the text sets its own.
+pub fn read_zone_str(s: &str, origin: Option<OwnedName>) -> Result<Vec<OwnedResourceRecord>, ZoneError> {
+    let mut reader = ZoneReader::new();
+    if let Some(origin) = origin {
+        reader.set_origin(origin);
+    }
+    reader.read_str(s)?;
+    Ok(reader.into_records())
+}
+
+/// The text of an unquoted token, if it could be a type or class.
+fn mnemonic(token: &Token) -> Option<&str> {
+    if token.quoted { None } else { str::from_utf8(&token.text).ok() }
+}
+
+/// Parse a TTL, either in seconds or as a sequence of numbers with
+/// `w`, `d`, `h`, `m` or `s` units such as `1h30m`. TTLs are limited to
+/// 2^31 - 1 as per [RFC2181§8](https://tools.ietf.org/html/rfc2181#section-8).
+fn parse_ttl(text: &[u8]) -> Result<u32, ZoneErrorKind> {
+    let mut total = 0u64;
+    let mut value: Option<u64> = None;
+    for c in text.iter() {
+        let multiplier = match c.to_ascii_lowercase() {
+            b'0'..=b'9' => {
+                let v = value.unwrap_or(0) * 10 + (c - b'0') as u64;
+                if v > 0x7FFFFFFF {
+                    return Err(ZoneErrorKind::TtlError);
+                }
+                value = Some(v);
+                continue;
+            },
+            b'w' => 604800,
+            b'd' => 86400,
+            b'h' => 3600,
+            b'm' => 60,
+            b's' => 1,
+            _ => return Err(ZoneErrorKind::TtlError),
+        };
+        let v = value.take().ok_or(ZoneErrorKind::TtlError)?;
+        total = add_ttl(total, v.checked_mul(multiplier))?;
+    }
+    total = add_ttl(total, Some(value.unwrap_or(0)))?;
+    if text.is_empty() {
+        return Err(ZoneErrorKind::TtlError);
+    }
+    Ok(total as u32)
+}
+
+/// Add a TTL component to a running total, failing as soon as the
+/// total exceeds 2^31 - 1 so that long runs of units cannot overflow.
+fn add_ttl(total: u64, component: Option<u64>) -> Result<u64, ZoneErrorKind> {
+    component.and_then(|c| total.checked_add(c)).filter(|&t| t <= 0x7FFFFFFF).ok_or(ZoneErrorKind::TtlError)
+}
+
+/// Parse a possibly relative domain name. `@` is the origin, and names
+/// not ending in an unescaped `.` are relative to it.
+fn parse_name(text: &[u8], origin: Option<&OwnedName>) -> Result<OwnedName, ZoneErrorKind> {
+    if text == b"@" {
+        return origin.cloned().ok_or(ZoneErrorKind::NoOriginError);
+    }
+    let (name, absolute) = Name::from_presentation(text).map_err(|e| match e {
+        ReadError::InvalidEscapeError(_) => ZoneErrorKind::EscapeError,
+        e => ZoneErrorKind::InvalidNameError(e),
+    })?;
+    if absolute {
+        return Ok(name.into_owned());
+    }
+    match origin {
+        Some(origin) => {
+            let mut name = name.into_owned();
+            name.labels.extend(origin.labels.iter().cloned());
+            let len = name.labels.iter().fold(1, |acc, l| acc + 1 + l.as_bytes().len());
+            if len > 255 {
+                return Err(ZoneErrorKind::InvalidNameError(ReadError::NameTooLongError(len)));
+            }
+            Ok(name)
+        },
+        None => Err(ZoneErrorKind::NoOriginError),
+    }
+}
+
+/// The fields of an entry, consumed from left to right.
+struct Fields<'e> {
+    file: &'e str,
+    entry: &'e Entry,
+    pos: usize,
+}
+
+impl<'e> Fields<'e> {
+    fn error(&self, token: &Token, kind: ZoneErrorKind) -> ZoneError {
+        ZoneError::new(self.file, token.line, token.column, kind)
+    }
+
+    fn peek(&self) -> Option<&'e Token> {
+        self.entry.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<&'e Token, ZoneError> {
+        match self.entry.tokens.get(self.pos) {
+            Some(token) => {
+                self.pos += 1;
+                Ok(token)
+            },
+            None => Err(ZoneError::new(self.file, self.entry.end_line, self.entry.end_column, ZoneErrorKind::MissingFieldError)),
+        }
+    }
+
+    /// Fail if any fields are left over.
+    fn finish(&self) -> Result<(), ZoneError> {
+        match self.peek() {
+            Some(token) => Err(self.error(token, ZoneErrorKind::ExtraFieldError)),
+            None => Ok(()),
+        }
+    }
+
+    fn name(&mut self, origin: Option<&OwnedName>) -> Result<OwnedName, ZoneError> {
+        let token = self.next()?;
+        parse_name(&token.text, origin).map_err(|k| self.error(token, k))
+    }
+
+    fn ttl(&mut self) -> Result<u32, ZoneError> {
+        let token = self.next()?;
+        parse_ttl(&token.text).map_err(|k| self.error(token, k))
+    }
+
+    fn number<T: str::FromStr>(&mut self) -> Result<T, ZoneError> {
+        let token = self.next()?;
+        str::from_utf8(&token.text).ok()
+            .and_then(|s| s.parse::<T>().ok())
+            .ok_or_else(|| self.error(token, ZoneErrorKind::NumberError))
+    }
+
+    fn address<T: str::FromStr>(&mut self) -> Result<T, ZoneError> {
+        let token = self.next()?;
+        str::from_utf8(&token.text).ok()
+            .and_then(|s| s.parse::<T>().ok())
+            .ok_or_else(|| self.error(token, ZoneErrorKind::AddressError))
+    }
+
+    fn string_token(&self, token: &Token) -> Result<Vec<u8>, ZoneError> {
+        unescape(&token.text).map_err(|k| self.error(token, k))
+    }
+
+    /// Read an RRSIG timestamp, either as `YYYYMMDDHHmmSS` or as a
+    /// number of seconds, as per
+    /// [RFC4034§3.2](https://tools.ietf.org/html/rfc4034#section-3.2).
+    fn timestamp(&mut self) -> Result<u32, ZoneError> {
+        let token = self.next()?;
+        let secs = if token.text.len() == 14 {
+            parse_timestamp(&token.text)
+        } else {
+            str::from_utf8(&token.text).ok().and_then(|s| s.parse::<u32>().ok())
+        };
+        secs.ok_or_else(|| self.error(token, ZoneErrorKind::TimestampError))
+    }
+
+    fn rtype(&mut self) -> Result<Type, ZoneError> {
+        let token = self.next()?;
+        mnemonic(token).and_then(Type::from_mnemonic).ok_or_else(|| self.error(token, ZoneErrorKind::UnknownTypeError))
+    }
+
+    /// Read base64 that may be split over all of the remaining fields.
+    fn base64(&mut self) -> Result<Cow<'static, [u8]>, ZoneError> {
+        let first = self.next()?;
+        let mut text = first.text.clone();
+        while let Some(token) = self.peek() {
+            text.extend_from_slice(&token.text);
+            self.pos += 1;
+        }
+        decode_base64(&text).map(Cow::Owned).ok_or_else(|| self.error(first, ZoneErrorKind::Base64Error))
+    }
+
+    /// Read hex that may be split over all of the remaining fields.
+    fn hex(&mut self) -> Result<Cow<'static, [u8]>, ZoneError> {
+        let first = self.next()?;
+        let mut text = first.text.clone();
+        while let Some(token) = self.peek() {
+            text.extend_from_slice(&token.text);
+            self.pos += 1;
+        }
+        decode_hex(&text).map(Cow::Owned).ok_or_else(|| self.error(first, ZoneErrorKind::HexError))
+    }
+
+    /// Read an NSEC3 salt: hex, or `-` for an empty salt.
+    fn salt(&mut self) -> Result<Cow<'static, [u8]>, ZoneError> {
+        let token = self.next()?;
+        if token.is("-") {
+            return Ok(Cow::Owned(Vec::new()));
+        }
+        match decode_hex(&token.text) {
+            Some(ref s) if s.is_empty() => Err(self.error(token, ZoneErrorKind::HexError)),
+            Some(s) => Ok(Cow::Owned(s)),
+            None => Err(self.error(token, ZoneErrorKind::HexError)),
+        }
+    }
+
+    fn base32hex(&mut self) -> Result<Cow<'static, [u8]>, ZoneError> {
+        let token = self.next()?;
+        decode_base32hex(&token.text).map(Cow::Owned).ok_or_else(|| self.error(token, ZoneErrorKind::Base32Error))
+    }
+
+    /// Read the types of an NSEC or NSEC3 type bitmap from all of the
+    /// remaining fields.
+    fn types(&mut self) -> Result<Vec<Type>, ZoneError> {
+        let mut types = Vec::new();
+        while self.peek().is_some() {
+            types.push(self.rtype()?);
+        }
+        Ok(types)
+    }
+
+    /// Read a `<character-string>`, which may be quoted.
+    fn string(&mut self) -> Result<Cow<'static, [u8]>, ZoneError> {
+        let token = self.next()?;
+        let s = self.string_token(token)?;
+        if s.len() > 255 {
+            let err = WriteError::CharacterStringTooLongError(s.len());
+            return Err(self.error(token, ZoneErrorKind::InvalidRDataError(err)));
+        }
+        Ok(Cow::Owned(s))
+    }
+}
+
+/// Read the RDATA fields of a record of type `rtype` and encode them in
+/// uncompressed wire format.
+fn read_rdata(rtype: Type, type_token: &Token, fields: &mut Fields, origin: Option<&OwnedName>) -> Result<Vec<u8>, ZoneError> {
+    if fields.peek().is_some_and(|t| t.is("\\#")) {
+        return read_generic_rdata(rtype, fields);
+    }
+
+    let rdata: OwnedRData = match rtype {
+        Type::A => RData::A(fields.address::<Ipv4Addr>()?),
+        Type::AAAA => RData::AAAA(fields.address::<Ipv6Addr>()?),
+        Type::NS => RData::NS(fields.name(origin)?),
+        Type::CNAME => RData::CNAME(fields.name(origin)?),
+        Type::MB => RData::MB(fields.name(origin)?),
+        Type::MG => RData::MG(fields.name(origin)?),
+        Type::MR => RData::MR(fields.name(origin)?),
+        Type::PTR => RData::PTR(fields.name(origin)?),
+        Type::DNAME => RData::DNAME(fields.name(origin)?),
+        Type::SOA => RData::SOA {
+            mname: fields.name(origin)?,
+            rname: fields.name(origin)?,
+            serial: fields.number()?,
+            refresh: fields.ttl()?,
+            retry: fields.ttl()?,
+            expire: fields.ttl()?,
+            minimum: fields.ttl()?,
+        },
+        Type::HINFO => RData::HINFO {
+            cpu: fields.string()?,
+            os: fields.string()?,
+        },
+        Type::MINFO => RData::MINFO {
+            rmailbx: fields.name(origin)?,
+            emailbx: fields.name(origin)?,
+        },
+        Type::MX => RData::MX {
+            preference: fields.number()?,
+            exchange: fields.name(origin)?,
+        },
+        Type::TXT | Type::SPF => {
+            let mut strings = vec![fields.string()?];
+            while fields.peek().is_some() {
+                strings.push(fields.string()?);
+            }
+            if rtype == Type::TXT { RData::TXT(strings) } else { RData::SPF(strings) }
+        },
+        Type::RP => RData::RP {
+            mbox: fields.name(origin)?,
+            txt: fields.name(origin)?,
+        },
+        Type::AFSDB => RData::AFSDB {
+            subtype: fields.number()?,
+            hostname: fields.name(origin)?,
+        },
+        Type::SRV => RData::SRV {
+            priority: fields.number()?,
+            weight: fields.number()?,
+            port: fields.number()?,
+            target: fields.name(origin)?,
+        },
+        Type::NAPTR => RData::NAPTR {
+            order: fields.number()?,
+            preference: fields.number()?,
+            flags: fields.string()?,
+            services: fields.string()?,
+            regexp: fields.string()?,
+            replacement: fields.name(origin)?,
+        },
+        Type::RRSIG => RData::RRSIG {
+            type_covered: fields.rtype()?,
+            algorithm: fields.number()?,
+            labels: fields.number()?,
+            original_ttl: fields.number()?,
+            expiration: fields.timestamp()?,
+            inception: fields.timestamp()?,
+            key_tag: fields.number()?,
+            signer_name: fields.name(origin)?,
+            signature: fields.base64()?,
+        },
+        Type::SIG => RData::SIG {
+            type_covered: fields.rtype()?,
+            algorithm: fields.number()?,
+            labels: fields.number()?,
+            original_ttl: fields.number()?,
+            expiration: fields.timestamp()?,
+            inception: fields.timestamp()?,
+            key_tag: fields.number()?,
+            signer_name: fields.name(origin)?,
+            signature: fields.base64()?,
+        },
+        Type::KEY => RData::KEY {
+            flags: fields.number()?,
+            protocol: fields.number()?,
+            algorithm: fields.number()?,
+            public_key: fields.base64()?,
+        },
+        Type::DNSKEY => RData::DNSKEY {
+            flags: fields.number()?,
+            protocol: fields.number()?,
+            algorithm: fields.number()?,
+            public_key: fields.base64()?,
+        },
+        Type::CDNSKEY => RData::CDNSKEY {
+            flags: fields.number()?,
+            protocol: fields.number()?,
+            algorithm: fields.number()?,
+            public_key: fields.base64()?,
+        },
+        Type::DS => RData::DS {
+            key_tag: fields.number()?,
+            algorithm: fields.number()?,
+            digest_type: fields.number()?,
+            digest: fields.hex()?,
+        },
+        Type::CDS => RData::CDS {
+            key_tag: fields.number()?,
+            algorithm: fields.number()?,
+            digest_type: fields.number()?,
+            digest: fields.hex()?,
+        },
+        Type::TA => RData::TA {
+            key_tag: fields.number()?,
+            algorithm: fields.number()?,
+            digest_type: fields.number()?,
+            digest: fields.hex()?,
+        },
+        Type::DLV => RData::DLV {
+            key_tag: fields.number()?,
+            algorithm: fields.number()?,
+            digest_type: fields.number()?,
+            digest: fields.hex()?,
+        },
+        Type::NSEC => RData::NSEC {
+            next_domain: fields.name(origin)?,
+            types: fields.types()?,
+        },
+        Type::NSEC3 => RData::NSEC3 {
+            hash_algorithm: fields.number()?,
+            flags: fields.number()?,
+            iterations: fields.number()?,
+            salt: fields.salt()?,
+            next_hashed_owner: fields.base32hex()?,
+            types: fields.types()?,
+        },
+        Type::NSEC3PARAM => RData::NSEC3PARAM {
+            hash_algorithm: fields.number()?,
+            flags: fields.number()?,
+            iterations: fields.number()?,
+            salt: fields.salt()?,
+        },
+        _ => return Err(fields.error(type_token, ZoneErrorKind::UnsupportedTypeError)),
+    };
+    fields.finish()?;
+    write_dns_rdata(&rdata).map_err(|e| fields.error(type_token, ZoneErrorKind::InvalidRDataError(e)))
+}
+
+/// Read RDATA in the generic `\# <length> <hex>...` form. The octets are
+/// decoded as `rtype` to check that they are valid for it.
+fn read_generic_rdata(rtype: Type, fields: &mut Fields) -> Result<Vec<u8>, ZoneError> {
+    let marker = fields.next()?;
+    let len: u16 = fields.number()?;
+    let mut data = Vec::with_capacity(len as usize);
+    while let Some(token) = fields.peek() {
+        let octets = decode_hex(&token.text).ok_or_else(|| fields.error(token, ZoneErrorKind::HexError))?;
+        data.extend_from_slice(&octets);
+        fields.pos += 1;
+    }
+    if data.len() != len as usize {
+        return Err(fields.error(marker, ZoneErrorKind::HexError));
+    }
+
+    // Compression pointers are meaningless here, so re-encode the
+    // decoded RDATA to make sure none remain
+    let mut i = 0;
+    let rdata = read_dns_rdata(&rtype, &data, &mut i, len)
+        .map_err(|e| fields.error(marker, ZoneErrorKind::InvalidRDataError(WriteError::from(e))))?;
+    write_dns_rdata(&rdata).map_err(|e| fields.error(marker, ZoneErrorKind::InvalidRDataError(e)))
+}
@@ -0,0 +1,403 @@
+use super::{Name,RData,ResourceRecord,Type,Class,OwnedResourceRecord};
+use super::{ZoneError,ZoneErrorKind,ZoneReader,ZoneWriter,read_zone_file,read_zone_str,write_zone_string};
+use super::super::parser::ReadError;
+
+use std::borrow::Cow;
+use std::io;
+use std::net::{Ipv4Addr,Ipv6Addr};
+
+fn origin() -> Option<Name<'static>> {
+    Some(Name::from_str("example.com.").ok().unwrap().into_owned())
+}
+
+fn name(s: &str) -> Name<'_> {
+    Name::from_str(s).ok().unwrap()
+}
+
+fn check(rr: &OwnedResourceRecord, owner: &str, rtype: Type, class: Class, ttl: i32, rdata: RData) {
+    assert_eq!(rr.rname, name(owner));
+    assert_eq!(rr.rtype, rtype);
+    assert_eq!(rr.rclass, class);
+    assert_eq!(rr.rttl, ttl);
+    assert_eq!(rr.rdata().ok().unwrap(), rdata);
+}
+
+fn err(s: &str) -> ZoneError {
+    read_zone_str(s, origin()).err().unwrap()
+}
+
+#[test]
+fn test_read_zone_file() {
+    let records = read_zone_file("tests/zones/example.com.zone", None).ok().unwrap();
+    assert_eq!(records.len(), 7);
+    check(&records[0], "example.com.", Type::SOA, Class::IN, 3600, RData::SOA {
+        mname: name("ns1.example.com."),
+        rname: name("hostmaster.example.com."),
+        serial: 2024010101,
+        refresh: 10800,
+        retry: 900,
+        expire: 604800,
+        minimum: 86400,
+    });
+    check(&records[1], "example.com.", Type::NS, Class::IN, 3600, RData::NS(name("ns1.example.com.")));
+    check(&records[2], "example.com.", Type::NS, Class::IN, 3600, RData::NS(name("ns2.example.net.")));
+    check(&records[3], "ns1.example.com.", Type::A, Class::IN, 3600, RData::A(Ipv4Addr::new(192, 0, 2, 1)));
+
+    // Records from the included file use its origin, and $TTL takes
+    // precedence over the previous record's TTL
+    check(&records[4], "host1.sub.example.com.", Type::A, Class::IN, 300, RData::A(Ipv4Addr::new(192, 0, 2, 10)));
+    check(&records[5], "host2.sub.example.com.", Type::A, Class::IN, 3600, RData::A(Ipv4Addr::new(192, 0, 2, 11)));
+
+    // The origin is restored after $INCLUDE
+    check(&records[6], "www.example.com.", Type::A, Class::IN, 3600, RData::A(Ipv4Addr::new(192, 0, 2, 80)));
+}
+
+#[test]
+fn test_read_zone_inheritance() {
+    let records = read_zone_str("
+a 60 CH TXT x
+  TXT y
+b IN 120 TXT z
+  300 TXT w
+", origin()).ok().unwrap();
+    assert_eq!(records.len(), 4);
+    check(&records[0], "a.example.com.", Type::TXT, Class::CH, 60, RData::TXT(vec![Cow::Borrowed(b"x")]));
+    check(&records[1], "a.example.com.", Type::TXT, Class::CH, 60, RData::TXT(vec![Cow::Borrowed(b"y")]));
+    check(&records[2], "b.example.com.", Type::TXT, Class::IN, 120, RData::TXT(vec![Cow::Borrowed(b"z")]));
+    check(&records[3], "b.example.com.", Type::TXT, Class::IN, 300, RData::TXT(vec![Cow::Borrowed(b"w")]));
+}
+
+#[test]
+fn test_read_zone_escapes() {
+    let records = read_zone_str(r#"
+$TTL 60
+a\.b\065    TXT "quoted \"string\"; with (specials)" \032unquoted\\ \255
+@           MX  10 mail\.box
+"#, origin()).ok().unwrap();
+    assert_eq!(records.len(), 2);
+
+    // An escaped dot is part of a label
+    assert_eq!(records[0].rname.labels.len(), 3);
+    assert_eq!(records[0].rname.labels[0].as_bytes(), b"a.bA");
+    assert_eq!(records[0].rdata().ok().unwrap(), RData::TXT(vec![
+        Cow::Borrowed(&b"quoted \"string\"; with (specials)"[..]),
+        Cow::Borrowed(&b" unquoted\\"[..]),
+        Cow::Borrowed(&b"\xFF"[..]),
+    ]));
+    match records[1].rdata().ok().unwrap() {
+        RData::MX { preference: 10, exchange } => {
+            assert_eq!(exchange.labels.len(), 3);
+            assert_eq!(exchange.labels[0].as_bytes(), b"mail.box");
+        },
+        x => panic!("unexpected RDATA {:?}", x),
+    }
+}
+
+#[test]
+fn test_read_zone_rdata() {
+    let records = read_zone_str("
+$TTL 60
+a    AAAA  2001:db8::1
+a    SRV   0 5 5060 sip
+a    NAPTR 100 10 \"S\" \"SIP+D2U\" \"\" _sip._udp
+a    HINFO \"PC\" Linux
+a    TYPE65280 \\# 4 dead BEEF
+a    A     \\# 4 C0000201
+a    TXT   \\# 0
+", origin()).ok().unwrap();
+    assert_eq!(records.len(), 7);
+    assert_eq!(records[0].rdata().ok().unwrap(), RData::AAAA("2001:db8::1".parse::<Ipv6Addr>().unwrap()));
+    assert_eq!(records[1].rdata().ok().unwrap(), RData::SRV {
+        priority: 0,
+        weight: 5,
+        port: 5060,
+        target: name("sip.example.com."),
+    });
+    assert_eq!(records[2].rdata().ok().unwrap(), RData::NAPTR {
+        order: 100,
+        preference: 10,
+        flags: Cow::Borrowed(b"S"),
+        services: Cow::Borrowed(b"SIP+D2U"),
+        regexp: Cow::Borrowed(b""),
+        replacement: name("_sip._udp.example.com."),
+    });
+    assert_eq!(records[3].rdata().ok().unwrap(), RData::HINFO { cpu: Cow::Borrowed(b"PC"), os: Cow::Borrowed(b"Linux") });
+    assert_eq!(records[4].rtype, Type::Unknown(65280));
+    assert_eq!(records[4].rdata().ok().unwrap(), RData::Opaque(Cow::Borrowed(&[0xDE, 0xAD, 0xBE, 0xEF])));
+    assert_eq!(records[5].rdata().ok().unwrap(), RData::A(Ipv4Addr::new(192, 0, 2, 1)));
+    assert_eq!(records[6].rdata().ok().unwrap(), RData::TXT(vec![]));
+}
+
+#[test]
+fn test_read_zone_reader() {
+    let mut reader = ZoneReader::new();
+    reader.set_origin(name("example.org.").into_owned());
+    reader.set_default_ttl(42);
+    reader.read_str("a A 192.0.2.1").ok().unwrap();
+    reader.read_str("$ORIGIN example.net.\nb A 192.0.2.2").ok().unwrap();
+    let records = reader.into_records();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].rname, name("a.example.org."));
+    assert_eq!(records[0].rttl, 42);
+    assert_eq!(records[1].rname, name("b.example.net."));
+    assert_eq!(ResourceRecord::new(name("b.example.net."), Type::A, Class::IN, 42, &[192, 0, 2, 2][..]), records[1]);
+}
+
+#[test]
+fn test_read_zone_errors() {
+    let e = err("$TTL 60\na A 192.0.2.1\nb A (\n192.0.2.2\n");
+    assert_eq!((e.line, e.column, e.kind), (3, 5, ZoneErrorKind::UnbalancedParenthesisError));
+    assert_eq!(format!("{}", e), "<string>:3:5: Unbalanced parenthesis");
+
+    let e = err("$TTL 60\na TXT \"open\n");
+    assert_eq!((e.line, e.column, e.kind), (2, 7, ZoneErrorKind::UnterminatedStringError));
+
+    let e = err("$TTL 60\na BOGUS 1");
+    assert_eq!((e.line, e.column, e.kind), (2, 3, ZoneErrorKind::UnknownTypeError));
+
+    let e = err("$TTL 60\na CLASS65536 A 192.0.2.1");
+    assert_eq!((e.line, e.column, e.kind), (2, 3, ZoneErrorKind::UnknownClassError));
+
+    let e = err("a A 192.0.2.1");
+    assert_eq!((e.line, e.column, e.kind), (1, 3, ZoneErrorKind::NoTtlError));
+
+    let e = err("$TTL 60\n  A 192.0.2.1");
+    assert_eq!((e.line, e.column, e.kind), (2, 3, ZoneErrorKind::NoOwnerError));
+
+    let e = err("$TTL 60\na MX 10");
+    assert_eq!((e.line, e.column, e.kind), (2, 8, ZoneErrorKind::MissingFieldError));
+
+    let e = err("$TTL 60\na A 192.0.2.1 192.0.2.2");
+    assert_eq!((e.line, e.column, e.kind), (2, 15, ZoneErrorKind::ExtraFieldError));
+
+    let e = err("$TTL 60\na A 192.0.2");
+    assert_eq!((e.line, e.column, e.kind), (2, 5, ZoneErrorKind::AddressError));
+
+    let e = err("$TTL 60\na MX 65536 b");
+    assert_eq!((e.line, e.column, e.kind), (2, 6, ZoneErrorKind::NumberError));
+
+    let e = err("$TTL 4294967295");
+    assert_eq!((e.line, e.column, e.kind), (1, 6, ZoneErrorKind::TtlError));
+
+    // Long runs of units are rejected rather than overflowing
+    let e = err(&format!("$TTL {}", "2147483647w".repeat(10000)));
+    assert_eq!((e.line, e.column, e.kind), (1, 6, ZoneErrorKind::TtlError));
+    let e = err("$TTL 2147483647s1s");
+    assert_eq!((e.line, e.column, e.kind), (1, 6, ZoneErrorKind::TtlError));
+
+    let e = err("$TTL 60\na\\256 A 192.0.2.1");
+    assert_eq!((e.line, e.column, e.kind), (2, 1, ZoneErrorKind::EscapeError));
+
+    let e = err("$TTL 60\na..b A 192.0.2.1");
+    assert_eq!(e.kind, ZoneErrorKind::InvalidNameError(ReadError::LabelZeroLengthError));
+
+    let e = err("$TTL 60\na A \\# 4 c00002");
+    assert_eq!((e.line, e.column, e.kind), (2, 5, ZoneErrorKind::HexError));
+
+    let e = err("$TTL 60\na TLSA 3 1 1 abcd");
+    assert_eq!((e.line, e.column, e.kind), (2, 3, ZoneErrorKind::UnsupportedTypeError));
+
+    let e = err("$GENERATE 1-2 a A 192.0.2.$");
+    assert_eq!((e.line, e.column, e.kind), (1, 1, ZoneErrorKind::UnknownDirectiveError));
+
+    let e = read_zone_str("$TTL 60\na A 192.0.2.1", None).err().unwrap();
+    assert_eq!((e.line, e.column, e.kind), (2, 1, ZoneErrorKind::NoOriginError));
+}
+
+#[test]
+fn test_read_zone_file_errors() {
+    let e = read_zone_file("tests/zones/missing.zone", None).err().unwrap();
+    assert_eq!((e.line, e.kind), (0, ZoneErrorKind::IoError(io::ErrorKind::NotFound)));
+    assert_eq!(format!("{}", e), "tests/zones/missing.zone: I/O error: NotFound");
+
+    let e = read_zone_file("tests/zones/loop.zone", None).err().unwrap();
+    assert_eq!((e.file.as_str(), e.line, e.column), ("tests/zones/loop.zone", 1, 1));
+    assert_eq!(e.kind, ZoneErrorKind::IncludeDepthError);
+}
+
+#[test]
+fn test_read_zone_dnssec() {
+    let records = read_zone_str("
+$TTL 86400
+@ DNSKEY 256 3 8 ( AwEAAa
+                   ECAwQ= )
+host RRSIG A 8 3 86400 20030322173103 (
+           20030220173103 2642 example.com.
+           AQID )
+", origin()).ok().unwrap();
+    assert_eq!(records[0].rdata().ok().unwrap(), RData::DNSKEY {
+        flags: 256,
+        protocol: 3,
+        algorithm: 8,
+        public_key: Cow::Borrowed(&[3, 1, 0, 1, 0xA1, 2, 3, 4]),
+    });
+    assert_eq!(records[1].rdata().ok().unwrap(), RData::RRSIG {
+        type_covered: Type::A,
+        algorithm: 8,
+        labels: 3,
+        original_ttl: 86400,
+        expiration: 1048354263,
+        inception: 1045762263,
+        key_tag: 2642,
+        signer_name: name("example.com."),
+        signature: Cow::Borrowed(&[1, 2, 3]),
+    });
+
+    let e = err("$TTL 60\na DNSKEY 256 3 8 AwEAA");
+    assert_eq!((e.line, e.column, e.kind), (2, 18, ZoneErrorKind::Base64Error));
+    let e = err("$TTL 60\na RRSIG A 8 3 60 20030230000000 20030220173103 1 b AQID");
+    assert_eq!((e.line, e.column, e.kind), (2, 18, ZoneErrorKind::TimestampError));
+}
+
+#[test]
+fn test_read_zone_dnssec_types() {
+    // Examples from RFC 4034 §4.3 and §5.4 and RFC 5155 Appendix A
+    let records = read_zone_str("
+$TTL 86400
+dskey DNSKEY 256 3 5 ( AQOeiiR0GOMYkDshWoSKz9Xz
+                       fwJr1AYtsmx3TGkJaNXVbfi/
+                       2pHm822aJ5iI9BMzNXxeYCmZ
+                       DRD99WYwYqUSdjMmmAphXdvx
+                       egXd/M5+X7OrzKBaMbCVdFLU
+                       Uh6DhweJBjEVv5f2wwjM9Xzc
+                       nOf+EPbtG9DMBmADjFDc2w/r
+                       ljwvFw== ) ; key id = 60485
+dskey DS 60485 5 1 ( 2BB183AF5F22588179A53B0A
+                     98631FAD1A292118 )
+alfa NSEC host.example.com. ( A MX RRSIG NSEC TYPE1234 )
+0p9mhaveqvm6t7vbl5lop2u3t2rp3tom NSEC3 1 1 12 aabbccdd (
+    2t7b4g4vsa5smi47k61mv5bv1a22bojr MX DNSKEY NS
+    SOA NSEC3PARAM RRSIG )
+@ NSEC3PARAM 1 0 12 -
+@ CDS 0 0 0 00
+@ CDNSKEY 0 3 0 AA==
+@ TA 0 0 0 00
+@ DLV 0 0 0 00
+@ KEY 512 3 15 AA==
+", origin()).ok().unwrap();
+    assert_eq!(records[0].rdata().ok().unwrap().key_tag(), Some(60485));
+    assert_eq!(records[1].rdata().ok().unwrap(), RData::DS {
+        key_tag: 60485,
+        algorithm: 5,
+        digest_type: 1,
+        digest: Cow::Borrowed(&[0x2B, 0xB1, 0x83, 0xAF, 0x5F, 0x22, 0x58, 0x81, 0x79, 0xA5,
+                                0x3B, 0x0A, 0x98, 0x63, 0x1F, 0xAD, 0x1A, 0x29, 0x21, 0x18]),
+    });
+    assert_eq!(&records[2].context[..], &b"\x04host\x07example\x03com\x00\
+        \x00\x06\x40\x01\x00\x00\x00\x03\
+        \x04\x1b\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x20"[..]);
+    assert_eq!(records[2].rdata().ok().unwrap(), RData::NSEC {
+        next_domain: name("host.example.com."),
+        types: vec![Type::A, Type::MX, Type::RRSIG, Type::NSEC, Type::Unknown(1234)],
+    });
+    match records[3].rdata().ok().unwrap() {
+        RData::NSEC3 { hash_algorithm, flags, iterations, salt, next_hashed_owner, types } => {
+            assert_eq!((hash_algorithm, flags, iterations), (1, 1, 12));
+            assert_eq!(&salt[..], &[0xAA, 0xBB, 0xCC, 0xDD]);
+            assert_eq!(next_hashed_owner.len(), 20);
+            assert_eq!(types, vec![Type::NS, Type::SOA, Type::MX, Type::RRSIG, Type::DNSKEY, Type::NSEC3PARAM]);
+        },
+        r => panic!("unexpected RDATA {:?}", r),
+    }
+
+    let text = write_zone_string(&records[1..], false);
+    assert_eq!(text, "\
+dskey.example.com. 86400 IN DS 60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118
+alfa.example.com. 86400 IN NSEC host.example.com. A MX RRSIG NSEC TYPE1234
+0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example.com. 86400 IN NSEC3 1 1 12 AABBCCDD 2t7b4g4vsa5smi47k61mv5bv1a22bojr NS SOA MX RRSIG DNSKEY NSEC3PARAM
+example.com. 86400 IN NSEC3PARAM 1 0 12 -
+example.com. 86400 IN CDS 0 0 0 00
+example.com. 86400 IN CDNSKEY 0 3 0 AA==
+example.com. 86400 IN TA 0 0 0 00
+example.com. 86400 IN DLV 0 0 0 00
+example.com. 86400 IN KEY 512 3 15 AA==
+");
+    assert_eq!(read_zone_str(&text, None).ok().unwrap(), &records[1..]);
+
+    // RRSIG timestamps may also be given as seconds
+    let records = read_zone_str("$TTL 60\n@ RRSIG A 8 2 60 1048354263 20030220173103 1 @ AQID", origin()).ok().unwrap();
+    match records[0].rdata().ok().unwrap() {
+        RData::RRSIG { expiration, inception, .. } => assert_eq!((expiration, inception), (1048354263, 1045762263)),
+        r => panic!("unexpected RDATA {:?}", r),
+    }
+
+    let e = err("$TTL 60\na NSEC3 1 0 0 - abc");
+    assert_eq!((e.line, e.column, e.kind), (2, 17, ZoneErrorKind::Base32Error));
+    let e = err("$TTL 60\na NSEC3PARAM 1 0 0 xyz");
+    assert_eq!((e.line, e.column, e.kind), (2, 20, ZoneErrorKind::HexError));
+    let e = err("$TTL 60\na NSEC b A BOGUS");
+    assert_eq!((e.line, e.column, e.kind), (2, 12, ZoneErrorKind::UnknownTypeError));
+    let e = err("$TTL 60\na DS 1 2 3 ABC");
+    assert_eq!((e.line, e.column, e.kind), (2, 12, ZoneErrorKind::HexError));
+}
+
+#[test]
+fn test_write_zone_presentation() {
+    let records = read_zone_str(r#"
+$TTL 300
+a\.b\$c    TXT   "say \"hi\"" back\\slash \009tab
+@           MX    10 mail
+@           TXT   \# 0
+@           HINFO "PC" Linux
+@           SOA   ns hostmaster 1 2 3 4 5
+@           CH TYPE65280 \# 2 BEEF
+@           DNSKEY 257 3 15 l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=
+@           RRSIG  DNSKEY 15 2 300 20240101000000 20231201000000 3613 example.com. AQID
+"#, origin()).ok().unwrap();
+    let expected = r#"a\046b\$c.example.com. 300 IN TXT "say \"hi\"" "back\\slash" "\009tab"
+example.com. 300 IN MX 10 mail.example.com.
+example.com. 300 IN TXT \# 0
+example.com. 300 IN HINFO "PC" "Linux"
+example.com. 300 IN SOA ns.example.com. hostmaster.example.com. 1 2 3 4 5
+example.com. 300 CH TYPE65280 \# 2 BEEF
+example.com. 300 CH DNSKEY 257 3 15 l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=
+example.com. 300 CH RRSIG DNSKEY 15 2 300 20240101000000 20231201000000 3613 example.com. AQID
+"#;
+    let text = write_zone_string(&records, false);
+    assert_eq!(text, expected);
+
+    // Written text reads back as the same records
+    assert_eq!(read_zone_str(&text, None).ok().unwrap(), records);
+}
+
+#[test]
+fn test_write_zone_round_trip() {
+    let records = read_zone_file("tests/zones/example.com.zone", None).ok().unwrap();
+    let mut writer = ZoneWriter::new(Vec::new());
+    writer.write_records(&records).ok().unwrap();
+    let text = String::from_utf8(writer.into_inner()).unwrap();
+    assert_eq!(read_zone_str(&text, None).ok().unwrap(), records);
+}
+
+#[test]
+fn test_write_zone_canonical_order() {
+    let records = read_zone_str("
+$TTL 60
+z           A     192.0.2.2
+Z           A     192.0.2.1
+\\200.z    A     192.0.2.1
+*.z         A     192.0.2.1
+b           NS    B.example.
+b           NS    a.example.
+a           TXT   x
+a           A     192.0.2.1
+@           NS    ns
+", origin()).ok().unwrap();
+    let text = write_zone_string(&records, true);
+    let order: Vec<&str> = text.lines().map(|l| l.split(' ').next().unwrap()).collect();
+    assert_eq!(order, vec![
+        "example.com.",
+        "a.example.com.",
+        "a.example.com.",
+        "b.example.com.",
+        "b.example.com.",
+        "Z.example.com.",
+        "z.example.com.",
+        "*.z.example.com.",
+        "\\200.z.example.com.",
+    ]);
+    assert!(text.contains("a.example.com. 60 IN A 192.0.2.1\na.example.com. 60 IN TXT \"x\"\n"));
+    assert!(text.contains("IN NS a.example.\nb.example.com. 60 IN NS B.example.\n"));
+    assert!(text.contains("Z.example.com. 60 IN A 192.0.2.1\nz.example.com. 60 IN A 192.0.2.2\n"));
+}
@@ -0,0 +1,84 @@
+use super::ResourceRecord;
+use super::super::writer::write_dns_rdata_canonical;
+
+use std::cmp::Ordering;
+use std::io;
+
+/// Writes resource records as RFC 1035 master file text, one record per
+/// line in the presentation format of `ResourceRecord`'s `Display`.
+///
+/// Records are written in the order given unless canonical ordering is
+/// enabled, in which case they are sorted as per
+/// [RFC4034§6](https://tools.ietf.org/html/rfc4034#section-6): by owner
+/// name in canonical order, then class, then type, then canonical RDATA.
+pub struct ZoneWriter<W: io::Write> {
+    out: W,
+    canonical: bool,
+}
+
+impl<W: io::Write> ZoneWriter<W> {
+    pub fn new(out: W) -> ZoneWriter<W> {
+        ZoneWriter {
+            out,
+            canonical: false,
+        }
+    }
+
+    /// Enable or disable sorting records into DNSSEC canonical order.
+    pub fn set_canonical_order(&mut self, canonical: bool) {
+        self.canonical = canonical;
+    }
+
+    /// Consume the writer, returning the underlying output.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Write a single record.
+    pub fn write_record(&mut self, rr: &ResourceRecord) -> io::Result<()> {
+        writeln!(self.out, "{}", rr)
+    }
+
+    /// Write a set of records, sorting them first if canonical ordering
+    /// is enabled.
+    pub fn write_records(&mut self, records: &[ResourceRecord]) -> io::Result<()> {
+        if self.canonical {
+            let mut keyed: Vec<(&ResourceRecord, Vec<u8>)> = records.iter().map(|rr| (rr, canonical_rdata(rr))).collect();
+            keyed.sort_by(|a, b| canonical_cmp(a.0, &a.1, b.0, &b.1));
+            for &(rr, _) in keyed.iter() {
+                self.write_record(rr)?;
+            }
+        } else {
+            for rr in records.iter() {
+                self.write_record(rr)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The canonical form of a record's RDATA, or its raw octets if the
+/// RDATA cannot be decoded.
+fn canonical_rdata(rr: &ResourceRecord) -> Vec<u8> {
+    match rr.rdata().map_err(From::from).and_then(|r| write_dns_rdata_canonical(&r)) {
+        Ok(data) => data,
+        Err(_) => rr.context.get(rr.rdata..rr.rdata + rr.rdlen as usize).unwrap_or(&[]).to_vec(),
+    }
+}
+
+fn canonical_cmp(a: &ResourceRecord, a_rdata: &[u8], b: &ResourceRecord, b_rdata: &[u8]) -> Ordering {
+    a.rname.canonical_cmp(&b.rname)
+        .then(a.rclass.to_u16().cmp(&b.rclass.to_u16()))
+        .then(a.rtype.to_u16().cmp(&b.rtype.to_u16()))
+        .then(a_rdata.cmp(b_rdata))
+}
+
+/// Write records as master file text, optionally in DNSSEC canonical
+/// order.
+pub fn write_zone_string(records: &[ResourceRecord], canonical: bool) -> String {
+    let mut writer = ZoneWriter::new(Vec::new());
+    writer.set_canonical_order(canonical);
+    // Writing to a Vec cannot fail
+    writer.write_records(records).unwrap();
+    String::from_utf8(writer.into_inner()).unwrap()
+}